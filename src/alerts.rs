@@ -0,0 +1,236 @@
+//! Alerts on the failure rate of the jobs and the depth of their queues.
+//!
+//! [`monitor`] watches the [events] of the jobs in this process and the
+//! queues of a backend, raising an [`Alert`] when the failure rate over a
+//! sliding window, or the depth of a queue, reaches the thresholds of an
+//! [`Alerting`].  Alerts go to callbacks or [`Notifier`]s:
+//!
+//! ```
+//! # use std::time::Duration;
+//! # use simple_jobs::FSJob;
+//! use simple_jobs::alerts::{self, Alerting};
+//!
+//! # fn example(job: FSJob<u16, String, u32, ()>) {
+//! let alerting = Alerting::new(Duration::from_secs(15 * 60))
+//!     .failure_rate(0.2)
+//!     .min_completions(10)
+//!     .queue_depth(1000)
+//!     .on_alert(|alert| eprintln!("{}", alert.text()));
+//! tokio::spawn(alerts::monitor(job, alerting));
+//! # }
+//! ```
+//!
+//! An alert is raised once when its threshold is reached, and again only
+//! after it went back below it.
+
+use std::{
+    collections::{BTreeSet, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+
+use tokio::{sync::broadcast::error::RecvError, time::Instant};
+
+use crate::{events, notifier::NotifyFuture, queue, Job, JobEvent, Notifier};
+
+/// A threshold that was reached.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Alert {
+    /// The jobs that completed in the window failed at `rate` (from 0 to
+    /// 1): `failed` of `completed`.
+    FailureRate {
+        rate: f64,
+        failed: usize,
+        completed: usize,
+        window: Duration,
+    },
+    /// The `queue` has `depth` pending jobs.
+    QueueDepth { queue: String, depth: usize },
+}
+
+impl Alert {
+    /// A one-line description, e.g. `Queue emails has 1200 pending jobs`.
+    pub fn text(&self) -> String {
+        match self {
+            Alert::FailureRate {
+                rate,
+                failed,
+                completed,
+                window,
+            } => format!(
+                "{:.0}% of the jobs failed in the last {}s ({failed} of \
+                 {completed})",
+                rate * 100.0,
+                window.as_secs()
+            ),
+            Alert::QueueDepth { queue, depth } => {
+                format!("Queue {queue} has {depth} pending jobs")
+            }
+        }
+    }
+}
+
+/// Calls a closure for every alert.
+struct Callback<F>(F);
+
+impl<F: Fn(&Alert) + Send + Sync> Notifier for Callback<F> {
+    fn on_alert(&self, alert: Alert) -> NotifyFuture {
+        (self.0)(&alert);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// The thresholds of the alerts, and where they go.
+#[derive(Clone)]
+pub struct Alerting {
+    window: Duration,
+    failure_rate: Option<f64>,
+    min_completions: usize,
+    queue_depth: Option<usize>,
+    interval: Duration,
+    notifiers: Vec<Arc<dyn Notifier>>,
+}
+
+impl Alerting {
+    /// No thresholds, computing the failure rate over the last `window`,
+    /// and checking the depth of the queues every 15 seconds.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            failure_rate: None,
+            min_completions: 1,
+            queue_depth: None,
+            interval: Duration::from_secs(15),
+            notifiers: vec![],
+        }
+    }
+
+    /// Alert when the jobs completing in the window fail at least at
+    /// `rate`, from 0 to 1.
+    pub fn failure_rate(mut self, rate: f64) -> Self {
+        self.failure_rate = Some(rate);
+        self
+    }
+
+    /// Only compute the failure rate once `completions` jobs completed in
+    /// the window, so a single failure isn't a rate of 100%.
+    ///
+    /// Defaults to 1.
+    pub fn min_completions(mut self, completions: usize) -> Self {
+        self.min_completions = completions.max(1);
+        self
+    }
+
+    /// Alert when a queue has at least `depth` pending jobs.
+    pub fn queue_depth(mut self, depth: usize) -> Self {
+        self.queue_depth = Some(depth);
+        self
+    }
+
+    /// Check the depth of the queues every `interval`.
+    pub fn check_every(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Call `f` with every alert.  It runs inline, and should return
+    /// quickly.
+    pub fn on_alert<F>(self, f: F) -> Self
+    where
+        F: Fn(&Alert) + Send + Sync + 'static,
+    {
+        self.notify(Callback(f))
+    }
+
+    /// Send every alert to `notifier` (see [`Notifier::on_alert`]), from a
+    /// task of its own.
+    pub fn notify<N: Notifier + 'static>(mut self, notifier: N) -> Self {
+        self.notifiers.push(Arc::new(notifier));
+        self
+    }
+
+    fn raise(&self, alert: Alert) {
+        for notifier in &self.notifiers {
+            let notify = notifier.on_alert(alert.clone());
+            // Nobody is there to report the errors to.
+            tokio::spawn(async move { notify.await.ok() });
+        }
+    }
+}
+
+/// Raise the alerts of `alerting` until the event bus closes, for the jobs
+/// of this process and the queues of `job`.
+pub async fn monitor<J: Job>(job: J, alerting: Alerting) {
+    let mut events = events::subscribe();
+    let mut ticks = tokio::time::interval(alerting.interval);
+    let mut completions = VecDeque::new();
+    let mut failing = false;
+    let mut deep = BTreeSet::new();
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let failed = match event {
+                    Ok(JobEvent::Finished { .. }) => false,
+                    Ok(JobEvent::Failed { .. }) => true,
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return,
+                };
+                completions.push_back((Instant::now(), failed));
+                check_failure_rate(&alerting, &mut completions, &mut failing);
+            }
+            _ = ticks.tick() => check_queue_depths(&job, &alerting, &mut deep),
+        }
+    }
+}
+
+/// Raise an alert if the failure rate of the `completions` in the window
+/// reached the threshold, and the alert isn't already `failing`.
+fn check_failure_rate(
+    alerting: &Alerting,
+    completions: &mut VecDeque<(Instant, bool)>,
+    failing: &mut bool,
+) {
+    while completions
+        .front()
+        .is_some_and(|(at, _)| at.elapsed() > alerting.window)
+    {
+        completions.pop_front();
+    }
+    let Some(threshold) = alerting.failure_rate else {
+        return;
+    };
+    let completed = completions.len();
+    let failed = completions.iter().filter(|(_, failed)| *failed).count();
+    let rate = failed as f64 / completed.max(1) as f64;
+    let reached = completed >= alerting.min_completions && rate >= threshold;
+    if reached && !*failing {
+        alerting.raise(Alert::FailureRate {
+            rate,
+            failed,
+            completed,
+            window: alerting.window,
+        });
+    }
+    *failing = reached;
+}
+
+/// Raise an alert for the queues of `job` that reached the threshold, and
+/// aren't already `deep`.
+fn check_queue_depths<J: Job>(
+    job: &J,
+    alerting: &Alerting,
+    deep: &mut BTreeSet<String>,
+) {
+    let Some(threshold) = alerting.queue_depth else {
+        return;
+    };
+    let Ok(depths) = queue::depths(job) else {
+        return;
+    };
+    deep.retain(|queue| depths.get(queue).is_some_and(|d| *d >= threshold));
+    for (queue, depth) in depths {
+        if depth >= threshold && deep.insert(queue.clone()) {
+            alerting.raise(Alert::QueueDepth { queue, depth });
+        }
+    }
+}
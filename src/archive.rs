@@ -0,0 +1,172 @@
+//! Export and import of jobs, as newline-delimited JSON.
+//!
+//! [`export`] writes the jobs of any backend to an archive, and [`import`]
+//! saves them into another backend (or the same one, later), e.g. to back
+//! jobs up, move them between environments or attach them to a bug report.
+//!
+//! An archive starts with a header line, followed by one line per job with
+//! its record, logs and outputs:
+//!
+//! ```text
+//! {"simple_jobs_archive":1}
+//! {"job":{"id":"...","status":"Finished",...},"logs":[...],"outputs":[...]}
+//! ```
+//!
+//! Status histories ([`Job::history`]) aren't exported.
+
+use std::io::{BufRead, Write};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{Info, Job, JobInfo, LogLine};
+
+/// The version of the archive layout.
+const ARCHIVE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    simple_jobs_archive: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry<O, E, M, S> {
+    job: JobInfo<O, E, M, S>,
+    #[serde(default)]
+    logs: Vec<LogLine>,
+    #[serde(default)]
+    outputs: Vec<serde_json::Value>,
+}
+
+/// What [`import`] did.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// The ids of the jobs saved into the backend.
+    pub imported: Vec<uuid::Uuid>,
+    /// The ids of the jobs left out because the backend already has them.
+    pub skipped: Vec<uuid::Uuid>,
+}
+
+/// Write the jobs of `job` for which `filter` returns `true` to `writer`,
+/// returning how many were exported.
+///
+/// Jobs that can't be loaded are skipped (see [`Job::scan`]); logs and
+/// outputs are exported when the backend stores them.
+pub fn export<J, W, F>(
+    job: &J,
+    mut writer: W,
+    mut filter: F,
+) -> Result<usize, std::io::Error>
+where
+    J: Job,
+    J::Output: Serialize,
+    J::Error: Serialize,
+    J::Metadata: Serialize,
+    J::Status: Serialize,
+    W: Write,
+    F: FnMut(&Info<J>) -> bool,
+{
+    let header = Header {
+        simple_jobs_archive: ARCHIVE_VERSION,
+    };
+    serde_json::to_writer(&mut writer, &header)?;
+    writer.write_all(b"\n")?;
+    let mut exported = 0;
+    for info in job.scan()? {
+        if !filter(&info) {
+            continue;
+        }
+        let entry = Entry {
+            logs: stored(job.logs(info.id))?,
+            outputs: stored(job.outputs(info.id, 0))?,
+            job: info,
+        };
+        serde_json::to_writer(&mut writer, &entry)?;
+        writer.write_all(b"\n")?;
+        exported += 1;
+    }
+    writer.flush()?;
+    Ok(exported)
+}
+
+/// Save the jobs of an archive written by [`export`] into `job`.
+///
+/// Jobs the backend already has are left untouched.  Logs and outputs are
+/// only imported into backends storing them.
+pub fn import<J, R>(job: &J, reader: R) -> Result<ImportReport, std::io::Error>
+where
+    J: Job,
+    J::Output: DeserializeOwned,
+    J::Error: DeserializeOwned,
+    J::Metadata: DeserializeOwned,
+    J::Status: DeserializeOwned,
+    R: BufRead,
+{
+    let mut lines = reader.lines();
+    let header = lines.next().transpose()?.unwrap_or_default();
+    match serde_json::from_str::<Header>(&header) {
+        Ok(header) if header.simple_jobs_archive <= ARCHIVE_VERSION => {}
+        Ok(header) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "the archive uses version {}, but this release only \
+                     supports versions up to {ARCHIVE_VERSION}",
+                    header.simple_jobs_archive
+                ),
+            ));
+        }
+        Err(_) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "not a job archive",
+            ));
+        }
+    }
+    let mut report = ImportReport::default();
+    for line in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: Entry<J::Output, J::Error, J::Metadata, J::Status> =
+            serde_json::from_str(&line)?;
+        let id = entry.job.id;
+        match job.load(id) {
+            Ok(_) => {
+                report.skipped.push(id);
+                continue;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        job.save(&entry.job)?;
+        for line in &entry.logs {
+            unless_unsupported(job.append_log(id, line))?;
+        }
+        for item in &entry.outputs {
+            unless_unsupported(job.append_output(id, item))?;
+        }
+        report.imported.push(id);
+    }
+    Ok(report)
+}
+
+/// The items a backend stores, or none if it doesn't store them.
+pub(crate) fn stored<T>(
+    items: Result<Vec<T>, std::io::Error>,
+) -> Result<Vec<T>, std::io::Error> {
+    match items {
+        Err(e) if e.kind() == std::io::ErrorKind::Unsupported => Ok(vec![]),
+        items => items,
+    }
+}
+
+/// Ignore the failure of a backend not storing something.
+pub(crate) fn unless_unsupported(
+    result: Result<(), std::io::Error>,
+) -> Result<(), std::io::Error> {
+    match result {
+        Err(e) if e.kind() == std::io::ErrorKind::Unsupported => Ok(()),
+        result => result,
+    }
+}
@@ -0,0 +1,85 @@
+//! The audit log of the saves of a job.
+//!
+//! Backends keeping it (e.g. [`FSJob::with_audit`](crate::FSJob::with_audit))
+//! append an [`AuditEntry`] for every save of a job, even those not
+//! changing its status, to a stream of their own that is only ever
+//! appended to.  [`Job::audit`](crate::Job::audit) reads it back, for
+//! compliance-sensitive workloads:
+//!
+//! ```
+//! # use simple_jobs::{audit::Actor, FSJob, Job};
+//! # fn example(job: FSJob<u16, String, u16, ()>, id: uuid::Uuid) -> std::io::Result<()> {
+//! for entry in job.audit(id)? {
+//!     if entry.actor == Actor::Canceler {
+//!         println!("canceled at {} from {}", entry.at, entry.process);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The actor of a save is inferred from the transition: saves are made
+//! through the backend, which doesn't know who calls it.  The audit log is
+//! removed with its job ([`Job::remove`](crate::Job::remove)).
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::StatusType;
+
+/// Who made a save, as inferred from the transition.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Actor {
+    /// The job was submitted or enqueued.
+    Submitter,
+    /// A worker claimed the job from its queue, or returned it there after
+    /// a failed attempt.
+    Worker,
+    /// The job saved an intermediate status, or its result.
+    Job,
+    /// The job was canceled, e.g. by [`Job::cancel`](crate::Job::cancel).
+    Canceler,
+    /// The job was interrupted by the shutdown of its process, or marked as
+    /// such by [`Job::recover`](crate::Job::recover).
+    Supervisor,
+    /// The status didn't change, e.g. the metadata was updated.
+    Other,
+}
+
+impl Actor {
+    /// The actor of a save changing the status of a job from `from` (`None`
+    /// for its first save) to `to`.
+    pub fn of<S: PartialEq>(
+        from: Option<&StatusType<S>>,
+        to: &StatusType<S>,
+    ) -> Self {
+        match (from, to) {
+            (None, _) => Actor::Submitter,
+            (Some(from), to) if from == to => Actor::Other,
+            (_, StatusType::Canceled(_)) => Actor::Canceler,
+            (_, StatusType::Interrupted) => Actor::Supervisor,
+            (Some(StatusType::Pending), StatusType::Started)
+            | (_, StatusType::Pending) => Actor::Worker,
+            _ => Actor::Job,
+        }
+    }
+}
+
+/// A save of a job.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry<S> {
+    /// When the job was saved.
+    pub at: DateTime<Utc>,
+    /// The status of the job before the save, `None` for its first save.
+    pub from: Option<StatusType<S>>,
+    /// The status the job was saved with.
+    pub to: StatusType<S>,
+    /// Who made the save.
+    pub actor: Actor,
+    /// The label of the process that made the save (see
+    /// [`worker::label`](crate::worker::label)).
+    pub process: String,
+    /// The version of the record (see
+    /// [`JobInfo::version`](crate::JobInfo::version)).
+    pub version: u64,
+}
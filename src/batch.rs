@@ -0,0 +1,137 @@
+//! Groups of jobs submitted together.
+//!
+//! [`Job::submit_batch`] starts a job for each of many metadata values,
+//! e.g. to send ten thousand notifications, and returns a [`Batch`]
+//! following them.  The jobs are saved with the id of the batch, so its
+//! [`BatchStatus`] can also be read from other processes with
+//! [`Job::batch_status`].
+//!
+//! ```
+//! # use simple_jobs::{FSJob, Job};
+//! # fn example(job: FSJob<u16, String, u16, ()>) -> std::io::Result<()> {
+//! let batch = job.submit_batch(|_, _, n| async move { Ok(n * 2) }, 1..=100)?;
+//! batch.on_batch_complete(|status| {
+//!     if let Ok(status) = status {
+//!         println!("{} sent, {} failed", status.succeeded, status.failed);
+//!     }
+//! });
+//! # Ok(())
+//! # }
+//! ```
+
+use uuid::Uuid;
+
+use crate::{wait, Job, JobInfo};
+
+/// The counts of the jobs of a batch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BatchStatus {
+    /// The jobs not done yet.
+    pub pending: usize,
+    /// The jobs that finished with an output.
+    pub succeeded: usize,
+    /// The jobs that finished with an error, failed, were canceled or
+    /// were interrupted.
+    pub failed: usize,
+}
+
+impl BatchStatus {
+    /// The number of jobs of the batch.
+    pub fn total(&self) -> usize {
+        self.pending + self.succeeded + self.failed
+    }
+
+    /// Whether all the jobs of the batch are done.
+    pub fn is_complete(&self) -> bool {
+        self.pending == 0
+    }
+
+    /// Count a job of the batch.
+    pub(crate) fn add<O, E, M, S>(&mut self, info: &JobInfo<O, E, M, S>) {
+        match &info.result {
+            _ if !info.status.is_terminal() => self.pending += 1,
+            Some(Ok(_)) => self.succeeded += 1,
+            _ => self.failed += 1,
+        }
+    }
+}
+
+/// The jobs submitted together by [`Job::submit_batch`].
+pub struct Batch<J: Job> {
+    job: J,
+    id: Uuid,
+    ids: Vec<Uuid>,
+}
+
+impl<J: Job> Clone for Batch<J> {
+    fn clone(&self) -> Self {
+        Self {
+            job: self.job.clone(),
+            id: self.id,
+            ids: self.ids.clone(),
+        }
+    }
+}
+
+impl<J: Job> std::fmt::Debug for Batch<J> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Batch")
+            .field("id", &self.id)
+            .field("ids", &self.ids)
+            .finish()
+    }
+}
+
+impl<J: Job> Batch<J> {
+    pub(crate) fn new(job: J, id: Uuid, ids: Vec<Uuid>) -> Self {
+        Self { job, id, ids }
+    }
+
+    /// The id of the batch, saved in the
+    /// [`batch_id`](crate::JobInfo::batch_id) of its jobs.
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// The ids of the jobs of the batch, in the order they were submitted.
+    pub fn ids(&self) -> &[Uuid] {
+        &self.ids
+    }
+
+    /// The counts of the jobs of the batch.
+    ///
+    /// Unlike [`Job::batch_status`], this loads the jobs of the batch
+    /// only.
+    pub fn status(&self) -> Result<BatchStatus, std::io::Error> {
+        let mut status = BatchStatus::default();
+        for info in self.job.load_many(&self.ids) {
+            status.add(&info?);
+        }
+        Ok(status)
+    }
+
+    /// Wait for all the jobs of the batch to be done, returning their
+    /// counts.
+    pub async fn wait(&self) -> Result<BatchStatus, std::io::Error> {
+        for &id in &self.ids {
+            wait(id, &self.job).await?;
+        }
+        self.status()
+    }
+
+    /// Call `f` with the counts of the jobs of the batch (or the error
+    /// loading them) once they are all done.
+    ///
+    /// The callback runs in a task of this process, spawned with the
+    /// [spawner](Job::spawner) of the backend: it isn't called if the
+    /// process stops first.
+    pub fn on_batch_complete<F>(&self, f: F)
+    where
+        F: FnOnce(Result<BatchStatus, std::io::Error>) + Send + 'static,
+    {
+        let batch = self.clone();
+        self.job
+            .spawner()
+            .spawn(Box::pin(async move { f(batch.wait().await) }));
+    }
+}
@@ -0,0 +1,161 @@
+//! Large outputs and metadata, stored apart from the job records.
+//!
+//! With [`FSJob::with_blobs`](crate::FSJob::with_blobs), outputs and
+//! metadata larger than a threshold are written to a [`BlobStore`] and
+//! replaced in the record by a pointer, so multi-megabyte results don't
+//! bloat the job files:
+//!
+//! ```text
+//! {"id":"...","result":{"Ok":{"$blob":"67e55044-...-bb680e5fe0c8.result"}},...}
+//! ```
+//!
+//! [`DirBlobStore`] keeps the blobs in a directory; implement [`BlobStore`]
+//! for other stores, e.g. an S3 bucket.  Blobs are only used with
+//! self-describing [encodings](crate::record::Encoding), and they are
+//! removed with their job ([`Job::remove`](crate::Job::remove)).  The
+//! migrations of a [`Schema`](crate::versioning::Schema) see the pointers,
+//! not the values they point to.
+
+use std::{path::PathBuf, sync::Arc};
+
+use serde_json::Value;
+use uuid::Uuid;
+
+/// The key of a pointer to a blob, in the records.
+const POINTER: &str = "$blob";
+
+/// Where large values are stored, by key.
+pub trait BlobStore: Send + Sync {
+    /// Store `blob` under `key`, replacing any blob with that key.
+    fn put(&self, key: &str, blob: &[u8]) -> Result<(), std::io::Error>;
+
+    /// The blob stored under `key`.
+    ///
+    /// Fails with [`std::io::ErrorKind::NotFound`] if there is none.
+    fn get(&self, key: &str) -> Result<Vec<u8>, std::io::Error>;
+
+    /// Remove the blob stored under `key`, if any.
+    fn delete(&self, key: &str) -> Result<(), std::io::Error>;
+}
+
+/// A [`BlobStore`] keeping each blob in a file of a directory, named after
+/// its key.
+#[derive(Clone, Debug)]
+pub struct DirBlobStore {
+    directory: PathBuf,
+}
+
+impl DirBlobStore {
+    /// Store the blobs in `directory`, created when the first blob is
+    /// stored.
+    pub fn new(directory: PathBuf) -> Self {
+        Self { directory }
+    }
+}
+
+impl BlobStore for DirBlobStore {
+    fn put(&self, key: &str, blob: &[u8]) -> Result<(), std::io::Error> {
+        std::fs::create_dir_all(&self.directory)?;
+        let path = self.directory.join(key);
+        let temporary = self.directory.join(format!(".{key}.tmp"));
+        std::fs::write(&temporary, blob)?;
+        std::fs::rename(temporary, path)
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>, std::io::Error> {
+        std::fs::read(self.directory.join(key))
+    }
+
+    fn delete(&self, key: &str) -> Result<(), std::io::Error> {
+        match std::fs::remove_file(self.directory.join(key)) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+}
+
+/// The values of the records moved to a [`BlobStore`], and when.
+#[derive(Clone)]
+pub(crate) struct Offload {
+    store: Arc<dyn BlobStore>,
+    threshold: usize,
+}
+
+impl Offload {
+    /// Move the values larger than `threshold` bytes (as JSON) to `store`.
+    pub(crate) fn new(store: Arc<dyn BlobStore>, threshold: usize) -> Self {
+        Self { store, threshold }
+    }
+
+    /// Replace the large output and metadata of the record of the job
+    /// `id` by pointers, storing them as blobs.
+    pub(crate) fn offload(
+        &self,
+        id: Uuid,
+        record: &mut Value,
+    ) -> Result<(), std::io::Error> {
+        let output = record.get_mut("result").and_then(|r| r.get_mut("Ok"));
+        if let Some(output) = output {
+            self.offload_value(&key(id, "result"), output)?;
+        }
+        if let Some(metadata) = record.get_mut("metadata") {
+            self.offload_value(&key(id, "metadata"), metadata)?;
+        }
+        Ok(())
+    }
+
+    fn offload_value(
+        &self,
+        key: &str,
+        value: &mut Value,
+    ) -> Result<(), std::io::Error> {
+        if value.is_null() {
+            return Ok(());
+        }
+        let blob = serde_json::to_vec(value)?;
+        if blob.len() > self.threshold {
+            self.store.put(key, &blob)?;
+            *value = serde_json::json!({ POINTER: key });
+        }
+        Ok(())
+    }
+
+    /// Replace the pointers of a record by the values they point to.
+    pub(crate) fn restore(
+        &self,
+        record: &mut Value,
+    ) -> Result<(), std::io::Error> {
+        let output = record.get_mut("result").and_then(|r| r.get_mut("Ok"));
+        if let Some(output) = output {
+            self.restore_value(output)?;
+        }
+        if let Some(metadata) = record.get_mut("metadata") {
+            self.restore_value(metadata)?;
+        }
+        Ok(())
+    }
+
+    fn restore_value(&self, value: &mut Value) -> Result<(), std::io::Error> {
+        let key = match value.as_object() {
+            Some(pointer) if pointer.len() == 1 => {
+                pointer.get(POINTER).cloned()
+            }
+            _ => None,
+        };
+        if let Some(Value::String(key)) = key {
+            *value = serde_json::from_slice(&self.store.get(&key)?)?;
+        }
+        Ok(())
+    }
+
+    /// Remove the blobs of the job `id`.
+    pub(crate) fn remove(&self, id: Uuid) -> Result<(), std::io::Error> {
+        self.store.delete(&key(id, "result"))?;
+        self.store.delete(&key(id, "metadata"))
+    }
+}
+
+/// The key of the blob of a field of the record of the job `id`.
+fn key(id: Uuid, field: &str) -> String {
+    format!("{id}.{field}")
+}
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+/// Why a job was canceled.
+///
+/// The reason is persisted in the job status (see
+/// [`StatusType::Canceled`](crate::StatusType::Canceled)), so "why didn't
+/// my job run?" has a recorded answer.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum CancelReason {
+    /// Canceled on request, e.g. by a user or an operator.
+    UserAction,
+    /// The job ran (or waited) for too long.
+    Timeout,
+    /// The process running the job is shutting down (e.g. for a deploy).
+    Drain,
+    /// A job this one depends on did not succeed.
+    DependencyFailed,
+    /// Any other reason, described by the string.
+    Other(String),
+}
+
+impl CancelReason {
+    /// A short, fixed label for the reason, suitable for metrics labels.
+    pub fn label(&self) -> &'static str {
+        match self {
+            CancelReason::UserAction => "user_action",
+            CancelReason::Timeout => "timeout",
+            CancelReason::Drain => "drain",
+            CancelReason::DependencyFailed => "dependency_failed",
+            CancelReason::Other(_) => "other",
+        }
+    }
+}
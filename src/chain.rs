@@ -0,0 +1,157 @@
+//! Pipelines of jobs, each step receiving the output of the previous one.
+//!
+//! A [`JobChain`] is an ordered sequence of steps, run as jobs of a backend
+//! whose metadata has the type of its outputs: each step gets the output
+//! of the previous step as metadata, and the first step gets the input of
+//! the chain.
+//!
+//! ```
+//! # use simple_jobs::{chain::JobChain, FSJob};
+//! # async fn example(job: FSJob<u32, String, u32, ()>) -> std::io::Result<()> {
+//! let chain = JobChain::new()
+//!     .then(|_, _, n| async move { Ok(n + 1) })
+//!     .then(|_, _, n| async move { Ok(n * 2) });
+//! let id = uuid::Uuid::new_v4();
+//! assert_eq!(chain.run(&job, id, 20).await?, Ok(42));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Each step is saved as a job of its own, with an id derived from the id
+//! of the chain (see [`step_id`]), so a chain interrupted by a crash is
+//! resumed by running it again with the same id: the steps that finished
+//! are skipped, and the steps marked as [interrupted](crate::Job::recover)
+//! are run again.
+
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use uuid::Uuid;
+
+use crate::{ids, run, wait, Job, JobInfo, StatusType};
+
+type StepFuture<O, E> = Pin<Box<dyn Future<Output = Result<O, E>> + Send>>;
+
+type Step<J> = Arc<
+    dyn Fn(
+            Uuid,
+            J,
+            <J as Job>::Output,
+        ) -> StepFuture<<J as Job>::Output, <J as Job>::Error>
+        + Send
+        + Sync,
+>;
+
+/// The id of the job running the step `step` (counting from 0) of the
+/// chain `id`.
+pub fn step_id(id: Uuid, step: usize) -> Uuid {
+    ids::from_key(&id, &step.to_string())
+}
+
+/// An ordered sequence of steps, run as jobs.
+pub struct JobChain<J: Job> {
+    steps: Vec<Step<J>>,
+}
+
+impl<J: Job> Clone for JobChain<J> {
+    fn clone(&self) -> Self {
+        Self {
+            steps: self.steps.clone(),
+        }
+    }
+}
+
+impl<J: Job> Default for JobChain<J> {
+    fn default() -> Self {
+        Self { steps: vec![] }
+    }
+}
+
+impl<J, O> JobChain<J>
+where
+    J: Job<Output = O, Metadata = O>,
+    O: 'static,
+{
+    /// Create a chain without steps.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a step, called like the closures of [`Job::submit`] with the
+    /// output of the previous step as metadata.
+    pub fn then<F, Fut>(mut self, step: F) -> Self
+    where
+        F: Fn(Uuid, J, O) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<O, J::Error>> + Send + 'static,
+    {
+        self.steps.push(Arc::new(move |id, job, input| {
+            Box::pin(step(id, job, input))
+        }));
+        self
+    }
+
+    /// The number of steps.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Whether the chain has no steps.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Run the chain `id` on `input`, or resume it, returning the output of
+    /// its last step, or the error of the first step that failed.
+    ///
+    /// Steps already saved are not run again: finished steps pass on their
+    /// output, and steps still running (e.g. in another process) are waited
+    /// for.  Interrupted steps are run again, so call [`Job::recover`]
+    /// after a crash before resuming.  Fails with
+    /// [`std::io::ErrorKind::Other`] if a step was canceled or panicked.
+    pub async fn run(
+        &self,
+        job: &J,
+        id: Uuid,
+        input: O,
+    ) -> Result<Result<O, J::Error>, std::io::Error> {
+        let mut value = input;
+        for (i, step) in self.steps.iter().enumerate() {
+            let step_id = step_id(id, i);
+            let saved = match job.load(step_id) {
+                Ok(info) if matches!(info.status, StatusType::Interrupted) => {
+                    None
+                }
+                Ok(info) => Some(info),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => return Err(e),
+            };
+            if saved.is_none() {
+                let step = step.clone();
+                let info = JobInfo {
+                    id: step_id,
+                    ..JobInfo::new()
+                };
+                run::submit(
+                    job,
+                    info,
+                    move |id, job, input| step(id, job, input),
+                    value,
+                )?;
+            }
+            let info = match saved {
+                Some(info) if info.status.is_terminal() => info,
+                _ => wait(step_id, job).await?,
+            };
+            value = match info.result {
+                Some(Ok(output)) => output,
+                Some(Err(e)) => return Ok(Err(e)),
+                None => {
+                    return Err(std::io::Error::other(format!(
+                        "step {i} of chain {id} was {}",
+                        info.status.label()
+                    )))
+                }
+            };
+        }
+        Ok(Ok(value))
+    }
+}
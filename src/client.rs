@@ -0,0 +1,247 @@
+//! A client of the JSON API of the module `http`.
+//!
+//! [`Client::watch`] follows a job of a remote
+//! [`router`](crate::http::router) as a stream of its records, like
+//! [`watch`](crate::watch::watch) does for a backend:
+//!
+//! ```
+//! # use futures::StreamExt;
+//! # use serde_json::Value;
+//! # use simple_jobs::{client::Client, error::SerializableError, registry::Payload, watch::Backoff, JobInfo};
+//! # async fn example(id: uuid::Uuid) {
+//! let client = Client::new("https://example.com/admin");
+//! let mut updates = Box::pin(client.watch(id, Backoff::default()));
+//! while let Some(info) = updates.next().await {
+//!     let info: JobInfo<Value, SerializableError, Payload, ()> = info;
+//!     println!("{:?}", info.status);
+//! }
+//! # }
+//! ```
+//!
+//! The client follows the server-sent events of `GET /jobs/{id}/events`
+//! when the server has them, and otherwise polls `GET /jobs/{id}` with
+//! `If-None-Match`, so unchanged jobs aren't downloaded again.  The polling
+//! interval grows while nothing changes, and is randomized so many watchers
+//! don't poll in lockstep.  Network errors are retried with the same
+//! backoff, resuming the stream from the last record yielded.
+//!
+//! Requires the feature `client`.
+
+use std::time::Duration;
+
+use futures::Stream;
+use reqwest::{
+    header::{ACCEPT, ETAG, IF_NONE_MATCH},
+    StatusCode,
+};
+use serde::de::DeserializeOwned;
+use uuid::Uuid;
+
+use crate::{retry::Backoff, JobInfo};
+
+/// A client of the API served by [`router`](crate::http::router).
+#[derive(Clone, Debug)]
+pub struct Client {
+    http: reqwest::Client,
+    base: String,
+}
+
+impl Client {
+    /// A client of the API served at `base`, e.g.
+    /// `https://example.com/admin` for a router nested under `/admin`.
+    pub fn new(base: impl Into<String>) -> Self {
+        Self::with_client(reqwest::Client::new(), base)
+    }
+
+    /// Like [`Client::new`], sending the requests with `http`, e.g. to set
+    /// the headers authenticating them.
+    pub fn with_client(http: reqwest::Client, base: impl Into<String>) -> Self {
+        let base = base.into().trim_end_matches('/').to_string();
+        Self { http, base }
+    }
+
+    /// Watch the job `id`, yielding its record each time it changes.
+    ///
+    /// The stream ends after yielding the finished job, or after
+    /// [`Backoff::max_failures`] consecutive failed requests.
+    pub fn watch<O, E, M, S>(
+        &self,
+        id: Uuid,
+        backoff: Backoff,
+    ) -> impl Stream<Item = JobInfo<O, E, M, S>> + Send + 'static
+    where
+        JobInfo<O, E, M, S>: DeserializeOwned + Send + 'static,
+    {
+        let state = State {
+            http: self.http.clone(),
+            url: format!("{}/jobs/{id}", self.base),
+            interval: backoff.initial,
+            backoff,
+            etag: None,
+            events: None,
+            streaming: true,
+            failures: 0,
+            done: false,
+        };
+        futures::stream::unfold(state, next)
+    }
+}
+
+struct State {
+    http: reqwest::Client,
+    /// The URL of the job.
+    url: String,
+    backoff: Backoff,
+    /// The tag of the last record yielded.
+    etag: Option<String>,
+    /// The open event stream, and what was received of its next events.
+    events: Option<(reqwest::Response, Vec<u8>)>,
+    /// Whether the server may stream events, until it refuses to.
+    streaming: bool,
+    interval: Duration,
+    failures: u32,
+    done: bool,
+}
+
+/// The next record of a watched job, once it changed.
+async fn next<O, E, M, S>(
+    mut state: State,
+) -> Option<(JobInfo<O, E, M, S>, State)>
+where
+    JobInfo<O, E, M, S>: DeserializeOwned,
+{
+    while !state.done {
+        let fetched = match state.streaming {
+            true => next_event::<JobInfo<O, E, M, S>>(&mut state).await,
+            false => poll(&mut state).await,
+        };
+        match fetched {
+            Ok(Some((etag, info))) => {
+                state.failures = 0;
+                state.interval = state.backoff.initial;
+                if state.etag.as_ref() == Some(&etag) {
+                    continue;
+                }
+                state.etag = Some(etag);
+                state.done = info.status.is_terminal();
+                return Some((info, state));
+            }
+            // Unchanged, or the event stream was closed.
+            Ok(None) => {}
+            Err(_) => {
+                state.failures += 1;
+                state.events = None;
+                if state.backoff.exhausted(state.failures) {
+                    return None;
+                }
+            }
+        }
+        tokio::time::sleep(state.backoff.jittered(state.interval)).await;
+        state.interval = state.backoff.next(state.interval);
+    }
+    None
+}
+
+/// The next event of the stream of the job, opened if needed, or `None` once
+/// the server closes it.
+///
+/// Falls back to polling if the server has no event streams.
+async fn next_event<T: DeserializeOwned>(
+    state: &mut State,
+) -> Result<Option<(String, T)>, std::io::Error> {
+    if state.events.is_none() {
+        let mut request = state
+            .http
+            .get(format!("{}/events", state.url))
+            .header(ACCEPT, "text/event-stream");
+        if let Some(etag) = &state.etag {
+            request = request.header("last-event-id", etag);
+        }
+        let response = request.send().await.map_err(std::io::Error::other)?;
+        match response.status() {
+            status if status.is_success() => {
+                state.events = Some((response, vec![]));
+            }
+            StatusCode::NOT_FOUND
+            | StatusCode::METHOD_NOT_ALLOWED
+            | StatusCode::NOT_IMPLEMENTED => {
+                state.streaming = false;
+                return poll(state).await;
+            }
+            status => return Err(unexpected(status)),
+        }
+    }
+    let (response, received) = state.events.as_mut().expect("just opened");
+    loop {
+        if let Some(end) = received.windows(2).position(|w| w == b"\n\n") {
+            let event: Vec<u8> = received.drain(..end + 2).collect();
+            match parse_event(&String::from_utf8_lossy(&event))? {
+                Some(event) => return Ok(Some(event)),
+                // E.g. a keep-alive comment.
+                None => continue,
+            }
+        }
+        match response.chunk().await.map_err(std::io::Error::other)? {
+            Some(chunk) => received.extend_from_slice(&chunk),
+            None => {
+                state.events = None;
+                return Ok(None);
+            }
+        }
+    }
+}
+
+/// The id and the JSON of a server-sent event, if it has data.
+fn parse_event<T: DeserializeOwned>(
+    event: &str,
+) -> Result<Option<(String, T)>, std::io::Error> {
+    let mut id = None;
+    let mut data = String::new();
+    for line in event.lines() {
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "id" => id = Some(value.to_string()),
+            "data" => data.push_str(value),
+            _ => {}
+        }
+    }
+    if data.is_empty() {
+        return Ok(None);
+    }
+    let info = serde_json::from_str(&data)?;
+    Ok(Some((id.unwrap_or(data), info)))
+}
+
+/// The record of the job, or `None` if it didn't change since the last one
+/// yielded.
+async fn poll<T: DeserializeOwned>(
+    state: &mut State,
+) -> Result<Option<(String, T)>, std::io::Error> {
+    let mut request = state.http.get(&state.url);
+    if let Some(etag) = &state.etag {
+        request = request.header(IF_NONE_MATCH, etag);
+    }
+    let response = request.send().await.map_err(std::io::Error::other)?;
+    match response.status() {
+        StatusCode::NOT_MODIFIED => Ok(None),
+        status if status.is_success() => {
+            let etag = response
+                .headers()
+                .get(ETAG)
+                .and_then(|etag| etag.to_str().ok())
+                .map(str::to_string);
+            let body = response.bytes().await.map_err(std::io::Error::other)?;
+            let info = serde_json::from_slice(&body)?;
+            let etag = etag
+                .unwrap_or_else(|| String::from_utf8_lossy(&body).into_owned());
+            Ok(Some((etag, info)))
+        }
+        status => Err(unexpected(status)),
+    }
+}
+
+/// The error of a response with an unexpected `status`.
+fn unexpected(status: StatusCode) -> std::io::Error {
+    std::io::Error::other(format!("unexpected response status {status}"))
+}
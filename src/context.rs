@@ -0,0 +1,174 @@
+//! What a running job can do besides updating its status.
+//!
+//! A job gets a [`JobContext`] from its backend with [`Job::context`]:
+//!
+//! ```
+//! # use simple_jobs::{context::LogLevel, FSJob, Job};
+//! # fn example(job: FSJob<u16, String, (), ()>) -> std::io::Result<()> {
+//! job.submit(
+//!     |id, job, _| async move {
+//!         let ctx = job.context(id);
+//!         ctx.log(LogLevel::Info, "downloading").ok();
+//!         Ok(1)
+//!     },
+//!     (),
+//! )?;
+//! # Ok(())
+//! # }
+//! ```
+
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{queue::Lease, Info, Job};
+
+/// The severity of a [`LogLine`].
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+/// A line logged by a job.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LogLine {
+    /// When the line was logged.
+    pub at: DateTime<Utc>,
+    /// The severity of the line.
+    pub level: LogLevel,
+    /// The logged message.
+    pub message: String,
+}
+
+/// The context of a running job, bound to its id.
+pub struct JobContext<J> {
+    id: Uuid,
+    job: J,
+    /// The token of the lease of the run, if claimed from the queue.
+    token: Option<Uuid>,
+}
+
+impl<J: Job> JobContext<J> {
+    /// The context of the job `id`.
+    ///
+    /// Created by a job claimed with [`Job::claim_next`], while it runs, the
+    /// context is bound to that claim (see [`JobContext::extend_lease`]).
+    pub fn new(id: Uuid, job: J) -> Self {
+        let token = crate::queue::claim_token();
+        Self { id, job, token }
+    }
+
+    /// The id of the job.
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// The backend of the job.
+    pub fn job(&self) -> &J {
+        &self.job
+    }
+
+    /// Append a timestamped line to the log of the job (see
+    /// [`Job::append_log`]).
+    pub fn log(
+        &self,
+        level: LogLevel,
+        message: impl Into<String>,
+    ) -> Result<(), std::io::Error> {
+        let line = LogLine {
+            at: Utc::now(),
+            level,
+            message: message.into(),
+        };
+        self.job.append_log(self.id, &line)
+    }
+
+    /// Emit an intermediate output item, for consumers of
+    /// [`Job::results_stream`] (see [`Job::append_output`]).
+    pub fn emit<T: Serialize>(&self, item: &T) -> Result<(), std::io::Error> {
+        let item = serde_json::to_value(item)?;
+        self.job.append_output(self.id, &item)
+    }
+
+    /// Change the metadata of the job as it runs, e.g. to record what it
+    /// discovered (see [`Job::update_metadata`]).
+    pub fn update_metadata<F>(&self, f: F) -> Result<Info<J>, std::io::Error>
+    where
+        F: FnMut(&mut Option<J::Metadata>),
+    {
+        self.job.update_metadata(self.id, f)
+    }
+
+    /// Save `state` as the checkpoint of the job, e.g. the last row an ETL
+    /// job loaded, replacing the previous one.
+    ///
+    /// The checkpoint survives the failures of the job, so a job retried
+    /// from the queue (see [`QueueConfig::with_max_attempts`](crate::QueueConfig::with_max_attempts))
+    /// or claimed again after a crash can resume from it with
+    /// [`JobContext::last_checkpoint`] instead of starting over.
+    pub fn checkpoint<T: Serialize>(
+        &self,
+        state: &T,
+    ) -> Result<(), std::io::Error> {
+        let state = serde_json::to_value(state)?;
+        self.job.update(self.id, |info| {
+            info.checkpoint = Some(state.clone());
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// The last checkpoint saved by the job (see [`JobContext::checkpoint`]),
+    /// e.g. by a previous attempt, or `None` if it saved none.
+    pub fn last_checkpoint<T: DeserializeOwned>(
+        &self,
+    ) -> Result<Option<T>, std::io::Error> {
+        match self.job.load(self.id)?.checkpoint {
+            Some(state) => Ok(Some(serde_json::from_value(state)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Renew the lease of this worker on a job claimed with
+    /// [`Job::claim_next`], so it isn't offered to other workers for another
+    /// [visibility timeout](crate::QueueConfig::with_visibility_timeout).
+    ///
+    /// Long jobs should call it regularly, more often than the timeout.
+    /// Fails with [`JobError::LeaseLost`](crate::JobError::LeaseLost) if the
+    /// job was claimed again since this run claimed it, e.g. because the
+    /// lease expired, even by this worker.  Contexts created outside the
+    /// run, e.g. in a task it spawned, renew any lease of this worker.
+    pub fn extend_lease(&self) -> Result<Lease, std::io::Error> {
+        crate::queue::extend_lease(&self.job, self.id, self.token)
+    }
+
+    /// Log a line with [`LogLevel::Info`].
+    pub fn info(
+        &self,
+        message: impl Into<String>,
+    ) -> Result<(), std::io::Error> {
+        self.log(LogLevel::Info, message)
+    }
+
+    /// Log a line with [`LogLevel::Warn`].
+    pub fn warn(
+        &self,
+        message: impl Into<String>,
+    ) -> Result<(), std::io::Error> {
+        self.log(LogLevel::Warn, message)
+    }
+
+    /// Log a line with [`LogLevel::Error`].
+    pub fn error(
+        &self,
+        message: impl Into<String>,
+    ) -> Result<(), std::io::Error> {
+        self.log(LogLevel::Error, message)
+    }
+}
@@ -0,0 +1,94 @@
+//! A snapshot of the state of the stored jobs, for status pages.
+//!
+//! [`Job::dashboard_snapshot`](crate::Job::dashboard_snapshot) scans a
+//! backend once for everything a status page shows:
+//!
+//! ```
+//! # use simple_jobs::{FSJob, Job};
+//! # fn example(job: FSJob<u16, String, u16, ()>) -> std::io::Result<()> {
+//! let snapshot = job.dashboard_snapshot()?;
+//! for (queue, depth) in &snapshot.queue_depths {
+//!     println!("{queue}: {depth} pending");
+//! }
+//! if let Some(age) = snapshot.oldest_pending_age {
+//!     println!("oldest pending job waiting for {age:?}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The [http router](crate::http) serves it at `GET /dashboard`, with the
+//! descriptions of the statuses of the failures when it has a catalog.
+
+use std::{cmp::Reverse, collections::BTreeMap, time::Duration};
+
+use chrono::{DateTime, Utc};
+
+use crate::{queue::DEFAULT_QUEUE, JobInfo, StatusType};
+
+/// The number of failures kept in [`DashboardSnapshot::recent_failures`].
+pub const RECENT_FAILURES: usize = 10;
+
+/// The state of some jobs at a point in time.
+#[derive(Clone, Debug)]
+pub struct DashboardSnapshot<Output, Error, Metadata, Status> {
+    /// When the snapshot was taken.
+    pub at: DateTime<Utc>,
+    /// The number of pending jobs in each queue, without the queues that
+    /// have none.
+    pub queue_depths: BTreeMap<String, usize>,
+    /// The number of jobs with each kind of status (see
+    /// [`StatusType::label`]), without the kinds that have none.
+    pub status_counts: BTreeMap<&'static str, usize>,
+    /// The last jobs to end with an error, or with [`StatusType::Failed`],
+    /// most recent first, up to [`RECENT_FAILURES`].
+    pub recent_failures: Vec<JobInfo<Output, Error, Metadata, Status>>,
+    /// How long the oldest pending job has been waiting since it was
+    /// submitted.
+    pub oldest_pending_age: Option<Duration>,
+}
+
+impl<Output, Error, Metadata, Status>
+    DashboardSnapshot<Output, Error, Metadata, Status>
+{
+    /// The snapshot of `infos`, taken now.
+    pub fn of(
+        infos: impl IntoIterator<Item = JobInfo<Output, Error, Metadata, Status>>,
+    ) -> Self {
+        let at = Utc::now();
+        let mut queue_depths = BTreeMap::new();
+        let mut status_counts = BTreeMap::new();
+        let mut failures = vec![];
+        let mut oldest_pending: Option<DateTime<Utc>> = None;
+        for info in infos {
+            *status_counts.entry(info.status.label()).or_default() += 1;
+            if matches!(info.status, StatusType::Pending) {
+                let queue = info.queue.as_deref().unwrap_or(DEFAULT_QUEUE);
+                *queue_depths.entry(queue.to_string()).or_default() += 1;
+                if let Some(created_at) = info.created_at {
+                    oldest_pending = Some(
+                        oldest_pending
+                            .map_or(created_at, |o| o.min(created_at)),
+                    );
+                }
+            }
+            let failed = matches!(info.status, StatusType::Failed(_))
+                || (info.status.is_terminal()
+                    && matches!(info.result, Some(Err(_))));
+            if failed {
+                failures.push(info);
+            }
+        }
+        failures.sort_by_key(|info| Reverse(info.finished_at));
+        failures.truncate(RECENT_FAILURES);
+        Self {
+            at,
+            queue_depths,
+            status_counts,
+            recent_failures: failures,
+            oldest_pending_age: oldest_pending.map(|created_at| {
+                (at - created_at).to_std().unwrap_or_default()
+            }),
+        }
+    }
+}
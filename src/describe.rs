@@ -0,0 +1,161 @@
+//! Human-readable descriptions of job statuses and failures.
+//!
+//! Status values and errors are identified by a stable message key (see
+//! [`Describe`]), and a [`Catalog`] maps keys to localized strings provided
+//! by the application, so end users see "Waiting to start" rather than
+//! `Started`.
+//!
+//! ```
+//! use simple_jobs::{describe::Catalog, StatusType};
+//!
+//! let catalog = Catalog::new("en")
+//!     .with_message("en", "status.started", "Waiting to start")
+//!     .with_message("es", "status.started", "Esperando para empezar");
+//! let status: StatusType<String> = StatusType::Started;
+//! assert_eq!(catalog.describe("es", &status), "Esperando para empezar");
+//! // Unknown locales fall back to the default one.
+//! assert_eq!(catalog.describe("fr", &status), "Waiting to start");
+//! // Or the locale best matching an `Accept-Language` header.
+//! assert_eq!(catalog.negotiate("fr-CA, es-MX;q=0.8"), "es");
+//! ```
+//!
+//! The router of the module `http` (with the feature `http`) adds the
+//! descriptions of a catalog to the jobs it serves.
+
+use std::collections::HashMap;
+
+use crate::StatusType;
+
+/// A value with a stable message key, used to look up its description.
+///
+/// Implement it for the custom status values and error types of a job.
+pub trait Describe {
+    /// The message key, e.g. `"status.downloading"` or `"error.timeout"`.
+    fn message_key(&self) -> String;
+}
+
+impl Describe for String {
+    fn message_key(&self) -> String {
+        self.clone()
+    }
+}
+
+/// For jobs without status values.
+impl Describe for () {
+    fn message_key(&self) -> String {
+        "status.value".to_string()
+    }
+}
+
+impl<T: Describe> Describe for StatusType<T> {
+    fn message_key(&self) -> String {
+        match self {
+            StatusType::Started => "status.started".to_string(),
+            StatusType::StatusValue(value) => value.message_key(),
+            StatusType::Finished => "status.finished".to_string(),
+            StatusType::Failed(_) => "status.failed".to_string(),
+            StatusType::Canceled(reason) => {
+                format!("status.canceled.{}", reason.label())
+            }
+            StatusType::Interrupted => "status.interrupted".to_string(),
+            StatusType::Pending => "status.pending".to_string(),
+        }
+    }
+}
+
+/// Localized messages, indexed by locale and message key.
+#[derive(Clone, Debug, Default)]
+pub struct Catalog {
+    default_locale: String,
+    messages: HashMap<String, HashMap<String, String>>,
+}
+
+impl Catalog {
+    /// Create an empty catalog, falling back to `default_locale` for
+    /// missing translations.
+    pub fn new(default_locale: &str) -> Self {
+        Self {
+            default_locale: default_locale.to_string(),
+            messages: HashMap::new(),
+        }
+    }
+
+    /// Add a message for a locale.
+    pub fn with_message(mut self, locale: &str, key: &str, text: &str) -> Self {
+        self.insert(locale, key, text);
+        self
+    }
+
+    /// Add a message for a locale.
+    pub fn insert(&mut self, locale: &str, key: &str, text: &str) {
+        self.messages
+            .entry(locale.to_string())
+            .or_default()
+            .insert(key.to_string(), text.to_string());
+    }
+
+    /// Add all the messages of a locale from a JSON object mapping keys to
+    /// texts.
+    pub fn load_json(
+        &mut self,
+        locale: &str,
+        json: &str,
+    ) -> Result<(), std::io::Error> {
+        let messages: HashMap<String, String> = serde_json::from_str(json)?;
+        self.messages
+            .entry(locale.to_string())
+            .or_default()
+            .extend(messages);
+        Ok(())
+    }
+
+    /// Look up the description of a key.
+    ///
+    /// Tries `locale`, then the default locale.
+    pub fn message(&self, locale: &str, key: &str) -> Option<&str> {
+        [locale, self.default_locale.as_str()]
+            .iter()
+            .find_map(|l| self.messages.get(*l)?.get(key))
+            .map(String::as_str)
+    }
+
+    /// The locale of the catalog best matching the value of an
+    /// `Accept-Language` header, e.g. `"de-CH, en;q=0.5"`: the first one
+    /// by quality that the catalog has, possibly without its region, or
+    /// the default locale.
+    pub fn negotiate(&self, accept_language: &str) -> &str {
+        let mut ranges: Vec<(&str, f32)> = accept_language
+            .split(',')
+            .filter_map(|range| {
+                let mut params = range.split(';').map(str::trim);
+                let tag = params.next().filter(|tag| !tag.is_empty())?;
+                let quality = params
+                    .find_map(|param| param.strip_prefix("q="))
+                    .and_then(|q| q.parse().ok())
+                    .unwrap_or(1.0);
+                (quality > 0.0).then_some((tag, quality))
+            })
+            .collect();
+        // Stable, so equal qualities keep the order of the header.
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranges
+            .into_iter()
+            .flat_map(|(tag, _)| [Some(tag), tag.split_once('-').map(|t| t.0)])
+            .flatten()
+            .find_map(|tag| {
+                self.messages
+                    .keys()
+                    .find(|locale| locale.eq_ignore_ascii_case(tag))
+            })
+            .map_or(self.default_locale.as_str(), String::as_str)
+    }
+
+    /// Describe a value, falling back to its message key when the catalog
+    /// has no text for it.
+    pub fn describe(&self, locale: &str, value: &impl Describe) -> String {
+        let key = value.message_key();
+        self.message(locale, &key)
+            .map(str::to_string)
+            .unwrap_or(key)
+    }
+}
@@ -0,0 +1,583 @@
+//! Encryption of the jobs saved by another backend.
+//!
+//! An [`EncryptedJob`] encrypts the result, metadata, checkpoint and
+//! status values of the jobs with AES-256-GCM before handing them to the wrapped backend,
+//! which stores them as [`Sealed`] values, and decrypts them when loading.
+//! Use it for jobs carrying personal data, so a leaked job directory or
+//! database doesn't expose it.
+//!
+//! The rest of the record stays in the clear, so the backend can still
+//! list, count and recover jobs: the id, the kind of status, the reason of
+//! failed jobs, the timestamps, the worker, the idempotency key, the
+//! tenant and the queue bookkeeping (except the errors of failed
+//! attempts).  Logs and outputs ([`Job::append_log`], [`Job::append_output`])
+//! aren't encrypted either.
+//!
+//! Every value is bound to the id of its job, so sealed values moved to
+//! another job fail to decrypt.
+//!
+//! Requires the feature `encryption`.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Nonce,
+};
+use base64::Engine;
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    ids::IdGenerator,
+    queue::QueueConfig,
+    queue::{Attempt, Failure},
+    retry::Backoff,
+    secrets::SecretProvider,
+    spawn::Spawner,
+    AuditEntry, Info, Job, JobInfo, JobStatus, StatusChange, StatusType,
+};
+
+/// The length of the nonce starting every sealed value.
+const NONCE_LEN: usize = 12;
+
+/// An encrypted value, as stored by the backend wrapped in an
+/// [`EncryptedJob`].
+///
+/// Serialized as a base64 string of the nonce followed by the ciphertext.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sealed(Vec<u8>);
+
+impl Serialize for Sealed {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(
+            &base64::engine::general_purpose::STANDARD.encode(&self.0),
+        )
+    }
+}
+
+impl<'de> Deserialize<'de> for Sealed {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        base64::engine::general_purpose::STANDARD
+            .decode(text)
+            .map(Sealed)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// The job types of an [`EncryptedJob`], kept without owning values of
+/// them.
+type JobTypes<Output, Error, Metadata, Status> =
+    PhantomData<fn() -> (Output, Error, Metadata, Status)>;
+
+/// A [`Job`] encrypting the jobs it saves in another backend (see the
+/// [module documentation](self)).
+///
+/// The wrapped backend stores [`Sealed`] outputs, errors, metadata and
+/// status values; `Output`, `Error`, `Metadata` and `Status` are the
+/// types of the jobs before encryption.
+pub struct EncryptedJob<B, Output, Error, Metadata, Status> {
+    inner: B,
+    cipher: Arc<Aes256Gcm>,
+    types: JobTypes<Output, Error, Metadata, Status>,
+}
+
+impl<B: Clone, Output, Error, Metadata, Status> Clone
+    for EncryptedJob<B, Output, Error, Metadata, Status>
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            cipher: self.cipher.clone(),
+            types: PhantomData,
+        }
+    }
+}
+
+impl<B, Output, Error, Metadata, Status>
+    EncryptedJob<B, Output, Error, Metadata, Status>
+where
+    B: Job<Output = Sealed, Error = Sealed, Metadata = Sealed, Status = Sealed>,
+    Output: Serialize + DeserializeOwned,
+    Error: Serialize + DeserializeOwned,
+    Metadata: Serialize + DeserializeOwned,
+    Status: Serialize + DeserializeOwned,
+{
+    /// Wrap a backend, encrypting the jobs with the AES-256 `key`.
+    pub fn new(inner: B, key: &[u8; 32]) -> Self {
+        Self {
+            inner,
+            cipher: Arc::new(Aes256Gcm::new(key.into())),
+            types: PhantomData,
+        }
+    }
+
+    /// The wrapped backend.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Encrypt `value`, bound to the job `id`.
+    fn seal<T: Serialize>(
+        &self,
+        id: Uuid,
+        value: &T,
+    ) -> Result<Sealed, std::io::Error> {
+        let plaintext = serde_json::to_vec(value)?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &plaintext,
+                    aad: id.as_bytes(),
+                },
+            )
+            .map_err(|_| {
+                std::io::Error::other(format!("cannot encrypt job {id}"))
+            })?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        Ok(Sealed(sealed))
+    }
+
+    /// Decrypt a value of the job `id`.
+    fn open<T: DeserializeOwned>(
+        &self,
+        id: Uuid,
+        sealed: &Sealed,
+    ) -> Result<T, std::io::Error> {
+        let undecryptable = || {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("cannot decrypt job {id}: wrong key or altered record"),
+            )
+        };
+        if sealed.0.len() < NONCE_LEN {
+            return Err(undecryptable());
+        }
+        let (nonce, ciphertext) = sealed.0.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: id.as_bytes(),
+                },
+            )
+            .map_err(|_| undecryptable())?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    fn seal_status(
+        &self,
+        id: Uuid,
+        status: &StatusType<Status>,
+    ) -> Result<StatusType<Sealed>, std::io::Error> {
+        Ok(match status {
+            StatusType::Started => StatusType::Started,
+            StatusType::StatusValue(value) => {
+                StatusType::StatusValue(self.seal(id, value)?)
+            }
+            StatusType::Finished => StatusType::Finished,
+            StatusType::Failed(reason) => StatusType::Failed(reason.clone()),
+            StatusType::Canceled(reason) => {
+                StatusType::Canceled(reason.clone())
+            }
+            StatusType::Interrupted => StatusType::Interrupted,
+            StatusType::Pending => StatusType::Pending,
+        })
+    }
+
+    fn open_status(
+        &self,
+        id: Uuid,
+        status: StatusType<Sealed>,
+    ) -> Result<StatusType<Status>, std::io::Error> {
+        Ok(match status {
+            StatusType::Started => StatusType::Started,
+            StatusType::StatusValue(value) => {
+                StatusType::StatusValue(self.open(id, &value)?)
+            }
+            StatusType::Finished => StatusType::Finished,
+            StatusType::Failed(reason) => StatusType::Failed(reason),
+            StatusType::Canceled(reason) => StatusType::Canceled(reason),
+            StatusType::Interrupted => StatusType::Interrupted,
+            StatusType::Pending => StatusType::Pending,
+        })
+    }
+
+    /// The record of a job, as saved in the wrapped backend.
+    fn seal_info(
+        &self,
+        info: &JobInfo<Output, Error, Metadata, Status>,
+    ) -> Result<Info<B>, std::io::Error> {
+        let id = info.id;
+        Ok(JobInfo {
+            id,
+            status: self.seal_status(id, &info.status)?,
+            result: match &info.result {
+                Some(Ok(output)) => Some(Ok(self.seal(id, output)?)),
+                Some(Err(error)) => Some(Err(self.seal(id, error)?)),
+                None => None,
+            },
+            metadata: match &info.metadata {
+                Some(metadata) => Some(self.seal(id, metadata)?),
+                None => None,
+            },
+            created_at: info.created_at,
+            started_at: info.started_at,
+            finished_at: info.finished_at,
+            worker: info.worker.clone(),
+            origin: info.origin.clone(),
+            idempotency_key: info.idempotency_key.clone(),
+            tenant_id: info.tenant_id.clone(),
+            lease: info.lease.clone(),
+            attempts: info
+                .attempts
+                .iter()
+                .map(|attempt| {
+                    Ok(Attempt {
+                        worker: attempt.worker.clone(),
+                        started_at: attempt.started_at,
+                        ended_at: attempt.ended_at,
+                        failure: match &attempt.failure {
+                            Failure::Error(error) => {
+                                Failure::Error(self.seal(id, error)?)
+                            }
+                            Failure::Panic(message) => {
+                                Failure::Panic(message.clone())
+                            }
+                            Failure::LeaseExpired => Failure::LeaseExpired,
+                        },
+                    })
+                })
+                .collect::<Result<_, std::io::Error>>()?,
+            dead_lettered_at: info.dead_lettered_at,
+            priority: info.priority,
+            queue: info.queue.clone(),
+            concurrency_key: info.concurrency_key.clone(),
+            throttle_key: info.throttle_key.clone(),
+            resources: info.resources.clone(),
+            resubmitted_from: info.resubmitted_from,
+            retention: info.retention,
+            checkpoint: match &info.checkpoint {
+                Some(checkpoint) => {
+                    Some(serde_json::to_value(self.seal(id, checkpoint)?)?)
+                }
+                None => None,
+            },
+            batch_id: info.batch_id,
+            webhooks: info.webhooks.clone(),
+            trace_context: info.trace_context.clone(),
+            version: info.version,
+        })
+    }
+
+    /// The record of a job, as loaded from the wrapped backend.
+    fn open_info(
+        &self,
+        info: Info<B>,
+    ) -> Result<JobInfo<Output, Error, Metadata, Status>, std::io::Error> {
+        let id = info.id;
+        Ok(JobInfo {
+            id,
+            status: self.open_status(id, info.status)?,
+            result: match &info.result {
+                Some(Ok(output)) => Some(Ok(self.open(id, output)?)),
+                Some(Err(error)) => Some(Err(self.open(id, error)?)),
+                None => None,
+            },
+            metadata: match &info.metadata {
+                Some(metadata) => Some(self.open(id, metadata)?),
+                None => None,
+            },
+            created_at: info.created_at,
+            started_at: info.started_at,
+            finished_at: info.finished_at,
+            worker: info.worker,
+            origin: info.origin,
+            idempotency_key: info.idempotency_key,
+            tenant_id: info.tenant_id,
+            lease: info.lease,
+            attempts: info
+                .attempts
+                .into_iter()
+                .map(|attempt| {
+                    Ok(Attempt {
+                        worker: attempt.worker,
+                        started_at: attempt.started_at,
+                        ended_at: attempt.ended_at,
+                        failure: match attempt.failure {
+                            Failure::Error(error) => {
+                                Failure::Error(self.open(id, &error)?)
+                            }
+                            Failure::Panic(message) => Failure::Panic(message),
+                            Failure::LeaseExpired => Failure::LeaseExpired,
+                        },
+                    })
+                })
+                .collect::<Result<_, std::io::Error>>()?,
+            dead_lettered_at: info.dead_lettered_at,
+            priority: info.priority,
+            queue: info.queue,
+            concurrency_key: info.concurrency_key,
+            throttle_key: info.throttle_key,
+            resources: info.resources,
+            resubmitted_from: info.resubmitted_from,
+            retention: info.retention,
+            checkpoint: match info.checkpoint {
+                Some(checkpoint) => {
+                    Some(self.open(id, &serde_json::from_value(checkpoint)?)?)
+                }
+                None => None,
+            },
+            batch_id: info.batch_id,
+            webhooks: info.webhooks,
+            trace_context: info.trace_context,
+            version: info.version,
+        })
+    }
+}
+
+impl<B, Output, Error, Metadata, Status> Job
+    for EncryptedJob<B, Output, Error, Metadata, Status>
+where
+    B: Job<Output = Sealed, Error = Sealed, Metadata = Sealed, Status = Sealed>,
+    Output: Serialize + DeserializeOwned + Send + 'static,
+    Error: Serialize + DeserializeOwned + Clone + Send + 'static,
+    Metadata: Serialize + DeserializeOwned + Clone + Send + 'static,
+    Status: Serialize + DeserializeOwned + PartialEq + Clone + Send + 'static,
+{
+    type Output = Output;
+    type Error = Error;
+    type Metadata = Metadata;
+    type Status = Status;
+
+    fn save(&self, info: &Info<Self>) -> Result<(), std::io::Error> {
+        self.inner.save(&self.seal_info(info)?)
+    }
+
+    fn load(&self, id: Uuid) -> Result<Info<Self>, std::io::Error> {
+        self.open_info(self.inner.load(id)?)
+    }
+
+    /// Only decrypts the status value.
+    fn load_status(
+        &self,
+        id: Uuid,
+    ) -> Result<JobStatus<Status>, std::io::Error> {
+        let status = self.inner.load_status(id)?;
+        Ok(JobStatus {
+            status: self.open_status(id, status.status)?,
+            id: status.id,
+            created_at: status.created_at,
+            started_at: status.started_at,
+            finished_at: status.finished_at,
+            version: status.version,
+        })
+    }
+
+    fn load_many(
+        &self,
+        ids: &[Uuid],
+    ) -> Vec<Result<Info<Self>, std::io::Error>> {
+        self.inner
+            .load_many(ids)
+            .into_iter()
+            .map(|info| self.open_info(info?))
+            .collect()
+    }
+
+    /// Saves the jobs it could encrypt with a single `save_many`.
+    fn save_many(
+        &self,
+        infos: &[Info<Self>],
+    ) -> Vec<Result<(), std::io::Error>> {
+        let mut sealed = vec![];
+        let mut results = vec![];
+        for info in infos {
+            match self.seal_info(info) {
+                Ok(info) => {
+                    sealed.push(info);
+                    results.push(None);
+                }
+                Err(e) => results.push(Some(Err(e))),
+            }
+        }
+        let mut saved = self.inner.save_many(&sealed).into_iter();
+        results
+            .into_iter()
+            .map(|result| {
+                result.unwrap_or_else(|| {
+                    saved.next().expect("one result per sealed job")
+                })
+            })
+            .collect()
+    }
+
+    fn save_if_version(
+        &self,
+        info: &mut Info<Self>,
+        expected: u64,
+    ) -> Result<u64, std::io::Error> {
+        let mut sealed = self.seal_info(info)?;
+        info.version = self.inner.save_if_version(&mut sealed, expected)?;
+        Ok(info.version)
+    }
+
+    fn append_log(
+        &self,
+        id: Uuid,
+        line: &crate::LogLine,
+    ) -> Result<(), std::io::Error> {
+        self.inner.append_log(id, line)
+    }
+
+    fn logs(&self, id: Uuid) -> Result<Vec<crate::LogLine>, std::io::Error> {
+        self.inner.logs(id)
+    }
+
+    fn append_output(
+        &self,
+        id: Uuid,
+        item: &serde_json::Value,
+    ) -> Result<(), std::io::Error> {
+        self.inner.append_output(id, item)
+    }
+
+    fn outputs(
+        &self,
+        id: Uuid,
+        from: usize,
+    ) -> Result<Vec<serde_json::Value>, std::io::Error> {
+        self.inner.outputs(id, from)
+    }
+
+    fn ids(&self) -> Result<Vec<Uuid>, std::io::Error> {
+        self.inner.ids()
+    }
+
+    fn tenant_ids(&self, tenant: &str) -> Result<Vec<Uuid>, std::io::Error> {
+        self.inner.tenant_ids(tenant)
+    }
+
+    fn set_queue_paused(
+        &self,
+        queue: &str,
+        paused: bool,
+    ) -> Result<(), std::io::Error> {
+        self.inner.set_queue_paused(queue, paused)
+    }
+
+    fn paused_queues(&self) -> Result<Vec<String>, std::io::Error> {
+        self.inner.paused_queues()
+    }
+
+    fn acquire_lease(
+        &self,
+        name: &str,
+        lease: &crate::queue::Lease,
+    ) -> Result<bool, std::io::Error> {
+        self.inner.acquire_lease(name, lease)
+    }
+
+    fn release_lease(
+        &self,
+        name: &str,
+        token: Uuid,
+    ) -> Result<(), std::io::Error> {
+        self.inner.release_lease(name, token)
+    }
+
+    fn history(
+        &self,
+        id: Uuid,
+    ) -> Result<Vec<StatusChange<Status>>, std::io::Error> {
+        self.inner
+            .history(id)?
+            .into_iter()
+            .map(|change| {
+                Ok(StatusChange {
+                    at: change.at,
+                    status: self.open_status(id, change.status)?,
+                })
+            })
+            .collect()
+    }
+
+    fn audit(
+        &self,
+        id: Uuid,
+    ) -> Result<Vec<AuditEntry<Status>>, std::io::Error> {
+        self.inner
+            .audit(id)?
+            .into_iter()
+            .map(|entry| {
+                Ok(AuditEntry {
+                    from: match entry.from {
+                        Some(from) => Some(self.open_status(id, from)?),
+                        None => None,
+                    },
+                    to: self.open_status(id, entry.to)?,
+                    at: entry.at,
+                    actor: entry.actor,
+                    process: entry.process,
+                    version: entry.version,
+                })
+            })
+            .collect()
+    }
+
+    fn remove(&self, id: Uuid) -> Result<(), std::io::Error> {
+        self.inner.remove(id)
+    }
+
+    fn changes(
+        &self,
+        id: Uuid,
+    ) -> impl futures::Stream<Item = ()> + Send + 'static {
+        self.inner.changes(id)
+    }
+
+    /// The statuses are stored in the clear: counts from the wrapped
+    /// backend, without decrypting the jobs.
+    fn counts(
+        &self,
+    ) -> Result<std::collections::BTreeMap<&'static str, usize>, std::io::Error>
+    {
+        self.inner.counts()
+    }
+
+    fn save_backoff(&self) -> Backoff {
+        self.inner.save_backoff()
+    }
+
+    fn id_generator(&self) -> Arc<dyn IdGenerator> {
+        self.inner.id_generator()
+    }
+
+    fn spawner(&self) -> Arc<dyn Spawner> {
+        self.inner.spawner()
+    }
+
+    fn admit(&self) -> Result<(), std::io::Error> {
+        self.inner.admit()
+    }
+
+    fn secret_provider(&self) -> Arc<dyn SecretProvider> {
+        self.inner.secret_provider()
+    }
+
+    fn queue_config(&self) -> Arc<QueueConfig> {
+        self.inner.queue_config()
+    }
+}
@@ -0,0 +1,230 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Errors specific to this crate.
+///
+/// The [`Job`](crate::Job) methods return [`std::io::Error`]s; these errors
+/// are wrapped inside them, and can be recovered with [`JobError::from_io`]:
+///
+/// ```
+/// # use simple_jobs::error::JobError;
+/// # fn example(e: std::io::Error) {
+/// if let Some(JobError::IncompatibleStore { found, .. }) = JobError::from_io(&e) {
+///     eprintln!("the store was written with format {found}");
+/// }
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum JobError {
+    /// The store was written with a format newer than this release can
+    /// read (see [`format`](crate::format)).
+    IncompatibleStore {
+        /// The format of the store.
+        found: u32,
+        /// The newest format this release supports.
+        supported: u32,
+    },
+    /// A record was changed by another writer since it was read (see
+    /// [`Job::save_if_version`](crate::Job::save_if_version)).
+    VersionConflict {
+        /// The id of the job.
+        id: uuid::Uuid,
+        /// The version the writer read.
+        expected: u64,
+        /// The version found in the backend.
+        found: u64,
+    },
+    /// A status change not allowed by the life cycle of jobs (see
+    /// [`StatusType::can_transition_to`](crate::StatusType::can_transition_to)).
+    InvalidTransition {
+        /// The id of the job.
+        id: uuid::Uuid,
+        /// The label of the current status.
+        from: &'static str,
+        /// The label of the rejected status.
+        to: &'static str,
+    },
+    /// A record failed its integrity checks (e.g. it was cut short by a
+    /// crash, or its checksum doesn't match; see
+    /// [`RecordCodec::with_checksums`](crate::record::RecordCodec::with_checksums)).
+    Corrupted {
+        /// The id of the job, when known.
+        id: Option<uuid::Uuid>,
+        /// What is wrong with the record.
+        reason: String,
+    },
+    /// The lease of a job claimed from the queue expired, and the job was
+    /// claimed by another worker (see
+    /// [`Job::claim_next`](crate::Job::claim_next)).
+    LeaseLost {
+        /// The id of the job.
+        id: uuid::Uuid,
+    },
+    /// A queue reached its capacity (see
+    /// [`QueueConfig::with_capacity`](crate::QueueConfig::with_capacity)).
+    QueueFull {
+        /// The name of the queue.
+        queue: String,
+        /// The number of pending jobs the queue accepts.
+        capacity: usize,
+    },
+    /// A change was attempted through a read-only backend (see
+    /// [`FSJob::read_only`](crate::FSJob::read_only)).
+    ReadOnly,
+}
+
+impl JobError {
+    /// The [`JobError`] wrapped in an I/O error, if any.
+    pub fn from_io(error: &std::io::Error) -> Option<&JobError> {
+        error.get_ref()?.downcast_ref()
+    }
+}
+
+impl fmt::Display for JobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobError::IncompatibleStore { found, supported } => write!(
+                f,
+                "the store uses format {found}, but this release only \
+                 supports formats up to {supported}"
+            ),
+            JobError::VersionConflict {
+                id,
+                expected,
+                found,
+            } => write!(
+                f,
+                "job {id} was changed concurrently: expected version \
+                 {expected}, found {found}"
+            ),
+            JobError::InvalidTransition { id, from, to } => {
+                write!(f, "job {id} cannot go from {from} to {to}")
+            }
+            JobError::Corrupted {
+                id: Some(id),
+                reason,
+            } => {
+                write!(f, "the record of job {id} is corrupted: {reason}")
+            }
+            JobError::Corrupted { id: None, reason } => {
+                write!(f, "corrupted record: {reason}")
+            }
+            JobError::LeaseLost { id } => {
+                write!(f, "the lease of job {id} was taken by another worker")
+            }
+            JobError::QueueFull { queue, capacity } => write!(
+                f,
+                "the queue {queue} is full: it has {capacity} pending jobs"
+            ),
+            JobError::ReadOnly => write!(f, "the backend is read-only"),
+        }
+    }
+}
+
+impl std::error::Error for JobError {}
+
+impl From<JobError> for std::io::Error {
+    fn from(error: JobError) -> Self {
+        let kind = match error {
+            JobError::IncompatibleStore { .. } => {
+                std::io::ErrorKind::InvalidData
+            }
+            JobError::VersionConflict { .. } => std::io::ErrorKind::Other,
+            JobError::InvalidTransition { .. } => {
+                std::io::ErrorKind::InvalidInput
+            }
+            JobError::Corrupted { .. } => std::io::ErrorKind::InvalidData,
+            JobError::LeaseLost { .. } => std::io::ErrorKind::Other,
+            JobError::QueueFull { .. } => std::io::ErrorKind::WouldBlock,
+            JobError::ReadOnly => std::io::ErrorKind::PermissionDenied,
+        };
+        std::io::Error::new(kind, error)
+    }
+}
+
+/// A serializable snapshot of any error, for jobs failing with errors that
+/// can't be persisted themselves (e.g. `Box<dyn Error>` or, with the
+/// `anyhow` feature, `anyhow::Error`):
+///
+/// ```
+/// # use simple_jobs::{error::SerializableError, FSJob, Job};
+/// # fn example(job: FSJob<u16, SerializableError, (), ()>) -> std::io::Result<()> {
+/// job.submit(
+///     |_, _, _| async {
+///         let n: u16 = "42".parse().map_err(|e| SerializableError::new(&e))?;
+///         Ok(n)
+///     },
+///     (),
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SerializableError {
+    /// The message of the error.
+    pub message: String,
+    /// The messages of its sources, outermost first.
+    #[serde(default)]
+    pub chain: Vec<String>,
+}
+
+impl SerializableError {
+    /// Capture the message and the chain of sources of `error`.
+    pub fn new<E: std::error::Error + ?Sized>(error: &E) -> Self {
+        let mut chain = vec![];
+        let mut source = error.source();
+        while let Some(e) = source {
+            chain.push(e.to_string());
+            source = e.source();
+        }
+        Self {
+            message: error.to_string(),
+            chain,
+        }
+    }
+
+    /// An error with a message and no sources.
+    pub fn msg(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            chain: vec![],
+        }
+    }
+}
+
+impl fmt::Display for SerializableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        for cause in &self.chain {
+            write!(f, ": {cause}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SerializableError {}
+
+impl From<Box<dyn std::error::Error>> for SerializableError {
+    fn from(error: Box<dyn std::error::Error>) -> Self {
+        Self::new(&*error)
+    }
+}
+
+impl From<Box<dyn std::error::Error + Send + Sync>> for SerializableError {
+    fn from(error: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        Self::new(&*error)
+    }
+}
+
+#[cfg(feature = "anyhow")]
+impl From<anyhow::Error> for SerializableError {
+    fn from(error: anyhow::Error) -> Self {
+        let mut chain = error.chain().map(ToString::to_string);
+        Self {
+            message: chain.next().unwrap_or_default(),
+            chain: chain.collect(),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Process-wide bus of job events.
+//!
+//! Every job submitted in this process, whatever its backend, publishes
+//! [`JobEvent`]s on a single [`tokio::sync::broadcast`] channel, so metrics
+//! and loggers can observe the whole system from one subscription:
+//!
+//! ```
+//! # async fn example() {
+//! let mut events = simple_jobs::events::subscribe();
+//! while let Ok(event) = events.recv().await {
+//!     println!("{:?} for job {}", event, event.id());
+//! }
+//! # }
+//! ```
+//!
+//! Slow subscribers may miss events (see
+//! [`broadcast::error::RecvError::Lagged`]).
+//!
+//! Events serialize to JSON tagged with their [name](JobEvent::name), e.g.
+//! `{"event":"finished","id":"67e55044-10b1-426f-9247-bb680e5fe0c8"}`.
+
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::CancelReason;
+
+/// Number of events buffered for each subscriber.
+const CAPACITY: usize = 1024;
+
+/// Something that happened to a job.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum JobEvent {
+    /// The job was saved for the first time and spawned.
+    Submitted { id: Uuid },
+    /// The job was saved as pending (see [`Job::enqueue`](crate::Job::enqueue)).
+    Enqueued { id: Uuid },
+    /// The pending job was claimed by this process and spawned (see
+    /// [`Job::claim_next`](crate::Job::claim_next)).
+    Claimed { id: Uuid },
+    /// The job saved a new intermediate status.
+    StatusChanged { id: Uuid },
+    /// The job completed with `Ok`.
+    Finished { id: Uuid },
+    /// The job completed with `Err`, or panicked.
+    Failed { id: Uuid },
+    /// The job claimed from the queue failed, and was returned to the queue
+    /// for another attempt (see
+    /// [`QueueConfig::with_max_attempts`](crate::QueueConfig::with_max_attempts)).
+    Retrying { id: Uuid },
+    /// The job claimed from the queue ran out of attempts (see
+    /// [`Job::dead_letters`](crate::Job::dead_letters)).
+    DeadLettered { id: Uuid },
+    /// The job was canceled.
+    Canceled { id: Uuid, reason: CancelReason },
+    /// The job was interrupted by the shutdown of its process.
+    Interrupted { id: Uuid },
+    /// The final state of the job could not be saved, even after retrying.
+    SaveFailed { id: Uuid, error: String },
+    /// The record of the job could not be loaded while scanning the
+    /// backend (see [`Job::scan`](crate::Job::scan)).
+    Unreadable { id: Uuid, error: String },
+}
+
+impl JobEvent {
+    /// The id of the job the event is about.
+    pub fn id(&self) -> Uuid {
+        match self {
+            JobEvent::Submitted { id }
+            | JobEvent::Enqueued { id }
+            | JobEvent::Claimed { id }
+            | JobEvent::StatusChanged { id }
+            | JobEvent::Finished { id }
+            | JobEvent::Failed { id }
+            | JobEvent::Retrying { id }
+            | JobEvent::DeadLettered { id }
+            | JobEvent::Canceled { id, .. }
+            | JobEvent::Interrupted { id }
+            | JobEvent::SaveFailed { id, .. }
+            | JobEvent::Unreadable { id, .. } => *id,
+        }
+    }
+
+    /// The name of the kind of event, e.g. `"dead_lettered"`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobEvent::Submitted { .. } => "submitted",
+            JobEvent::Enqueued { .. } => "enqueued",
+            JobEvent::Claimed { .. } => "claimed",
+            JobEvent::StatusChanged { .. } => "status_changed",
+            JobEvent::Finished { .. } => "finished",
+            JobEvent::Failed { .. } => "failed",
+            JobEvent::Retrying { .. } => "retrying",
+            JobEvent::DeadLettered { .. } => "dead_lettered",
+            JobEvent::Canceled { .. } => "canceled",
+            JobEvent::Interrupted { .. } => "interrupted",
+            JobEvent::SaveFailed { .. } => "save_failed",
+            JobEvent::Unreadable { .. } => "unreadable",
+        }
+    }
+}
+
+fn bus() -> &'static broadcast::Sender<JobEvent> {
+    static BUS: OnceLock<broadcast::Sender<JobEvent>> = OnceLock::new();
+    BUS.get_or_init(|| broadcast::channel(CAPACITY).0)
+}
+
+/// Subscribe to the events of all the jobs in this process.
+pub fn subscribe() -> broadcast::Receiver<JobEvent> {
+    bus().subscribe()
+}
+
+/// Publish an event to all current subscribers.
+pub fn publish(event: JobEvent) {
+    // An error only means that nobody is listening.
+    let _ = bus().send(event);
+}
@@ -0,0 +1,357 @@
+use futures::Stream;
+use uuid::Uuid;
+
+use crate::{AuditEntry, Info, Job, JobInfo, LogLine, StatusChange};
+
+/// The result of a read through a [`FailoverJob`].
+#[derive(Clone, Debug)]
+pub struct ReplicaRead<T> {
+    /// The job information that was read.
+    pub info: T,
+    /// `true` when the primary could not be reached and the information
+    /// comes from the secondary, which may lag behind.
+    pub stale: bool,
+}
+
+/// How a [`FailoverJob`] picks between the records of its two backends.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Resolution {
+    /// The primary is the source of truth: the secondary is only read for
+    /// finished jobs, or when the primary is down.
+    #[default]
+    PrimaryWins,
+    /// For stores written from several regions, where both backends may
+    /// hold a newer record: both are read, and the winner of
+    /// [`latest_terminal_wins`] is returned.  Mirrored writes never replace
+    /// a secondary record that wins over them.
+    LatestTerminalWins,
+}
+
+/// Pick the winner between two records of the same job.
+///
+/// A terminal record wins over a running one (a job that finished in one
+/// region is finished everywhere); then the most recently finished, then
+/// the most recently started; the remaining ties go to the greatest
+/// [`origin`](crate::JobInfo::origin), so every region picks the same
+/// record.  `a` wins records that are equal in all these respects.
+pub fn latest_terminal_wins<'a, O, E, M, S>(
+    a: &'a JobInfo<O, E, M, S>,
+    b: &'a JobInfo<O, E, M, S>,
+) -> &'a JobInfo<O, E, M, S> {
+    let key = |info: &'a JobInfo<O, E, M, S>| {
+        (
+            info.status.is_terminal(),
+            info.finished_at,
+            info.started_at,
+            info.origin.as_deref(),
+        )
+    };
+    if key(b) > key(a) {
+        b
+    } else {
+        a
+    }
+}
+
+/// A [`Job`] combining a primary backend with a read replica.
+///
+/// Writes always go to the primary (and, optionally, are mirrored to the
+/// secondary).  Reads of finished jobs are served by the secondary, since a
+/// finished job never changes again; reads of running jobs go to the primary
+/// and fail over to the secondary when the primary is down, in which case the
+/// read is marked as stale (see [`FailoverJob::load_marked`]).
+///
+/// When both backends are written to, e.g. by workers in different regions
+/// of a replicated deployment, use
+/// [`Resolution::LatestTerminalWins`] to resolve conflicting records.
+#[derive(Clone)]
+pub struct FailoverJob<Primary, Secondary> {
+    primary: Primary,
+    secondary: Secondary,
+    mirror_writes: bool,
+    resolution: Resolution,
+}
+
+impl<Primary, Secondary> FailoverJob<Primary, Secondary>
+where
+    Primary: Job,
+    Secondary: Job<
+        Output = Primary::Output,
+        Error = Primary::Error,
+        Metadata = Primary::Metadata,
+        Status = Primary::Status,
+    >,
+{
+    /// Create a new [`FailoverJob`].
+    ///
+    /// The secondary is expected to be replicated by other means (e.g. a
+    /// database read replica); use [`FailoverJob::with_mirror_writes`] to
+    /// have this wrapper copy every write to it.
+    pub fn new(primary: Primary, secondary: Secondary) -> Self {
+        Self {
+            primary,
+            secondary,
+            mirror_writes: false,
+            resolution: Resolution::default(),
+        }
+    }
+
+    /// Also save every write to the secondary.
+    ///
+    /// Failures writing to the secondary are ignored: the primary is the
+    /// source of truth.
+    pub fn with_mirror_writes(mut self, mirror_writes: bool) -> Self {
+        self.mirror_writes = mirror_writes;
+        self
+    }
+
+    /// Change how conflicting records are resolved.
+    pub fn with_resolution(mut self, resolution: Resolution) -> Self {
+        self.resolution = resolution;
+        self
+    }
+
+    /// The primary backend.
+    pub fn primary(&self) -> &Primary {
+        &self.primary
+    }
+
+    /// The secondary backend.
+    pub fn secondary(&self) -> &Secondary {
+        &self.secondary
+    }
+
+    /// Load a job, reporting whether the information may be stale.
+    pub fn load_marked(
+        &self,
+        id: Uuid,
+    ) -> Result<ReplicaRead<Info<Self>>, std::io::Error> {
+        if self.resolution == Resolution::LatestTerminalWins {
+            return self.load_resolved(id);
+        }
+        if let Ok(info) = self.secondary.load(id) {
+            if info.status.is_terminal() {
+                return Ok(ReplicaRead { info, stale: false });
+            }
+        }
+        match self.primary.load(id) {
+            Ok(info) => Ok(ReplicaRead { info, stale: false }),
+            Err(e) => self
+                .secondary
+                .load(id)
+                .map(|info| ReplicaRead { info, stale: true })
+                .map_err(|_| e),
+        }
+    }
+
+    fn load_resolved(
+        &self,
+        id: Uuid,
+    ) -> Result<ReplicaRead<Info<Self>>, std::io::Error> {
+        match (self.primary.load(id), self.secondary.load(id)) {
+            (Ok(primary), Ok(secondary)) => {
+                let primary_wins = std::ptr::eq(
+                    latest_terminal_wins(&primary, &secondary),
+                    &primary,
+                );
+                let info = if primary_wins { primary } else { secondary };
+                Ok(ReplicaRead { info, stale: false })
+            }
+            (Ok(info), Err(_)) => Ok(ReplicaRead { info, stale: false }),
+            (Err(_), Ok(info)) => Ok(ReplicaRead { info, stale: true }),
+            (Err(e), Err(_)) => Err(e),
+        }
+    }
+
+    /// Copy a write to the secondary, unless the secondary has a record
+    /// winning over it.
+    fn mirror(&self, info: &Info<Self>) {
+        if !self.mirror_writes {
+            return;
+        }
+        if self.resolution == Resolution::LatestTerminalWins {
+            if let Ok(secondary) = self.secondary.load(info.id) {
+                if !std::ptr::eq(latest_terminal_wins(info, &secondary), info) {
+                    return;
+                }
+            }
+        }
+        let _ = self.secondary.save(info);
+    }
+}
+
+impl<Primary, Secondary> Job for FailoverJob<Primary, Secondary>
+where
+    Primary: Job,
+    Secondary: Job<
+        Output = Primary::Output,
+        Error = Primary::Error,
+        Metadata = Primary::Metadata,
+        Status = Primary::Status,
+    >,
+{
+    type Output = Primary::Output;
+    type Error = Primary::Error;
+    type Metadata = Primary::Metadata;
+    type Status = Primary::Status;
+
+    fn save(&self, info: &Info<Self>) -> Result<(), std::io::Error> {
+        self.primary.save(info)?;
+        self.mirror(info);
+        Ok(())
+    }
+
+    fn load(&self, id: Uuid) -> Result<Info<Self>, std::io::Error> {
+        self.load_marked(id).map(|read| read.info)
+    }
+
+    /// Compares with, and writes to, the primary, mirroring the result if
+    /// enabled.
+    fn save_if_version(
+        &self,
+        info: &mut Info<Self>,
+        expected: u64,
+    ) -> Result<u64, std::io::Error> {
+        let version = self.primary.save_if_version(info, expected)?;
+        self.mirror(info);
+        Ok(version)
+    }
+
+    /// Updates the primary, mirroring the result if enabled.
+    fn update<F>(&self, id: Uuid, f: F) -> Result<Info<Self>, std::io::Error>
+    where
+        F: FnMut(&mut Info<Self>) -> Result<(), std::io::Error>,
+    {
+        let info = self.primary.update(id, f)?;
+        self.mirror(&info);
+        Ok(info)
+    }
+
+    fn append_log(
+        &self,
+        id: Uuid,
+        line: &LogLine,
+    ) -> Result<(), std::io::Error> {
+        self.primary.append_log(id, line)?;
+        if self.mirror_writes {
+            let _ = self.secondary.append_log(id, line);
+        }
+        Ok(())
+    }
+
+    /// Reads the primary, falling back to the secondary if it is down.
+    fn logs(&self, id: Uuid) -> Result<Vec<LogLine>, std::io::Error> {
+        self.primary
+            .logs(id)
+            .or_else(|e| self.secondary.logs(id).map_err(|_| e))
+    }
+
+    fn append_output(
+        &self,
+        id: Uuid,
+        item: &serde_json::Value,
+    ) -> Result<(), std::io::Error> {
+        self.primary.append_output(id, item)?;
+        if self.mirror_writes {
+            let _ = self.secondary.append_output(id, item);
+        }
+        Ok(())
+    }
+
+    /// Reads the primary, falling back to the secondary if it is down.
+    fn outputs(
+        &self,
+        id: Uuid,
+        from: usize,
+    ) -> Result<Vec<serde_json::Value>, std::io::Error> {
+        self.primary
+            .outputs(id, from)
+            .or_else(|e| self.secondary.outputs(id, from).map_err(|_| e))
+    }
+
+    /// Lists the primary, falling back to the secondary if it is down.
+    fn ids(&self) -> Result<Vec<Uuid>, std::io::Error> {
+        self.primary
+            .ids()
+            .or_else(|e| self.secondary.ids().map_err(|_| e))
+    }
+
+    fn tenant_ids(&self, tenant: &str) -> Result<Vec<Uuid>, std::io::Error> {
+        self.primary
+            .tenant_ids(tenant)
+            .or_else(|e| self.secondary.tenant_ids(tenant).map_err(|_| e))
+    }
+
+    /// Pauses or resumes the queue in the primary, and in the secondary if
+    /// writes are mirrored.
+    fn set_queue_paused(
+        &self,
+        queue: &str,
+        paused: bool,
+    ) -> Result<(), std::io::Error> {
+        self.primary.set_queue_paused(queue, paused)?;
+        if self.mirror_writes {
+            let _ = self.secondary.set_queue_paused(queue, paused);
+        }
+        Ok(())
+    }
+
+    fn paused_queues(&self) -> Result<Vec<String>, std::io::Error> {
+        self.primary
+            .paused_queues()
+            .or_else(|e| self.secondary.paused_queues().map_err(|_| e))
+    }
+
+    /// Held in the primary only: taking it in the secondary while the
+    /// primary is down would let two processes hold it.
+    fn acquire_lease(
+        &self,
+        name: &str,
+        lease: &crate::queue::Lease,
+    ) -> Result<bool, std::io::Error> {
+        self.primary.acquire_lease(name, lease)
+    }
+
+    fn release_lease(
+        &self,
+        name: &str,
+        token: Uuid,
+    ) -> Result<(), std::io::Error> {
+        self.primary.release_lease(name, token)
+    }
+
+    /// Reads the primary, falling back to the secondary if it is down.
+    fn history(
+        &self,
+        id: Uuid,
+    ) -> Result<Vec<StatusChange<Self::Status>>, std::io::Error> {
+        self.primary
+            .history(id)
+            .or_else(|e| self.secondary.history(id).map_err(|_| e))
+    }
+
+    /// Reads the primary, falling back to the secondary if it is down.
+    fn audit(
+        &self,
+        id: Uuid,
+    ) -> Result<Vec<AuditEntry<Self::Status>>, std::io::Error> {
+        self.primary
+            .audit(id)
+            .or_else(|e| self.secondary.audit(id).map_err(|_| e))
+    }
+
+    /// The changes seen by the primary, where jobs are written.
+    fn changes(&self, id: Uuid) -> impl Stream<Item = ()> + Send + 'static {
+        self.primary.changes(id)
+    }
+
+    /// Removes from the primary, and from the secondary if writes are
+    /// mirrored.
+    fn remove(&self, id: Uuid) -> Result<(), std::io::Error> {
+        self.primary.remove(id)?;
+        if self.mirror_writes {
+            let _ = self.secondary.remove(id);
+        }
+        Ok(())
+    }
+}
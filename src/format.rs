@@ -0,0 +1,84 @@
+//! Versions of the format used to persist jobs.
+//!
+//! Every change to the persisted records that an older release cannot read
+//! (new status variants, new encodings...) bumps the store format.  Backends
+//! record the format of the store (e.g. [`FSJob`](crate::FSJob) keeps it in
+//! a marker file), and refuse to touch a store newer than [`CURRENT`] with a
+//! [`JobError::IncompatibleStore`], instead of mangling its records.
+//!
+//! Before rolling back to an older release, check with [`FORMATS`] that it
+//! can still read the format of the store:
+//!
+//! ```
+//! # use simple_jobs::format;
+//! // The store was written with format 2; can release 0.2.2 read it?
+//! assert!(!format::readable_by(2, "0.2.2"));
+//! assert!(format::readable_by(1, "0.2.2"));
+//! // This release reads the format it writes.
+//! let release = env!("CARGO_PKG_VERSION");
+//! assert!(format::readable_by(format::CURRENT, release));
+//! ```
+
+use crate::error::JobError;
+
+/// The format written by this release.
+pub const CURRENT: u32 = 2;
+
+/// The format of stores that don't record one (written before formats were
+/// tracked).
+pub const LEGACY: u32 = 1;
+
+/// A store format, and the first release able to read it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StoreFormat {
+    /// The format version.
+    pub version: u32,
+    /// The first release of the crate reading and writing it.
+    pub since: &'static str,
+    /// What changed from the previous version.
+    pub changes: &'static str,
+}
+
+/// The compatibility table: every format, oldest first.
+pub const FORMATS: &[StoreFormat] = &[
+    StoreFormat {
+        version: 1,
+        since: "0.1.0",
+        changes: "JSON records with id, status, result and metadata",
+    },
+    StoreFormat {
+        version: 2,
+        since: "0.3.0",
+        changes: "timestamps, Failed, Canceled and Pending statuses, format \
+                  marker, record headers for compressed, checksummed or \
+                  non-JSON records, schema envelopes, templated file names, \
+                  leases, attempts and dead letters, priorities, queues, \
+                  concurrency and throttle keys, batches, resources, \
+                  checkpoints, resubmissions, retentions, \
+                  blob pointers, trace contexts, webhooks",
+    },
+];
+
+/// Whether the release `crate_version` (e.g. `"0.2.2"`) can read a store
+/// with the given format.
+pub fn readable_by(format: u32, crate_version: &str) -> bool {
+    FORMATS
+        .iter()
+        .find(|f| f.version == format)
+        .is_some_and(|f| parse(crate_version) >= parse(f.since))
+}
+
+fn parse(version: &str) -> Vec<u64> {
+    version.split('.').map(|n| n.parse().unwrap_or(0)).collect()
+}
+
+/// Fail if a store with the given format can't be used by this release.
+pub fn check(found: u32) -> Result<(), JobError> {
+    if found > CURRENT {
+        return Err(JobError::IncompatibleStore {
+            found,
+            supported: CURRENT,
+        });
+    }
+    Ok(())
+}
@@ -1,28 +1,261 @@
 use std::{
-    fs::File,
-    io::{Read, Write},
+    collections::BTreeMap,
     marker::PhantomData,
-    path::PathBuf,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
+use chrono::Utc;
 use serde::{de::DeserializeOwned, Serialize};
 use uuid::Uuid;
 
-use crate::{Info, Job, JobInfo};
+use crate::{
+    audit::{Actor, AuditEntry},
+    blobs::{BlobStore, Offload},
+    error::JobError,
+    format,
+    index::{self, IndexEntry},
+    local,
+    naming::FileNaming,
+    queue::Lease,
+    record::{Compression, Encoding, RecordCodec},
+    versioning::Schema,
+    worker, Info, Job, JobInfo, JobStatus, LogLine, StatusChange, StatusType,
+};
+
+/// Name of the file recording the store format of a job directory.
+const FORMAT_FILE: &str = ".format";
+
+/// Name of the index file of a job directory (see [`crate::index`]).
+const INDEX_FILE: &str = ".index";
+
+/// Name of the directory holding a file per paused queue (see
+/// [`Job::pause_queue`]).
+const PAUSED_DIR: &str = ".paused";
+
+/// Name of the directory holding a file per lease (see
+/// [`Job::acquire_lease`]).
+const LEASES_DIR: &str = ".leases";
+
+/// A builder for an [`FSJob`] with several options, created with
+/// [`FSJob::builder`].
+///
+/// Each option is described by the [`FSJob`] method of the same name
+/// prefixed with `with_`; [`FSJobBuilder::build`] checks the
+/// configuration and the job directory at once.
+///
+/// ```
+/// # use simple_jobs::fs_job::{Durability, FSJob};
+/// # fn example() -> std::io::Result<()> {
+/// let job: FSJob<u16, String, (), String> = FSJob::builder("/tmp/jobs".into())
+///     .locking(true)
+///     .subdirectories(true)
+///     .durability(Durability::Fsync)
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct FSJobBuilder<Output, Error, Metadata, Status> {
+    job: FSJob<Output, Error, Metadata, Status>,
+}
+
+impl<Output, Error, Metadata, Status>
+    FSJobBuilder<Output, Error, Metadata, Status>
+{
+    /// See [`FSJob::with_compression`].
+    pub fn compression<C>(self, compression: C) -> Self
+    where
+        C: Compression + 'static,
+    {
+        self.map(|job| job.with_compression(compression))
+    }
+
+    /// See [`FSJob::with_readable_compression`].
+    pub fn readable_compression<C>(self, compression: C) -> Self
+    where
+        C: Compression + 'static,
+    {
+        self.map(|job| job.with_readable_compression(compression))
+    }
+
+    /// See [`FSJob::with_encoding`].
+    pub fn encoding(self, encoding: Encoding) -> Self {
+        self.map(|job| job.with_encoding(encoding))
+    }
+
+    /// See [`FSJob::with_checksums`].
+    pub fn checksums(self, checksums: bool) -> Self {
+        self.map(|job| job.with_checksums(checksums))
+    }
+
+    /// See [`FSJob::with_quarantine`].
+    pub fn quarantine(self, directory: PathBuf) -> Self {
+        self.map(|job| job.with_quarantine(directory))
+    }
+
+    /// See [`FSJob::with_schema`].
+    pub fn schema(self, schema: Schema) -> Self {
+        self.map(|job| job.with_schema(schema))
+    }
+
+    /// See [`FSJob::with_durability`].
+    pub fn durability(self, durability: Durability) -> Self {
+        self.map(|job| job.with_durability(durability))
+    }
+
+    /// See [`FSJob::with_locking`].
+    pub fn locking(self, locking: bool) -> Self {
+        self.map(|job| job.with_locking(locking))
+    }
+
+    /// See [`FSJob::with_subdirectories`].
+    pub fn subdirectories(self, subdirectories: bool) -> Self {
+        self.map(|job| job.with_subdirectories(subdirectories))
+    }
+
+    /// See [`FSJob::with_naming`].
+    pub fn naming(self, naming: FileNaming) -> Self {
+        self.map(|job| job.with_naming(naming))
+    }
+
+    /// See [`FSJob::with_index`].
+    pub fn index(self, index: bool) -> Self {
+        self.map(|job| job.with_index(index))
+    }
+
+    /// See [`FSJob::with_namespace`].
+    pub fn namespace(self, namespace: &str) -> Self {
+        self.map(|job| job.with_namespace(namespace))
+    }
+
+    /// See [`FSJob::with_read_only`].
+    pub fn read_only(self, read_only: bool) -> Self {
+        self.map(|job| job.with_read_only(read_only))
+    }
+
+    /// See [`FSJob::with_history`].
+    pub fn history(self, history: bool) -> Self {
+        self.map(|job| job.with_history(history))
+    }
+
+    /// See [`FSJob::with_audit`].
+    pub fn audit(self, audit: bool) -> Self {
+        self.map(|job| job.with_audit(audit))
+    }
+
+    /// See [`FSJob::with_blobs`].
+    pub fn blobs<B>(self, store: B, threshold: usize) -> Self
+    where
+        B: BlobStore + 'static,
+    {
+        self.map(|job| job.with_blobs(store, threshold))
+    }
+
+    fn map<F>(self, f: F) -> Self
+    where
+        F: FnOnce(
+            FSJob<Output, Error, Metadata, Status>,
+        ) -> FSJob<Output, Error, Metadata, Status>,
+    {
+        Self { job: f(self.job) }
+    }
+
+    /// Build the [`FSJob`], creating its directory if needed.
+    ///
+    /// Fails if the options don't go together (e.g. a [`Schema`] with
+    /// migrations and an encoding they can't be run on), or if the
+    /// directory can't be used (see [`FSJob::init`]).
+    pub fn build(
+        self,
+    ) -> Result<FSJob<Output, Error, Metadata, Status>, std::io::Error> {
+        let encoding = self.job.codec.encoding();
+        if !encoding.is_self_describing() {
+            self.job.check_unversioned(encoding)?;
+        }
+        self.job.checked()
+    }
+}
+
+/// How far [`FSJob`] writes job files to disk before a save returns.
+///
+/// Each level includes the previous ones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Durability {
+    /// Hand the file to the operating system, which writes it to disk
+    /// later: a crash of the machine may lose recent saves.
+    #[default]
+    None,
+    /// Flush the contents of the file to disk (`fdatasync`).
+    Flush,
+    /// Flush the contents and metadata of the file to disk (`fsync`).
+    Fsync,
+    /// Also flush the directory, so the file replacing the previous version
+    /// survives a crash.
+    FsyncDir,
+}
 
 /// A basic implementation of the trait [`Job`].
 ///
 /// This implementation saves the job metadata [`JobInfo`] in a file, using
 /// the job id to make the file unique.
-#[derive(Clone)]
+///
+/// The [store format](crate::format) is recorded in a `.format` file in the
+/// directory; directories written by a newer release are rejected.
 pub struct FSJob<Output, Error, Metadata, Status> {
     job_directory: PathBuf,
+    format_checked: Arc<AtomicBool>,
+    codec: RecordCodec,
+    schema: Schema,
+    durability: Durability,
+    locking: bool,
+    subdirectories: bool,
+    index: bool,
+    history: bool,
+    audit: bool,
+    quarantine: Option<PathBuf>,
+    naming: FileNaming,
+    read_only: bool,
+    namespaced: bool,
+    blobs: Option<Offload>,
     output_type: PhantomData<Output>,
     error_type: PhantomData<Error>,
     metadata_type: PhantomData<Metadata>,
     status_type: PhantomData<Status>,
 }
 
+// Not derived, so the type parameters (e.g. an output that can't be cloned)
+// don't need to be `Clone`.
+impl<Output, Error, Metadata, Status> Clone
+    for FSJob<Output, Error, Metadata, Status>
+{
+    fn clone(&self) -> Self {
+        Self {
+            job_directory: self.job_directory.clone(),
+            format_checked: self.format_checked.clone(),
+            codec: self.codec.clone(),
+            schema: self.schema.clone(),
+            durability: self.durability,
+            locking: self.locking,
+            subdirectories: self.subdirectories,
+            index: self.index,
+            history: self.history,
+            audit: self.audit,
+            quarantine: self.quarantine.clone(),
+            naming: self.naming.clone(),
+            read_only: self.read_only,
+            namespaced: self.namespaced,
+            blobs: self.blobs.clone(),
+            output_type: PhantomData,
+            error_type: PhantomData,
+            metadata_type: PhantomData,
+            status_type: PhantomData,
+        }
+    }
+}
+
 impl<Output, Error, Metadata, Status> FSJob<Output, Error, Metadata, Status> {
     /// Create a new [`FSJob`].
     ///
@@ -30,16 +263,827 @@ impl<Output, Error, Metadata, Status> FSJob<Output, Error, Metadata, Status> {
     pub fn new(job_directory: PathBuf) -> Self {
         Self {
             job_directory,
+            format_checked: Arc::new(AtomicBool::new(false)),
+            codec: RecordCodec::default(),
+            schema: Schema::default(),
+            durability: Durability::None,
+            locking: false,
+            subdirectories: false,
+            index: false,
+            history: false,
+            audit: false,
+            quarantine: None,
+            naming: FileNaming::default(),
+            read_only: false,
+            namespaced: false,
+            blobs: None,
             output_type: PhantomData,
             error_type: PhantomData,
             metadata_type: PhantomData,
             status_type: PhantomData,
         }
     }
+
+    /// Create a new [`FSJob`], creating its directory if needed and checking
+    /// up front that it can be used.
+    ///
+    /// Unlike [`FSJob::new`], whose errors only show up when jobs are saved
+    /// (possibly inside their tasks), this fails right away if the directory
+    /// can't be created or written to, or holds a store written by a newer
+    /// release.  The errors name the directory.
+    pub fn init(job_directory: PathBuf) -> Result<Self, std::io::Error> {
+        Self::new(job_directory).checked()
+    }
+
+    /// Create a new [`FSJob`] that only reads the jobs in `job_directory`,
+    /// e.g. for dashboards or reports that must never change them.
+    ///
+    /// Everything changing the directory (saving, submitting, cancelling,
+    /// logging, removing jobs...) fails with [`JobError::ReadOnly`], and
+    /// nothing is ever written to it: corrupted files aren't quarantined
+    /// (see [`FSJob::with_quarantine`]), and an older store format isn't
+    /// upgraded.
+    pub fn read_only(job_directory: PathBuf) -> Self {
+        Self::new(job_directory).with_read_only(true)
+    }
+
+    /// Only read jobs, like [`FSJob::read_only`].
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Keep the jobs in the subdirectory `namespace` of the job directory,
+    /// so several applications or environments (e.g. staging and
+    /// production) can share a directory without seeing each other's jobs.
+    ///
+    /// # Panics
+    ///
+    /// If `namespace` isn't a plain file name, or is two hexadecimal digits
+    /// (which would be taken for the subdirectories of
+    /// [`FSJob::with_subdirectories`]).
+    pub fn with_namespace(mut self, namespace: &str) -> Self {
+        let plain = !namespace.is_empty()
+            && !namespace.starts_with('.')
+            && !namespace.contains(['/', '\\']);
+        let hex = namespace.len() == 2
+            && namespace.chars().all(|c| c.is_ascii_hexdigit());
+        assert!(plain && !hex, "invalid namespace {namespace:?}");
+        self.job_directory = self.job_directory.join(namespace);
+        self.namespaced = true;
+        self
+    }
+
+    /// Fail with [`JobError::ReadOnly`] if the job is read-only.
+    fn check_writable(&self) -> Result<(), std::io::Error> {
+        if self.read_only {
+            return Err(JobError::ReadOnly.into());
+        }
+        Ok(())
+    }
+
+    /// Configure a new [`FSJob`] with several options (see
+    /// [`FSJobBuilder`]).
+    pub fn builder(
+        job_directory: PathBuf,
+    ) -> FSJobBuilder<Output, Error, Metadata, Status> {
+        FSJobBuilder {
+            job: Self::new(job_directory),
+        }
+    }
+
+    /// This job, once its directory is checked like by [`FSJob::init`].
+    fn checked(self) -> Result<Self, std::io::Error> {
+        self.check_directory().map_err(|e| {
+            std::io::Error::new(
+                e.kind(),
+                format!(
+                    "cannot use the job directory {}: {e}",
+                    self.job_directory.display()
+                ),
+            )
+        })?;
+        Ok(self)
+    }
+
+    fn check_directory(&self) -> Result<(), std::io::Error> {
+        if self.read_only {
+            if !std::fs::metadata(&self.job_directory)?.is_dir() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::NotADirectory,
+                    "not a directory",
+                ));
+            }
+            return self.check_format(false);
+        }
+        std::fs::create_dir_all(&self.job_directory)?;
+        if !self.job_directory.is_dir() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotADirectory,
+                "not a directory",
+            ));
+        }
+        let probe = self
+            .job_directory
+            .join(format!(".{}.probe", Uuid::new_v4()));
+        std::fs::write(&probe, b"")?;
+        std::fs::remove_file(&probe)?;
+        self.check_format(true)
+    }
+
+    /// Compress the job files written from now on.
+    ///
+    /// Every file records how it was written, so files written before (with
+    /// no compression, or with a compression configured with
+    /// [`FSJob::with_readable_compression`]) can still be loaded.
+    pub fn with_compression<C>(mut self, compression: C) -> Self
+    where
+        C: Compression + 'static,
+    {
+        self.codec = self.codec.with_compression(compression);
+        self
+    }
+
+    /// Save job files in an envelope with the version of `schema`, and
+    /// upgrade the files written with older versions when loading them.
+    pub fn with_schema(mut self, schema: Schema) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Flush job files to disk (`fsync`) before they replace the previous
+    /// version, so a crash can't leave a job file empty.
+    ///
+    /// The same as [`FSJob::with_durability`] with [`Durability::Fsync`]
+    /// (or [`Durability::None`]).
+    pub fn with_sync(self, sync: bool) -> Self {
+        self.with_durability(if sync {
+            Durability::Fsync
+        } else {
+            Durability::None
+        })
+    }
+
+    /// How far job files are written to disk before a save returns (see
+    /// [`Durability`]).
+    ///
+    /// Job files are always replaced atomically (written to a temporary file
+    /// renamed into place); without syncing, a crash shortly after a save
+    /// may still lose it.
+    pub fn with_durability(mut self, durability: Durability) -> Self {
+        self.durability = durability;
+        self
+    }
+
+    /// Take advisory file locks around saves and loads, so several
+    /// processes can share the job directory.
+    ///
+    /// Every job gets a lock file `.<id>.lock`: saves hold it exclusively
+    /// and loads shared, and a [`Job::save_if_version`] holds it across its
+    /// read and write, so concurrent updates from different processes are
+    /// detected as conflicts instead of overwriting each other.
+    pub fn with_locking(mut self, locking: bool) -> Self {
+        self.locking = locking;
+        self
+    }
+
+    /// Spread the files of the jobs over subdirectories named after the
+    /// first bytes of their ids (`ab/cd/abcd...`), for directories holding
+    /// too many jobs to list quickly.
+    ///
+    /// Jobs are found in either layout, so the option can be turned on (or
+    /// off) for an existing directory; each job file moves to the
+    /// configured layout when it is next saved.
+    pub fn with_subdirectories(mut self, subdirectories: bool) -> Self {
+        self.subdirectories = subdirectories;
+        self
+    }
+
+    /// Name the job files after `naming` (see [`crate::naming`]), e.g. with
+    /// an extension and the status of the jobs.
+    ///
+    /// Finding a job whose file name includes its date or status lists the
+    /// directory of the job: combine it with
+    /// [`FSJob::with_subdirectories`] for directories holding many jobs.
+    pub fn with_naming(mut self, naming: FileNaming) -> Self {
+        self.naming = naming;
+        self
+    }
+
+    /// Keep an index of the status of the jobs (see [`crate::index`]),
+    /// used by [`Job::counts`] and [`FSJob::index`].
+    pub fn with_index(mut self, index: bool) -> Self {
+        self.index = index;
+        self
+    }
+
+    /// The current entries of the index (see [`FSJob::with_index`]).
+    pub fn index(&self) -> Result<Vec<IndexEntry>, std::io::Error> {
+        match std::fs::read_to_string(self.job_directory.join(INDEX_FILE)) {
+            Ok(text) => Ok(index::fold(&text)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Append a line to the index, if enabled.
+    fn append_index<T: Serialize>(
+        &self,
+        line: &T,
+    ) -> Result<(), std::io::Error> {
+        if !self.index {
+            return Ok(());
+        }
+        append_json_line(&self.job_directory.join(INDEX_FILE), line)
+    }
+
+    /// Record every status a job is saved with in the file `<id>.history`,
+    /// for [`Job::history`].
+    pub fn with_history(mut self, history: bool) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// Append an entry for every save of a job to the file `<id>.audit`,
+    /// for [`Job::audit`].
+    pub fn with_audit(mut self, audit: bool) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    /// Write job files with the given encoding (JSON by default).
+    ///
+    /// Every file records how it was written, so files written before with
+    /// other encodings can still be loaded.
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.codec = self.codec.with_encoding(encoding);
+        self
+    }
+
+    /// Write job files with a checksum, so damaged files are reported as
+    /// [`JobError::Corrupted`] when loaded (see
+    /// [`RecordCodec::with_checksums`]).
+    ///
+    /// Without checksums, job files that are cut short or aren't valid JSON
+    /// are still reported as corrupted.
+    pub fn with_checksums(mut self, checksums: bool) -> Self {
+        self.codec = self.codec.with_checksums(checksums);
+        self
+    }
+
+    /// Write outputs and metadata larger than `threshold` bytes (as JSON)
+    /// to `store` instead of the job files, which point to them (see
+    /// [`blobs`](crate::blobs)).
+    ///
+    /// Job files pointing to blobs can only be loaded with a store holding
+    /// them.
+    pub fn with_blobs<B>(mut self, store: B, threshold: usize) -> Self
+    where
+        B: BlobStore + 'static,
+    {
+        self.blobs = Some(Offload::new(Arc::new(store), threshold));
+        self
+    }
+
+    /// Move corrupted job files into `directory` when loading them, so they
+    /// can be inspected and the job is no longer found.
+    ///
+    /// The load still fails with [`JobError::Corrupted`]; if the file can't
+    /// be moved it is left in place.
+    pub fn with_quarantine(mut self, directory: PathBuf) -> Self {
+        self.quarantine = Some(directory);
+        self
+    }
+
+    /// Move the job file at `path` into the quarantine directory, if `error`
+    /// reports it as corrupted.
+    fn quarantine(&self, path: &Path, error: std::io::Error) -> std::io::Error {
+        if self.read_only {
+            return error;
+        }
+        if let (Some(directory), Some(JobError::Corrupted { .. })) =
+            (&self.quarantine, JobError::from_io(&error))
+        {
+            if let Some(name) = path.file_name() {
+                let _ = std::fs::create_dir_all(directory)
+                    .and_then(|_| std::fs::rename(path, directory.join(name)));
+            }
+        }
+        error
+    }
+
+    /// Accept loading job files written with `compression`, without
+    /// compressing new files with it.
+    pub fn with_readable_compression<C>(mut self, compression: C) -> Self
+    where
+        C: Compression + 'static,
+    {
+        self.codec = self.codec.with_readable(compression);
+        self
+    }
+
+    /// The store format recorded in the job directory.
+    pub fn store_format(&self) -> Result<u32, std::io::Error> {
+        match std::fs::read_to_string(self.job_directory.join(FORMAT_FILE)) {
+            Ok(s) => s.trim().parse().map_err(|_| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("invalid store format {s:?}"),
+                )
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Ok(format::LEGACY)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The directory for the files of the job `id`, with or without
+    /// subdirectories.
+    fn job_dir(&self, id: Uuid, subdirectories: bool) -> PathBuf {
+        if !subdirectories {
+            return self.job_directory.clone();
+        }
+        let hex = id.simple().to_string();
+        self.job_directory.join(&hex[..2]).join(&hex[2..4])
+    }
+
+    /// The file of the job `id` named with `suffix`, in the configured
+    /// layout.
+    fn job_file(&self, id: Uuid, suffix: &str) -> PathBuf {
+        self.job_dir(id, self.subdirectories)
+            .join(format!("{id}{suffix}"))
+    }
+
+    /// The file of the job `id` named with `suffix`, in the other layout.
+    fn moved_job_file(&self, id: Uuid, suffix: &str) -> PathBuf {
+        self.job_dir(id, !self.subdirectories)
+            .join(format!("{id}{suffix}"))
+    }
+
+    /// The file of the job `id` named with `suffix`, in whichever layout
+    /// it exists.
+    fn existing_job_file(&self, id: Uuid, suffix: &str) -> PathBuf {
+        let path = self.job_file(id, suffix);
+        let moved = self.moved_job_file(id, suffix);
+        if !path.exists() && moved.exists() {
+            return moved;
+        }
+        path
+    }
+
+    /// The files holding the job `id`, in either layout and named after
+    /// the configured naming or the default one.
+    fn record_files(&self, id: Uuid) -> Result<Vec<PathBuf>, std::io::Error> {
+        let mut files = vec![];
+        for subdirectories in [self.subdirectories, !self.subdirectories] {
+            let dir = self.job_dir(id, subdirectories);
+            if self.naming.is_fixed() {
+                for name in [self.naming.fixed_name(id), id.to_string()] {
+                    let path = dir.join(name);
+                    if path.is_file() && !files.contains(&path) {
+                        files.push(path);
+                    }
+                }
+                continue;
+            }
+            let entries = match std::fs::read_dir(&dir) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                entries => entries?,
+            };
+            for entry in entries {
+                let entry = entry?;
+                let name = entry.file_name();
+                if self.naming.parse(&name.to_string_lossy()) == Some(id)
+                    && entry.file_type()?.is_file()
+                {
+                    files.push(entry.path());
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    /// The file of the job `id`, failing if there is none.
+    fn record_file(&self, id: Uuid) -> Result<PathBuf, std::io::Error> {
+        self.record_files(id)?.into_iter().next().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("job {id} not found"),
+            )
+        })
+    }
+
+    /// The file a job is written to, in the configured layout and naming.
+    fn new_record_file(
+        &self,
+        info: &JobInfo<Output, Error, Metadata, Status>,
+    ) -> PathBuf {
+        self.job_dir(info.id, self.subdirectories)
+            .join(self.naming.name(info.id, info.created_at, &info.status))
+    }
+
+    /// Create the directory of the job `id` in the configured layout.
+    fn create_job_dir(&self, id: Uuid) -> Result<(), std::io::Error> {
+        self.create_namespace()?;
+        if self.subdirectories {
+            std::fs::create_dir_all(self.job_dir(id, true))?;
+        }
+        Ok(())
+    }
+
+    /// Create the directory of the namespace, if any; the job directory it
+    /// is in must exist.
+    fn create_namespace(&self) -> Result<(), std::io::Error> {
+        if !self.namespaced {
+            return Ok(());
+        }
+        match std::fs::create_dir(&self.job_directory) {
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(()),
+            created => created,
+        }
+    }
+
+    /// The file holding the log of the job `id`.
+    fn log_file(&self, id: Uuid) -> PathBuf {
+        self.existing_job_file(id, ".log")
+    }
+
+    fn output_file(&self, id: Uuid) -> PathBuf {
+        self.existing_job_file(id, ".out")
+    }
+
+    fn history_file(&self, id: Uuid) -> PathBuf {
+        self.existing_job_file(id, ".history")
+    }
+
+    fn audit_file(&self, id: Uuid) -> PathBuf {
+        self.existing_job_file(id, ".audit")
+    }
+
+    /// Take the advisory lock on the job `id`, if locking is enabled; the
+    /// lock is held until the returned file is dropped.
+    fn lock(
+        &self,
+        id: Uuid,
+        exclusive: bool,
+    ) -> Result<Option<std::fs::File>, std::io::Error> {
+        if !self.locking {
+            return Ok(None);
+        }
+        if exclusive {
+            self.create_job_dir(id)?;
+        }
+        lock_file(&self.lock_file(id), exclusive)
+    }
+
+    /// The lock file of the job `id` (see [`FSJob::with_locking`]).
+    fn lock_file(&self, id: Uuid) -> PathBuf {
+        self.job_dir(id, self.subdirectories)
+            .join(format!(".{id}.lock"))
+    }
+
+    /// The temporary file to write `path` through.
+    fn temporary_file(path: &Path) -> PathBuf {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        path.with_file_name(format!(".{name}.{}.tmp", Uuid::new_v4()))
+    }
+
+    /// Replace the file at `path` with `contents`, through a temporary file
+    /// in the same directory, so readers never see a partial file.
+    fn write_atomic(
+        &self,
+        path: &Path,
+        contents: &[u8],
+    ) -> Result<(), std::io::Error> {
+        use std::io::Write;
+
+        let tmp = Self::temporary_file(path);
+        let written = std::fs::File::create(&tmp).and_then(|mut file| {
+            file.write_all(contents)?;
+            match self.durability {
+                Durability::None => {}
+                Durability::Flush => file.sync_data()?,
+                Durability::Fsync | Durability::FsyncDir => file.sync_all()?,
+            }
+            Ok(())
+        });
+        if let Err(e) = written.and_then(|()| std::fs::rename(&tmp, path)) {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(e);
+        }
+        if self.durability == Durability::FsyncDir {
+            sync_directory(path)?;
+        }
+        Ok(())
+    }
+
+    /// Fail if records with `encoding` would need migrations, which can't
+    /// be run on encodings that aren't self-describing.
+    fn check_unversioned(
+        &self,
+        encoding: Encoding,
+    ) -> Result<(), std::io::Error> {
+        if self.schema.version() == 0 {
+            return Ok(());
+        }
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("records encoded with {encoding:?} can't be migrated"),
+        ))
+    }
+
+    /// Check (once) that this release can use the job directory and, when
+    /// about to write, that the directory is marked with the current format.
+    fn check_format(&self, writing: bool) -> Result<(), std::io::Error> {
+        if self.format_checked.load(Ordering::Acquire) {
+            return Ok(());
+        }
+        let found = self.store_format()?;
+        format::check(found)?;
+        if found < format::CURRENT {
+            if !writing {
+                return Ok(());
+            }
+            self.create_namespace()?;
+            std::fs::write(
+                self.job_directory.join(FORMAT_FILE),
+                format::CURRENT.to_string(),
+            )?;
+        }
+        self.format_checked.store(true, Ordering::Release);
+        Ok(())
+    }
+}
+
+impl<Output, Error, Metadata, Status> FSJob<Output, Error, Metadata, Status>
+where
+    Output: Serialize + DeserializeOwned,
+    Error: Serialize + DeserializeOwned,
+    Metadata: Serialize + DeserializeOwned,
+    Status: PartialEq + Serialize + DeserializeOwned,
+{
+    /// Append the status of a job to its history, if enabled and it changed
+    /// since the last entry.
+    fn record_history(
+        &self,
+        id: Uuid,
+        status: &StatusType<Status>,
+    ) -> Result<(), std::io::Error> {
+        if !self.history {
+            return Ok(());
+        }
+        let path = self.history_file(id);
+        let history: Vec<StatusChange<Status>> = read_json_lines(&path)?;
+        if history.last().is_some_and(|last| last.status == *status) {
+            return Ok(());
+        }
+        let change = serde_json::json!({ "at": Utc::now(), "status": status });
+        append_json_line(&path, &change)
+    }
+
+    /// Append the save of a job to its audit log, if enabled.
+    fn record_audit(
+        &self,
+        id: Uuid,
+        status: &StatusType<Status>,
+        version: u64,
+    ) -> Result<(), std::io::Error> {
+        if !self.audit {
+            return Ok(());
+        }
+        let path = self.audit_file(id);
+        let audit: Vec<AuditEntry<Status>> = read_json_lines(&path)?;
+        let from = audit.into_iter().last().map(|entry| entry.to);
+        let entry = serde_json::json!({
+            "at": Utc::now(),
+            "actor": Actor::of(from.as_ref(), status),
+            "from": from,
+            "to": status,
+            "process": worker::label(),
+            "version": version,
+        });
+        append_json_line(&path, &entry)
+    }
+
+    /// Serialize a job, as far as possible without doing any I/O.
+    fn serialize_record(
+        &self,
+        info: &JobInfo<Output, Error, Metadata, Status>,
+    ) -> Result<Serialized, std::io::Error> {
+        let encoding = self.codec.encoding();
+        if encoding.is_self_describing() {
+            Ok(Serialized::Value(serde_json::to_value(info)?))
+        } else {
+            self.check_unversioned(encoding)?;
+            Ok(Serialized::Encoded(encoding.to_vec(info)?))
+        }
+    }
+
+    /// The contents of the file of the job `id`, serialized with
+    /// [`FSJob::serialize_record`].
+    fn encode_record(
+        &self,
+        id: Uuid,
+        serialized: Serialized,
+    ) -> Result<Vec<u8>, std::io::Error> {
+        let payload = match serialized {
+            Serialized::Value(mut record) => {
+                if let Some(blobs) = &self.blobs {
+                    blobs.offload(id, &mut record)?;
+                }
+                self.codec.encoding().to_vec(&self.schema.wrap(record))?
+            }
+            Serialized::Encoded(payload) => payload,
+        };
+        self.codec.encode(payload)
+    }
+
+    /// Deserialize a job from the contents of its file.
+    ///
+    /// Errors showing that the contents are damaged, rather than of another
+    /// type, are reported as [`JobError::Corrupted`] job `id`.
+    fn decode_record(
+        &self,
+        id: Uuid,
+        record: &[u8],
+    ) -> Result<JobInfo<Output, Error, Metadata, Status>, std::io::Error> {
+        let decode = || {
+            let (encoding, payload) = self.codec.decode(record)?;
+            if !encoding.is_self_describing() {
+                self.check_unversioned(encoding)?;
+                return encoding.from_slice(&payload);
+            }
+            let mut record =
+                self.schema.upgrade(encoding.from_slice(&payload)?)?;
+            if let Some(blobs) = &self.blobs {
+                blobs.restore(&mut record)?;
+            }
+            let j: JobInfo<_, _, _, _> = serde_json::from_value(record)?;
+            Ok(j)
+        };
+        decode().map_err(|e| corrupted(id, e))
+    }
+
+    /// Deserialize the status of a job from the contents of its file,
+    /// skipping its result and metadata, like [`FSJob::decode_record`].
+    ///
+    /// Without migrations, self-describing records are read straight into
+    /// a [`JobStatus`], so the other fields are only scanned over.
+    fn decode_status(
+        &self,
+        id: Uuid,
+        record: &[u8],
+    ) -> Result<JobStatus<Status>, std::io::Error> {
+        let (encoding, payload) =
+            self.codec.decode(record).map_err(|e| corrupted(id, e))?;
+        if !encoding.is_self_describing() || self.schema.version() > 0 {
+            return self.decode_record(id, record).map(JobStatus::from);
+        }
+        encoding
+            .from_slice(&payload)
+            .or_else(|_| self.decode_record(id, record).map(JobStatus::from))
+    }
+
+    /// Write the file of a job, without locking.
+    fn write_record(
+        &self,
+        info: &JobInfo<Output, Error, Metadata, Status>,
+    ) -> Result<(), std::io::Error>
+    where
+        Status: Clone,
+    {
+        self.check_writable()?;
+        self.write_save(self.prepare_save(info)?)
+    }
+
+    /// Take from a job what saving it writes.
+    fn prepare_save(
+        &self,
+        info: &JobInfo<Output, Error, Metadata, Status>,
+    ) -> Result<Save<Status>, std::io::Error>
+    where
+        Status: Clone,
+    {
+        Ok(Save {
+            id: info.id,
+            version: info.version,
+            status: info.status.clone(),
+            path: self.new_record_file(info),
+            record: self.serialize_record(info)?,
+            index: IndexEntry::of(info)?,
+        })
+    }
+
+    /// Write the files of a save, without locking.
+    fn write_save(&self, save: Save<Status>) -> Result<(), std::io::Error> {
+        self.check_format(true)?;
+        self.create_job_dir(save.id)?;
+        let record = self.encode_record(save.id, save.record)?;
+        self.write_atomic(&save.path, &record)?;
+        self.remove_old_records(save.id, &save.path)?;
+        self.record_history(save.id, &save.status)?;
+        self.record_audit(save.id, &save.status, save.version)?;
+        self.append_index(&save.index)
+    }
+
+    /// Remove the files of the job `id` other than `path`, saved before in
+    /// the other layout or under another name.
+    fn remove_old_records(
+        &self,
+        id: Uuid,
+        path: &Path,
+    ) -> Result<(), std::io::Error> {
+        for old in self.record_files(id)? {
+            if old == path {
+                continue;
+            }
+            match std::fs::remove_file(old) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(e);
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Read the file of a job, without locking.
+    fn read_record(
+        &self,
+        id: Uuid,
+    ) -> Result<JobInfo<Output, Error, Metadata, Status>, std::io::Error> {
+        self.check_format(false)?;
+        let path = self.record_file(id)?;
+        let record = std::fs::read(&path)?;
+        self.decode_record(id, &record)
+            .map_err(|e| self.quarantine(&path, e))
+    }
+
+    /// Save a job like [`Job::save`], doing the file I/O on a thread
+    /// dedicated to blocking work (see [`tokio::task::spawn_blocking`])
+    /// instead of blocking the runtime.
+    ///
+    /// The job is serialized on the calling thread.
+    pub async fn save_async(
+        &self,
+        info: &JobInfo<Output, Error, Metadata, Status>,
+    ) -> Result<(), std::io::Error>
+    where
+        Output: Send + 'static,
+        Error: Send + 'static,
+        Metadata: Send + 'static,
+        Status: Clone + Send + 'static,
+    {
+        self.check_writable()?;
+        let save = self.prepare_save(info)?;
+        let job = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let _lock = job.lock(save.id, true)?;
+            job.write_save(save)
+        })
+        .await?
+    }
+
+    /// Rewrite the index from the job files, e.g. after saving jobs without
+    /// it (see [`FSJob::with_index`]); unreadable job files are left out.
+    pub fn rebuild_index(&self) -> Result<(), std::io::Error> {
+        self.check_writable()?;
+        let mut ids = vec![];
+        collect_ids(&self.job_directory, 2, &self.naming, &mut ids)?;
+        let mut index = vec![];
+        for id in ids {
+            if let Ok(info) = self.read_record(id) {
+                index.extend(serde_json::to_vec(&IndexEntry::of(&info)?)?);
+                index.push(b'\n');
+            }
+        }
+        self.write_atomic(&self.job_directory.join(INDEX_FILE), &index)
+    }
+
+    /// Load a job like [`Job::load`], doing the file I/O on a thread
+    /// dedicated to blocking work, like [`FSJob::save_async`].
+    pub async fn load_async(
+        &self,
+        id: Uuid,
+    ) -> Result<JobInfo<Output, Error, Metadata, Status>, std::io::Error>
+    where
+        Output: Send + 'static,
+        Error: Send + 'static,
+        Metadata: Send + 'static,
+        Status: Send + 'static,
+    {
+        let job = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let _lock = job.lock(id, false)?;
+            job.read_record(id)
+        })
+        .await?
+    }
 }
 
 impl<
-        Output: Clone + Send + Sync + Serialize + DeserializeOwned + 'static,
+        Output: Send + Sync + Serialize + DeserializeOwned + 'static,
         Error: Clone + Send + Sync + Serialize + DeserializeOwned + 'static,
         Metadata: Clone + Send + Sync + Serialize + DeserializeOwned + 'static,
         Status: PartialEq
@@ -56,18 +1100,524 @@ impl<
     type Metadata = Metadata;
     type Status = Status;
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(job.id = %info.id))
+    )]
     fn save(&self, info: &Info<Self>) -> Result<(), std::io::Error> {
-        let mut file =
-            File::create(self.job_directory.join(info.id.to_string()))?;
-        file.write_all(serde_json::to_string(info)?.as_bytes())?;
-        Ok(())
+        self.check_writable()?;
+        let _lock = self.lock(info.id, true)?;
+        self.write_record(info)
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(job.id = %id))
+    )]
     fn load(&self, id: Uuid) -> Result<Info<Self>, std::io::Error> {
-        let mut file = File::open(self.job_directory.join(id.to_string()))?;
-        let mut s = String::new();
-        file.read_to_string(&mut s)?;
-        let j: JobInfo<_, _, _, _> = serde_json::from_str(&s)?;
-        Ok(j)
+        let _lock = self.lock(id, false)?;
+        self.read_record(id)
+    }
+
+    /// Doesn't deserialize the result and metadata, nor read their blobs
+    /// (see [`FSJob::with_blobs`]).
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(job.id = %id))
+    )]
+    fn load_status(
+        &self,
+        id: Uuid,
+    ) -> Result<JobStatus<Status>, std::io::Error> {
+        let _lock = self.lock(id, false)?;
+        self.check_format(false)?;
+        let path = self.record_file(id)?;
+        let record = std::fs::read(&path)?;
+        self.decode_status(id, &record)
+            .map_err(|e| self.quarantine(&path, e))
+    }
+
+    /// Compares and writes while holding the lock of the job, both within
+    /// the process and, with [`FSJob::with_locking`], across processes.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(job.id = %info.id))
+    )]
+    fn save_if_version(
+        &self,
+        info: &mut Info<Self>,
+        expected: u64,
+    ) -> Result<u64, std::io::Error> {
+        self.check_writable()?;
+        let lock = local::record_lock(info.id);
+        let _guard = lock.lock().expect("cannot get lock");
+        let _lock = self.lock(info.id, true)?;
+        let found = self.read_record(info.id)?.version;
+        if found != expected {
+            return Err(JobError::VersionConflict {
+                id: info.id,
+                expected,
+                found,
+            }
+            .into());
+        }
+        info.version = expected + 1;
+        if let Err(e) = self.write_record(info) {
+            info.version = expected;
+            return Err(e);
+        }
+        Ok(info.version)
+    }
+
+    /// Appends a JSON line to the file `<id>.log`.
+    fn append_log(
+        &self,
+        id: Uuid,
+        line: &LogLine,
+    ) -> Result<(), std::io::Error> {
+        self.check_writable()?;
+        append_json_line(&self.log_file(id), line)
+    }
+
+    fn logs(&self, id: Uuid) -> Result<Vec<LogLine>, std::io::Error> {
+        read_json_lines(&self.log_file(id))
+    }
+
+    /// Appends a JSON line to the file `<id>.out`.
+    fn append_output(
+        &self,
+        id: Uuid,
+        item: &serde_json::Value,
+    ) -> Result<(), std::io::Error> {
+        self.check_writable()?;
+        append_json_line(&self.output_file(id), item)
+    }
+
+    fn outputs(
+        &self,
+        id: Uuid,
+        from: usize,
+    ) -> Result<Vec<serde_json::Value>, std::io::Error> {
+        let mut items = read_json_lines(&self.output_file(id))?;
+        Ok(items.split_off(from.min(items.len())))
+    }
+
+    fn history(
+        &self,
+        id: Uuid,
+    ) -> Result<Vec<StatusChange<Status>>, std::io::Error> {
+        read_json_lines(&self.history_file(id))
+    }
+
+    fn audit(
+        &self,
+        id: Uuid,
+    ) -> Result<Vec<AuditEntry<Status>>, std::io::Error> {
+        read_json_lines(&self.audit_file(id))
+    }
+
+    /// With the `notify` feature, watches the directory of the job for
+    /// changes to its file instead of polling.  Since some file systems
+    /// (e.g. network ones) don't report every change, the stream also
+    /// yields every second; it polls like the default when the directory
+    /// can't be watched.
+    #[cfg(feature = "notify")]
+    fn changes(
+        &self,
+        id: Uuid,
+    ) -> impl futures::Stream<Item = ()> + Send + 'static {
+        watch_file(
+            &self.job_dir(id, self.subdirectories),
+            id,
+            self.naming.clone(),
+        )
+    }
+
+    /// Removes the files of the job, in either layout.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(job.id = %id))
+    )]
+    fn remove(&self, id: Uuid) -> Result<(), std::io::Error> {
+        self.check_writable()?;
+        let lock = local::record_lock(id);
+        let _guard = lock.lock().expect("cannot get lock");
+        let _lock = self.lock(id, true)?;
+        let records = self.record_files(id)?;
+        let found = !records.is_empty();
+        let others = [".log", ".out", ".history", ".audit"]
+            .into_iter()
+            .flat_map(|suffix| {
+                [self.job_file(id, suffix), self.moved_job_file(id, suffix)]
+            });
+        for path in records.into_iter().chain(others) {
+            match std::fs::remove_file(path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(e);
+                }
+                _ => {}
+            }
+        }
+        if self.locking {
+            let _ = std::fs::remove_file(self.lock_file(id));
+        }
+        if let Some(blobs) = &self.blobs {
+            blobs.remove(id)?;
+        }
+        if !found {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("job {id} not found"),
+            ));
+        }
+        self.append_index(&index::removal(id))
+    }
+
+    /// Counts from the index if enabled (see [`FSJob::with_index`]),
+    /// without reading the job files.
+    fn counts(&self) -> Result<BTreeMap<&'static str, usize>, std::io::Error> {
+        let mut counts = BTreeMap::new();
+        if self.index {
+            for entry in self.index()? {
+                *counts.entry(entry.status.label()).or_default() += 1;
+            }
+        } else {
+            for info in self.scan()? {
+                *counts.entry(info.status.label()).or_default() += 1;
+            }
+        }
+        Ok(counts)
+    }
+
+    /// The ids of the files named after an id, in the job directory and
+    /// its subdirectories (see [`FSJob::with_subdirectories`]).
+    fn ids(&self) -> Result<Vec<Uuid>, std::io::Error> {
+        let mut ids = vec![];
+        collect_ids(&self.job_directory, 2, &self.naming, &mut ids)?;
+        let mut seen = std::collections::HashSet::new();
+        ids.retain(|id| seen.insert(*id));
+        Ok(ids)
+    }
+
+    /// Reads the index if enabled (see [`FSJob::with_index`]), without
+    /// reading the job files.
+    fn tenant_ids(&self, tenant: &str) -> Result<Vec<Uuid>, std::io::Error> {
+        if !self.index {
+            return Ok(self
+                .scan()?
+                .filter(|info| info.tenant_id.as_deref() == Some(tenant))
+                .map(|info| info.id)
+                .collect());
+        }
+        Ok(self
+            .index()?
+            .into_iter()
+            .filter(|entry| entry.tenant_id.as_deref() == Some(tenant))
+            .map(|entry| entry.id)
+            .collect())
+    }
+
+    /// Creates or removes the file named after the queue in `.paused`.
+    fn set_queue_paused(
+        &self,
+        queue: &str,
+        paused: bool,
+    ) -> Result<(), std::io::Error> {
+        self.check_writable()?;
+        check_file_name("queue", queue)?;
+        let dir = self.job_directory.join(PAUSED_DIR);
+        if paused {
+            self.create_namespace()?;
+            std::fs::create_dir_all(&dir)?;
+            std::fs::write(dir.join(queue), b"")
+        } else {
+            match std::fs::remove_file(dir.join(queue)) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                removed => removed,
+            }
+        }
+    }
+
+    fn paused_queues(&self) -> Result<Vec<String>, std::io::Error> {
+        let entries =
+            match std::fs::read_dir(self.job_directory.join(PAUSED_DIR)) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    return Ok(vec![]);
+                }
+                entries => entries?,
+            };
+        let mut queues = vec![];
+        for entry in entries {
+            queues.push(entry?.file_name().to_string_lossy().into_owned());
+        }
+        queues.sort();
+        Ok(queues)
+    }
+
+    /// Compares and writes the file named after the lease in `.leases`
+    /// while holding its lock file, whether or not
+    /// [`FSJob::with_locking`] is enabled.
+    fn acquire_lease(
+        &self,
+        name: &str,
+        lease: &Lease,
+    ) -> Result<bool, std::io::Error> {
+        self.check_writable()?;
+        check_file_name("lease", name)?;
+        let dir = self.job_directory.join(LEASES_DIR);
+        self.create_namespace()?;
+        std::fs::create_dir_all(&dir)?;
+        let _lock = lock_file(&dir.join(format!(".{name}.lock")), true)?;
+        let path = dir.join(name);
+        match std::fs::read(&path) {
+            Ok(held) => {
+                let held: Lease = serde_json::from_slice(&held)?;
+                if held.token != lease.token && !held.is_expired() {
+                    return Ok(false);
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        self.write_atomic(&path, &serde_json::to_vec(lease)?)?;
+        Ok(true)
     }
+
+    fn release_lease(
+        &self,
+        name: &str,
+        token: Uuid,
+    ) -> Result<(), std::io::Error> {
+        self.check_writable()?;
+        check_file_name("lease", name)?;
+        let dir = self.job_directory.join(LEASES_DIR);
+        let _lock = match lock_file(&dir.join(format!(".{name}.lock")), false) {
+            Ok(Some(lock)) => lock,
+            // Never taken.
+            Ok(None) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        let path = dir.join(name);
+        match std::fs::read(&path) {
+            Ok(held) => {
+                let held: Lease = serde_json::from_slice(&held)?;
+                if held.token == token {
+                    std::fs::remove_file(&path)?;
+                }
+                Ok(())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Rejects submissions to a read-only job (see [`FSJob::read_only`])
+    /// before they start.
+    fn admit(&self) -> Result<(), std::io::Error> {
+        self.check_writable()
+    }
+}
+
+/// How often [`watch_file`] yields even without notifications.
+#[cfg(feature = "notify")]
+const NOTIFY_FALLBACK: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// A stream yielding when the file of the job `id` in `dir` changes.
+#[cfg(feature = "notify")]
+fn watch_file(
+    dir: &Path,
+    id: Uuid,
+    naming: FileNaming,
+) -> futures::stream::BoxStream<'static, ()> {
+    use futures::StreamExt;
+    use notify::Watcher;
+
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let watcher = notify::recommended_watcher(
+        move |event: notify::Result<notify::Event>| {
+            // Errors may hide a change.
+            let relevant = event.map_or(true, |event| {
+                event.paths.iter().filter_map(|path| path.file_name()).any(
+                    |name| naming.parse(&name.to_string_lossy()) == Some(id),
+                )
+            });
+            if relevant {
+                let _ = tx.send(());
+            }
+        },
+    );
+    let watcher = watcher.and_then(|mut watcher| {
+        watcher.watch(dir, notify::RecursiveMode::NonRecursive)?;
+        Ok(watcher)
+    });
+    let Ok(watcher) = watcher else {
+        return crate::poll_changes().boxed();
+    };
+    futures::stream::unfold((watcher, rx), |(watcher, mut rx)| async move {
+        let _ = tokio::time::timeout(NOTIFY_FALLBACK, rx.recv()).await;
+        while rx.try_recv().is_ok() {}
+        Some(((), (watcher, rx)))
+    })
+    .boxed()
+}
+
+/// What saving a job writes, taken from its [`JobInfo`] so it can be
+/// written from another thread (see [`FSJob::save_async`]).
+struct Save<Status> {
+    id: Uuid,
+    version: u64,
+    status: StatusType<Status>,
+    /// The file to write the record to.
+    path: PathBuf,
+    record: Serialized,
+    index: IndexEntry,
+}
+
+/// A job serialized by [`FSJob::serialize_record`].
+enum Serialized {
+    /// As JSON, still to be offloaded to blobs and encoded.
+    Value(serde_json::Value),
+    /// Encoded without a schema (see [`Encoding::is_self_describing`]).
+    Encoded(Vec<u8>),
+}
+
+/// Fails with [`std::io::ErrorKind::InvalidInput`] unless `name` can name a
+/// file of the job directory, e.g. of the `kind` of a paused queue.
+fn check_file_name(kind: &str, name: &str) -> Result<(), std::io::Error> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("invalid {kind} name {name:?}"),
+        ));
+    }
+    Ok(())
+}
+
+/// Open the lock file at `path` and lock it, blocking until the lock is
+/// available.
+///
+/// Shared locks don't create the lock file: a job without one was never
+/// written with locking, and its file is replaced atomically anyway.
+fn lock_file(
+    path: &Path,
+    exclusive: bool,
+) -> Result<Option<std::fs::File>, std::io::Error> {
+    let file = match std::fs::OpenOptions::new()
+        .create(exclusive)
+        .truncate(false)
+        .write(true)
+        .open(path)
+    {
+        Ok(file) => file,
+        Err(e) if !exclusive && e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(None);
+        }
+        Err(e) => return Err(e),
+    };
+    if exclusive {
+        file.lock()?;
+    } else {
+        file.lock_shared()?;
+    }
+    Ok(Some(file))
+}
+
+/// Collect the ids of the job files in `dir`, descending `depth` levels of
+/// subdirectories.
+fn collect_ids(
+    dir: &Path,
+    depth: usize,
+    naming: &FileNaming,
+    ids: &mut Vec<Uuid>,
+) -> Result<(), std::io::Error> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if let Some(id) = naming.parse(&name) {
+            ids.push(id);
+        } else if depth > 0
+            && name.len() == 2
+            && name.chars().all(|c| c.is_ascii_hexdigit())
+            && entry.file_type()?.is_dir()
+        {
+            collect_ids(&entry.path(), depth - 1, naming, ids)?;
+        }
+    }
+    Ok(())
+}
+
+/// Flush the directory containing `path` to disk.
+fn sync_directory(path: &Path) -> Result<(), std::io::Error> {
+    // Directories can't be opened (nor need flushing) on Windows.
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        let parent = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+        std::fs::File::open(parent)?.sync_all()?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+/// Report `error`, from decoding the file of job `id`, as
+/// [`JobError::Corrupted`] if it shows the file is damaged: its checksum
+/// doesn't match, or it isn't valid JSON (e.g. it was cut short).
+fn corrupted(id: Uuid, error: std::io::Error) -> std::io::Error {
+    if let Some(JobError::Corrupted { id: None, reason }) =
+        JobError::from_io(&error)
+    {
+        return JobError::Corrupted {
+            id: Some(id),
+            reason: reason.clone(),
+        }
+        .into();
+    }
+    let damaged = error
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<serde_json::Error>())
+        .filter(|e| e.is_syntax() || e.is_eof());
+    match damaged {
+        Some(e) => JobError::Corrupted {
+            id: Some(id),
+            reason: e.to_string(),
+        }
+        .into(),
+        None => error,
+    }
+}
+
+fn append_json_line<T: Serialize>(
+    path: &Path,
+    value: &T,
+) -> Result<(), std::io::Error> {
+    use std::io::Write;
+
+    let mut json = serde_json::to_vec(value)?;
+    json.push(b'\n');
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(&json)
+}
+
+/// The JSON lines of a file, or none if it doesn't exist.
+fn read_json_lines<T: DeserializeOwned>(
+    path: &Path,
+) -> Result<Vec<T>, std::io::Error> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(vec![]);
+        }
+        Err(e) => return Err(e),
+    };
+    text.lines()
+        .map(|line| Ok(serde_json::from_str(line)?))
+        .collect()
 }
@@ -0,0 +1,110 @@
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::{run, wait, CancelReason, Job, StatusType};
+
+/// A handle to a submitted job, returned by [`Job::submit`].
+///
+/// The job keeps running when the handle is dropped.
+pub struct JobHandle<J: Job> {
+    id: Uuid,
+    job: J,
+    /// The task running the job, from the [spawner](Job::spawner).
+    task: JoinHandle<()>,
+}
+
+impl<J: Job> JobHandle<J> {
+    pub(crate) fn new(id: Uuid, job: J, task: JoinHandle<()>) -> Self {
+        Self { id, job, task }
+    }
+
+    /// The id of the job.
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// The backend the job was submitted to.
+    pub fn job(&self) -> &J {
+        &self.job
+    }
+
+    /// Load the current status of the job.
+    pub fn status(&self) -> Result<StatusType<J::Status>, std::io::Error> {
+        Ok(self.job.load(self.id)?.status)
+    }
+
+    /// Wait for the job to finish, and return its result.
+    ///
+    /// The result is `None` when the job ended without producing one (it
+    /// failed or was canceled).
+    pub async fn result(
+        &self,
+    ) -> Result<Option<Result<J::Output, J::Error>>, std::io::Error> {
+        Ok(wait(self.id, &self.job).await?.result)
+    }
+
+    /// Cancel the job (see [`Job::cancel`]).
+    pub fn cancel(&self, reason: CancelReason) -> Result<(), std::io::Error> {
+        self.job.cancel(self.id, reason)
+    }
+
+    /// Stop the job now, saving it as canceled by
+    /// [`CancelReason::UserAction`] (see [`JobHandle::cancel`] for another
+    /// reason).
+    ///
+    /// Aborting a job that already ended does nothing.  The task is only
+    /// aborted once the cancellation is saved: if the save fails, the job
+    /// keeps running, and the error is returned.
+    pub fn abort(&self) -> Result<(), std::io::Error> {
+        match self.cancel(CancelReason::UserAction) {
+            Ok(()) => {}
+            Err(e) if run::is_invalid_transition(&e) => {}
+            Err(e) => return Err(e),
+        }
+        self.task.abort();
+        Ok(())
+    }
+
+    /// Whether the task running the job has completed.
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl<J: Job> std::fmt::Debug for JobHandle<J> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JobHandle")
+            .field("id", &self.id)
+            .field("finished", &self.is_finished())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<J: Job> From<JobHandle<J>> for Uuid {
+    fn from(handle: JobHandle<J>) -> Self {
+        handle.id
+    }
+}
+
+/// The outcome of [`Job::submit_unique`].
+pub enum UniqueSubmission<J: Job> {
+    /// No active job had the key: a new one was started.
+    New(JobHandle<J>),
+    /// The id of the active job submitted earlier with the key.
+    Existing(Uuid),
+}
+
+impl<J: Job> UniqueSubmission<J> {
+    /// The id of the job, new or existing.
+    pub fn id(&self) -> Uuid {
+        match self {
+            UniqueSubmission::New(handle) => handle.id(),
+            UniqueSubmission::Existing(id) => *id,
+        }
+    }
+
+    /// Whether a new job was started.
+    pub fn is_new(&self) -> bool {
+        matches!(self, UniqueSubmission::New(_))
+    }
+}
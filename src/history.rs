@@ -0,0 +1,19 @@
+//! The history of the statuses of a job.
+//!
+//! Backends keeping it (e.g. [`FSJob::with_history`](crate::FSJob::with_history))
+//! record every status a job was saved with, so [`Job::history`](crate::Job::history)
+//! shows how the job progressed, not only where it ended.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::StatusType;
+
+/// A status a job went through.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StatusChange<S> {
+    /// When the job was saved with the status.
+    pub at: DateTime<Utc>,
+    /// The new status.
+    pub status: StatusType<S>,
+}
@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::{
+    ids::IdGenerator, layers::DynLayer, queue::QueueConfig, retry::Backoff,
+    secrets::SecretProvider, spawn::Spawner, CancelReason, Job,
+};
+
+/// Callbacks invoked at the different stages of a job's life.
+///
+/// All methods default to doing nothing, so implementations only override
+/// what they need.  Hooks run inline, in the submitting code or in the job's
+/// task, and should return quickly (spawn a task for slow work like sending
+/// a message).
+pub trait JobHooks<Output, Error>: Send + Sync {
+    /// The job was saved for the first time and is about to be spawned.
+    fn on_submit(&self, _id: Uuid) {}
+
+    /// The job's future started running.
+    fn on_start(&self, _id: Uuid) {}
+
+    /// The job completed with `Ok`.
+    fn on_success(&self, _id: Uuid, _output: &Output) {}
+
+    /// The job completed with `Err`.
+    fn on_failure(&self, _id: Uuid, _error: &Error) {}
+
+    /// The job panicked, with the given message.
+    fn on_panic(&self, _id: Uuid, _message: &str) {}
+
+    /// The job was canceled through [`Job::cancel`].
+    fn on_cancel(&self, _id: Uuid, _reason: &CancelReason) {}
+
+    /// Saving the job to the backend failed.
+    fn on_save_error(&self, _id: Uuid, _error: &std::io::Error) {}
+}
+
+/// Shared, type-erased hooks for a job with the given output and error.
+pub type DynHooks<Output, Error> = Arc<dyn JobHooks<Output, Error>>;
+
+/// A [`Job`] wrapping another backend, running [`JobHooks`] for every job
+/// submitted through it.
+///
+/// Since only the outermost backend is seen by [`Job::submit`], `Hooked`
+/// should wrap any other combinator (e.g. a [`ShardedJob`]).
+///
+/// [`ShardedJob`]: crate::ShardedJob
+pub struct Hooked<J: Job> {
+    inner: J,
+    hooks: Vec<DynHooks<J::Output, J::Error>>,
+}
+
+impl<J: Job> Clone for Hooked<J> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            hooks: self.hooks.clone(),
+        }
+    }
+}
+
+impl<J: Job> Hooked<J> {
+    /// Wrap a backend, keeping the hooks it already has (if any).
+    pub fn new(inner: J) -> Self {
+        Self {
+            hooks: inner.hooks().to_vec(),
+            inner,
+        }
+    }
+
+    /// Add hooks, run after the ones already added.
+    pub fn with_hooks<H>(mut self, hooks: H) -> Self
+    where
+        H: JobHooks<J::Output, J::Error> + 'static,
+    {
+        self.hooks.push(Arc::new(hooks));
+        self
+    }
+
+    /// The wrapped backend.
+    pub fn inner(&self) -> &J {
+        &self.inner
+    }
+}
+
+impl<J: Job> Job for Hooked<J> {
+    type Output = J::Output;
+    type Error = J::Error;
+    type Metadata = J::Metadata;
+    type Status = J::Status;
+
+    delegate_storage!(inner);
+
+    fn hooks(&self) -> &[DynHooks<Self::Output, Self::Error>] {
+        &self.hooks
+    }
+
+    fn layers(&self) -> &[DynLayer<Self::Output, Self::Error>] {
+        self.inner.layers()
+    }
+
+    fn save_backoff(&self) -> Backoff {
+        self.inner.save_backoff()
+    }
+
+    fn id_generator(&self) -> Arc<dyn IdGenerator> {
+        self.inner.id_generator()
+    }
+
+    fn spawner(&self) -> Arc<dyn Spawner> {
+        self.inner.spawner()
+    }
+
+    fn admit(&self) -> Result<(), std::io::Error> {
+        self.inner.admit()
+    }
+
+    fn secret_provider(&self) -> Arc<dyn SecretProvider> {
+        self.inner.secret_provider()
+    }
+
+    fn queue_config(&self) -> Arc<QueueConfig> {
+        self.inner.queue_config()
+    }
+}
@@ -0,0 +1,657 @@
+//! A JSON API over the jobs of a [`JobRegistry`], as an [`axum::Router`].
+//!
+//! [`router`] serves the admin endpoints every application ends up
+//! writing:
+//!
+//! | Endpoint | |
+//! |---|---|
+//! | `POST /jobs` | submit a [`Payload`] with [`JobRegistry::submit`], returning `{"id": ...}` |
+//! | `GET /jobs` | list the jobs, newest first, filtered by the [`Query`] string |
+//! | `POST /jobs/status` | the [statuses](crate::JobStatus) of the jobs of `{"ids": [...]}`, at most [`MAX_STATUS_IDS`], in the same order |
+//! | `GET /jobs/{id}` | the record of a job |
+//! | `GET /jobs/{id}/events` | server-sent events with the record of a job each time it changes, until it ends |
+//! | `POST /jobs/{id}/cancel` | cancel a job, by [`CancelReason::UserAction`] |
+//! | `POST /jobs/{id}/retry` | resubmit a job with [`JobRegistry::resubmit`], returning `{"id": ...}` |
+//! | `DELETE /jobs/{id}` | remove a job |
+//! | `GET /dashboard` | the [snapshot](Job::dashboard_snapshot) of the jobs, for a status page |
+//!
+//! With [`router_with_catalog`], the jobs and statuses served also have a
+//! `description` of their status from a [`Catalog`], in the locale best
+//! matching the `Accept-Language` header of the request (see
+//! [`Catalog::negotiate`]).
+//!
+//! The responses of `GET /jobs` and `GET /jobs/{id}` carry an `ETag`,
+//! derived from the
+//! [version](crate::JobInfo::version) and the content of the job, or for
+//! listings from the [statuses](Job::load_status) of all the jobs:
+//! requests with a matching `If-None-Match` get an empty `304 Not
+//! Modified`, so polling clients only download what changed.  The tag of
+//! a listing is weak, as it misses the changes to the metadata of a job
+//! that keep its status, timestamps and version.  Responses of at least
+//! [`COMPRESSION_THRESHOLD`] bytes are compressed with gzip for the clients
+//! accepting it.
+//!
+//! The calls to the backend are made on the blocking threads of Tokio
+//! (see [`tokio::task::spawn_blocking`]).
+//!
+//! The events of `GET /jobs/{id}/events` have the `ETag` of the record as
+//! their id, so a client reconnecting with it as `Last-Event-ID` doesn't
+//! get the record again if it didn't change (see the module `client`, with
+//! the feature `client`).
+//!
+//! Errors are returned as `{"error": "..."}`, with a status from their
+//! [kind](std::io::ErrorKind): 404 for unknown jobs or handlers, 400 for
+//! invalid payloads, 409 for invalid transitions (e.g. canceling a finished
+//! job), 501 for what the backend doesn't support.
+//!
+//! ```
+//! # use serde_json::Value;
+//! # use simple_jobs::{error::SerializableError, registry::{JobRegistry, Payload}, FSJob};
+//! # async fn example(job: FSJob<Value, SerializableError, Payload, ()>) -> std::io::Result<()> {
+//! let mut registry = JobRegistry::new(job);
+//! registry.register("double", |_, _, n: u32| async move { Ok(2 * n) });
+//! let app = axum::Router::new().nest("/admin", simple_jobs::http::router(registry));
+//! let listener = tokio::net::TcpListener::bind("127.0.0.1:8080").await?;
+//! axum::serve(listener, app).await
+//! # }
+//! ```
+//!
+//! The API has no authentication: put it behind the middleware of the
+//! application.
+//!
+//! Requires the feature `http`.
+
+use std::{convert::Infallible, io::Write, sync::Arc};
+
+use axum::{
+    body::Body,
+    extract::{FromRef, Path, Query as QueryString, Request, State},
+    http::{
+        header::{
+            ACCEPT_ENCODING, ACCEPT_LANGUAGE, CONTENT_ENCODING, CONTENT_LENGTH,
+            CONTENT_TYPE, ETAG, IF_NONE_MATCH, VARY,
+        },
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    middleware::{self, Next},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, post},
+    Json, Router,
+};
+use flate2::{write::GzEncoder, Compression};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{
+    describe::{Catalog, Describe},
+    error::SerializableError,
+    queue::DEFAULT_QUEUE,
+    registry::{JobRegistry, Payload},
+    CancelReason, Job, JobError, JobInfo, JobStatus, StatusType,
+};
+
+/// The size from which responses are compressed, in bytes.
+pub const COMPRESSION_THRESHOLD: usize = 1024;
+
+/// The most ids of a `POST /jobs/status`.
+pub const MAX_STATUS_IDS: usize = 100;
+
+/// The filters of `GET /jobs`, all optional.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Query {
+    /// Only the jobs with this kind of status (see
+    /// [`StatusType::label`](crate::StatusType::label)), e.g. `failed`.
+    pub status: Option<String>,
+    /// Only the jobs enqueued to this queue.
+    pub queue: Option<String>,
+    /// Only the jobs run by this handler.
+    pub handler: Option<String>,
+    /// Only the jobs of this tenant.
+    pub tenant: Option<String>,
+    /// At most this many jobs.
+    pub limit: Option<usize>,
+}
+
+impl Query {
+    /// Whether the job of `info` passes the filters.
+    pub fn matches<O, E, S>(&self, info: &JobInfo<O, E, Payload, S>) -> bool {
+        let queue = info.queue.as_deref().unwrap_or(DEFAULT_QUEUE);
+        let handler = info.metadata.as_ref().map(|m| m.handler.as_str());
+        let tenant = info.tenant_id.as_deref();
+        self.status
+            .as_deref()
+            .is_none_or(|s| s == info.status.label())
+            && self.queue.as_deref().is_none_or(|q| q == queue)
+            && self.handler.as_deref().is_none_or(|h| Some(h) == handler)
+            && self.tenant.as_deref().is_none_or(|t| Some(t) == tenant)
+    }
+}
+
+/// The routes of the API over the jobs of `registry`.
+pub fn router<J, S>(registry: JobRegistry<J>) -> Router<S>
+where
+    J: Job<Output = Value, Error = SerializableError, Metadata = Payload>,
+    J::Status: Serialize,
+    S: Clone + Send + Sync + 'static,
+{
+    routes(Api {
+        registry,
+        descriptions: None,
+    })
+}
+
+/// Like [`router`], describing the statuses of the jobs with `catalog`.
+pub fn router_with_catalog<J, S>(
+    registry: JobRegistry<J>,
+    catalog: Catalog,
+) -> Router<S>
+where
+    J: Job<Output = Value, Error = SerializableError, Metadata = Payload>,
+    J::Status: Serialize + Describe,
+    S: Clone + Send + Sync + 'static,
+{
+    let descriptions = Descriptions {
+        catalog: Arc::new(catalog),
+        key: |status| status.message_key(),
+    };
+    routes(Api {
+        registry,
+        descriptions: Some(descriptions),
+    })
+    .layer(middleware::from_fn(vary_language))
+}
+
+/// The state of the routes.
+struct Api<J: Job> {
+    registry: JobRegistry<J>,
+    descriptions: Option<Descriptions<J::Status>>,
+}
+
+impl<J: Job> Clone for Api<J> {
+    fn clone(&self) -> Self {
+        Self {
+            registry: self.registry.clone(),
+            descriptions: self.descriptions.clone(),
+        }
+    }
+}
+
+impl<J: Job> FromRef<Api<J>> for JobRegistry<J> {
+    fn from_ref(api: &Api<J>) -> Self {
+        api.registry.clone()
+    }
+}
+
+impl<J: Job> FromRef<Api<J>> for Option<Descriptions<J::Status>> {
+    fn from_ref(api: &Api<J>) -> Self {
+        api.descriptions.clone()
+    }
+}
+
+/// How the statuses of the jobs are described.
+struct Descriptions<Status> {
+    catalog: Arc<Catalog>,
+    /// The [message key](Describe::message_key) of a status.
+    key: fn(&StatusType<Status>) -> String,
+}
+
+impl<Status> Clone for Descriptions<Status> {
+    fn clone(&self) -> Self {
+        Self {
+            catalog: self.catalog.clone(),
+            key: self.key,
+        }
+    }
+}
+
+impl<Status> Descriptions<Status> {
+    /// The descriptions in the locale best matching the request with
+    /// `headers`.
+    fn localized(self, headers: &HeaderMap) -> Localized<Status> {
+        let accepted = headers
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|languages| languages.to_str().ok())
+            .unwrap_or_default();
+        let locale = self.catalog.negotiate(accepted).to_string();
+        Localized {
+            descriptions: self,
+            locale,
+        }
+    }
+}
+
+/// The descriptions of the statuses of the jobs in a locale.
+struct Localized<Status> {
+    descriptions: Descriptions<Status>,
+    locale: String,
+}
+
+impl<Status> Localized<Status> {
+    fn describe(&self, status: &StatusType<Status>) -> String {
+        let key = (self.descriptions.key)(status);
+        match self.descriptions.catalog.message(&self.locale, &key) {
+            Some(text) => text.to_string(),
+            None => key,
+        }
+    }
+}
+
+/// The JSON of `value`, with the description of `status` if the router has
+/// a catalog.
+fn to_json<T: Serialize, Status>(
+    value: &T,
+    status: &StatusType<Status>,
+    localized: Option<&Localized<Status>>,
+) -> Result<Value, std::io::Error> {
+    let mut json = serde_json::to_value(value)?;
+    if let (Some(localized), Value::Object(fields)) = (localized, &mut json) {
+        let description = localized.describe(status);
+        fields.insert("description".to_string(), description.into());
+    }
+    Ok(json)
+}
+
+/// Mark the responses as depending on the `Accept-Language` of the
+/// request, as their descriptions do.
+async fn vary_language(request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    let value = HeaderValue::from_static("accept-language");
+    response.headers_mut().append(VARY, value);
+    response
+}
+
+fn routes<J, S>(api: Api<J>) -> Router<S>
+where
+    J: Job<Output = Value, Error = SerializableError, Metadata = Payload>,
+    J::Status: Serialize,
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/jobs", post(submit::<J>).get(list::<J>))
+        .route("/jobs/status", post(statuses::<J>))
+        .route("/jobs/{id}", get(load::<J>).delete(remove::<J>))
+        .route("/jobs/{id}/events", get(events::<J>))
+        .route("/jobs/{id}/cancel", post(cancel::<J>))
+        .route("/jobs/{id}/retry", post(retry::<J>))
+        .route("/dashboard", get(dashboard::<J>))
+        .with_state(api)
+        .layer(middleware::from_fn(compress))
+}
+
+/// An error of the backend, as a response.
+struct Error(std::io::Error);
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Self(error)
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        use std::io::ErrorKind;
+
+        let status = match (JobError::from_io(&self.0), self.0.kind()) {
+            (
+                Some(
+                    JobError::InvalidTransition { .. }
+                    | JobError::VersionConflict { .. },
+                ),
+                _,
+            ) => StatusCode::CONFLICT,
+            (_, ErrorKind::NotFound) => StatusCode::NOT_FOUND,
+            (_, ErrorKind::InvalidInput | ErrorKind::InvalidData) => {
+                StatusCode::BAD_REQUEST
+            }
+            (_, ErrorKind::Unsupported) => StatusCode::NOT_IMPLEMENTED,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = Json(json!({ "error": self.0.to_string() }));
+        (status, body).into_response()
+    }
+}
+
+/// Run the calls `f` to the backend on a blocking thread.
+async fn blocking<T, F>(f: F) -> Result<T, Error>
+where
+    F: FnOnce() -> Result<T, std::io::Error> + Send + 'static,
+    T: Send + 'static,
+{
+    let result = tokio::task::spawn_blocking(f)
+        .await
+        .map_err(std::io::Error::other)?;
+    Ok(result?)
+}
+
+/// The JSON of `value`, tagged from its content and `version`, or an empty
+/// `304 Not Modified` if the client has it already.
+fn tagged<T: Serialize>(
+    headers: &HeaderMap,
+    version: u64,
+    value: &T,
+) -> Result<Response, Error> {
+    let body = serde_json::to_vec(value).map_err(std::io::Error::from)?;
+    let etag = etag(version, &body);
+    if is_cached(headers, &etag) {
+        return Ok(not_modified(etag));
+    }
+    Ok(json_response(etag, body))
+}
+
+/// The tag of the JSON `body` of a job with `version`.
+fn etag(version: u64, body: &[u8]) -> String {
+    format!("\"{version}-{:08x}\"", crc32fast::hash(body))
+}
+
+/// The weak tag of a listing of the jobs with `statuses`.
+fn listing_etag<S: Serialize>(
+    statuses: &[JobStatus<S>],
+) -> Result<String, std::io::Error> {
+    let mut hasher = crc32fast::Hasher::new();
+    for status in statuses {
+        hasher.update(&serde_json::to_vec(status)?);
+    }
+    Ok(format!(
+        "W/\"{}-{:08x}\"",
+        statuses.len(),
+        hasher.finalize()
+    ))
+}
+
+/// Whether the `If-None-Match` header of the request has `etag`.
+fn is_cached(headers: &HeaderMap, etag: &str) -> bool {
+    let etag = etag.trim_start_matches("W/");
+    headers
+        .get(IF_NONE_MATCH)
+        .and_then(|tags| tags.to_str().ok())
+        .is_some_and(|tags| matches_etag(tags, etag))
+}
+
+fn not_modified(etag: String) -> Response {
+    (StatusCode::NOT_MODIFIED, [(ETAG, etag)]).into_response()
+}
+
+fn json_response(etag: String, body: Vec<u8>) -> Response {
+    let headers =
+        [(CONTENT_TYPE, "application/json".to_string()), (ETAG, etag)];
+    (headers, body).into_response()
+}
+
+/// Whether the `If-None-Match` header `tags` has `etag`, weakly compared.
+fn matches_etag(tags: &str, etag: &str) -> bool {
+    tags.split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// Whether the `Accept-Encoding` header `codings` accepts gzip.
+fn accepts_gzip(codings: &str) -> bool {
+    codings.split(',').any(|coding| {
+        let mut params = coding.split(';').map(str::trim);
+        let name = params.next().unwrap_or_default();
+        let quality = params
+            .find_map(|param| param.strip_prefix("q="))
+            .and_then(|q| q.parse::<f32>().ok())
+            .unwrap_or(1.0);
+        (name.eq_ignore_ascii_case("gzip") || name == "*") && quality > 0.0
+    })
+}
+
+/// Compress the large responses for the clients accepting gzip.
+async fn compress(request: Request, next: Next) -> Response {
+    let accepted = request
+        .headers()
+        .get(ACCEPT_ENCODING)
+        .and_then(|codings| codings.to_str().ok())
+        .is_some_and(accepts_gzip);
+    let response = next.run(request).await;
+    // Event streams would never be complete enough to compress.
+    let streamed = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_some_and(|t| t.as_bytes().starts_with(b"text/event-stream"));
+    if !accepted
+        || streamed
+        || response.headers().contains_key(CONTENT_ENCODING)
+    {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, usize::MAX).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    if bytes.len() < COMPRESSION_THRESHOLD {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    let Ok(compressed) =
+        encoder.write_all(&bytes).and_then(|_| encoder.finish())
+    else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let headers = &mut parts.headers;
+    headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+    headers.append(VARY, HeaderValue::from_static("accept-encoding"));
+    headers.remove(CONTENT_LENGTH);
+    // The compressed body isn't byte-for-byte the tagged one.
+    if let Some(etag) = headers.get(ETAG).and_then(|e| e.to_str().ok()) {
+        if !etag.starts_with("W/") {
+            if let Ok(weak) = HeaderValue::from_str(&format!("W/{etag}")) {
+                headers.insert(ETAG, weak);
+            }
+        }
+    }
+    Response::from_parts(parts, Body::from(compressed))
+}
+
+async fn submit<J>(
+    State(registry): State<JobRegistry<J>>,
+    Json(payload): Json<Payload>,
+) -> Result<impl IntoResponse, Error>
+where
+    J: Job<Output = Value, Error = SerializableError, Metadata = Payload>,
+{
+    let id = blocking(move || {
+        let handle = registry.submit(&payload.handler, &payload.payload)?;
+        Ok(handle.id())
+    })
+    .await?;
+    Ok((StatusCode::CREATED, Json(json!({ "id": id }))))
+}
+
+async fn list<J>(
+    State(registry): State<JobRegistry<J>>,
+    State(descriptions): State<Option<Descriptions<J::Status>>>,
+    QueryString(query): QueryString<Query>,
+    headers: HeaderMap,
+) -> Result<Response, Error>
+where
+    J: Job<Output = Value, Error = SerializableError, Metadata = Payload>,
+    J::Status: Serialize,
+{
+    let job = registry.job().clone();
+    let localized = descriptions.map(|d| d.localized(&headers));
+    blocking(move || {
+        // Jobs removed or unreadable since being listed are skipped, as by
+        // the scan.
+        let statuses: Vec<_> = job
+            .ids()?
+            .into_iter()
+            .filter_map(|id| job.load_status(id).ok())
+            .collect();
+        let mut etag = listing_etag(&statuses)?;
+        if let Some(localized) = &localized {
+            // The descriptions differ with the locale.
+            etag = format!("W/\"{}-{}", localized.locale, &etag[3..]);
+        }
+        if is_cached(&headers, &etag) {
+            return Ok(not_modified(etag));
+        }
+        let mut infos: Vec<_> =
+            job.scan()?.filter(|info| query.matches(info)).collect();
+        infos.sort_by_key(|info| std::cmp::Reverse(info.created_at));
+        infos.truncate(query.limit.unwrap_or(usize::MAX));
+        let infos = infos
+            .iter()
+            .map(|info| to_json(info, &info.status, localized.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(json_response(etag, serde_json::to_vec(&infos)?))
+    })
+    .await
+}
+
+/// The body of `POST /jobs/status`.
+#[derive(Deserialize)]
+struct Ids {
+    ids: Vec<Uuid>,
+}
+
+async fn statuses<J>(
+    State(registry): State<JobRegistry<J>>,
+    State(descriptions): State<Option<Descriptions<J::Status>>>,
+    headers: HeaderMap,
+    Json(Ids { ids }): Json<Ids>,
+) -> Result<Json<Vec<Value>>, Error>
+where
+    J: Job<Output = Value, Error = SerializableError, Metadata = Payload>,
+    J::Status: Serialize,
+{
+    if ids.len() > MAX_STATUS_IDS {
+        return Err(Error(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("at most {MAX_STATUS_IDS} ids are accepted"),
+        )));
+    }
+    let job = registry.job().clone();
+    let infos =
+        blocking(move || Ok(job.load_many(&ids).into_iter().zip(ids))).await?;
+    let localized = descriptions.map(|d| d.localized(&headers));
+    let statuses = infos
+        .map(|(info, id)| match info {
+            Ok(info) => {
+                let status = JobStatus::from(info);
+                to_json(&status, &status.status, localized.as_ref())
+            }
+            Err(e) => Ok(json!({ "id": id, "error": e.to_string() })),
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Json(statuses))
+}
+
+async fn load<J>(
+    State(registry): State<JobRegistry<J>>,
+    State(descriptions): State<Option<Descriptions<J::Status>>>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Response, Error>
+where
+    J: Job<Output = Value, Error = SerializableError, Metadata = Payload>,
+    J::Status: Serialize,
+{
+    let job = registry.job().clone();
+    let info = blocking(move || job.load(id)).await?;
+    let localized = descriptions.map(|d| d.localized(&headers));
+    let json = to_json(&info, &info.status, localized.as_ref())?;
+    tagged(&headers, info.version, &json)
+}
+
+async fn events<J>(
+    State(registry): State<JobRegistry<J>>,
+    State(descriptions): State<Option<Descriptions<J::Status>>>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, Error>
+where
+    J: Job<Output = Value, Error = SerializableError, Metadata = Payload>,
+    J::Status: Serialize,
+{
+    // Unknown jobs are errors rather than empty streams.
+    let job = registry.job().clone();
+    blocking(move || job.load_status(id)).await?;
+    let last = headers
+        .get("last-event-id")
+        .and_then(|id| id.to_str().ok())
+        .map(str::to_string);
+    let localized = descriptions.map(|d| d.localized(&headers));
+    let events = registry.job().subscribe(id).filter_map(move |info| {
+        let body = to_json(&info, &info.status, localized.as_ref())
+            .ok()
+            .map(|json| json.to_string());
+        let last = last.clone();
+        async move {
+            let body = body?;
+            let etag = etag(info.version, body.as_bytes());
+            let event = Event::default().id(etag.clone()).data(body);
+            (last != Some(etag)).then_some(Ok::<_, Infallible>(event))
+        }
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+async fn cancel<J>(
+    State(registry): State<JobRegistry<J>>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error>
+where
+    J: Job<Output = Value, Error = SerializableError, Metadata = Payload>,
+{
+    let job = registry.job().clone();
+    blocking(move || job.cancel(id, CancelReason::UserAction)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn retry<J>(
+    State(registry): State<JobRegistry<J>>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, Error>
+where
+    J: Job<Output = Value, Error = SerializableError, Metadata = Payload>,
+{
+    let id = blocking(move || Ok(registry.resubmit(id)?.id())).await?;
+    Ok((StatusCode::CREATED, Json(json!({ "id": id }))))
+}
+
+async fn remove<J>(
+    State(registry): State<JobRegistry<J>>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error>
+where
+    J: Job<Output = Value, Error = SerializableError, Metadata = Payload>,
+{
+    let job = registry.job().clone();
+    blocking(move || job.remove(id)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn dashboard<J>(
+    State(registry): State<JobRegistry<J>>,
+    State(descriptions): State<Option<Descriptions<J::Status>>>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, Error>
+where
+    J: Job<Output = Value, Error = SerializableError, Metadata = Payload>,
+    J::Status: Serialize,
+{
+    let job = registry.job().clone();
+    let snapshot = blocking(move || job.dashboard_snapshot()).await?;
+    let localized = descriptions.map(|d| d.localized(&headers));
+    let failures = snapshot
+        .recent_failures
+        .iter()
+        .map(|info| to_json(info, &info.status, localized.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Json(json!({
+        "at": snapshot.at,
+        "queue_depths": snapshot.queue_depths,
+        "status_counts": snapshot.status_counts,
+        "recent_failures": failures,
+        "oldest_pending_age": snapshot
+            .oldest_pending_age
+            .map(|age| age.as_secs_f64()),
+    })))
+}
@@ -0,0 +1,126 @@
+//! Strategies for the ids of new jobs.
+//!
+//! Jobs get random (version 4) UUIDs by default.  Wrap a backend in a
+//! [`WithIds`] to use another [`IdGenerator`], e.g. [`TimeOrderedIds`] so
+//! that ids sort by submission time, or submit jobs with an explicit id
+//! derived from a business key with [`from_key`] and
+//! [`Job::submit_with_id`].
+
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::{
+    hooks::DynHooks, layers::DynLayer, queue::QueueConfig, retry::Backoff,
+    secrets::SecretProvider, spawn::Spawner, Job,
+};
+
+/// Something able to generate the ids of new jobs.
+pub trait IdGenerator: Send + Sync {
+    /// A new id, different from all the ids already generated.
+    fn generate(&self) -> Uuid;
+}
+
+/// Random ids (UUID version 4), the default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RandomIds;
+
+impl IdGenerator for RandomIds {
+    fn generate(&self) -> Uuid {
+        Uuid::new_v4()
+    }
+}
+
+/// Time-ordered ids (UUID version 7): ids of jobs submitted later sort
+/// after the earlier ones (up to the millisecond).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TimeOrderedIds;
+
+impl IdGenerator for TimeOrderedIds {
+    fn generate(&self) -> Uuid {
+        Uuid::now_v7()
+    }
+}
+
+/// The id derived from a business key (UUID version 5), always the same
+/// for the same namespace and key.
+///
+/// `namespace` should be a UUID chosen once for each kind of key.
+pub fn from_key(namespace: &Uuid, key: &str) -> Uuid {
+    Uuid::new_v5(namespace, key.as_bytes())
+}
+
+/// A [`Job`] wrapping another backend, generating the ids of its jobs with
+/// a custom [`IdGenerator`].
+pub struct WithIds<J> {
+    inner: J,
+    generator: Arc<dyn IdGenerator>,
+}
+
+impl<J: Clone> Clone for WithIds<J> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            generator: self.generator.clone(),
+        }
+    }
+}
+
+impl<J: Job> WithIds<J> {
+    /// Wrap a backend, generating ids with `generator`.
+    pub fn new<G>(inner: J, generator: G) -> Self
+    where
+        G: IdGenerator + 'static,
+    {
+        Self {
+            inner,
+            generator: Arc::new(generator),
+        }
+    }
+
+    /// The wrapped backend.
+    pub fn inner(&self) -> &J {
+        &self.inner
+    }
+}
+
+impl<J: Job> Job for WithIds<J> {
+    type Output = J::Output;
+    type Error = J::Error;
+    type Metadata = J::Metadata;
+    type Status = J::Status;
+
+    delegate_storage!(inner);
+
+    fn hooks(&self) -> &[DynHooks<Self::Output, Self::Error>] {
+        self.inner.hooks()
+    }
+
+    fn layers(&self) -> &[DynLayer<Self::Output, Self::Error>] {
+        self.inner.layers()
+    }
+
+    fn save_backoff(&self) -> Backoff {
+        self.inner.save_backoff()
+    }
+
+    fn id_generator(&self) -> Arc<dyn IdGenerator> {
+        self.generator.clone()
+    }
+
+    fn spawner(&self) -> Arc<dyn Spawner> {
+        self.inner.spawner()
+    }
+
+    fn admit(&self) -> Result<(), std::io::Error> {
+        self.inner.admit()
+    }
+
+    fn secret_provider(&self) -> Arc<dyn SecretProvider> {
+        self.inner.secret_provider()
+    }
+
+    fn queue_config(&self) -> Arc<QueueConfig> {
+        self.inner.queue_config()
+    }
+}
@@ -0,0 +1,97 @@
+//! A compact index of the jobs in a job directory.
+//!
+//! With [`FSJob::with_index`](crate::FSJob::with_index), every save appends
+//! a line with the id, status and timestamps of the job to an index file, so
+//! queries on the status of many jobs (e.g. [`Job::counts`](crate::Job::counts)
+//! or a dashboard) read one small file instead of every job file.  The last
+//! line of a job wins; removed jobs get a line marking them so.
+//!
+//! The index is only as fresh as the saves made with it enabled: rebuild it
+//! with [`FSJob::rebuild_index`](crate::FSJob::rebuild_index) after saving
+//! without it, or after a crash between a save and its index line.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{JobInfo, StatusType};
+
+/// What the index knows about a job.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    /// The id of the job.
+    pub id: Uuid,
+    /// The status of the job, with its value as JSON.
+    pub status: StatusType<Value>,
+    /// When the job was submitted.
+    pub created_at: Option<DateTime<Utc>>,
+    /// When the job started executing.
+    pub started_at: Option<DateTime<Utc>>,
+    /// When the job reached a terminal status.
+    pub finished_at: Option<DateTime<Utc>>,
+    /// The tenant owning the job.
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+}
+
+impl IndexEntry {
+    /// The entry of a job.
+    pub fn of<O, E, M, S: Serialize>(
+        info: &JobInfo<O, E, M, S>,
+    ) -> Result<Self, std::io::Error> {
+        Ok(Self {
+            id: info.id,
+            status: serde_json::from_value(serde_json::to_value(
+                &info.status,
+            )?)?,
+            created_at: info.created_at,
+            started_at: info.started_at,
+            finished_at: info.finished_at,
+            tenant_id: info.tenant_id.clone(),
+        })
+    }
+}
+
+/// The line marking the job `id` as removed.
+pub(crate) fn removal(id: Uuid) -> Value {
+    serde_json::json!({ "id": id, "removed": true })
+}
+
+/// The current entries of an index, in the order jobs were first indexed.
+///
+/// Lines that can't be parsed (e.g. cut short by a crash) are skipped.
+pub(crate) fn fold(index: &str) -> Vec<IndexEntry> {
+    let mut entries: Vec<Option<IndexEntry>> = vec![];
+    let mut positions = HashMap::new();
+    for line in index.lines() {
+        let Ok(line) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        let removed = line.get("removed") == Some(&Value::Bool(true));
+        let entry = if removed {
+            let Some(id) = line.get("id").cloned() else {
+                continue;
+            };
+            let Ok(id) = serde_json::from_value::<Uuid>(id) else {
+                continue;
+            };
+            (id, None)
+        } else {
+            let Ok(entry) = serde_json::from_value::<IndexEntry>(line) else {
+                continue;
+            };
+            (entry.id, Some(entry))
+        };
+        match positions.get(&entry.0) {
+            Some(&i) => entries[i] = entry.1,
+            None => {
+                positions.insert(entry.0, entries.len());
+                entries.push(entry.1);
+            }
+        }
+    }
+    entries.into_iter().flatten().collect()
+}
@@ -0,0 +1,176 @@
+//! Job submissions from low-tech integration points.
+//!
+//! An [`Ingest`] maps named handlers to submissions: each [`Submission`]
+//! names its handler and carries text fields, which the handler maps to the
+//! metadata of a job.  Adapters build submissions from inbound data:
+//!
+//! * with the `form` feature, [`Submission::from_form`] parses the body of
+//!   an HTTP form post (`application/x-www-form-urlencoded`), to be called
+//!   from the route of any web framework;
+//! * with the `email` feature, [`Submission::from_email`] parses a raw
+//!   email, e.g. fetched by an IMAP client.
+
+use std::{collections::HashMap, sync::Arc};
+
+use uuid::Uuid;
+
+use crate::{Job, JobHandle};
+
+/// A request to submit a job, from an adapter.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Submission {
+    /// The name of the handler submitting the job.
+    pub handler: String,
+    /// The fields of the submission, e.g. form fields or email headers.
+    pub fields: HashMap<String, String>,
+    /// The free-form text of the submission, e.g. the body of an email.
+    pub body: String,
+}
+
+impl Submission {
+    /// Create an empty submission for a handler.
+    pub fn new(handler: &str) -> Self {
+        Self {
+            handler: handler.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Add a field.
+    pub fn with_field(mut self, name: &str, value: &str) -> Self {
+        self.fields.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// A field, if present.
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields.get(name).map(String::as_str)
+    }
+
+    /// Parse the body of an HTTP form post for a handler (typically taken
+    /// from the route).
+    ///
+    /// When a field is repeated, its last value is kept.
+    #[cfg(feature = "form")]
+    pub fn from_form(handler: &str, body: &[u8]) -> Self {
+        Self {
+            handler: handler.to_string(),
+            fields: form_urlencoded::parse(body).into_owned().collect(),
+            body: String::new(),
+        }
+    }
+
+    /// Parse a raw email.
+    ///
+    /// The handler is the first word of the subject, the fields are the
+    /// headers (with lowercase names) and the body is the text of the email
+    /// (its first `text/plain` part, for multipart emails).
+    #[cfg(feature = "email")]
+    pub fn from_email(raw: &[u8]) -> Result<Self, std::io::Error> {
+        use mailparse::MailHeaderMap;
+
+        let invalid = |e: mailparse::MailParseError| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+        };
+        let mail = mailparse::parse_mail(raw).map_err(invalid)?;
+        let subject =
+            mail.headers.get_first_value("Subject").unwrap_or_default();
+        let fields = mail
+            .headers
+            .iter()
+            .map(|h| (h.get_key().to_lowercase(), h.get_value()))
+            .collect();
+        let text = std::iter::once(&mail)
+            .chain(mail.subparts.iter())
+            .find(|part| part.ctype.mimetype == "text/plain")
+            .unwrap_or(&mail);
+        Ok(Self {
+            handler: subject
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+            fields,
+            body: text.get_body().map_err(invalid)?,
+        })
+    }
+}
+
+/// Submits the job of a [`Submission`].
+///
+/// Implemented for closures taking the backend and the submission.
+pub trait SubmissionHandler<J: Job>: Send + Sync {
+    /// Submit a job, mapping the submission to its metadata.
+    fn submit(
+        &self,
+        job: &J,
+        submission: Submission,
+    ) -> Result<JobHandle<J>, std::io::Error>;
+}
+
+impl<J, F> SubmissionHandler<J> for F
+where
+    J: Job,
+    F: Fn(&J, Submission) -> Result<JobHandle<J>, std::io::Error> + Send + Sync,
+{
+    fn submit(
+        &self,
+        job: &J,
+        submission: Submission,
+    ) -> Result<JobHandle<J>, std::io::Error> {
+        self(job, submission)
+    }
+}
+
+/// A set of named handlers, submitting jobs to a backend.
+pub struct Ingest<J: Job> {
+    job: J,
+    handlers: HashMap<String, Arc<dyn SubmissionHandler<J>>>,
+}
+
+impl<J: Job> Clone for Ingest<J> {
+    fn clone(&self) -> Self {
+        Self {
+            job: self.job.clone(),
+            handlers: self.handlers.clone(),
+        }
+    }
+}
+
+impl<J: Job> Ingest<J> {
+    /// Create an [`Ingest`] without handlers.
+    pub fn new(job: J) -> Self {
+        Self {
+            job,
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// Register a handler.
+    pub fn with_handler<H>(mut self, name: &str, handler: H) -> Self
+    where
+        H: SubmissionHandler<J> + 'static,
+    {
+        self.handlers.insert(name.to_string(), Arc::new(handler));
+        self
+    }
+
+    /// Submit the job of a submission with its handler.
+    ///
+    /// Fails with [`std::io::ErrorKind::NotFound`] for unknown handlers.
+    pub fn submit(
+        &self,
+        submission: Submission,
+    ) -> Result<Uuid, std::io::Error> {
+        let handler =
+            self.handlers.get(&submission.handler).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("no handler named {:?}", submission.handler),
+                )
+            })?;
+        handler
+            .submit(&self.job, submission)
+            .map(|handle| handle.id())
+    }
+}
@@ -0,0 +1,186 @@
+//! Job submissions from files dropped into a directory.
+//!
+//! A common way to integrate with systems that can only write files: each
+//! file dropped into the inbox of a [`DropDirectory`] submits a job.  The
+//! file name selects the handler (everything before the first `.`, so
+//! `resize.1234.json` goes to the `resize` handler) and the contents are
+//! passed to it as arguments.
+//!
+//! Once submitted, a file is moved to the `processed` subdirectory of the
+//! inbox; files that can't be submitted (no handler, or the handler
+//! returned an error) are moved to `failed`.  Files starting with a `.` are
+//! ignored, so writers should write to a hidden file and rename it once
+//! complete.
+//!
+//! ```no_run
+//! # use simple_jobs::{intake::DropDirectory, FSJob, Job};
+//! # async fn example() -> std::io::Result<()> {
+//! let job: FSJob<u16, String, String, String> = FSJob::new("/var/jobs".into());
+//! let inbox = DropDirectory::new(job, "/var/inbox".into())?.with_handler(
+//!     "count",
+//!     |job: &FSJob<_, _, _, _>, args: Vec<u8>| {
+//!         let text = String::from_utf8_lossy(&args).to_string();
+//!         job.submit(
+//!             |_, _, text: String| async move { Ok(text.lines().count() as u16) },
+//!             text,
+//!         )
+//!     },
+//! );
+//! inbox.run().await
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use uuid::Uuid;
+
+use crate::{Job, JobHandle};
+
+/// Submits the job for a file dropped into the inbox.
+///
+/// Implemented for closures taking the backend and the contents of the file.
+pub trait IntakeHandler<J: Job>: Send + Sync {
+    /// Submit a job with the contents of a file as arguments.
+    fn submit(
+        &self,
+        job: &J,
+        args: Vec<u8>,
+    ) -> Result<JobHandle<J>, std::io::Error>;
+}
+
+impl<J, F> IntakeHandler<J> for F
+where
+    J: Job,
+    F: Fn(&J, Vec<u8>) -> Result<JobHandle<J>, std::io::Error> + Send + Sync,
+{
+    fn submit(
+        &self,
+        job: &J,
+        args: Vec<u8>,
+    ) -> Result<JobHandle<J>, std::io::Error> {
+        self(job, args)
+    }
+}
+
+/// The outcome of handling one dropped file.
+#[derive(Debug)]
+pub struct Intake {
+    /// The name of the file.
+    pub file_name: String,
+    /// The id of the submitted job, or why it couldn't be submitted.
+    pub result: Result<Uuid, std::io::Error>,
+}
+
+/// A directory whose files are submitted as jobs.
+pub struct DropDirectory<J: Job> {
+    job: J,
+    inbox: PathBuf,
+    handlers: HashMap<String, Arc<dyn IntakeHandler<J>>>,
+    poll_interval: Duration,
+}
+
+impl<J: Job> DropDirectory<J> {
+    /// Default delay between two scans of the inbox.
+    pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Watch `inbox`, submitting jobs to `job`.
+    ///
+    /// Creates the inbox and its `processed` and `failed` subdirectories
+    /// if needed.
+    pub fn new(job: J, inbox: PathBuf) -> Result<Self, std::io::Error> {
+        let intake = Self {
+            job,
+            inbox,
+            handlers: HashMap::new(),
+            poll_interval: Self::POLL_INTERVAL,
+        };
+        std::fs::create_dir_all(intake.processed_dir())?;
+        std::fs::create_dir_all(intake.failed_dir())?;
+        Ok(intake)
+    }
+
+    /// Handle the files whose name starts with `name`.
+    pub fn with_handler<H>(mut self, name: &str, handler: H) -> Self
+    where
+        H: IntakeHandler<J> + 'static,
+    {
+        self.handlers.insert(name.to_string(), Arc::new(handler));
+        self
+    }
+
+    /// Change the delay between two scans of the inbox.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Where submitted files are moved.
+    pub fn processed_dir(&self) -> PathBuf {
+        self.inbox.join("processed")
+    }
+
+    /// Where files that couldn't be submitted are moved.
+    pub fn failed_dir(&self) -> PathBuf {
+        self.inbox.join("failed")
+    }
+
+    /// Handle all the files currently in the inbox.
+    ///
+    /// Fails only if the inbox can't be read or a file can't be moved out
+    /// of it.
+    pub fn scan(&self) -> Result<Vec<Intake>, std::io::Error> {
+        let mut files = vec![];
+        for entry in std::fs::read_dir(&self.inbox)? {
+            let entry = entry?;
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if entry.file_type()?.is_file() && !file_name.starts_with('.') {
+                files.push(file_name);
+            }
+        }
+        files.sort();
+        files
+            .into_iter()
+            .map(|file_name| {
+                let path = self.inbox.join(&file_name);
+                let result = self.submit(&file_name, &path);
+                let target = match result {
+                    Ok(_) => self.processed_dir(),
+                    Err(_) => self.failed_dir(),
+                };
+                std::fs::rename(&path, target.join(&file_name))?;
+                Ok(Intake { file_name, result })
+            })
+            .collect()
+    }
+
+    fn submit(
+        &self,
+        file_name: &str,
+        path: &Path,
+    ) -> Result<Uuid, std::io::Error> {
+        let name = file_name.split('.').next().unwrap_or_default();
+        let handler = self.handlers.get(name).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("no handler for {file_name}"),
+            )
+        })?;
+        let args = std::fs::read(path)?;
+        handler.submit(&self.job, args).map(|handle| handle.id())
+    }
+
+    /// Scan the inbox forever, every poll interval.
+    ///
+    /// Returns only if a scan fails.
+    pub async fn run(&self) -> Result<(), std::io::Error> {
+        loop {
+            self.scan()?;
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+}
@@ -0,0 +1,349 @@
+//! Middleware around job execution.
+//!
+//! A [`JobLayer`] receives the future built by the closure given to
+//! [`Job::submit`] and returns a new future wrapping it, in the spirit of
+//! `tower` layers.  Layers are attached to a backend with [`Layered`] and
+//! apply to every job submitted through it, so cross-cutting concerns
+//! (timeouts, concurrency and rate limits, logging...) are written once:
+//!
+//! ```
+//! # use simple_jobs::{FSJob, layers::{Layered, TimeoutLayer}};
+//! # use std::time::Duration;
+//! let job: FSJob<u16, String, (), ()> = FSJob::new("/tmp".into());
+//! let job = Layered::new(job).layer(TimeoutLayer::new(
+//!     Duration::from_secs(60),
+//!     || "timed out".to_string(),
+//! ));
+//! ```
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::Future;
+use tokio::{sync::Semaphore, time::Instant};
+use uuid::Uuid;
+
+use crate::{
+    hooks::DynHooks, ids::IdGenerator, queue::QueueConfig, retry::Backoff,
+    secrets::SecretProvider, spawn::Spawner, Job,
+};
+
+/// The boxed future of a job, as seen by layers.
+pub type JobFuture<Output, Error> =
+    Pin<Box<dyn Future<Output = Result<Output, Error>> + Send>>;
+
+/// Middleware wrapping the future of every job.
+pub trait JobLayer<Output, Error>: Send + Sync {
+    /// Wrap the future of the job `id`.
+    fn wrap(
+        &self,
+        id: Uuid,
+        fut: JobFuture<Output, Error>,
+    ) -> JobFuture<Output, Error>;
+
+    /// Wrap the future of the job `id`, given its metadata.
+    ///
+    /// Layers depending on the metadata downcast it to the metadata type of
+    /// the jobs.  Defaults to [`JobLayer::wrap`].
+    fn wrap_with_metadata(
+        &self,
+        id: Uuid,
+        _metadata: Option<&dyn Any>,
+        fut: JobFuture<Output, Error>,
+    ) -> JobFuture<Output, Error> {
+        self.wrap(id, fut)
+    }
+}
+
+impl<Output, Error, F> JobLayer<Output, Error> for F
+where
+    F: Fn(Uuid, JobFuture<Output, Error>) -> JobFuture<Output, Error>
+        + Send
+        + Sync,
+{
+    fn wrap(
+        &self,
+        id: Uuid,
+        fut: JobFuture<Output, Error>,
+    ) -> JobFuture<Output, Error> {
+        self(id, fut)
+    }
+}
+
+/// Shared, type-erased layer for a job with the given output and error.
+pub type DynLayer<Output, Error> = Arc<dyn JobLayer<Output, Error>>;
+
+/// Apply `layers` to the future of a job with the given metadata, the first
+/// layer being the outermost one.
+pub(crate) fn apply<Output, Error, Metadata: 'static>(
+    layers: &[DynLayer<Output, Error>],
+    id: Uuid,
+    metadata: &Option<Metadata>,
+    fut: JobFuture<Output, Error>,
+) -> JobFuture<Output, Error> {
+    let metadata = metadata.as_ref().map(|m| m as &dyn Any);
+    layers.iter().rev().fold(fut, |fut, layer| {
+        layer.wrap_with_metadata(id, metadata, fut)
+    })
+}
+
+/// Fail jobs that take longer than a given duration.
+pub struct TimeoutLayer<F> {
+    duration: Duration,
+    on_timeout: Arc<F>,
+}
+
+impl<F> TimeoutLayer<F> {
+    /// Fail the jobs running longer than `duration` with the error returned
+    /// by `on_timeout`.
+    pub fn new(duration: Duration, on_timeout: F) -> Self {
+        Self {
+            duration,
+            on_timeout: Arc::new(on_timeout),
+        }
+    }
+}
+
+impl<Output, Error, F> JobLayer<Output, Error> for TimeoutLayer<F>
+where
+    Output: Send + 'static,
+    Error: Send + 'static,
+    F: Fn() -> Error + Send + Sync + 'static,
+{
+    fn wrap(
+        &self,
+        _id: Uuid,
+        fut: JobFuture<Output, Error>,
+    ) -> JobFuture<Output, Error> {
+        let duration = self.duration;
+        let on_timeout = self.on_timeout.clone();
+        Box::pin(async move {
+            tokio::time::timeout(duration, fut)
+                .await
+                .unwrap_or_else(|_| Err(on_timeout()))
+        })
+    }
+}
+
+/// Limit the number of jobs running at the same time.
+///
+/// Jobs over the limit wait (in `Started` status) until a running job
+/// completes.
+#[derive(Clone)]
+pub struct ConcurrencyLimitLayer {
+    semaphore: Arc<Semaphore>,
+}
+
+impl ConcurrencyLimitLayer {
+    /// Allow at most `max` jobs to run at the same time.
+    pub fn new(max: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max)),
+        }
+    }
+}
+
+impl<Output, Error> JobLayer<Output, Error> for ConcurrencyLimitLayer
+where
+    Output: Send + 'static,
+    Error: Send + 'static,
+{
+    fn wrap(
+        &self,
+        _id: Uuid,
+        fut: JobFuture<Output, Error>,
+    ) -> JobFuture<Output, Error> {
+        let semaphore = self.semaphore.clone();
+        Box::pin(async move {
+            let _permit = semaphore.acquire_owned().await;
+            fut.await
+        })
+    }
+}
+
+type RateKey<Metadata> = Arc<dyn Fn(&Metadata) -> String + Send + Sync>;
+
+/// Limit how many jobs start per period, e.g. so jobs calling an external
+/// API respect its quota.
+///
+/// Jobs over the rate wait (in `Started` status) for their turn; starts
+/// are spread evenly over the period.  A keyed limiter (see
+/// [`RateLimitLayer::keyed`]) applies the rate to the jobs sharing a key
+/// separately, e.g. one quota per customer.
+pub struct RateLimitLayer<Metadata = ()> {
+    interval: Duration,
+    key: Option<RateKey<Metadata>>,
+    next: Arc<Mutex<HashMap<String, Instant>>>,
+}
+
+impl<Metadata> Clone for RateLimitLayer<Metadata> {
+    fn clone(&self) -> Self {
+        Self {
+            interval: self.interval,
+            key: self.key.clone(),
+            next: self.next.clone(),
+        }
+    }
+}
+
+impl RateLimitLayer {
+    /// Start at most `jobs` jobs every `period`.
+    pub fn new(jobs: u32, period: Duration) -> Self {
+        Self {
+            interval: period / jobs.max(1),
+            key: None,
+            next: Default::default(),
+        }
+    }
+}
+
+impl<Metadata> RateLimitLayer<Metadata> {
+    /// Start at most `jobs` jobs every `period` for each key returned by
+    /// `key` from the metadata of the jobs, e.g. a field of it.
+    ///
+    /// Jobs without metadata of type `Metadata` share the empty key.
+    pub fn keyed<F>(jobs: u32, period: Duration, key: F) -> Self
+    where
+        F: Fn(&Metadata) -> String + Send + Sync + 'static,
+    {
+        Self {
+            interval: period / jobs.max(1),
+            key: Some(Arc::new(key)),
+            next: Default::default(),
+        }
+    }
+
+    /// When the next job with `key` may start, reserving that turn.
+    fn turn(&self, key: String) -> Instant {
+        let now = Instant::now();
+        let mut next = self.next.lock().expect("cannot get lock");
+        // Forget the keys whose turns have passed.
+        next.retain(|_, at| *at > now);
+        let at = next.get(&key).copied().unwrap_or(now).max(now);
+        next.insert(key, at + self.interval);
+        at
+    }
+}
+
+impl<Output, Error, Metadata> JobLayer<Output, Error>
+    for RateLimitLayer<Metadata>
+where
+    Output: Send + 'static,
+    Error: Send + 'static,
+    Metadata: 'static,
+{
+    fn wrap(
+        &self,
+        id: Uuid,
+        fut: JobFuture<Output, Error>,
+    ) -> JobFuture<Output, Error> {
+        self.wrap_with_metadata(id, None, fut)
+    }
+
+    fn wrap_with_metadata(
+        &self,
+        _id: Uuid,
+        metadata: Option<&dyn Any>,
+        fut: JobFuture<Output, Error>,
+    ) -> JobFuture<Output, Error> {
+        let key = self
+            .key
+            .as_ref()
+            .zip(metadata.and_then(|m| m.downcast_ref::<Metadata>()))
+            .map(|(key, metadata)| key(metadata))
+            .unwrap_or_default();
+        let turn = self.turn(key);
+        Box::pin(async move {
+            tokio::time::sleep_until(turn).await;
+            fut.await
+        })
+    }
+}
+
+/// A [`Job`] wrapping another backend, applying [`JobLayer`]s to every
+/// job submitted through it.
+///
+/// Like [`Hooked`](crate::Hooked), it should be the outermost wrapper.
+pub struct Layered<J: Job> {
+    inner: J,
+    layers: Vec<DynLayer<J::Output, J::Error>>,
+}
+
+impl<J: Job> Clone for Layered<J> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            layers: self.layers.clone(),
+        }
+    }
+}
+
+impl<J: Job> Layered<J> {
+    /// Wrap a backend, keeping the layers it already has (if any).
+    pub fn new(inner: J) -> Self {
+        Self {
+            layers: inner.layers().to_vec(),
+            inner,
+        }
+    }
+
+    /// Add a layer, inside the ones already added.
+    pub fn layer<L>(mut self, layer: L) -> Self
+    where
+        L: JobLayer<J::Output, J::Error> + 'static,
+    {
+        self.layers.push(Arc::new(layer));
+        self
+    }
+
+    /// The wrapped backend.
+    pub fn inner(&self) -> &J {
+        &self.inner
+    }
+}
+
+impl<J: Job> Job for Layered<J> {
+    type Output = J::Output;
+    type Error = J::Error;
+    type Metadata = J::Metadata;
+    type Status = J::Status;
+
+    delegate_storage!(inner);
+
+    fn hooks(&self) -> &[DynHooks<Self::Output, Self::Error>] {
+        self.inner.hooks()
+    }
+
+    fn layers(&self) -> &[DynLayer<Self::Output, Self::Error>] {
+        &self.layers
+    }
+
+    fn save_backoff(&self) -> Backoff {
+        self.inner.save_backoff()
+    }
+
+    fn id_generator(&self) -> Arc<dyn IdGenerator> {
+        self.inner.id_generator()
+    }
+
+    fn spawner(&self) -> Arc<dyn Spawner> {
+        self.inner.spawner()
+    }
+
+    fn admit(&self) -> Result<(), std::io::Error> {
+        self.inner.admit()
+    }
+
+    fn secret_provider(&self) -> Arc<dyn SecretProvider> {
+        self.inner.secret_provider()
+    }
+
+    fn queue_config(&self) -> Arc<QueueConfig> {
+        self.inner.queue_config()
+    }
+}
@@ -32,18 +32,121 @@
 //!     let my_metadata = MyMetadata {};
 //!     let id = job.submit(|id, job, metadata| async move {
 //!         Ok(0u16)
-//!     }, my_metadata)?;
+//!     }, my_metadata)?.id();
 //!     let info = job.load(id)?;
 //!     println!("Job status: {:?}", info.status);
 //!     Ok(())
 //! }
 //! ```
 //!
+//! ## Stability
+//!
+//! The items re-exported at the crate root, and the [`prelude`], are the
+//! stable interface of the crate.  They are also reachable through the
+//! modules defining them, whose layout may change between minor releases
+//! as the crate grows.
+//!
 //! [`Tokio`]: https://tokio.rs/
 
-pub use self::fs_job::FSJob;
+pub use self::audit::AuditEntry;
+pub use self::batch::{Batch, BatchStatus};
+pub use self::cancel::CancelReason;
+pub use self::chain::JobChain;
+pub use self::context::{JobContext, LogLevel, LogLine};
+pub use self::dashboard::DashboardSnapshot;
+pub use self::describe::{Catalog, Describe};
+#[cfg(feature = "encryption")]
+pub use self::encrypted_job::EncryptedJob;
+pub use self::error::{JobError, SerializableError};
+pub use self::events::JobEvent;
+pub use self::failover_job::{FailoverJob, ReplicaRead, Resolution};
+pub use self::fs_job::{FSJob, FSJobBuilder};
+pub use self::handle::{JobHandle, UniqueSubmission};
+pub use self::history::StatusChange;
+pub use self::hooks::{Hooked, JobHooks};
+pub use self::ids::{IdGenerator, WithIds};
+pub use self::ingest::{Ingest, Submission};
+pub use self::intake::DropDirectory;
+pub use self::layers::{
+    ConcurrencyLimitLayer, JobFuture, JobLayer, Layered, RateLimitLayer,
+    TimeoutLayer,
+};
+pub use self::notifier::Notifier;
+pub use self::queue::{QueueConfig, WithQueues};
+pub use self::record::{Compression, RecordCodec};
+pub use self::registry::JobRegistry;
+pub use self::relay::Relay;
+pub use self::retry::{Backoff, RetrySaves};
+pub use self::run::SubmitOptions;
+pub use self::secrets::{SecretProvider, Secrets, WithSecrets};
+pub use self::sharded_job::{ConsistentHash, Partitioner, ShardedJob};
+pub use self::spawn::{Spawner, WithSpawner};
+pub use self::stats::{JobStats, Percentiles};
+pub use self::supervisor::JobSupervisor;
+pub use self::versioning::Schema;
+pub use self::watch::watch;
 
+#[macro_use]
+mod macros;
+
+pub mod alerts;
+pub mod archive;
+pub mod audit;
+pub mod batch;
+pub mod blobs;
+pub mod cancel;
+pub mod chain;
+#[cfg(feature = "client")]
+pub mod client;
+pub mod context;
+pub mod dashboard;
+pub mod describe;
+#[cfg(feature = "encryption")]
+pub mod encrypted_job;
+pub mod error;
+pub mod events;
+pub mod failover_job;
+pub mod format;
 pub mod fs_job;
+mod handle;
+pub mod history;
+pub mod hooks;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod ids;
+pub mod index;
+pub mod ingest;
+pub mod intake;
+pub mod layers;
+mod local;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod migrate;
+pub mod naming;
+pub mod notifier;
+pub mod prelude;
+#[cfg(any(feature = "kafka", feature = "nats"))]
+pub mod publish;
+pub mod queue;
+pub mod record;
+pub mod registry;
+pub mod relay;
+pub mod retention;
+pub mod retry;
+mod run;
+pub mod schedule;
+pub mod secrets;
+pub mod sharded_job;
+#[cfg(feature = "tracing")]
+pub mod spans;
+pub mod spawn;
+pub mod stats;
+pub mod supervisor;
+pub mod versioning;
+pub mod watch;
+#[cfg(feature = "webhooks")]
+pub mod webhooks;
+pub mod worker;
 
 // #[cfg(feature = "diesel_jobs")]
 // #[macro_use]
@@ -55,140 +158,1547 @@ pub mod fs_job;
 // #[cfg(feature = "diesel_jobs")]
 // pub mod schema;
 
-use std::{fmt::Debug, time::Duration};
+use std::{
+    any::Any,
+    collections::{BTreeMap, VecDeque},
+    fmt::Debug,
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use futures::{Future, Stream, StreamExt};
+use hooks::DynHooks;
+use layers::DynLayer;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Interval between two loads when polling a backend for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Type for Status values.
+///
+/// The user can implement this trait to provide their own status values.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum StatusType<T> {
+    Started,
+    StatusValue(T),
+    Finished,
+    /// The job ended without producing a result (e.g. it panicked); holds
+    /// the reason.
+    Failed(String),
+    /// The job was canceled before completing.
+    Canceled(CancelReason),
+    /// The process running the job shut down before it completed (see
+    /// [`JobSupervisor::shutdown`]).
+    Interrupted,
+    /// The job was saved by [`Job::enqueue`], and waits for a worker to
+    /// claim it (see [`Job::claim_next`]).
+    Pending,
+}
+
+impl<T> StatusType<T> {
+    /// Whether the job is done, and its status will not change anymore.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            StatusType::Finished
+                | StatusType::Failed(_)
+                | StatusType::Canceled(_)
+                | StatusType::Interrupted
+        )
+    }
+
+    /// Whether the job is not done yet.
+    pub fn is_active(&self) -> bool {
+        !self.is_terminal()
+    }
+
+    /// A short name for the kind of status, without its value.
+    pub fn label(&self) -> &'static str {
+        match self {
+            StatusType::Started => "started",
+            StatusType::StatusValue(_) => "status value",
+            StatusType::Finished => "finished",
+            StatusType::Failed(_) => "failed",
+            StatusType::Canceled(_) => "canceled",
+            StatusType::Interrupted => "interrupted",
+            StatusType::Pending => "pending",
+        }
+    }
+}
+
+impl<T: PartialEq> StatusType<T> {
+    /// Whether a job may go from this status to `next`.
+    ///
+    /// Jobs go from [`StatusType::Started`] (possibly after
+    /// [`StatusType::Pending`]) through any number of
+    /// [`StatusType::StatusValue`]s to a terminal status, which never
+    /// changes.  Active jobs may go back to [`StatusType::Pending`], to be
+    /// claimed again after a failed attempt.
+    pub fn can_transition_to(&self, next: &StatusType<T>) -> bool {
+        match (self, next) {
+            (StatusType::Pending, _) => true,
+            (current, StatusType::Pending) => !current.is_terminal(),
+            (StatusType::Started, _) => true,
+            (_, StatusType::Started) => false,
+            (current, _) => !current.is_terminal(),
+        }
+    }
+}
+
+/// Metadata for a job.
+///
+/// This is the data that gets saved and restored.
+///
+/// The field result is `None` while there is no output from the job. On completion,
+/// the proper branch for `Result` is set.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JobInfo<Output, Error, Metadata, Status> {
+    /// The unique id for a job (UUID v4).
+    pub id: Uuid,
+    /// Job status (see [`StatusType`]).
+    pub status: StatusType<Status>,
+    /// Result of the job (`None` while there is no output).
+    pub result: Option<Result<Output, Error>>,
+    /// Metadata passed to the job by the user at start time.
+    pub metadata: Option<Metadata>,
+    /// When the job was submitted.
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    /// When the job started executing.
+    #[serde(default)]
+    pub started_at: Option<DateTime<Utc>>,
+    /// When the job reached a terminal status.
+    #[serde(default)]
+    pub finished_at: Option<DateTime<Utc>>,
+    /// The label of the worker that ran the job (see [`worker`]), set when
+    /// the job starts executing.
+    #[serde(default)]
+    pub worker: Option<String>,
+    /// The region the job was submitted from (see [`worker::set_region`]).
+    #[serde(default)]
+    pub origin: Option<String>,
+    /// The key of a job submitted with [`Job::submit_unique`], or enqueued
+    /// with [`EnqueueOptions::unique`](queue::EnqueueOptions::unique).
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+    /// The tenant owning the job (see [`SubmitOptions::tenant`]).
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+    /// The lease of the worker running the job, if it claimed it from the
+    /// queue (see [`Job::claim_next`]).
+    #[serde(default)]
+    pub lease: Option<queue::Lease>,
+    /// The failed attempts of a job claimed from the queue, oldest first
+    /// (see [`QueueConfig::with_max_attempts`]).
+    #[serde(default = "Vec::new")]
+    pub attempts: Vec<queue::Attempt<Error>>,
+    /// When the job ran out of attempts (see [`Job::dead_letters`]).
+    #[serde(default)]
+    pub dead_lettered_at: Option<DateTime<Utc>>,
+    /// The priority of a pending job, higher first (see
+    /// [`Job::enqueue_with_priority`]).
+    #[serde(default)]
+    pub priority: u8,
+    /// The queue of an enqueued job (see [`Job::enqueue_to`]).
+    #[serde(default)]
+    pub queue: Option<String>,
+    /// The key shared by the enqueued jobs that must not run at the same
+    /// time (see [`EnqueueOptions::concurrency_key`](queue::EnqueueOptions::concurrency_key)).
+    #[serde(default)]
+    pub concurrency_key: Option<String>,
+    /// The key of the group of enqueued jobs sharing a window limit (see
+    /// [`EnqueueOptions::throttle_key`](queue::EnqueueOptions::throttle_key)).
+    #[serde(default)]
+    pub throttle_key: Option<String>,
+    /// The resources an enqueued job holds while it runs (see
+    /// [`EnqueueOptions::resource`](queue::EnqueueOptions::resource)).
+    #[serde(default)]
+    pub resources: Vec<String>,
+    /// The last checkpoint of the job, as JSON (see
+    /// [`JobContext::checkpoint`]).
+    #[serde(default)]
+    pub checkpoint: Option<serde_json::Value>,
+    /// The job this one is a new run of (see [`Job::resubmit`]).
+    #[serde(default)]
+    pub resubmitted_from: Option<Uuid>,
+    /// How long the record of the job is kept once it ends (see
+    /// [`retention`]).
+    #[serde(default)]
+    pub retention: Option<Duration>,
+    /// The batch of the job (see [`Job::submit_batch`]).
+    #[serde(default)]
+    pub batch_id: Option<Uuid>,
+    /// The URLs notified when the job ends, besides the global ones (see
+    /// the module `webhooks`, with the feature `webhooks`).
+    #[serde(default)]
+    pub webhooks: Vec<String>,
+    /// The trace context of the submitter, as W3C Trace Context headers
+    /// (see the module `spans`, with the feature `opentelemetry`).
+    #[serde(default)]
+    pub trace_context: BTreeMap<String, String>,
+    /// Incremented by every save through [`Job::save_if_version`], to
+    /// detect concurrent changes.
+    #[serde(default)]
+    pub version: u64,
+}
+
+impl<Output, Error, Metadata, Status> Default
+    for JobInfo<Output, Error, Metadata, Status>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Output, Error, Metadata, Status> JobInfo<Output, Error, Metadata, Status> {
+    /// Create new information for a job.
+    ///
+    /// Usually, the user does not need to create this struct manually.
+    pub fn new() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            status: StatusType::Started,
+            result: None,
+            metadata: None,
+            created_at: Some(Utc::now()),
+            started_at: None,
+            finished_at: None,
+            worker: None,
+            origin: worker::region(),
+            idempotency_key: None,
+            tenant_id: None,
+            lease: None,
+            attempts: vec![],
+            dead_lettered_at: None,
+            priority: 0,
+            queue: None,
+            concurrency_key: None,
+            throttle_key: None,
+            resources: vec![],
+            checkpoint: None,
+            resubmitted_from: None,
+            retention: None,
+            batch_id: None,
+            webhooks: vec![],
+            #[cfg(feature = "opentelemetry")]
+            trace_context: spans::current_context(),
+            #[cfg(not(feature = "opentelemetry"))]
+            trace_context: BTreeMap::new(),
+            version: 0,
+        }
+    }
+
+    /// Change the status of the job, if valid (see
+    /// [`StatusType::can_transition_to`]).
+    ///
+    /// Backends and wrappers changing the status of a stored job should go
+    /// through this method.
+    pub fn transition(
+        &mut self,
+        next: StatusType<Status>,
+    ) -> Result<(), JobError>
+    where
+        Status: PartialEq,
+    {
+        if !self.status.can_transition_to(&next) {
+            return Err(JobError::InvalidTransition {
+                id: self.id,
+                from: self.status.label(),
+                to: next.label(),
+            });
+        }
+        self.status = next;
+        Ok(())
+    }
+
+    /// Time the job waited between being submitted and starting to execute.
+    pub fn queue_latency(&self) -> Option<Duration> {
+        (self.started_at? - self.created_at?).to_std().ok()
+    }
+
+    /// Time the job took executing, once it finished.
+    pub fn run_duration(&self) -> Option<Duration> {
+        (self.finished_at? - self.started_at?).to_std().ok()
+    }
+}
+
+/// The status of a job and its timestamps, without its result and metadata
+/// (see [`Job::load_status`]).
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct JobStatus<Status> {
+    /// The id of the job.
+    pub id: Uuid,
+    /// Job status (see [`StatusType`]).
+    pub status: StatusType<Status>,
+    /// When the job was submitted.
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    /// When the job started executing.
+    #[serde(default)]
+    pub started_at: Option<DateTime<Utc>>,
+    /// When the job reached a terminal status.
+    #[serde(default)]
+    pub finished_at: Option<DateTime<Utc>>,
+    /// The [version](JobInfo::version) of the record.
+    #[serde(default)]
+    pub version: u64,
+}
+
+impl<Output, Error, Metadata, Status>
+    From<JobInfo<Output, Error, Metadata, Status>> for JobStatus<Status>
+{
+    fn from(info: JobInfo<Output, Error, Metadata, Status>) -> Self {
+        Self {
+            id: info.id,
+            status: info.status,
+            created_at: info.created_at,
+            started_at: info.started_at,
+            finished_at: info.finished_at,
+            version: info.version,
+        }
+    }
+}
+
+/// Convenience alias for using [`JobInfo`] together with the associated types
+/// from [`Job`].
+type Info<T> = JobInfo<
+    <T as Job>::Output,
+    <T as Job>::Error,
+    <T as Job>::Metadata,
+    <T as Job>::Status,
+>;
+
+/// Convenience alias for using [`DashboardSnapshot`] together with the
+/// associated types from [`Job`].
+type Snapshot<T> = DashboardSnapshot<
+    <T as Job>::Output,
+    <T as Job>::Error,
+    <T as Job>::Metadata,
+    <T as Job>::Status,
+>;
+
+/// A job.
+///
+/// This is the main trait that the user should implement.
+pub trait Job: Clone + Send + Sync + 'static {
+    type Output: Send + 'static;
+    type Error: Clone + Send + 'static;
+    type Metadata: Clone + Send + 'static;
+    type Status: PartialEq + Clone + Send + 'static;
+
+    /// Save the job metadata.
+    ///
+    /// Given a reference to a [`JobInfo`], save it in the chosen backend.
+    fn save(&self, info: &Info<Self>) -> Result<(), std::io::Error>;
+
+    /// Load the metadata for a job.
+    ///
+    /// Given the id for a job, build a [`JobInfo`] from the chosen backend.
+    fn load(&self, id: Uuid) -> Result<Info<Self>, std::io::Error>;
+
+    /// Load the status and timestamps of a job, without its result and
+    /// metadata.
+    ///
+    /// Used by [`wait`] to poll jobs.  The default implementation calls
+    /// [`Job::load`]; backends able to read the status alone, sparing the
+    /// deserialization of large results, should override it.
+    fn load_status(
+        &self,
+        id: Uuid,
+    ) -> Result<JobStatus<Self::Status>, std::io::Error> {
+        self.load(id).map(JobStatus::from)
+    }
+
+    /// Load the metadata for several jobs.
+    ///
+    /// Returns one result per id, in the same order as `ids`.  The default
+    /// implementation calls [`Job::load`] for each id; backends able to fetch
+    /// many records in one round-trip should override it.
+    fn load_many(
+        &self,
+        ids: &[Uuid],
+    ) -> Vec<Result<Info<Self>, std::io::Error>> {
+        ids.iter().map(|id| self.load(*id)).collect()
+    }
+
+    /// Save several jobs.
+    ///
+    /// Returns one result per job, in the same order as `infos`.  The
+    /// default implementation calls [`Job::save`] for each job; backends
+    /// able to write many records in one round-trip should override it.
+    fn save_many(
+        &self,
+        infos: &[Info<Self>],
+    ) -> Vec<Result<(), std::io::Error>> {
+        infos.iter().map(|info| self.save(info)).collect()
+    }
+
+    /// Save `info` only if the version in the backend is still `expected`,
+    /// i.e. if nobody saved the job since it was read (compare-and-swap).
+    ///
+    /// The record is saved with the next version, which is returned and set
+    /// in `info` (which is left untouched if the save fails).  Fails
+    /// with a [`JobError::VersionConflict`](error::JobError::VersionConflict)
+    /// if the record changed.  The default implementation is only atomic
+    /// within this process; backends able to compare and write records
+    /// atomically (e.g. in a transaction) should override it.
+    fn save_if_version(
+        &self,
+        info: &mut Info<Self>,
+        expected: u64,
+    ) -> Result<u64, std::io::Error> {
+        let lock = local::record_lock(info.id);
+        let _guard = lock.lock().expect("cannot get lock");
+        let found = self.load(info.id)?.version;
+        if found != expected {
+            return Err(error::JobError::VersionConflict {
+                id: info.id,
+                expected,
+                found,
+            }
+            .into());
+        }
+        info.version = expected + 1;
+        if let Err(e) = self.save(info) {
+            info.version = expected;
+            return Err(e);
+        }
+        Ok(info.version)
+    }
+
+    /// Atomically update the record of a job (read-modify-write).
+    ///
+    /// Loads the job, passes it to `f` and saves it with
+    /// [`Job::save_if_version`], unless `f` fails.  When the job was saved
+    /// in between (e.g. by its own task), the update is retried on the new
+    /// record, so `f` may be called several times.  Returns the saved
+    /// information, or the conflict error if the retries are exhausted.
+    ///
+    /// Use it for any change to a running job, including status updates
+    /// from inside the job, so they don't race with the other writers.
+    fn update<F>(
+        &self,
+        id: Uuid,
+        mut f: F,
+    ) -> Result<Info<Self>, std::io::Error>
+    where
+        F: FnMut(&mut Info<Self>) -> Result<(), std::io::Error>,
+    {
+        let mut conflicts = 0;
+        loop {
+            let mut info = self.load(id)?;
+            let expected = info.version;
+            f(&mut info)?;
+            match self.save_if_version(&mut info, expected) {
+                Ok(_) => return Ok(info),
+                Err(e)
+                    if run::is_conflict(&e)
+                        && conflicts < run::MAX_CONFLICTS =>
+                {
+                    conflicts += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Change the metadata of a job, e.g. to annotate a running job.
+    ///
+    /// The update goes through [`Job::update`], and the job's own progress
+    /// saves keep the metadata found in the backend, so the change is not
+    /// lost when the job later saves its status.
+    fn update_metadata<F>(
+        &self,
+        id: Uuid,
+        mut f: F,
+    ) -> Result<Info<Self>, std::io::Error>
+    where
+        F: FnMut(&mut Option<Self::Metadata>),
+    {
+        self.update(id, |info| {
+            f(&mut info.metadata);
+            Ok(())
+        })
+    }
+
+    /// The ids of all the jobs saved in the backend.
+    ///
+    /// Backends that can't enumerate their jobs fail with
+    /// [`std::io::ErrorKind::Unsupported`], the default.
+    fn ids(&self) -> Result<Vec<Uuid>, std::io::Error> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "this backend cannot list its jobs",
+        ))
+    }
+
+    /// The statuses the job went through, oldest first (see [`history`]).
+    ///
+    /// Backends that don't keep them fail with
+    /// [`std::io::ErrorKind::Unsupported`], the default.
+    fn history(
+        &self,
+        _id: Uuid,
+    ) -> Result<Vec<StatusChange<Self::Status>>, std::io::Error> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "this backend does not keep the history of jobs",
+        ))
+    }
+
+    /// The saves of the job, oldest first (see [`audit`]).
+    ///
+    /// Backends that don't keep them fail with
+    /// [`std::io::ErrorKind::Unsupported`], the default.
+    fn audit(
+        &self,
+        _id: Uuid,
+    ) -> Result<Vec<AuditEntry<Self::Status>>, std::io::Error> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "this backend does not keep an audit log",
+        ))
+    }
+
+    /// A stream yielding each time the record of the job `id` may have
+    /// changed, used by [`wait`] and [`Job::subscribe`] to know when to load
+    /// it again.
+    ///
+    /// Changes made after the stream is created are never missed, but the
+    /// stream may also yield when nothing changed.  The default yields every
+    /// few milliseconds (i.e. polls); backends able to be notified of changes
+    /// should override it.
+    fn changes(&self, _id: Uuid) -> impl Stream<Item = ()> + Send + 'static {
+        poll_changes()
+    }
+
+    /// Delete a job (its record, and any logs and outputs) from the backend.
+    ///
+    /// Backends that can't delete jobs fail with
+    /// [`std::io::ErrorKind::Unsupported`], the default.
+    fn remove(&self, _id: Uuid) -> Result<(), std::io::Error> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "this backend cannot remove jobs",
+        ))
+    }
+
+    /// Iterate over the jobs of the backend (see [`Job::ids`]), loading
+    /// each one as the iterator reaches it.
+    ///
+    /// Jobs that can't be loaded are skipped, publishing a
+    /// [`JobEvent::Unreadable`], rather than failing the whole scan; jobs
+    /// removed since they were listed are silently skipped.
+    fn scan(
+        &self,
+    ) -> Result<impl Iterator<Item = Info<Self>> + '_, std::io::Error> {
+        Ok(self
+            .ids()?
+            .into_iter()
+            .filter_map(|id| match self.load(id) {
+                Ok(info) => Some(info),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => {
+                    events::publish(JobEvent::Unreadable {
+                        id,
+                        error: e.to_string(),
+                    });
+                    None
+                }
+            }))
+    }
+
+    /// All the readable jobs of the backend (see [`Job::scan`]).
+    fn list(&self) -> Result<Vec<Info<Self>>, std::io::Error> {
+        Ok(self.scan()?.collect())
+    }
+
+    /// The number of jobs of the backend by status, keyed by
+    /// [`StatusType::label`].
+    fn counts(&self) -> Result<BTreeMap<&'static str, usize>, std::io::Error> {
+        let mut counts = BTreeMap::new();
+        for info in self.scan()? {
+            *counts.entry(info.status.label()).or_default() += 1;
+        }
+        Ok(counts)
+    }
+
+    /// The ids of the jobs of `tenant` (see [`SubmitOptions::tenant`]).
+    ///
+    /// The default implementation loads every job (see [`Job::scan`]);
+    /// backends able to query jobs by tenant should override it.
+    fn tenant_ids(&self, tenant: &str) -> Result<Vec<Uuid>, std::io::Error> {
+        Ok(self
+            .scan()?
+            .filter(|info| info.tenant_id.as_deref() == Some(tenant))
+            .map(|info| info.id)
+            .collect())
+    }
+
+    /// Pause or resume the named `queue` (see [`Job::pause_queue`]).
+    ///
+    /// Backends that can't record it fail with
+    /// [`std::io::ErrorKind::Unsupported`], the default.
+    fn set_queue_paused(
+        &self,
+        _queue: &str,
+        _paused: bool,
+    ) -> Result<(), std::io::Error> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "this backend cannot pause queues",
+        ))
+    }
+
+    /// The names of the paused queues.
+    ///
+    /// Defaults to none, for backends that can't pause queues.
+    fn paused_queues(&self) -> Result<Vec<String>, std::io::Error> {
+        Ok(vec![])
+    }
+
+    /// Take the lease `name` as `lease`, e.g. to elect a leader among the
+    /// processes sharing the backend (see [`schedule::Scheduler`]); whether
+    /// it was taken.
+    ///
+    /// The lease is taken if nobody holds it, if it expired, or to renew it
+    /// if the holder has the same [token](queue::Lease::token).  Backends
+    /// must compare and write atomically across processes; those that can't
+    /// fail with [`std::io::ErrorKind::Unsupported`], the default.
+    fn acquire_lease(
+        &self,
+        _name: &str,
+        _lease: &queue::Lease,
+    ) -> Result<bool, std::io::Error> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "this backend cannot hold leases",
+        ))
+    }
+
+    /// Give up the lease `name` if it is held with `token` (see
+    /// [`Job::acquire_lease`]), so another process can take it without
+    /// waiting for it to expire.
+    fn release_lease(
+        &self,
+        _name: &str,
+        _token: Uuid,
+    ) -> Result<(), std::io::Error> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "this backend cannot hold leases",
+        ))
+    }
+
+    /// All the readable jobs of `tenant`.
+    fn list_tenant(
+        &self,
+        tenant: &str,
+    ) -> Result<Vec<Info<Self>>, std::io::Error> {
+        let ids = self.tenant_ids(tenant)?;
+        Ok(self
+            .load_many(&ids)
+            .into_iter()
+            .flatten()
+            // The job may have changed since it was listed.
+            .filter(|info| info.tenant_id.as_deref() == Some(tenant))
+            .collect())
+    }
+
+    /// Remove the terminal jobs of `tenant` for which `f` returns `true`
+    /// (see [`Job::purge`]), returning their ids.
+    fn purge_tenant<F>(
+        &self,
+        tenant: &str,
+        mut f: F,
+    ) -> Result<Vec<Uuid>, std::io::Error>
+    where
+        F: FnMut(&Info<Self>) -> bool,
+    {
+        let mut removed = vec![];
+        for info in self.list_tenant(tenant)? {
+            if info.status.is_terminal() && f(&info) {
+                self.remove(info.id)?;
+                removed.push(info.id);
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Remove the terminal jobs for which `f` returns `true`, e.g. the ones
+    /// finished long ago, returning their ids.
+    ///
+    /// Jobs still running are never removed.
+    fn purge<F>(&self, mut f: F) -> Result<Vec<Uuid>, std::io::Error>
+    where
+        F: FnMut(&Info<Self>) -> bool,
+    {
+        let mut removed = vec![];
+        for info in self.scan()? {
+            if info.status.is_terminal() && f(&info) {
+                self.remove(info.id)?;
+                removed.push(info.id);
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Append a line to the log of a job, stored apart from its record.
+    ///
+    /// Backends without log storage fail with
+    /// [`std::io::ErrorKind::Unsupported`], the default.
+    fn append_log(
+        &self,
+        _id: Uuid,
+        _line: &LogLine,
+    ) -> Result<(), std::io::Error> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "this backend cannot store logs",
+        ))
+    }
+
+    /// The log of a job, oldest line first; empty if it logged nothing.
+    fn logs(&self, _id: Uuid) -> Result<Vec<LogLine>, std::io::Error> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "this backend cannot store logs",
+        ))
+    }
+
+    /// Append an intermediate output item of a job (see
+    /// [`JobContext::emit`]), stored apart from its record.
+    ///
+    /// Backends without output storage fail with
+    /// [`std::io::ErrorKind::Unsupported`], the default.
+    fn append_output(
+        &self,
+        _id: Uuid,
+        _item: &serde_json::Value,
+    ) -> Result<(), std::io::Error> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "this backend cannot store outputs",
+        ))
+    }
+
+    /// The intermediate output items of a job, starting with the item
+    /// number `from` (the first one is 0).
+    fn outputs(
+        &self,
+        _id: Uuid,
+        _from: usize,
+    ) -> Result<Vec<serde_json::Value>, std::io::Error> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "this backend cannot store outputs",
+        ))
+    }
+
+    /// Stream the intermediate output items of a job as they are emitted.
+    ///
+    /// The backend is polled; the stream ends, after yielding every item,
+    /// once the job is terminal.  Errors (including items that don't
+    /// deserialize as `T`) are yielded and end the stream.
+    fn results_stream<T>(
+        &self,
+        id: Uuid,
+    ) -> impl Stream<Item = Result<T, std::io::Error>> + Send + 'static
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let state = (self.clone(), 0, VecDeque::new(), false);
+        futures::stream::unfold(
+            state,
+            move |(job, mut next, mut buffer, done)| async move {
+                loop {
+                    if let Some(item) = buffer.pop_front() {
+                        let item = serde_json::from_value(item)
+                            .map_err(std::io::Error::from);
+                        let done = done || item.is_err();
+                        return Some((item, (job, next, buffer, done)));
+                    }
+                    if done {
+                        return None;
+                    }
+                    // Read the status first, so no item emitted before the
+                    // job finished is missed.
+                    let terminal = match job.load(id) {
+                        Ok(info) => info.status.is_terminal(),
+                        Err(e) => {
+                            return Some((Err(e), (job, next, buffer, true)))
+                        }
+                    };
+                    match job.outputs(id, next) {
+                        Ok(items) if items.is_empty() && terminal => {
+                            return None
+                        }
+                        Ok(items) if items.is_empty() => {
+                            tokio::time::sleep(POLL_INTERVAL).await;
+                        }
+                        Ok(items) => {
+                            next += items.len();
+                            buffer.extend(items);
+                        }
+                        Err(e) => {
+                            return Some((Err(e), (job, next, buffer, true)))
+                        }
+                    }
+                }
+            },
+        )
+    }
+
+    /// The context of a job, for use from inside the job (see
+    /// [`JobContext`]).
+    fn context(&self, id: Uuid) -> JobContext<Self> {
+        JobContext::new(id, self.clone())
+    }
+
+    /// Start a job.
+    ///
+    /// Start a job, passing it the id ([`Uuid`]) and the job metadata ([`JobInfo`]).
+    /// With that information, the job can update its status (using `.load` and
+    /// `.save`).
+    ///
+    /// Returns a [`JobHandle`] to follow, cancel or abort the job, or the
+    /// error of [`Job::admit`] or of the first save.
+    fn submit<F, Fut>(
+        &self,
+        f: F,
+        metadata: Self::Metadata,
+    ) -> Result<JobHandle<Self>, std::io::Error>
+    where
+        F: FnOnce(Uuid, Self, Self::Metadata) -> Fut,
+        Fut:
+            Future<Output = Result<Self::Output, Self::Error>> + Send + 'static,
+    {
+        let info = JobInfo {
+            id: self.id_generator().generate(),
+            ..JobInfo::new()
+        };
+        run::submit(self, info, f, metadata)
+    }
+
+    /// Start a job with a given id, instead of one from the
+    /// [`Job::id_generator`].
+    ///
+    /// Useful for ids derived from business keys (see [`ids::from_key`]).
+    /// Fails with [`std::io::ErrorKind::AlreadyExists`] if the backend
+    /// already has a job with this id.
+    fn submit_with_id<F, Fut>(
+        &self,
+        id: Uuid,
+        f: F,
+        metadata: Self::Metadata,
+    ) -> Result<JobHandle<Self>, std::io::Error>
+    where
+        F: FnOnce(Uuid, Self, Self::Metadata) -> Fut,
+        Fut:
+            Future<Output = Result<Self::Output, Self::Error>> + Send + 'static,
+    {
+        match self.load(id) {
+            Ok(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("job {id} already exists"),
+            )),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => run::submit(
+                self,
+                JobInfo {
+                    id,
+                    ..JobInfo::new()
+                },
+                f,
+                metadata,
+            ),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Start a job unless one with the same idempotency key is active.
+    ///
+    /// For jobs triggered by deliveries that may be repeated (e.g.
+    /// webhooks): the key identifies the request, and while a job submitted
+    /// with it is not terminal, this returns the id of that job instead of
+    /// starting a duplicate.
+    ///
+    /// The id of the job is derived from the key (see
+    /// [`queue::unique_id`]), so looking for the active job is a single
+    /// [`Job::load`], and a job submitted once the previous one ended
+    /// replaces its record.  Concurrent calls in this process are
+    /// serialized; calls racing in several processes may each start a
+    /// task, but they share one record.
+    fn submit_unique<F, Fut>(
+        &self,
+        key: &str,
+        f: F,
+        metadata: Self::Metadata,
+    ) -> Result<UniqueSubmission<Self>, std::io::Error>
+    where
+        F: FnOnce(Uuid, Self, Self::Metadata) -> Fut,
+        Fut:
+            Future<Output = Result<Self::Output, Self::Error>> + Send + 'static,
+    {
+        let id = queue::unique_id(key);
+        let lock = local::record_lock(id);
+        let _guard = lock.lock().expect("cannot get lock");
+        if queue::is_active(self, id)? {
+            return Ok(UniqueSubmission::Existing(id));
+        }
+        let info = JobInfo {
+            id,
+            idempotency_key: Some(key.to_string()),
+            ..JobInfo::new()
+        };
+        run::submit(self, info, f, metadata).map(UniqueSubmission::New)
+    }
+
+    /// Like [`Job::submit`], with all the [`SubmitOptions`].
+    fn submit_with<F, Fut>(
+        &self,
+        f: F,
+        metadata: Self::Metadata,
+        options: &SubmitOptions,
+    ) -> Result<JobHandle<Self>, std::io::Error>
+    where
+        F: FnOnce(Uuid, Self, Self::Metadata) -> Fut,
+        Fut:
+            Future<Output = Result<Self::Output, Self::Error>> + Send + 'static,
+    {
+        run::submit(self, options.info(self), f, metadata)
+    }
+
+    /// Save a job as [`StatusType::Pending`] in the
+    /// [default queue](queue::DEFAULT_QUEUE), to be run by a worker calling
+    /// [`Job::claim_next`], possibly in another process (see [`queue`]).
+    ///
+    /// Returns the id of the job, or the error of [`Job::admit`] or of the
+    /// save, or [`JobError::QueueFull`] if the queue is at its
+    /// [capacity](QueueConfig::with_capacity).
+    fn enqueue(
+        &self,
+        metadata: Self::Metadata,
+    ) -> Result<Uuid, std::io::Error> {
+        self.enqueue_with(metadata, &queue::EnqueueOptions::new())
+    }
+
+    /// Like [`Job::enqueue`], with a priority: workers claim the jobs with
+    /// the highest priority first.  Jobs enqueued without one have priority
+    /// 0.
+    fn enqueue_with_priority(
+        &self,
+        metadata: Self::Metadata,
+        priority: u8,
+    ) -> Result<Uuid, std::io::Error> {
+        let options = queue::EnqueueOptions::new().priority(priority);
+        self.enqueue_with(metadata, &options)
+    }
+
+    /// Like [`Job::enqueue_with_priority`], to the named `queue`, whose jobs
+    /// are only claimed by the workers subscribing to it (see
+    /// [`Job::claim_next_from`]).
+    fn enqueue_to(
+        &self,
+        queue: &str,
+        metadata: Self::Metadata,
+        priority: u8,
+    ) -> Result<Uuid, std::io::Error> {
+        let options =
+            queue::EnqueueOptions::new().queue(queue).priority(priority);
+        self.enqueue_with(metadata, &options)
+    }
+
+    /// Like [`Job::enqueue`], with all the [`EnqueueOptions`](queue::EnqueueOptions).
+    fn enqueue_with(
+        &self,
+        metadata: Self::Metadata,
+        options: &queue::EnqueueOptions,
+    ) -> Result<Uuid, std::io::Error> {
+        queue::enqueue(self, metadata, options)
+    }
+
+    /// Stop the workers from claiming the jobs of `queue`, e.g. for a
+    /// maintenance window, until [`Job::resume_queue`].
+    ///
+    /// Jobs can still be enqueued to it, and the jobs already claimed run to
+    /// completion.  The pause is saved in the backend, so every worker
+    /// sharing it observes the pause.
+    fn pause_queue(&self, queue: &str) -> Result<(), std::io::Error> {
+        self.set_queue_paused(queue, true)
+    }
+
+    /// Let the workers claim the jobs of a paused `queue` again.
+    fn resume_queue(&self, queue: &str) -> Result<(), std::io::Error> {
+        self.set_queue_paused(queue, false)
+    }
+
+    /// All the readable jobs enqueued to `queue`.
+    ///
+    /// Requires a backend able to list its jobs (see [`Job::ids`]).
+    fn list_queue(
+        &self,
+        queue: &str,
+    ) -> Result<Vec<Info<Self>>, std::io::Error> {
+        Ok(self
+            .scan()?
+            .filter(|info| info.queue.as_deref() == Some(queue))
+            .collect())
+    }
+
+    /// Claim the pending job of the [default queue](queue::DEFAULT_QUEUE)
+    /// with the highest priority, and the oldest among those (see
+    /// [`Job::enqueue`]), and start it with `f`, like [`Job::submit`];
+    /// `None` if no job is pending or the queue is
+    /// [paused](Job::pause_queue).
+    ///
+    /// The job is claimed with [`Job::save_if_version`], so several workers
+    /// sharing the backend never claim the same job, provided it is atomic
+    /// across their processes (e.g. [`FSJob::with_locking`]).  The worker
+    /// holds a [`Lease`](queue::Lease) on the job for the
+    /// [visibility timeout](QueueConfig::with_visibility_timeout); jobs whose lease
+    /// expired (e.g. their worker crashed) are claimed again, and the
+    /// previous worker can't save them anymore.  Requires a backend able
+    /// to list its jobs (see [`Job::ids`]).
+    fn claim_next<F, Fut>(
+        &self,
+        f: F,
+    ) -> Result<Option<JobHandle<Self>>, std::io::Error>
+    where
+        F: FnOnce(Uuid, Self, Self::Metadata) -> Fut,
+        Fut:
+            Future<Output = Result<Self::Output, Self::Error>> + Send + 'static,
+    {
+        queue::claim_next(self, &[queue::DEFAULT_QUEUE], f)
+    }
+
+    /// Like [`Job::claim_next`], from any of the named `queues` (see
+    /// [`Job::enqueue_to`]), skipping those that are
+    /// [paused](Job::pause_queue) or at their
+    /// [concurrency limit](QueueConfig::with_concurrency).
+    fn claim_next_from<F, Fut>(
+        &self,
+        queues: &[&str],
+        f: F,
+    ) -> Result<Option<JobHandle<Self>>, std::io::Error>
+    where
+        F: FnOnce(Uuid, Self, Self::Metadata) -> Fut,
+        Fut:
+            Future<Output = Result<Self::Output, Self::Error>> + Send + 'static,
+    {
+        queue::claim_next(self, queues, f)
+    }
+
+    /// The jobs claimed from the queue that ran out of attempts, oldest
+    /// first, with their final error and failed attempts.
+    ///
+    /// Requires a backend able to list its jobs (see [`Job::ids`]).
+    fn dead_letters(&self) -> Result<Vec<Info<Self>>, std::io::Error> {
+        let mut infos: Vec<_> = self
+            .scan()?
+            .filter(|info| info.dead_lettered_at.is_some())
+            .collect();
+        infos.sort_by_key(|info| info.dead_lettered_at);
+        Ok(infos)
+    }
+
+    /// Offer a dead-lettered job (see [`Job::dead_letters`]) to the workers
+    /// again, as [`StatusType::Pending`] with all its attempts.
+    ///
+    /// The failed attempts and the final error are dropped from the record:
+    /// inspect them first.  Fails with [`std::io::ErrorKind::InvalidInput`]
+    /// if the job isn't dead-lettered.
+    fn redrive(&self, id: Uuid) -> Result<Info<Self>, std::io::Error> {
+        queue::redrive(self, id)
+    }
+
+    /// Start a new job with `f` and the metadata of the job `id`, e.g. to
+    /// run a failed job again from an admin UI.
+    ///
+    /// The new job records `id` as its
+    /// [`resubmitted_from`](JobInfo::resubmitted_from), and keeps its
+    /// tenant; the job `id` is left untouched.  Fails with
+    /// [`std::io::ErrorKind::InvalidInput`] if the job has no metadata.
+    fn resubmit<F, Fut>(
+        &self,
+        id: Uuid,
+        f: F,
+    ) -> Result<JobHandle<Self>, std::io::Error>
+    where
+        F: FnOnce(Uuid, Self, Self::Metadata) -> Fut,
+        Fut:
+            Future<Output = Result<Self::Output, Self::Error>> + Send + 'static,
+    {
+        let original = self.load(id)?;
+        let metadata = original.metadata.ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("job {id} has no metadata to resubmit"),
+            )
+        })?;
+        let info = JobInfo {
+            id: self.id_generator().generate(),
+            tenant_id: original.tenant_id,
+            resubmitted_from: Some(id),
+            ..JobInfo::new()
+        };
+        run::submit(self, info, f, metadata)
+    }
+
+    /// Resubmit with `f` (see [`Job::resubmit`]) the failed jobs for which
+    /// `filter` returns `true`, e.g. those that failed during an outage,
+    /// returning the ids of the new jobs.
+    ///
+    /// Failed jobs are those that ended with an error, or with
+    /// [`StatusType::Failed`].  Jobs already resubmitted are skipped, so
+    /// retrying twice doesn't run them twice.  Requires a backend able to
+    /// list its jobs (see [`Job::ids`]).
+    fn retry_failed<P, F, Fut>(
+        &self,
+        mut filter: P,
+        f: F,
+    ) -> Result<Vec<Uuid>, std::io::Error>
+    where
+        P: FnMut(&Info<Self>) -> bool,
+        F: FnOnce(Uuid, Self, Self::Metadata) -> Fut + Clone,
+        Fut:
+            Future<Output = Result<Self::Output, Self::Error>> + Send + 'static,
+    {
+        let infos: Vec<_> = self.scan()?.collect();
+        let resubmitted: Vec<Uuid> = infos
+            .iter()
+            .filter_map(|info| info.resubmitted_from)
+            .collect();
+        let mut ids = vec![];
+        for info in infos {
+            let failed = matches!(info.status, StatusType::Failed(_))
+                || matches!(info.result, Some(Err(_)));
+            if failed && !resubmitted.contains(&info.id) && filter(&info) {
+                ids.push(self.resubmit(info.id, f.clone())?.id());
+            }
+        }
+        Ok(ids)
+    }
+
+    /// The [statistics](stats) of the jobs for which `filter` returns
+    /// `true`, e.g. those submitted in the last hour.
+    ///
+    /// Requires a backend able to list its jobs (see [`Job::ids`]).
+    fn stats<P>(&self, mut filter: P) -> Result<JobStats, std::io::Error>
+    where
+        P: FnMut(&Info<Self>) -> bool,
+    {
+        Ok(JobStats::of(self.scan()?.filter(|info| filter(info))))
+    }
+
+    /// A [snapshot](dashboard) of the queues, statuses and recent failures
+    /// of the jobs, for a status page.
+    ///
+    /// Requires a backend able to list its jobs (see [`Job::ids`]).
+    fn dashboard_snapshot(&self) -> Result<Snapshot<Self>, std::io::Error> {
+        Ok(DashboardSnapshot::of(self.scan()?))
+    }
+
+    /// Start a job with `f` for each of `metadata`, as a [`Batch`], e.g. to
+    /// send many notifications and be told when they are all sent.
+    ///
+    /// Returns the error of the first submission that failed; the jobs
+    /// submitted before it keep running, as part of the batch.
+    fn submit_batch<F, Fut, I>(
+        &self,
+        f: F,
+        metadata: I,
+    ) -> Result<batch::Batch<Self>, std::io::Error>
+    where
+        F: FnOnce(Uuid, Self, Self::Metadata) -> Fut + Clone,
+        Fut:
+            Future<Output = Result<Self::Output, Self::Error>> + Send + 'static,
+        I: IntoIterator<Item = Self::Metadata>,
+    {
+        let batch = self.id_generator().generate();
+        let mut ids = vec![];
+        for metadata in metadata {
+            let info = JobInfo {
+                id: self.id_generator().generate(),
+                batch_id: Some(batch),
+                ..JobInfo::new()
+            };
+            ids.push(run::submit(self, info, f.clone(), metadata)?.id());
+        }
+        Ok(batch::Batch::new(self.clone(), batch, ids))
+    }
+
+    /// The counts of the jobs of the batch `batch` (see
+    /// [`Job::submit_batch`]), e.g. from another process than the one that
+    /// submitted it.
+    ///
+    /// Requires a backend able to list its jobs (see [`Job::ids`]).
+    fn batch_status(
+        &self,
+        batch: Uuid,
+    ) -> Result<batch::BatchStatus, std::io::Error> {
+        let mut status = batch::BatchStatus::default();
+        self.scan()?
+            .filter(|info| info.batch_id == Some(batch))
+            .for_each(|info| status.add(&info));
+        Ok(status)
+    }
+
+    /// Start a CPU-bound (or otherwise blocking) job.
+    ///
+    /// Like [`Job::submit`], but the closure runs on a thread dedicated to
+    /// blocking work (see [`tokio::task::spawn_blocking`]), so it doesn't
+    /// stall the async runtime.  Status transitions are persisted as for any
+    /// other job.  Note that a blocking job can't be interrupted: canceling
+    /// it (or timing it out) stops waiting for it, but the thread runs the
+    /// closure to completion.  A job whose closure never ran because the
+    /// runtime shut down is saved as [`StatusType::Interrupted`].
+    fn submit_blocking<F>(
+        &self,
+        f: F,
+        metadata: Self::Metadata,
+    ) -> Result<JobHandle<Self>, std::io::Error>
+    where
+        F: FnOnce(
+                Uuid,
+                Self,
+                Self::Metadata,
+            ) -> Result<Self::Output, Self::Error>
+            + Send
+            + 'static,
+    {
+        self.submit(
+            |id, job, metadata| async move {
+                let backend = job.clone();
+                let task =
+                    tokio::task::spawn_blocking(move || f(id, job, metadata));
+                match task.await {
+                    Ok(res) => res,
+                    Err(e) if e.is_panic() => {
+                        std::panic::resume_unwind(e.into_panic())
+                    }
+                    Err(_) => {
+                        // The runtime is shutting down, and drops this task
+                        // too: record it as a crash would be recovered.
+                        let _ = supervisor::interrupt(&backend, id);
+                        futures::future::pending().await
+                    }
+                }
+            },
+            metadata,
+        )
+    }
+
+    /// Start a job needing secrets.
+    ///
+    /// Like [`Job::submit`], but the secrets named in `secrets` are resolved
+    /// with the [`Job::secret_provider`] and passed to the closure, so they
+    /// are never saved with the job.  Returns the error of the provider,
+    /// without submitting the job, if a secret can't be resolved.
+    fn submit_with_secrets<F, Fut>(
+        &self,
+        secrets: &[&str],
+        f: F,
+        metadata: Self::Metadata,
+    ) -> Result<JobHandle<Self>, std::io::Error>
+    where
+        F: FnOnce(Uuid, Self, Self::Metadata, Secrets) -> Fut + Send + 'static,
+        Fut:
+            Future<Output = Result<Self::Output, Self::Error>> + Send + 'static,
+    {
+        let names: Vec<String> =
+            secrets.iter().map(|s| s.to_string()).collect();
+        let secrets =
+            Secrets::resolve(self.secret_provider().as_ref(), &names)?;
+        self.submit(|id, job, metadata| f(id, job, metadata, secrets), metadata)
+    }
+
+    /// The hooks run for every job submitted through this backend.
+    ///
+    /// Backends have no hooks by default; wrap them in a [`Hooked`] to add
+    /// some.
+    fn hooks(&self) -> &[DynHooks<Self::Output, Self::Error>] {
+        &[]
+    }
+
+    /// The layers wrapping every job submitted through this backend.
+    ///
+    /// Backends have no layers by default; wrap them in a [`Layered`] to add
+    /// some.
+    fn layers(&self) -> &[DynLayer<Self::Output, Self::Error>] {
+        &[]
+    }
 
-use futures::Future;
-use serde::{Deserialize, Serialize};
-use uuid::Uuid;
+    /// How to retry the final save of a job when it fails.
+    ///
+    /// Wrap a backend in a [`RetrySaves`] to change the default [`Backoff`].
+    fn save_backoff(&self) -> Backoff {
+        Backoff::default()
+    }
 
-/// Type for Status values.
-///
-/// The user can implement this trait to provide their own status values.
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
-pub enum StatusType<T> {
-    Started,
-    StatusValue(T),
-    Finished,
-}
+    /// How the ids of new jobs are generated.
+    ///
+    /// Wrap a backend in a [`WithIds`] to change the default
+    /// [`RandomIds`](ids::RandomIds).
+    fn id_generator(&self) -> Arc<dyn IdGenerator> {
+        Arc::new(ids::RandomIds)
+    }
 
-/// Metadata for a job.
-///
-/// This is the data that gets saved and restored.
-///
-/// The field result is `None` while there is no output from the job. On completion,
-/// the proper branch for `Result` is set.
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct JobInfo<Output, Error, Metadata, Status> {
-    /// The unique id for a job (UUID v4).
-    pub id: Uuid,
-    /// Job status (see [`StatusType`]).
-    pub status: StatusType<Status>,
-    /// Result of the job (`None` while there is no output).
-    pub result: Option<Result<Output, Error>>,
-    /// Metadata passed to the job by the user at start time.
-    pub metadata: Option<Metadata>,
-}
+    /// Where the tasks of the jobs submitted through this backend run.
+    ///
+    /// Wrap a backend in a [`WithSpawner`] to change the default
+    /// [`CurrentRuntime`](spawn::CurrentRuntime).
+    fn spawner(&self) -> Arc<dyn Spawner> {
+        Arc::new(spawn::CurrentRuntime)
+    }
 
-impl<Output, Error, Metadata, Status> Default
-    for JobInfo<Output, Error, Metadata, Status>
-{
-    fn default() -> Self {
-        Self::new()
+    /// Check that new jobs may be submitted, failing the submission
+    /// otherwise.
+    ///
+    /// Always `Ok` by default; a [`JobSupervisor`] rejects submissions once
+    /// shutting down.
+    fn admit(&self) -> Result<(), std::io::Error> {
+        Ok(())
     }
-}
 
-impl<Output, Error, Metadata, Status> JobInfo<Output, Error, Metadata, Status> {
-    /// Create new information for a job.
+    /// Where the secrets of [`Job::submit_with_secrets`] come from.
     ///
-    /// Usually, the user does not need to create this struct manually.
-    pub fn new() -> Self {
-        Self {
-            id: Uuid::new_v4(),
-            status: StatusType::Started,
-            result: None,
-            metadata: None,
-        }
+    /// Wrap a backend in a [`WithSecrets`] to change the default
+    /// [`EnvSecrets`](secrets::EnvSecrets).
+    fn secret_provider(&self) -> Arc<dyn SecretProvider> {
+        Arc::new(secrets::EnvSecrets)
     }
-}
 
-/// Convenience alias for using [`JobInfo`] together with the associated types
-/// from [`Job`].
-type Info<T> = JobInfo<
-    <T as Job>::Output,
-    <T as Job>::Error,
-    <T as Job>::Metadata,
-    <T as Job>::Status,
->;
+    /// How the queues of this backend are run (see [`queue`]).
+    ///
+    /// Wrap a backend in a [`WithQueues`] to change the default
+    /// [`QueueConfig`].
+    fn queue_config(&self) -> Arc<QueueConfig> {
+        Arc::new(QueueConfig::default())
+    }
 
-/// A job.
-///
-/// This is the main trait that the user should implement.
-pub trait Job: Clone + Send + Sync + 'static {
-    type Output: Clone + Send + 'static;
-    type Error: Clone + Send + 'static;
-    type Metadata: Clone + Send + 'static;
-    type Status: PartialEq + Clone + Send + 'static;
+    /// Cancel a job, persisting the reason in its status.
+    ///
+    /// If the job runs in this process, its task is aborted.  A job running
+    /// in another process is only marked as canceled, and keeps running.
+    /// Canceling a job that already finished is an error
+    /// ([`JobError::InvalidTransition`]).
+    fn cancel(
+        &self,
+        id: Uuid,
+        reason: CancelReason,
+    ) -> Result<(), std::io::Error> {
+        self.update(id, |info| {
+            info.transition(StatusType::Canceled(reason.clone()))?;
+            info.finished_at = Some(Utc::now());
+            Ok(())
+        })?;
+        // Only once the cancellation is saved, so a failed save leaves the
+        // job running rather than dead with a stale status.
+        local::abort(id);
+        self.hooks().iter().for_each(|h| h.on_cancel(id, &reason));
+        events::publish(JobEvent::Canceled { id, reason });
+        Ok(())
+    }
 
-    /// Save the job metadata.
+    /// Cancel (see [`Job::cancel`]) the jobs that aren't terminal and for
+    /// which `filter` returns `true`, e.g. all the pending jobs of a tenant,
+    /// returning the ids of the canceled jobs.
     ///
-    /// Given a reference to a [`JobInfo`], save it in the chosen backend.
-    fn save(&self, info: &Info<Self>) -> Result<(), std::io::Error>;
+    /// Jobs ending while they are canceled are skipped.  Requires a backend
+    /// able to list its jobs (see [`Job::ids`]).
+    fn cancel_many<P>(
+        &self,
+        mut filter: P,
+        reason: CancelReason,
+    ) -> Result<Vec<Uuid>, std::io::Error>
+    where
+        P: FnMut(&Info<Self>) -> bool,
+    {
+        let ids: Vec<Uuid> = self
+            .scan()?
+            .filter(|info| !info.status.is_terminal() && filter(info))
+            .map(|info| info.id)
+            .collect();
+        let mut canceled = vec![];
+        for id in ids {
+            match self.cancel(id, reason.clone()) {
+                Ok(()) => canceled.push(id),
+                Err(e) if run::is_invalid_transition(&e) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(canceled)
+    }
 
-    /// Load the metadata for a job.
+    /// Mark as [`StatusType::Interrupted`] the orphaned jobs of the backend:
+    /// those left unfinished by a process that crashed.
     ///
-    /// Given the id for a job, build a [`JobInfo`] from the chosen backend.
-    fn load(&self, id: Uuid) -> Result<Info<Self>, std::io::Error>;
+    /// A job is orphaned when it isn't terminal, it doesn't run in this
+    /// process and it was started by this worker (see [`worker`]), or never
+    /// started at all.  Since jobs submitted by other workers may not be
+    /// started yet, this should be called on startup, before the other
+    /// workers submit jobs.  Requires a backend able to list its jobs (see
+    /// [`Job::ids`]).
+    ///
+    /// Returns the ids of the interrupted jobs.
+    fn recover(&self) -> Result<Vec<Uuid>, std::io::Error> {
+        let recovered = self.recover_with(|_, _| Ok(None))?;
+        Ok(recovered.into_iter().map(|(id, _)| id).collect())
+    }
 
-    /// Start a job.
+    /// Like [`Job::recover`], then pass every interrupted job to `resubmit`,
+    /// which may submit it again (e.g. with the same
+    /// [`metadata`](JobInfo::metadata)).
     ///
-    /// Start a job, passing it the id ([`Uuid`]) and the job metadata ([`JobInfo`]).
-    /// With that information, the job can update its status (using `.load` and
-    /// `.save`).
-    fn submit<F, Fut>(
+    /// Returns the ids of the interrupted jobs, with the ids of the jobs
+    /// resubmitted in their place.  Tries to recover every job before
+    /// returning the first error.
+    fn recover_with<H>(
         &self,
-        f: F,
-        metadata: Self::Metadata,
-    ) -> Result<Uuid, std::io::Error>
+        mut resubmit: H,
+    ) -> Result<Vec<(Uuid, Option<Uuid>)>, std::io::Error>
     where
-        F: FnOnce(Uuid, Self, Self::Metadata) -> Fut,
-        Fut:
-            Future<Output = Result<Self::Output, Self::Error>> + Send + 'static,
+        H: FnMut(
+            &Self,
+            &Info<Self>,
+        ) -> Result<Option<JobHandle<Self>>, std::io::Error>,
     {
-        let mut info: JobInfo<_, _, _, _> = JobInfo::default();
-        self.save(&info)?;
-        let id = info.id;
-        {
-            let this = self.clone();
-            let that = self.clone();
-            let fut = f(id, that, metadata);
-            tokio::spawn(async move {
-                let res = fut.await;
-                info.status = StatusType::Finished;
-                info.result = Some(res);
-                this.save(&info).unwrap();
+        let orphans: Vec<Uuid> = self
+            .ids()?
+            .into_iter()
+            .filter(|id| local::subscribe(*id).is_none())
+            .collect();
+        let worker = worker::label();
+        let mut error = None;
+        let mut recovered = vec![];
+        for (id, info) in orphans.iter().zip(self.load_many(&orphans)) {
+            let result = info.and_then(|info| {
+                let mine = info.worker.as_ref().is_none_or(|w| *w == worker);
+                // Pending jobs wait for a worker to claim them, and leased
+                // ones are offered again when their lease expires.
+                let queued = matches!(info.status, StatusType::Pending)
+                    || info.lease.is_some();
+                if info.status.is_terminal() || queued || !mine {
+                    return Ok(None);
+                }
+                let Some(info) = supervisor::interrupt(self, info.id)? else {
+                    return Ok(None);
+                };
+                let handle = resubmit(self, &info)?;
+                Ok(Some(handle.map(|h| h.id())))
             });
+            match result {
+                Ok(Some(new_id)) => recovered.push((*id, new_id)),
+                Ok(None) => {}
+                Err(e) => {
+                    error.get_or_insert(e);
+                }
+            }
         }
+        match error {
+            Some(e) => Err(e),
+            None => Ok(recovered),
+        }
+    }
+
+    /// Save a new status for a job.
+    ///
+    /// Loads the job, replaces its status and saves it back, publishing a
+    /// [`JobEvent::StatusChanged`].  Fails with
+    /// [`JobError::InvalidTransition`] if the job is already terminal.
+    fn set_status(
+        &self,
+        id: Uuid,
+        status: StatusType<Self::Status>,
+    ) -> Result<(), std::io::Error> {
+        self.update(id, |info| Ok(info.transition(status.clone())?))?;
+        events::publish(JobEvent::StatusChanged { id });
+        Ok(())
+    }
 
-        Ok(id)
+    /// Subscribe to the updates of a job.
+    ///
+    /// The stream yields the current [`JobInfo`], and then a new item each
+    /// time the persisted status or result changes.  It ends after yielding
+    /// the finished job, or when the job cannot be loaded.
+    fn subscribe(
+        &self,
+        id: Uuid,
+    ) -> impl Stream<Item = Info<Self>> + Send + 'static + use<Self> {
+        let changes = self.changes(id).chain(poll_changes()).boxed();
+        let state = (self.clone(), changes, None, false);
+        futures::stream::unfold(
+            state,
+            move |(job, mut changes, last, done)| async move {
+                if done {
+                    return None;
+                }
+                loop {
+                    let info = job.load(id).ok()?;
+                    let current =
+                        Some((info.status.clone(), info.result.is_some()));
+                    if current != last {
+                        let done = info.status.is_terminal();
+                        return Some((info, (job, changes, current, done)));
+                    }
+                    changes.next().await?;
+                }
+            },
+        )
     }
 }
 
+/// Extract the message from the payload of a panic.
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "job panicked".to_string())
+}
+
+/// Wait for a job to finish (or fail), returning its final [`JobInfo`].
+///
+/// When the job is running in the current process, this awaits a
+/// notification from the job itself.  Otherwise (e.g. the job was submitted
+/// by another process sharing the backend), the backend is polled.
 pub async fn wait<J>(id: Uuid, job: &J) -> Result<Info<J>, std::io::Error>
 where
     J: Job,
 {
+    if let Some(mut done) = local::subscribe(id) {
+        while !*done.borrow() {
+            if done.changed().await.is_err() {
+                break;
+            }
+        }
+    }
+    // Fall back to polling should the stream of changes end.
+    let mut changes = job.changes(id).chain(poll_changes()).boxed();
     loop {
-        let the_job = job.load(id)?;
-        if the_job.status == StatusType::Finished {
-            return Ok(the_job);
+        if job.load_status(id)?.status.is_terminal() {
+            return job.load(id);
         }
-        tokio::time::sleep(Duration::from_millis(10)).await;
+        changes.next().await;
     }
 }
 
+/// A stream yielding every [`POLL_INTERVAL`], the default of
+/// [`Job::changes`].
+pub(crate) fn poll_changes() -> impl Stream<Item = ()> + Send + 'static {
+    futures::stream::unfold((), |()| async {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        Some(((), ()))
+    })
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{wait, Job, StatusType};
+    use crate::{events, wait, CancelReason, Job, JobEvent, StatusType};
+    use futures::StreamExt;
     use lazy_static::lazy_static;
     use uuid::Uuid;
 
@@ -247,7 +1757,7 @@ mod tests {
     async fn submit_should_save_with_saver() -> Result<(), std::io::Error> {
         let saver = MySaver {};
         let metadata = Default::default();
-        let id = saver.submit(|_, _, _| async { Ok(2u16) }, metadata)?;
+        let id = saver.submit(|_, _, _| async { Ok(2u16) }, metadata)?.id();
         let saved = SAVED.lock().expect("couldn't get lock");
         assert_eq!(saved.get(&id).expect("couldn't get id").id, id);
         Ok(())
@@ -258,13 +1768,15 @@ mod tests {
     {
         let saver = MySaver {};
         let metadata = Default::default();
-        let id = saver.submit(
-            |_, _, _| async {
-                tokio::time::sleep(Duration::from_secs(1)).await;
-                Ok(10u16)
-            },
-            metadata,
-        )?;
+        let id = saver
+            .submit(
+                |_, _, _| async {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    Ok(10u16)
+                },
+                metadata,
+            )?
+            .id();
         let saved = SAVED.lock().expect("coudn't get lock");
         let a = saved.get(&id).unwrap();
         assert_eq!(a.status, StatusType::Started);
@@ -275,13 +1787,15 @@ mod tests {
     async fn task_should_finish_with_saver() -> Result<(), std::io::Error> {
         let saver = MySaver {};
         let metadata = Default::default();
-        let id = saver.submit(
-            |_, _, _| async {
-                tokio::time::sleep(Duration::from_millis(500)).await;
-                Ok(10u16)
-            },
-            metadata,
-        )?;
+        let id = saver
+            .submit(
+                |_, _, _| async {
+                    tokio::time::sleep(Duration::from_millis(500)).await;
+                    Ok(10u16)
+                },
+                metadata,
+            )?
+            .id();
         tokio::time::sleep(Duration::from_secs(1)).await;
         let saved = SAVED.lock().expect("coudn't get lock");
         let a = saved.get(&id).unwrap();
@@ -294,7 +1808,9 @@ mod tests {
     async fn task_should_save_error() -> Result<(), std::io::Error> {
         let saver = MySaver {};
         let metadata = Default::default();
-        let id = saver.submit(|_, _, _| async { Err(MyError {}) }, metadata)?;
+        let id = saver
+            .submit(|_, _, _| async { Err(MyError {}) }, metadata)?
+            .id();
         tokio::time::sleep(Duration::from_millis(100)).await;
         let saved = SAVED.lock().expect("coudn't get lock");
         let a = saved.get(&id).unwrap();
@@ -307,15 +1823,17 @@ mod tests {
     async fn can_read_from_task_with_saver() -> Result<(), std::io::Error> {
         let saver = MySaver {};
         let metadata = Default::default();
-        let id = saver.submit(
-            |id, _, _| async move {
-                let saver = MySaver {};
-                let j = saver.load(id).unwrap();
-                let i = j.id.as_fields().1;
-                Ok(i)
-            },
-            metadata,
-        )?;
+        let id = saver
+            .submit(
+                |id, _, _| async move {
+                    let saver = MySaver {};
+                    let j = saver.load(id).unwrap();
+                    let i = j.id.as_fields().1;
+                    Ok(i)
+                },
+                metadata,
+            )?
+            .id();
         tokio::time::sleep(Duration::from_millis(100)).await;
         let saved = SAVED.lock().expect("coudn't get lock");
         let a = saved.get(&id).unwrap();
@@ -331,13 +1849,15 @@ mod tests {
     {
         let job = MySaver {};
         let metadata = Default::default();
-        let id = job.submit(
-            |id, job, _| async move {
-                let jobinfo = job.load(id).unwrap();
-                Ok(jobinfo.id.as_fields().1)
-            },
-            metadata,
-        )?;
+        let id = job
+            .submit(
+                |id, job, _| async move {
+                    let jobinfo = job.load(id).unwrap();
+                    Ok(jobinfo.id.as_fields().1)
+                },
+                metadata,
+            )?
+            .id();
         tokio::time::sleep(Duration::from_millis(100)).await;
         let saved = SAVED.lock().expect("coudn't get lock");
         let a = saved.get(&id).unwrap();
@@ -352,17 +1872,19 @@ mod tests {
     async fn can_write_from_task_with_saver() -> Result<(), std::io::Error> {
         let saver = MySaver {};
         let metadata = Default::default();
-        let id = saver.submit(
-            |id, _, _| async move {
-                let saver = MySaver {};
-                let mut j = saver.load(id).unwrap();
-                j.status = StatusType::StatusValue("running".to_string());
-                saver.save(&j).unwrap();
-                tokio::time::sleep(Duration::from_millis(500)).await;
-                Ok(2u16)
-            },
-            metadata,
-        )?;
+        let id = saver
+            .submit(
+                |id, _, _| async move {
+                    let saver = MySaver {};
+                    let mut j = saver.load(id).unwrap();
+                    j.status = StatusType::StatusValue("running".to_string());
+                    saver.save(&j).unwrap();
+                    tokio::time::sleep(Duration::from_millis(500)).await;
+                    Ok(2u16)
+                },
+                metadata,
+            )?
+            .id();
         tokio::time::sleep(Duration::from_millis(100)).await;
         let saved = SAVED.lock().expect("coudn't get lock");
         let a = saved.get(&id).unwrap();
@@ -375,13 +1897,15 @@ mod tests {
         let saver = MySaver {};
         let metadata = Default::default();
         let s = String::from("test");
-        let id = saver.submit(
-            |_id, _job, _| async move {
-                let out = s.len() as u16;
-                Ok(out)
-            },
-            metadata,
-        )?;
+        let id = saver
+            .submit(
+                |_id, _job, _| async move {
+                    let out = s.len() as u16;
+                    Ok(out)
+                },
+                metadata,
+            )?
+            .id();
         tokio::time::sleep(Duration::from_millis(10)).await;
         let x = saver.load(id)?.result.unwrap().unwrap();
         assert_eq!(x, 4);
@@ -392,10 +1916,12 @@ mod tests {
     async fn should_pass_metadata() -> Result<(), std::io::Error> {
         let saver = MySaver {};
         let metadata = MyMetadata { value: 5usize };
-        let id = saver.submit(
-            |_id, _job, md| async move { Ok(md.value as u16) },
-            metadata,
-        )?;
+        let id = saver
+            .submit(
+                |_id, _job, md| async move { Ok(md.value as u16) },
+                metadata,
+            )?
+            .id();
         tokio::time::sleep(Duration::from_millis(10)).await;
         let x = saver.load(id)?.result.unwrap().unwrap();
         assert_eq!(x, 5);
@@ -407,15 +1933,395 @@ mod tests {
     async fn test_wait() -> Result<(), std::io::Error> {
         let saver = MySaver {};
         let metadata = MyMetadata { value: 5usize };
-        let id = saver.submit(
-            |_id, _job, md| async move {
-                tokio::time::sleep(Duration::from_millis(100)).await;
+        let id = saver
+            .submit(
+                |_id, _job, md| async move {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    Ok(md.value as u16)
+                },
+                metadata,
+            )?
+            .id();
+        let r = wait(id, &saver).await?;
+        assert_eq!(r.result.unwrap().unwrap(), 5);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wait_without_local_job() -> Result<(), std::io::Error> {
+        let saver = MySaver {};
+        let mut info = JobInfo::new();
+        saver.save(&info)?;
+        let id = info.id;
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            info.status = StatusType::Finished;
+            info.result = Some(Ok(7u16));
+            MySaver {}.save(&info).unwrap();
+        });
+        let r = wait(id, &saver).await?;
+        assert_eq!(r.result.unwrap().unwrap(), 7);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_subscribe() -> Result<(), std::io::Error> {
+        let saver = MySaver {};
+        let metadata = Default::default();
+        let id = saver
+            .submit(
+                |id, job, _| async move {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    let mut info = job.load(id).unwrap();
+                    info.status =
+                        StatusType::StatusValue("running".to_string());
+                    job.save(&info).unwrap();
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    Ok(3u16)
+                },
+                metadata,
+            )?
+            .id();
+        let updates: Vec<_> = saver.subscribe(id).collect().await;
+        let statuses: Vec<_> = updates.into_iter().map(|u| u.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                StatusType::Started,
+                StatusType::StatusValue("running".to_string()),
+                StatusType::Finished
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_load_many() -> Result<(), std::io::Error> {
+        let saver = MySaver {};
+        let ids = [
+            saver
+                .submit(|_, _, _| async { Ok(1u16) }, Default::default())?
+                .id(),
+            saver
+                .submit(|_, _, _| async { Ok(2u16) }, Default::default())?
+                .id(),
+        ];
+        let infos = saver.load_many(&ids);
+        assert_eq!(infos.len(), 2);
+        for (id, info) in ids.iter().zip(infos) {
+            assert_eq!(&info?.id, id);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_events() -> Result<(), std::io::Error> {
+        let mut events = events::subscribe();
+        let saver = MySaver {};
+        let metadata = Default::default();
+        let id = saver
+            .submit(
+                |id, job, _| async move {
+                    job.set_status(id, StatusType::StatusValue("half".into()))
+                        .unwrap();
+                    Err(MyError {})
+                },
+                metadata,
+            )?
+            .id();
+        let mut seen = vec![];
+        while !matches!(seen.last(), Some(JobEvent::Failed { .. })) {
+            let event = events.recv().await.unwrap();
+            if event.id() == id {
+                seen.push(event);
+            }
+        }
+        assert_eq!(
+            seen,
+            vec![
+                JobEvent::Submitted { id },
+                JobEvent::StatusChanged { id },
+                JobEvent::Failed { id }
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn task_should_save_panic() -> Result<(), std::io::Error> {
+        let saver = MySaver {};
+        let metadata = Default::default();
+        let id = saver
+            .submit(
+                |_, _, _| async {
+                    if true {
+                        panic!("boom");
+                    }
+                    Ok(1u16)
+                },
+                metadata,
+            )?
+            .id();
+        let r = wait(id, &saver).await?;
+        assert_eq!(r.status, StatusType::Failed("boom".to_string()));
+        assert!(r.result.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cancel() -> Result<(), std::io::Error> {
+        let saver = MySaver {};
+        let metadata = Default::default();
+        let id = saver
+            .submit(
+                |_, _, _| async {
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                    Ok(1u16)
+                },
+                metadata,
+            )?
+            .id();
+        saver.cancel(id, CancelReason::Timeout)?;
+        let r = wait(id, &saver).await?;
+        assert_eq!(r.status, StatusType::Canceled(CancelReason::Timeout));
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let r = saver.load(id)?;
+        assert_eq!(r.status, StatusType::Canceled(CancelReason::Timeout));
+        assert!(saver.cancel(id, CancelReason::UserAction).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_timestamps() -> Result<(), std::io::Error> {
+        let saver = MySaver {};
+        let metadata = Default::default();
+        let id = saver
+            .submit(
+                |_, _, _| async {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    Ok(1u16)
+                },
+                metadata,
+            )?
+            .id();
+        let r = wait(id, &saver).await?;
+        assert!(r.created_at.is_some());
+        assert!(r.queue_latency().unwrap() < Duration::from_millis(100));
+        assert!(r.run_duration().unwrap() >= Duration::from_millis(100));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_job_handle() -> Result<(), std::io::Error> {
+        let saver = MySaver {};
+        let handle = saver.submit(
+            |_, _, _| async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Ok(4u16)
+            },
+            Default::default(),
+        )?;
+        assert_eq!(handle.status()?, StatusType::Started);
+        assert_eq!(handle.result().await?.unwrap().unwrap(), 4u16);
+
+        let handle = saver.submit(
+            |_, _, _| async {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                Ok(4u16)
+            },
+            Default::default(),
+        )?;
+        handle.abort()?;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(handle.is_finished());
+        assert_eq!(
+            handle.status()?,
+            StatusType::Canceled(CancelReason::UserAction)
+        );
+        // The job already ended.
+        handle.abort()?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_submit_blocking() -> Result<(), std::io::Error> {
+        let saver = MySaver {};
+        let handle = saver.submit_blocking(
+            |_, _, md| {
+                std::thread::sleep(Duration::from_millis(50));
                 Ok(md.value as u16)
             },
-            metadata,
+            MyMetadata { value: 6 },
         )?;
+        assert_eq!(handle.status()?, StatusType::Started);
+        assert_eq!(handle.result().await?.unwrap().unwrap(), 6u16);
+
+        let id = saver
+            .submit_blocking(|_, _, _| panic!("heavy"), Default::default())?
+            .id();
         let r = wait(id, &saver).await?;
-        assert_eq!(r.result.unwrap().unwrap(), 5);
+        assert_eq!(r.status, StatusType::Failed("heavy".to_string()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resubmit() -> Result<(), std::io::Error> {
+        let saver = MySaver {};
+        let metadata = MyMetadata { value: 3 };
+        let failed =
+            saver.submit(|_, _, _| async { Err(MyError {}) }, metadata)?;
+        wait(failed.id(), &saver).await?;
+        let handle = saver
+            .resubmit(failed.id(), |_, _, m: MyMetadata| async move {
+                Ok(m.value as u16)
+            })?;
+        let info = wait(handle.id(), &saver).await?;
+        assert_eq!(info.result.unwrap().unwrap(), 3);
+        assert_eq!(info.resubmitted_from, Some(failed.id()));
+        assert!(saver.load(failed.id())?.result.unwrap().is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_metadata() -> Result<(), std::io::Error> {
+        let saver = MySaver {};
+        let metadata = MyMetadata { value: 1 };
+        let id = saver
+            .submit(
+                |id, job: MySaver, _| async move {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    job.set_status(id, StatusType::StatusValue("half".into()))
+                        .unwrap();
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    Ok(1u16)
+                },
+                metadata,
+            )?
+            .id();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let info = saver.update_metadata(id, |m| {
+            m.as_mut().unwrap().value = 42;
+        })?;
+        assert_eq!(info.metadata.unwrap().value, 42);
+        let r = wait(id, &saver).await?;
+        assert_eq!(r.status, StatusType::Finished);
+        assert_eq!(r.metadata.unwrap().value, 42);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_save_if_version() -> Result<(), std::io::Error> {
+        let saver = MySaver {};
+        let id = saver
+            .submit(|_, _, _| async { Ok(1u16) }, Default::default())?
+            .id();
+        let info = wait(id, &saver).await?;
+        assert!(info.version > 0);
+        let mut stale = info.clone();
+
+        let mut first = info.clone();
+        first.status = StatusType::StatusValue("first".into());
+        let version = saver.save_if_version(&mut first, info.version)?;
+        assert_eq!(version, info.version + 1);
+        assert_eq!(first.version, version);
+        assert_eq!(saver.load(id)?.version, version);
+
+        let err = saver.save_if_version(&mut stale, info.version).unwrap_err();
+        assert_eq!(
+            crate::error::JobError::from_io(&err),
+            Some(&crate::error::JobError::VersionConflict {
+                id,
+                expected: stale.version,
+                found: version,
+            })
+        );
+        assert_eq!(
+            saver.load(id)?.status,
+            StatusType::StatusValue("first".into())
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_updates() -> Result<(), std::io::Error> {
+        let saver = MySaver {};
+        let id = saver
+            .submit(
+                |id, job: MySaver, _| async move {
+                    for i in 0..10 {
+                        job.set_status(
+                            id,
+                            StatusType::StatusValue(i.to_string()),
+                        )
+                        .unwrap();
+                        tokio::task::yield_now().await;
+                    }
+                    Ok(1u16)
+                },
+                MyMetadata { value: 0 },
+            )?
+            .id();
+        let updates: Vec<_> = (0..8)
+            .map(|_| {
+                let saver = saver.clone();
+                tokio::spawn(async move {
+                    saver.update_metadata(id, |m| {
+                        m.as_mut().unwrap().value += 1;
+                    })
+                })
+            })
+            .collect();
+        for update in updates {
+            update.await.unwrap()?;
+        }
+        let r = wait(id, &saver).await?;
+        assert_eq!(r.status, StatusType::Finished);
+        assert_eq!(r.metadata.unwrap().value, 8);
+        Ok(())
+    }
+
+    #[test]
+    fn test_transitions() {
+        let mut info: JobInfo<u16, MyError, MyMetadata, String> =
+            JobInfo::new();
+        assert!(info.transition(StatusType::StatusValue("a".into())).is_ok());
+        assert!(info.transition(StatusType::StatusValue("b".into())).is_ok());
+        assert!(info.transition(StatusType::Started).is_err());
+        assert!(info.transition(StatusType::Finished).is_ok());
+        assert!(info.status.is_terminal() && !info.status.is_active());
+        assert_eq!(
+            info.transition(StatusType::Canceled(CancelReason::UserAction)),
+            Err(crate::JobError::InvalidTransition {
+                id: info.id,
+                from: "finished",
+                to: "canceled",
+            })
+        );
+        assert_eq!(info.status, StatusType::Finished);
+    }
+
+    #[tokio::test]
+    async fn test_terminal_status_is_not_overwritten(
+    ) -> Result<(), std::io::Error> {
+        let saver = MySaver {};
+        let handle = saver.submit(
+            |_, _, _| async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Ok(1u16)
+            },
+            Default::default(),
+        )?;
+        // Canceled by another process, which can't abort the task.
+        let mut info = saver.load(handle.id())?;
+        info.status = StatusType::Canceled(CancelReason::UserAction);
+        saver.save(&info)?;
+        while !handle.is_finished() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let info = saver.load(handle.id())?;
+        assert_eq!(info.status, StatusType::Canceled(CancelReason::UserAction));
+        assert!(info.result.is_none());
+        assert!(saver.set_status(handle.id(), StatusType::Finished).is_err());
         Ok(())
     }
 }
@@ -0,0 +1,117 @@
+//! Process-local bookkeeping for jobs spawned by this process.
+//!
+//! Backends can be shared between processes, so nothing in here is required
+//! for correctness: it only lets waiters living in the same process as the
+//! job be woken up instead of polling the backend, lets the process stop
+//! the tasks of canceled jobs, and serializes the updates of a record made
+//! within the process.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock, Weak},
+};
+
+use futures::future::AbortHandle;
+use tokio::sync::watch;
+use uuid::Uuid;
+
+struct Entry {
+    done: watch::Receiver<bool>,
+    abort: Option<AbortHandle>,
+}
+
+type Registry = Mutex<HashMap<Uuid, Entry>>;
+
+fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// Handle held by a running job, used to announce its completion.
+pub(crate) struct Completion {
+    id: Uuid,
+    sender: watch::Sender<bool>,
+}
+
+impl Completion {
+    /// Register the job `id` as running in this process.
+    pub(crate) fn register(id: Uuid) -> Self {
+        let (sender, done) = watch::channel(false);
+        let entry = Entry { done, abort: None };
+        registry()
+            .lock()
+            .expect("cannot get lock")
+            .insert(id, entry);
+        Self { id, sender }
+    }
+
+    /// Wake up every waiter for the job.
+    ///
+    /// Must be called after the final state of the job has been saved.
+    pub(crate) fn notify(self) {
+        let _ = self.sender.send(true);
+    }
+}
+
+impl Drop for Completion {
+    fn drop(&mut self) {
+        registry().lock().expect("cannot get lock").remove(&self.id);
+    }
+}
+
+/// Subscribe to the completion of the job `id`.
+///
+/// Returns `None` when the job is not running in this process (or it has
+/// already finished).
+pub(crate) fn subscribe(id: Uuid) -> Option<watch::Receiver<bool>> {
+    registry()
+        .lock()
+        .expect("cannot get lock")
+        .get(&id)
+        .map(|entry| entry.done.clone())
+}
+
+/// Remember how to abort the task running the job `id`.
+pub(crate) fn set_abort_handle(id: Uuid, handle: AbortHandle) {
+    if let Some(entry) =
+        registry().lock().expect("cannot get lock").get_mut(&id)
+    {
+        entry.abort = Some(handle);
+    }
+}
+
+/// Abort the task running the job `id`, if it runs in this process.
+///
+/// Returns whether there was a task to abort.
+pub(crate) fn abort(id: Uuid) -> bool {
+    let registry = registry().lock().expect("cannot get lock");
+    match registry.get(&id).and_then(|entry| entry.abort.as_ref()) {
+        Some(handle) => {
+            handle.abort();
+            true
+        }
+        None => false,
+    }
+}
+
+type Locks = Mutex<HashMap<Uuid, Weak<Mutex<()>>>>;
+
+/// The lock serializing the read-modify-write cycles on the record of the
+/// job `id` in this process.
+///
+/// The lock is shared by all the callers holding it at the same time, and
+/// forgotten once none does.
+pub(crate) fn record_lock(id: Uuid) -> Arc<Mutex<()>> {
+    static LOCKS: OnceLock<Locks> = OnceLock::new();
+    let mut locks = LOCKS
+        .get_or_init(Default::default)
+        .lock()
+        .expect("cannot get lock");
+    if let Some(lock) = locks.get(&id).and_then(Weak::upgrade) {
+        return lock;
+    }
+    locks.retain(|_, lock| lock.strong_count() > 0);
+    let lock = Arc::new(Mutex::new(()));
+    locks.insert(id, Arc::downgrade(&lock));
+    lock
+}
@@ -0,0 +1,157 @@
+/// Implement the storage methods of [`Job`](crate::Job) by forwarding them
+/// to the backend in `self.$inner`.
+///
+/// Used by the wrappers that only change how jobs are executed, so new
+/// storage methods only need to be forwarded in one place.
+macro_rules! delegate_storage {
+    ($inner:ident) => {
+        fn save(
+            &self,
+            info: &$crate::Info<Self>,
+        ) -> Result<(), std::io::Error> {
+            self.$inner.save(info)
+        }
+
+        fn load(
+            &self,
+            id: uuid::Uuid,
+        ) -> Result<$crate::Info<Self>, std::io::Error> {
+            self.$inner.load(id)
+        }
+
+        fn load_status(
+            &self,
+            id: uuid::Uuid,
+        ) -> Result<$crate::JobStatus<Self::Status>, std::io::Error> {
+            self.$inner.load_status(id)
+        }
+
+        fn load_many(
+            &self,
+            ids: &[uuid::Uuid],
+        ) -> Vec<Result<$crate::Info<Self>, std::io::Error>> {
+            self.$inner.load_many(ids)
+        }
+
+        fn save_many(
+            &self,
+            infos: &[$crate::Info<Self>],
+        ) -> Vec<Result<(), std::io::Error>> {
+            self.$inner.save_many(infos)
+        }
+
+        fn save_if_version(
+            &self,
+            info: &mut $crate::Info<Self>,
+            expected: u64,
+        ) -> Result<u64, std::io::Error> {
+            self.$inner.save_if_version(info, expected)
+        }
+
+        fn update<F>(
+            &self,
+            id: uuid::Uuid,
+            f: F,
+        ) -> Result<$crate::Info<Self>, std::io::Error>
+        where
+            F: FnMut(&mut $crate::Info<Self>) -> Result<(), std::io::Error>,
+        {
+            self.$inner.update(id, f)
+        }
+
+        fn append_log(
+            &self,
+            id: uuid::Uuid,
+            line: &$crate::LogLine,
+        ) -> Result<(), std::io::Error> {
+            self.$inner.append_log(id, line)
+        }
+
+        fn logs(
+            &self,
+            id: uuid::Uuid,
+        ) -> Result<Vec<$crate::LogLine>, std::io::Error> {
+            self.$inner.logs(id)
+        }
+
+        fn append_output(
+            &self,
+            id: uuid::Uuid,
+            item: &serde_json::Value,
+        ) -> Result<(), std::io::Error> {
+            self.$inner.append_output(id, item)
+        }
+
+        fn outputs(
+            &self,
+            id: uuid::Uuid,
+            from: usize,
+        ) -> Result<Vec<serde_json::Value>, std::io::Error> {
+            self.$inner.outputs(id, from)
+        }
+
+        fn ids(&self) -> Result<Vec<uuid::Uuid>, std::io::Error> {
+            self.$inner.ids()
+        }
+
+        fn tenant_ids(
+            &self,
+            tenant: &str,
+        ) -> Result<Vec<uuid::Uuid>, std::io::Error> {
+            self.$inner.tenant_ids(tenant)
+        }
+
+        fn set_queue_paused(
+            &self,
+            queue: &str,
+            paused: bool,
+        ) -> Result<(), std::io::Error> {
+            self.$inner.set_queue_paused(queue, paused)
+        }
+
+        fn paused_queues(&self) -> Result<Vec<String>, std::io::Error> {
+            self.$inner.paused_queues()
+        }
+
+        fn acquire_lease(
+            &self,
+            name: &str,
+            lease: &$crate::queue::Lease,
+        ) -> Result<bool, std::io::Error> {
+            self.$inner.acquire_lease(name, lease)
+        }
+
+        fn release_lease(
+            &self,
+            name: &str,
+            token: uuid::Uuid,
+        ) -> Result<(), std::io::Error> {
+            self.$inner.release_lease(name, token)
+        }
+
+        fn history(
+            &self,
+            id: uuid::Uuid,
+        ) -> Result<Vec<$crate::StatusChange<Self::Status>>, std::io::Error> {
+            self.$inner.history(id)
+        }
+
+        fn audit(
+            &self,
+            id: uuid::Uuid,
+        ) -> Result<Vec<$crate::AuditEntry<Self::Status>>, std::io::Error> {
+            self.$inner.audit(id)
+        }
+
+        fn remove(&self, id: uuid::Uuid) -> Result<(), std::io::Error> {
+            self.$inner.remove(id)
+        }
+
+        fn changes(
+            &self,
+            id: uuid::Uuid,
+        ) -> impl futures::Stream<Item = ()> + Send + 'static {
+            self.$inner.changes(id)
+        }
+    };
+}
@@ -0,0 +1,101 @@
+//! Metrics of the jobs, through the [`metrics`] facade.
+//!
+//! [`record`] feeds the metrics from the [events] of the jobs in this
+//! process, and from the pending jobs of a backend:
+//!
+//! | Metric | Kind | |
+//! |---|---|---|
+//! | `simple_jobs_submitted_total` | counter | jobs submitted or enqueued |
+//! | `simple_jobs_succeeded_total` | counter | jobs completed with `Ok` |
+//! | `simple_jobs_failed_total` | counter | jobs completed with `Err`, or panicked |
+//! | `simple_jobs_queue_latency_seconds` | histogram | time from submission to start |
+//! | `simple_jobs_run_duration_seconds` | histogram | time from start to end |
+//! | `simple_jobs_queue_depth` | gauge, by `queue` | pending jobs |
+//!
+//! The metrics go to the recorder installed in the process, e.g. the
+//! Prometheus exporter of `metrics-exporter-prometheus`, serving them on
+//! `/metrics`:
+//!
+//! ```
+//! # use std::time::Duration;
+//! # use simple_jobs::FSJob;
+//! # fn example(job: FSJob<u16, String, u32, ()>) {
+//! tokio::spawn(simple_jobs::metrics::record(job, Duration::from_secs(15)));
+//! # }
+//! ```
+//!
+//! Requires the feature `metrics`.
+
+use std::{collections::BTreeSet, time::Duration};
+
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{events, queue, Job, JobEvent};
+
+/// Record the metrics of the jobs of this process until the event bus
+/// closes, refreshing the depths of the queues of `job` every `interval`.
+///
+/// Latencies and durations are only recorded for the jobs of `job`.
+pub async fn record<J: Job>(job: J, interval: Duration) {
+    let mut events = events::subscribe();
+    let mut ticks = tokio::time::interval(interval);
+    let mut queues = BTreeSet::new();
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => observe(&job, &event),
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => return,
+            },
+            _ = ticks.tick() => record_queue_depths(&job, &mut queues),
+        }
+    }
+}
+
+/// Record the metrics of an event of a job of this process.
+pub fn observe<J: Job>(job: &J, event: &JobEvent) {
+    match event {
+        JobEvent::Submitted { .. } | JobEvent::Enqueued { .. } => {
+            ::metrics::counter!("simple_jobs_submitted_total").increment(1);
+        }
+        JobEvent::Finished { id } => {
+            ::metrics::counter!("simple_jobs_succeeded_total").increment(1);
+            record_durations(job, *id);
+        }
+        JobEvent::Failed { id } => {
+            ::metrics::counter!("simple_jobs_failed_total").increment(1);
+            record_durations(job, *id);
+        }
+        _ => {}
+    }
+}
+
+/// Record the latency and duration of the ended job `id`, if it is a job
+/// of `job`.
+fn record_durations<J: Job>(job: &J, id: uuid::Uuid) {
+    let Ok(info) = job.load(id) else {
+        return;
+    };
+    if let Some(latency) = info.queue_latency() {
+        ::metrics::histogram!("simple_jobs_queue_latency_seconds")
+            .record(latency.as_secs_f64());
+    }
+    if let Some(duration) = info.run_duration() {
+        ::metrics::histogram!("simple_jobs_run_duration_seconds")
+            .record(duration.as_secs_f64());
+    }
+}
+
+/// Set the depth of the queues of `job`, and of the `queues` seen before,
+/// which are now empty if they have no pending jobs.
+fn record_queue_depths<J: Job>(job: &J, queues: &mut BTreeSet<String>) {
+    let Ok(depths) = queue::depths(job) else {
+        return;
+    };
+    queues.extend(depths.keys().cloned());
+    for queue in queues.iter() {
+        let depth = depths.get(queue).copied().unwrap_or_default();
+        ::metrics::gauge!("simple_jobs_queue_depth", "queue" => queue.clone())
+            .set(depth as f64);
+    }
+}
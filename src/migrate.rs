@@ -0,0 +1,194 @@
+//! Copying jobs between backends.
+//!
+//! [`migrate`] copies the jobs of a backend into another one, e.g. to adopt
+//! a new backend without losing the jobs saved so far; a [`Migration`]
+//! adds a dry run and progress reporting:
+//!
+//! ```
+//! # use simple_jobs::{migrate::Migration, FSJob};
+//! # fn example(
+//! #     old: FSJob<u16, String, (), String>,
+//! #     new: FSJob<u16, String, (), String>,
+//! # ) -> std::io::Result<()> {
+//! let report = Migration::new(&old, &new)
+//!     .dry_run(true)
+//!     .on_progress(|p| eprintln!("{}/{}", p.done, p.total))
+//!     .run()?;
+//! println!("{} jobs would be copied", report.copied.len());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Records, logs and outputs are copied; status histories
+//! ([`Job::history`]) can't be written through the [`Job`] trait, so they
+//! stay behind.
+
+use uuid::Uuid;
+
+use crate::{
+    archive::{stored, unless_unsupported},
+    Info, Job,
+};
+
+/// How far a [`Migration`] got.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    /// The job just handled.
+    pub id: Uuid,
+    /// The number of jobs handled so far.
+    pub done: usize,
+    /// The number of jobs in the source backend.
+    pub total: usize,
+}
+
+/// What a [`Migration`] did (or, in a dry run, would do).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// The ids of the jobs copied.
+    pub copied: Vec<Uuid>,
+    /// The ids of the jobs left out by the filter.
+    pub filtered: Vec<Uuid>,
+    /// The ids of the jobs the target already has, left untouched.
+    pub existing: Vec<Uuid>,
+    /// The jobs of the source that couldn't be loaded, with the error.
+    pub unreadable: Vec<(Uuid, String)>,
+}
+
+type Filter<'a, J> = Box<dyn FnMut(&Info<J>) -> bool + 'a>;
+
+/// A copy of the jobs of a backend into another one.
+pub struct Migration<'a, A: Job, B> {
+    from: &'a A,
+    to: &'a B,
+    filter: Filter<'a, A>,
+    progress: Box<dyn FnMut(Progress) + 'a>,
+    dry_run: bool,
+}
+
+impl<'a, A, B> Migration<'a, A, B>
+where
+    A: Job,
+    B: Job<
+        Output = A::Output,
+        Error = A::Error,
+        Metadata = A::Metadata,
+        Status = A::Status,
+    >,
+{
+    /// Copy all the jobs of `from` into `to`.
+    pub fn new(from: &'a A, to: &'a B) -> Self {
+        Self {
+            from,
+            to,
+            filter: Box::new(|_| true),
+            progress: Box::new(|_| {}),
+            dry_run: false,
+        }
+    }
+
+    /// Only copy the jobs for which `filter` returns `true`.
+    pub fn filter<F>(mut self, filter: F) -> Self
+    where
+        F: FnMut(&Info<A>) -> bool + 'a,
+    {
+        self.filter = Box::new(filter);
+        self
+    }
+
+    /// Call `progress` after handling each job.
+    pub fn on_progress<F>(mut self, progress: F) -> Self
+    where
+        F: FnMut(Progress) + 'a,
+    {
+        self.progress = Box::new(progress);
+        self
+    }
+
+    /// Only report what would be copied, without writing to the target.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Copy the jobs.
+    ///
+    /// Jobs the source can't load are reported and skipped; errors from the
+    /// target stop the migration, which can be run again to resume it.
+    pub fn run(mut self) -> Result<MigrationReport, std::io::Error> {
+        let ids = self.from.ids()?;
+        let total = ids.len();
+        let mut report = MigrationReport::default();
+        for (done, id) in ids.into_iter().enumerate() {
+            self.copy(id, &mut report)?;
+            (self.progress)(Progress {
+                id,
+                done: done + 1,
+                total,
+            });
+        }
+        Ok(report)
+    }
+
+    fn copy(
+        &mut self,
+        id: Uuid,
+        report: &mut MigrationReport,
+    ) -> Result<(), std::io::Error> {
+        let info = match self.from.load(id) {
+            Ok(info) => info,
+            // Removed since it was listed.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(());
+            }
+            Err(e) => {
+                report.unreadable.push((id, e.to_string()));
+                return Ok(());
+            }
+        };
+        if !(self.filter)(&info) {
+            report.filtered.push(id);
+            return Ok(());
+        }
+        match self.to.load(id) {
+            Ok(_) => {
+                report.existing.push(id);
+                return Ok(());
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        if !self.dry_run {
+            let logs = stored(self.from.logs(id))?;
+            let outputs = stored(self.from.outputs(id, 0))?;
+            self.to.save(&info)?;
+            for line in &logs {
+                unless_unsupported(self.to.append_log(id, line))?;
+            }
+            for item in &outputs {
+                unless_unsupported(self.to.append_output(id, item))?;
+            }
+        }
+        report.copied.push(id);
+        Ok(())
+    }
+}
+
+/// Copy the jobs of `from` for which `filter` returns `true` into `to`
+/// (see [`Migration`]).
+pub fn migrate<'a, A, B, F>(
+    from: &'a A,
+    to: &'a B,
+    filter: F,
+) -> Result<MigrationReport, std::io::Error>
+where
+    A: Job,
+    B: Job<
+        Output = A::Output,
+        Error = A::Error,
+        Metadata = A::Metadata,
+        Status = A::Status,
+    >,
+    F: FnMut(&Info<A>) -> bool + 'a,
+{
+    Migration::new(from, to).filter(filter).run()
+}
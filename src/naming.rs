@@ -0,0 +1,143 @@
+//! Names of the job files written by [`FSJob`](crate::FSJob).
+//!
+//! By default the file of a job is named after its id.  A [`FileNaming`]
+//! (see [`FSJob::with_naming`](crate::FSJob::with_naming)) adds a prefix, an
+//! extension, the date the job was submitted or its status, so the job
+//! directory can be browsed and globbed by other tools:
+//!
+//! ```text
+//! {prefix}{date}_{id}.{status}{extension}
+//! report-2024-05-01_67e55044-10b1-426f-9247-bb680e5fe0c8.finished.json
+//! ```
+//!
+//! Files named after the status are renamed when the status changes.  Jobs
+//! are found whatever their names, so the naming of an existing directory
+//! can be changed; each file is renamed when its job is next saved.
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::StatusType;
+
+/// The length of the dates in file names, like `2024-05-01_`.
+const DATE_LEN: usize = "YYYY-MM-DD_".len();
+
+/// The length of a hyphenated id.
+const ID_LEN: usize = 36;
+
+/// The statuses, as they appear in file names.
+const STATUSES: [&str; 7] = [
+    "started",
+    "status-value",
+    "finished",
+    "failed",
+    "canceled",
+    "interrupted",
+    "pending",
+];
+
+/// A template for the names of job files.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FileNaming {
+    prefix: String,
+    extension: String,
+    date: bool,
+    status: bool,
+}
+
+impl FileNaming {
+    /// Files named after the job id only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start the names with `prefix`, e.g. the kind of jobs in the
+    /// directory.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// End the names with `extension`, like `.json`.
+    pub fn with_extension(mut self, extension: impl Into<String>) -> Self {
+        self.extension = extension.into();
+        self
+    }
+
+    /// Include the date the job was submitted, so names sort by date.
+    pub fn with_date(mut self, date: bool) -> Self {
+        self.date = date;
+        self
+    }
+
+    /// Include the status of the job (e.g. `finished`).
+    pub fn with_status(mut self, status: bool) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Whether the name of a job only depends on its id.
+    pub(crate) fn is_fixed(&self) -> bool {
+        !self.date && !self.status
+    }
+
+    /// The name of the file of a job.
+    ///
+    /// Jobs without a submission date are named without it.
+    pub(crate) fn name<S>(
+        &self,
+        id: Uuid,
+        created_at: Option<DateTime<Utc>>,
+        status: &StatusType<S>,
+    ) -> String {
+        let mut name = self.prefix.clone();
+        if let Some(created_at) = created_at.filter(|_| self.date) {
+            name.push_str(&created_at.format("%Y-%m-%d_").to_string());
+        }
+        name.push_str(&id.to_string());
+        if self.status {
+            name.push('.');
+            name.push_str(&status.label().replace(' ', "-"));
+        }
+        name.push_str(&self.extension);
+        name
+    }
+
+    /// The name of the file of a job, when it only depends on its id.
+    pub(crate) fn fixed_name(&self, id: Uuid) -> String {
+        format!("{}{id}{}", self.prefix, self.extension)
+    }
+
+    /// The id of the job whose file is `name`, in this naming or the default
+    /// one.
+    pub(crate) fn parse(&self, name: &str) -> Option<Uuid> {
+        if let Ok(id) = Uuid::parse_str(name) {
+            return Some(id);
+        }
+        let name = name
+            .strip_prefix(&self.prefix)?
+            .strip_suffix(&self.extension)?;
+        let name = match name.get(..DATE_LEN) {
+            Some(date) if self.date && is_date(date) => &name[DATE_LEN..],
+            _ => name,
+        };
+        let id = Uuid::try_parse(name.get(..ID_LEN)?).ok()?;
+        match &name[ID_LEN..] {
+            "" => Some(id),
+            rest if self.status => rest
+                .strip_prefix('.')
+                .filter(|status| STATUSES.contains(status))
+                .map(|_| id),
+            _ => None,
+        }
+    }
+}
+
+/// Whether `date` is a date like `2024-05-01_`.
+fn is_date(date: &str) -> bool {
+    date.bytes().enumerate().all(|(i, b)| match i {
+        4 | 7 => b == b'-',
+        10 => b == b'_',
+        _ => b.is_ascii_digit(),
+    })
+}
@@ -0,0 +1,301 @@
+//! Notifications of the ends of jobs, e.g. to the on-call engineers.
+//!
+//! A [`Notifier`] is told when a job finishes or fails.  [`Notify`] adapts
+//! it to [`JobHooks`], to attach it to any backend with [`Hooked`]:
+//!
+//! ```
+//! # use simple_jobs::{FSJob, Hooked};
+//! use simple_jobs::notifier::{Notice, Notifier, Notify, NotifyFuture};
+//!
+//! struct Log;
+//!
+//! impl Notifier for Log {
+//!     fn on_failed(&self, notice: Notice) -> NotifyFuture {
+//!         Box::pin(async move {
+//!             eprintln!("{}", notice.text());
+//!             Ok(())
+//!         })
+//!     }
+//! }
+//!
+//! let job: FSJob<u16, String, (), ()> = FSJob::new("/tmp".into());
+//! let job = Hooked::new(job).with_hooks(Notify::new(Log).named("nightly report"));
+//! ```
+//!
+//! With the feature `slack`, `SlackNotifier` posts to a Slack incoming
+//! webhook; with the feature `smtp`, `EmailNotifier` sends an email.
+//!
+//! Notifications are sent from a task of their own, so slow notifiers don't
+//! delay the jobs, and the failed ones are dropped.
+//!
+//! Notifiers also receive the alerts of the [`alerts`](crate::alerts)
+//! module.
+//!
+//! [`Hooked`]: crate::Hooked
+
+use std::{fmt::Debug, pin::Pin, sync::Arc};
+
+use chrono::{DateTime, Utc};
+use futures::Future;
+use uuid::Uuid;
+
+use crate::{alerts::Alert, JobHooks};
+
+/// The future sending a notification.
+pub type NotifyFuture =
+    Pin<Box<dyn Future<Output = Result<(), std::io::Error>> + Send>>;
+
+/// Something notified of the ends of jobs.
+///
+/// All methods default to doing nothing.
+pub trait Notifier: Send + Sync {
+    /// The job completed with `Ok`.
+    fn on_finished(&self, _notice: Notice) -> NotifyFuture {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// The job completed with `Err`, or panicked.
+    fn on_failed(&self, _notice: Notice) -> NotifyFuture {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// A threshold was reached (see [`alerts`](crate::alerts)).
+    fn on_alert(&self, _alert: Alert) -> NotifyFuture {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// The end of a job, as notified.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Notice {
+    /// The id of the job.
+    pub id: Uuid,
+    /// The name of the jobs, if given to [`Notify::named`].
+    pub name: Option<String>,
+    /// The error of a failed job (its `Debug` representation), or its
+    /// panic message.
+    pub error: Option<String>,
+    /// When the job ended.
+    pub at: DateTime<Utc>,
+}
+
+impl Notice {
+    /// A one-line summary, e.g. `Job nightly report 67e55044-... failed`.
+    pub fn subject(&self) -> String {
+        let outcome = match self.error {
+            Some(_) => "failed",
+            None => "finished",
+        };
+        match &self.name {
+            Some(name) => format!("Job {name} {} {outcome}", self.id),
+            None => format!("Job {} {outcome}", self.id),
+        }
+    }
+
+    /// The summary, followed by the error of a failed job.
+    pub fn text(&self) -> String {
+        match &self.error {
+            Some(error) => format!("{}: {error}", self.subject()),
+            None => self.subject(),
+        }
+    }
+}
+
+/// [`JobHooks`] sending the ends of jobs to a [`Notifier`].
+///
+/// The failed attempts of jobs claimed from the queue are notified too,
+/// even if the job is returned to the queue.
+pub struct Notify<N> {
+    notifier: Arc<N>,
+    name: Option<String>,
+    failures_only: bool,
+}
+
+impl<N: Notifier + 'static> Notify<N> {
+    /// Notify `notifier` of the ends of all the jobs.
+    pub fn new(notifier: N) -> Self {
+        Self {
+            notifier: Arc::new(notifier),
+            name: None,
+            failures_only: false,
+        }
+    }
+
+    /// Name the jobs in the notices (see [`Notice::name`]).
+    pub fn named(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Only notify the jobs that fail.
+    pub fn failures_only(mut self) -> Self {
+        self.failures_only = true;
+        self
+    }
+
+    /// Notify the end of the job `id` in the background.
+    fn send(&self, id: Uuid, error: Option<String>) {
+        let notice = Notice {
+            id,
+            name: self.name.clone(),
+            error,
+            at: Utc::now(),
+        };
+        let notifier = self.notifier.clone();
+        tokio::spawn(async move {
+            // Nobody is there to report the errors to.
+            let _ = match notice.error {
+                Some(_) => notifier.on_failed(notice),
+                None => notifier.on_finished(notice),
+            }
+            .await;
+        });
+    }
+}
+
+impl<Output, Error, N> JobHooks<Output, Error> for Notify<N>
+where
+    Error: Debug,
+    N: Notifier + 'static,
+{
+    fn on_success(&self, id: Uuid, _output: &Output) {
+        if !self.failures_only {
+            self.send(id, None);
+        }
+    }
+
+    fn on_failure(&self, id: Uuid, error: &Error) {
+        self.send(id, Some(format!("{error:?}")));
+    }
+
+    fn on_panic(&self, id: Uuid, message: &str) {
+        self.send(id, Some(format!("panicked: {message}")));
+    }
+}
+
+/// A [`Notifier`] posting the [text](Notice::text) of the notices, and of
+/// the alerts, to a Slack incoming webhook.
+///
+/// Requires the feature `slack`.
+#[cfg(feature = "slack")]
+#[derive(Clone)]
+pub struct SlackNotifier {
+    url: String,
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "slack")]
+impl SlackNotifier {
+    /// Post to the incoming webhook `url`, e.g.
+    /// `https://hooks.slack.com/services/...`.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn post(&self, text: String) -> NotifyFuture {
+        let body = serde_json::json!({ "text": text }).to_string();
+        let request = self
+            .client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body);
+        Box::pin(async move {
+            request
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map(drop)
+                .map_err(std::io::Error::other)
+        })
+    }
+}
+
+#[cfg(feature = "slack")]
+impl Notifier for SlackNotifier {
+    fn on_finished(&self, notice: Notice) -> NotifyFuture {
+        self.post(notice.text())
+    }
+
+    fn on_failed(&self, notice: Notice) -> NotifyFuture {
+        self.post(notice.text())
+    }
+
+    fn on_alert(&self, alert: Alert) -> NotifyFuture {
+        self.post(alert.text())
+    }
+}
+
+/// A [`Notifier`] sending the notices by email, with their
+/// [subject](Notice::subject) and [text](Notice::text), and the alerts.
+///
+/// Requires the feature `smtp`.
+#[cfg(feature = "smtp")]
+#[derive(Clone)]
+pub struct EmailNotifier {
+    transport: lettre::AsyncSmtpTransport<lettre::Tokio1Executor>,
+    from: lettre::message::Mailbox,
+    to: Vec<lettre::message::Mailbox>,
+}
+
+#[cfg(feature = "smtp")]
+impl EmailNotifier {
+    /// Send from `from` to `to` through `transport`, e.g.
+    /// `AsyncSmtpTransport::<Tokio1Executor>::relay("smtp.example.com")?`
+    /// with its credentials.
+    pub fn new(
+        transport: lettre::AsyncSmtpTransport<lettre::Tokio1Executor>,
+        from: lettre::message::Mailbox,
+        to: lettre::message::Mailbox,
+    ) -> Self {
+        Self {
+            transport,
+            from,
+            to: vec![to],
+        }
+    }
+
+    /// Also send to `to`.
+    pub fn to(mut self, to: lettre::message::Mailbox) -> Self {
+        self.to.push(to);
+        self
+    }
+
+    fn send(&self, subject: String, text: String) -> NotifyFuture {
+        use lettre::AsyncTransport;
+
+        let mut message = lettre::Message::builder()
+            .from(self.from.clone())
+            .subject(subject);
+        for to in &self.to {
+            message = message.to(to.clone());
+        }
+        let message = message.body(text);
+        let transport = self.transport.clone();
+        Box::pin(async move {
+            let message = message.map_err(std::io::Error::other)?;
+            transport
+                .send(message)
+                .await
+                .map(drop)
+                .map_err(std::io::Error::other)
+        })
+    }
+}
+
+#[cfg(feature = "smtp")]
+impl Notifier for EmailNotifier {
+    fn on_finished(&self, notice: Notice) -> NotifyFuture {
+        self.send(notice.subject(), notice.text())
+    }
+
+    fn on_failed(&self, notice: Notice) -> NotifyFuture {
+        self.send(notice.subject(), notice.text())
+    }
+
+    fn on_alert(&self, alert: Alert) -> NotifyFuture {
+        self.send(format!("Alert: {}", alert.text()), alert.text())
+    }
+}
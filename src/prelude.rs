@@ -0,0 +1,13 @@
+//! The items needed by most programs, for glob importing:
+//!
+//! ```
+//! use simple_jobs::prelude::*;
+//! ```
+//!
+//! Like the re-exports at the crate root, the prelude is stable: items are
+//! only added to it in minor releases, and removed in major ones.
+
+pub use crate::{
+    wait, CancelReason, FSJob, Job, JobError, JobEvent, JobHandle, JobHooks,
+    JobInfo, JobLayer, StatusType,
+};
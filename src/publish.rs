@@ -0,0 +1,81 @@
+//! Publishing the events of the jobs to a message broker.
+//!
+//! With the feature `kafka`, `to_kafka` sends every [`JobEvent`] of this
+//! process to a Kafka topic, keyed by the id of its job; with the feature
+//! `nats`, `to_nats` publishes them under a NATS subject.  The events are
+//! JSON (see [`events`]), so other services react to the completions of
+//! jobs without depending on this crate or its backends:
+//!
+//! ```
+//! # #[cfg(feature = "nats")]
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = async_nats::connect("localhost:4222").await?;
+//! tokio::spawn(simple_jobs::publish::to_nats(client, "jobs"));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Events missed by a lagging publisher, or that the broker doesn't accept,
+//! are dropped.
+//!
+//! Requires the feature `kafka` or `nats`.
+
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::{events, JobEvent};
+
+/// Publish the events of the jobs of this process to the Kafka `topic`,
+/// keyed by the id of their job, until the event bus closes.
+///
+/// Requires the feature `kafka`.
+#[cfg(feature = "kafka")]
+pub async fn to_kafka(
+    producer: rdkafka::producer::FutureProducer,
+    topic: impl Into<String>,
+) {
+    use std::time::Duration;
+
+    use rdkafka::{producer::FutureRecord, util::Timeout};
+
+    let topic = topic.into();
+    let mut events = events::subscribe();
+    while let Some((event, payload)) = next(&mut events).await {
+        let key = event.id().to_string();
+        let record = FutureRecord::to(&topic).key(&key).payload(&payload);
+        let timeout = Timeout::After(Duration::from_secs(5));
+        let _ = producer.send(record, timeout).await;
+    }
+}
+
+/// Publish the events of the jobs of this process under the NATS
+/// `subject`, e.g. the event `finished` on `jobs.finished` for the subject
+/// `jobs`, until the event bus closes.
+///
+/// Requires the feature `nats`.
+#[cfg(feature = "nats")]
+pub async fn to_nats(client: async_nats::Client, subject: impl Into<String>) {
+    let subject = subject.into();
+    let mut events = events::subscribe();
+    while let Some((event, payload)) = next(&mut events).await {
+        let subject = format!("{subject}.{}", event.name());
+        let _ = client.publish(subject, payload.into()).await;
+    }
+    let _ = client.flush().await;
+}
+
+/// The next event from `events`, with its JSON, or `None` once the event
+/// bus closes.
+async fn next(
+    events: &mut broadcast::Receiver<JobEvent>,
+) -> Option<(JobEvent, Vec<u8>)> {
+    loop {
+        match events.recv().await {
+            Ok(event) => match serde_json::to_vec(&event) {
+                Ok(payload) => return Some((event, payload)),
+                Err(_) => continue,
+            },
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
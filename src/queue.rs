@@ -0,0 +1,1055 @@
+//! Pending jobs, saved by a process and run by another one.
+//!
+//! [`Job::enqueue`] saves a job as [`StatusType::Pending`] without running
+//! it, and a worker process sharing the backend runs it with
+//! [`Job::claim_next`]:
+//!
+//! ```
+//! # use simple_jobs::{FSJob, Job};
+//! # async fn example(job: FSJob<u16, String, u16, String>) -> std::io::Result<()> {
+//! // In the submitting process:
+//! job.enqueue(41)?;
+//!
+//! // In a worker process:
+//! while let Some(handle) =
+//!     job.claim_next(|_id, _job, n| async move { Ok(n + 1) })?
+//! {
+//!     handle.result().await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The metadata of the job is its payload: it is the only input of the
+//! closure given to [`Job::claim_next`], which should decide what to run
+//! from it.  Workers claim the pending job with the highest priority first
+//! (see [`Job::enqueue_with_priority`]), and the oldest among those.  With
+//! [aging](QueueConfig::with_aging), jobs gain priority as they wait, so a steady flow of
+//! urgent jobs doesn't starve the others forever.
+//!
+//! Jobs go to the [default queue](DEFAULT_QUEUE) unless enqueued to a named
+//! one with [`Job::enqueue_to`], e.g. `"emails"` or `"reports"`.  Workers
+//! claim jobs from the queues they subscribe to with
+//! [`Job::claim_next_from`], and each queue can have its own
+//! [concurrency limit](QueueConfig::with_concurrency) in a worker process.
+//! Queues can be paused for all the workers with [`Job::pause_queue`], and
+//! limited to a number of runs per time window with
+//! [`QueueConfig::with_window_limit`].
+//!
+//! Jobs can also require scarce resources of the worker, like `"gpu"`
+//! (see [`EnqueueOptions::resource`]): a worker process runs at most the
+//! [capacity](QueueConfig::with_resource_capacity) of each resource at the same time,
+//! across all its queues.
+//!
+//! Queues are unbounded unless given a [capacity](QueueConfig::with_capacity):
+//! enqueuing
+//! to a full queue then fails with
+//! [`JobError::QueueFull`], or waits for room
+//! with [`enqueue_when_ready`].
+//!
+//! A worker claiming a job holds a [`Lease`] on it for the
+//! [visibility timeout](QueueConfig::with_visibility_timeout).  If the lease expires
+//! before the job ends (e.g. because the worker crashed), the job is offered
+//! again to the workers, and the saves of the previous worker fail with
+//! [`JobError::LeaseLost`], so the job never
+//! ends twice.  Jobs running longer than the timeout should renew their
+//! lease as they progress, with
+//! [`JobContext::extend_lease`](crate::JobContext::extend_lease).
+//!
+//! A job failing (returning an error, panicking or losing its lease) is
+//! recorded as a failed [`Attempt`] and offered again, up to the
+//! [maximum number of attempts](QueueConfig::with_max_attempts).  It is
+//! then dead-lettered: it keeps its final status, error and attempts for
+//! inspection with [`Job::dead_letters`], until it is fixed and re-driven
+//! with [`Job::redrive`].
+//!
+//! These limits are those of the [`QueueConfig`] of the backend (see
+//! [`Job::queue_config`]), which workers sharing the backend should agree
+//! on.  Wrap a backend in a [`WithQueues`] to change the defaults:
+//!
+//! ```
+//! # use std::time::Duration;
+//! # use simple_jobs::{queue::{QueueConfig, WithQueues}, FSJob};
+//! # fn example(job: FSJob<u16, String, u16, String>) {
+//! let config = QueueConfig::new()
+//!     .with_max_attempts(5)
+//!     .with_concurrency("thumbnails", 4)
+//!     .with_visibility_timeout(Duration::from_secs(60));
+//! let job = WithQueues::new(job, config);
+//! # }
+//! ```
+//!
+//! [`StatusType::Pending`]: crate::StatusType::Pending
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use futures::Future;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    events, hooks::DynHooks, ids, ids::IdGenerator, layers::DynLayer, local,
+    retry::Backoff, run, secrets::SecretProvider, spawn::Spawner, worker, Info,
+    Job, JobError, JobEvent, JobHandle, JobInfo, StatusType,
+};
+
+/// The queue of the jobs enqueued without naming one.
+pub const DEFAULT_QUEUE: &str = "default";
+
+/// How often [`enqueue_when_ready`] checks a full queue.
+const CAPACITY_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+tokio::task_local! {
+    /// The token of the lease held by the job running in the task, if it
+    /// was claimed from the queue.
+    static CLAIM: Uuid;
+}
+
+/// The default [visibility timeout](QueueConfig::with_visibility_timeout).
+const DEFAULT_VISIBILITY_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// The default [maximum number of attempts](QueueConfig::with_max_attempts).
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// How the queues of a backend are run (see [`Job::queue_config`]).
+///
+/// The concurrency limits and the resource capacities hold for the jobs
+/// claimed through the backends sharing the same configuration, e.g. the
+/// clones of a [`WithQueues`].
+#[derive(Debug)]
+pub struct QueueConfig {
+    visibility_timeout: Duration,
+    max_attempts: u32,
+    aging: Option<Duration>,
+    concurrency: BTreeMap<String, usize>,
+    capacity: BTreeMap<String, usize>,
+    window_limits: BTreeMap<String, (u32, Duration)>,
+    resources: BTreeMap<String, usize>,
+    /// The number of jobs of each queue running.
+    running: Mutex<BTreeMap<String, usize>>,
+    /// The number of running jobs holding each resource.
+    in_use: Mutex<BTreeMap<String, usize>>,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            visibility_timeout: DEFAULT_VISIBILITY_TIMEOUT,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            aging: None,
+            concurrency: BTreeMap::new(),
+            capacity: BTreeMap::new(),
+            window_limits: BTreeMap::new(),
+            resources: BTreeMap::new(),
+            running: Mutex::new(BTreeMap::new()),
+            in_use: Mutex::new(BTreeMap::new()),
+        }
+    }
+}
+
+impl QueueConfig {
+    /// Unbounded queues without limits, with a visibility timeout of 5
+    /// minutes and 3 attempts per job.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how long a claimed job stays invisible to the other workers.
+    ///
+    /// Defaults to 5 minutes.
+    pub fn with_visibility_timeout(mut self, timeout: Duration) -> Self {
+        self.visibility_timeout = timeout;
+        self
+    }
+
+    /// How long a claimed job stays invisible to the other workers.
+    pub fn visibility_timeout(&self) -> Duration {
+        self.visibility_timeout
+    }
+
+    /// Set how many times a job claimed from the queue is run before it is
+    /// dead-lettered.
+    ///
+    /// Defaults to 3.
+    pub fn with_max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts;
+        self
+    }
+
+    /// How many times a job claimed from the queue is run before it is
+    /// dead-lettered.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Raise the priority of pending jobs by one for every `period` they
+    /// wait.
+    ///
+    /// Disabled by default: jobs keep the priority they were enqueued with.
+    pub fn with_aging(mut self, period: Duration) -> Self {
+        self.aging = Some(period);
+        self
+    }
+
+    /// How long pending jobs wait to gain one level of priority, if they
+    /// age.
+    pub fn aging(&self) -> Option<Duration> {
+        self.aging
+    }
+
+    /// Run at most `max` jobs of `queue` at the same time.
+    ///
+    /// Queues have no limit by default.  Workers over the limit of a queue
+    /// claim jobs from the other queues they subscribe to.
+    pub fn with_concurrency(mut self, queue: &str, max: usize) -> Self {
+        self.concurrency.insert(queue.to_string(), max);
+        self
+    }
+
+    /// How many jobs of `queue` may run at the same time, if limited.
+    pub fn concurrency(&self, queue: &str) -> Option<usize> {
+        self.concurrency.get(queue).copied()
+    }
+
+    /// Make the enqueues to `queue` fail once it has `max` pending jobs.
+    ///
+    /// Queues are unbounded by default.  The pending jobs are counted at
+    /// each enqueue (see [`Job::scan`]), so processes enqueuing concurrently
+    /// may slightly exceed the capacity.
+    pub fn with_capacity(mut self, queue: &str, max: usize) -> Self {
+        self.capacity.insert(queue.to_string(), max);
+        self
+    }
+
+    /// How many pending jobs `queue` accepts, if bounded.
+    pub fn capacity(&self, queue: &str) -> Option<usize> {
+        self.capacity.get(queue).copied()
+    }
+
+    /// Start at most `runs` jobs of `queue` in any `window`, for each
+    /// throttle key (see [`EnqueueOptions::throttle_key`]), e.g. 100 emails
+    /// a minute per tenant.
+    ///
+    /// Queues have no window limit by default.  The runs are counted from
+    /// the records of the jobs (with their failed attempts), so the limit
+    /// holds across restarts and for all the workers with the same limit;
+    /// jobs over it wait in their queue.
+    pub fn with_window_limit(
+        mut self,
+        queue: &str,
+        runs: u32,
+        window: Duration,
+    ) -> Self {
+        self.window_limits.insert(queue.to_string(), (runs, window));
+        self
+    }
+
+    /// The number of runs of the jobs of `queue` allowed in a window, and
+    /// the window, if limited.
+    pub fn window_limit(&self, queue: &str) -> Option<(u32, Duration)> {
+        self.window_limits.get(queue).copied()
+    }
+
+    /// Run at most `max` jobs requiring `resource` (see
+    /// [`EnqueueOptions::resource`]) at the same time, e.g. the number of
+    /// GPUs of the machine.
+    ///
+    /// Resources are unlimited until given a capacity.  Workers without a
+    /// permit for all the resources of a job claim other jobs.
+    pub fn with_resource_capacity(
+        mut self,
+        resource: &str,
+        max: usize,
+    ) -> Self {
+        self.resources.insert(resource.to_string(), max);
+        self
+    }
+
+    /// How many jobs requiring `resource` may run at the same time, if
+    /// limited.
+    pub fn resource_capacity(&self, resource: &str) -> Option<usize> {
+        self.resources.get(resource).copied()
+    }
+
+    /// When a lease taken now expires.
+    fn expiry(&self) -> DateTime<Utc> {
+        chrono::Duration::from_std(self.visibility_timeout)
+            .ok()
+            .and_then(|timeout| Utc::now().checked_add_signed(timeout))
+            .unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+}
+
+/// A [`Job`] wrapping another backend, running its queues with a custom
+/// [`QueueConfig`].
+pub struct WithQueues<J> {
+    inner: J,
+    config: Arc<QueueConfig>,
+}
+
+impl<J: Clone> Clone for WithQueues<J> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            config: self.config.clone(),
+        }
+    }
+}
+
+impl<J: Job> WithQueues<J> {
+    /// Wrap a backend, running its queues with `config`.
+    pub fn new(inner: J, config: QueueConfig) -> Self {
+        Self {
+            inner,
+            config: Arc::new(config),
+        }
+    }
+
+    /// The wrapped backend.
+    pub fn inner(&self) -> &J {
+        &self.inner
+    }
+}
+
+impl<J: Job> Job for WithQueues<J> {
+    type Output = J::Output;
+    type Error = J::Error;
+    type Metadata = J::Metadata;
+    type Status = J::Status;
+
+    delegate_storage!(inner);
+
+    fn hooks(&self) -> &[DynHooks<Self::Output, Self::Error>] {
+        self.inner.hooks()
+    }
+
+    fn layers(&self) -> &[DynLayer<Self::Output, Self::Error>] {
+        self.inner.layers()
+    }
+
+    fn save_backoff(&self) -> Backoff {
+        self.inner.save_backoff()
+    }
+
+    fn id_generator(&self) -> Arc<dyn IdGenerator> {
+        self.inner.id_generator()
+    }
+
+    fn spawner(&self) -> Arc<dyn Spawner> {
+        self.inner.spawner()
+    }
+
+    fn admit(&self) -> Result<(), std::io::Error> {
+        self.inner.admit()
+    }
+
+    fn secret_provider(&self) -> Arc<dyn SecretProvider> {
+        self.inner.secret_provider()
+    }
+
+    fn queue_config(&self) -> Arc<QueueConfig> {
+        self.config.clone()
+    }
+}
+
+/// The claim of a worker on a job, or of a process on a named lease of the
+/// backend (see [`Job::acquire_lease`]).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lease {
+    /// The label of the worker (see [`worker::label`]).
+    pub owner: String,
+    /// Identifies the claim, so a worker claiming the same job twice holds
+    /// different leases.
+    pub token: Uuid,
+    /// When the job is offered again to the workers, or another process can
+    /// take the lease.
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Lease {
+    /// A lease for this worker, expiring after the visibility timeout of
+    /// `config`.
+    fn new(config: &QueueConfig) -> Self {
+        Self {
+            owner: worker::label(),
+            token: Uuid::new_v4(),
+            expires_at: config.expiry(),
+        }
+    }
+
+    /// Whether the lease has expired.
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
+
+/// A failed run of a job claimed from the queue.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Attempt<Error> {
+    /// The label of the worker that ran the job.
+    pub worker: Option<String>,
+    /// When the job was claimed.
+    pub started_at: Option<DateTime<Utc>>,
+    /// When the attempt failed.
+    pub ended_at: DateTime<Utc>,
+    /// Why the attempt failed.
+    pub failure: Failure<Error>,
+}
+
+/// Why an [`Attempt`] failed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Failure<Error> {
+    /// The job returned an error.
+    Error(Error),
+    /// The job panicked, with this message.
+    Panic(String),
+    /// The lease of the worker expired before the job ended.
+    LeaseExpired,
+}
+
+/// The priority of a pending job, raised as it waits with aging.
+fn effective_priority<J: Job>(
+    config: &QueueConfig,
+    info: &Info<J>,
+    now: DateTime<Utc>,
+) -> u32 {
+    let priority = info.priority as u32;
+    let (Some(period), Some(created_at)) = (config.aging(), info.created_at)
+    else {
+        return priority;
+    };
+    let waited = (now - created_at).to_std().unwrap_or_default();
+    let levels = waited.as_nanos() / period.as_nanos().max(1);
+    priority.saturating_add(levels.min(u32::MAX as u128) as u32)
+}
+
+/// Fail with [`JobError::QueueFull`] if `queue` has reached its capacity.
+fn check_capacity<J: Job>(job: &J, queue: &str) -> Result<(), std::io::Error> {
+    let Some(capacity) = job.queue_config().capacity(queue) else {
+        return Ok(());
+    };
+    let pending = job
+        .scan()?
+        .filter(|info| {
+            matches!(info.status, StatusType::Pending)
+                && queue_of::<J>(info) == queue
+        })
+        .count();
+    if pending >= capacity {
+        return Err(JobError::QueueFull {
+            queue: queue.to_string(),
+            capacity,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Like [`Job::enqueue_to`], waiting for room in `queue` if it is full
+/// (see [`QueueConfig::with_capacity`]).
+pub async fn enqueue_when_ready<J: Job>(
+    job: &J,
+    queue: &str,
+    metadata: J::Metadata,
+    priority: u8,
+) -> Result<Uuid, std::io::Error> {
+    loop {
+        match job.enqueue_to(queue, metadata.clone(), priority) {
+            Err(e) if is_queue_full(&e) => {
+                tokio::time::sleep(CAPACITY_POLL_INTERVAL).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Whether an error is a [`JobError::QueueFull`].
+fn is_queue_full(error: &std::io::Error) -> bool {
+    matches!(JobError::from_io(error), Some(JobError::QueueFull { .. }))
+}
+
+/// A running job of a queue, counted in its configuration until dropped.
+struct Slot {
+    config: Arc<QueueConfig>,
+    queue: String,
+}
+
+impl Slot {
+    /// A slot to run a job of `queue`, if under its concurrency limit.
+    fn reserve(config: &Arc<QueueConfig>, queue: &str) -> Option<Self> {
+        let mut running = config.running.lock().expect("cannot get lock");
+        let count = running.entry(queue.to_string()).or_default();
+        if config.concurrency(queue).is_some_and(|max| *count >= max) {
+            return None;
+        }
+        *count += 1;
+        Some(Self {
+            config: config.clone(),
+            queue: queue.to_string(),
+        })
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let mut running = self.config.running.lock().expect("cannot get lock");
+        if let Some(count) = running.get_mut(&self.queue) {
+            *count = count.saturating_sub(1);
+        }
+    }
+}
+
+/// The resources held by a running job, counted in its configuration until
+/// dropped.
+struct Permits {
+    config: Arc<QueueConfig>,
+    resources: Vec<String>,
+}
+
+impl Permits {
+    /// A permit for each of `resources`, if all are under their capacity.
+    fn acquire(
+        config: &Arc<QueueConfig>,
+        resources: &[String],
+    ) -> Option<Self> {
+        let mut in_use = config.in_use.lock().expect("cannot get lock");
+        let available = resources.iter().all(|resource| {
+            let count = in_use.get(resource).copied().unwrap_or(0);
+            config
+                .resource_capacity(resource)
+                .is_none_or(|max| count < max)
+        });
+        if !available {
+            return None;
+        }
+        for resource in resources {
+            *in_use.entry(resource.clone()).or_default() += 1;
+        }
+        Some(Self {
+            config: config.clone(),
+            resources: resources.to_vec(),
+        })
+    }
+}
+
+impl Drop for Permits {
+    fn drop(&mut self) {
+        let mut in_use = self.config.in_use.lock().expect("cannot get lock");
+        for resource in &self.resources {
+            if let Some(count) = in_use.get_mut(resource) {
+                *count = count.saturating_sub(1);
+            }
+        }
+    }
+}
+
+/// The queue of a job, if it was enqueued.
+fn queue_of<J: Job>(info: &Info<J>) -> &str {
+    info.queue.as_deref().unwrap_or(DEFAULT_QUEUE)
+}
+
+/// The number of pending jobs in each queue of `job`, without the queues
+/// that have none.
+pub fn depths<J: Job>(
+    job: &J,
+) -> Result<BTreeMap<String, usize>, std::io::Error> {
+    let mut depths = BTreeMap::new();
+    for info in job.scan()? {
+        if matches!(info.status, StatusType::Pending) {
+            *depths.entry(queue_of::<J>(&info).to_string()).or_default() += 1;
+        }
+    }
+    Ok(depths)
+}
+
+/// The token of the lease of the claimed job running in the current task
+/// (see [`Job::claim_next`]), if any.
+pub(crate) fn claim_token() -> Option<Uuid> {
+    CLAIM.try_with(|token| *token).ok()
+}
+
+/// Renew the lease of the claim `token` on the job `id` for another
+/// visibility timeout (see
+/// [`JobContext::extend_lease`](crate::JobContext::extend_lease)).
+///
+/// Without a token, any lease of this worker is renewed.
+pub(crate) fn extend_lease<J: Job>(
+    job: &J,
+    id: Uuid,
+    token: Option<Uuid>,
+) -> Result<Lease, std::io::Error> {
+    let owner = worker::label();
+    let config = job.queue_config();
+    let held = |lease: &Lease| match token {
+        Some(token) => lease.token == token,
+        None => lease.owner == owner,
+    };
+    let info = job.update(id, |info| match info.lease.as_mut() {
+        Some(lease) if held(lease) => {
+            lease.expires_at = config.expiry();
+            Ok(())
+        }
+        Some(_) => Err(JobError::LeaseLost { id }.into()),
+        None => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("job {id} was not claimed from the queue"),
+        )),
+    })?;
+    Ok(info.lease.expect("the lease was just renewed"))
+}
+
+/// Record a failed attempt of a claimed job, whose final state is in
+/// `info`, and return it to the queue if it has attempts left in `config`.
+///
+/// Returns whether the job was returned to the queue; otherwise it is
+/// dead-lettered, keeping its final state.
+pub(crate) fn fail<J: Job>(
+    config: &QueueConfig,
+    info: &mut Info<J>,
+    failure: Failure<J::Error>,
+) -> bool {
+    let now = Utc::now();
+    info.attempts.push(Attempt {
+        worker: info.worker.clone(),
+        started_at: info.started_at,
+        ended_at: now,
+        failure,
+    });
+    if info.attempts.len() < config.max_attempts() as usize {
+        info.status = StatusType::Pending;
+        info.result = None;
+        info.finished_at = None;
+        true
+    } else {
+        info.dead_lettered_at = Some(now);
+        false
+    }
+}
+
+/// Offer a dead-lettered job again (see [`Job::redrive`]).
+pub(crate) fn redrive<J: Job>(
+    job: &J,
+    id: Uuid,
+) -> Result<Info<J>, std::io::Error> {
+    let info = job.update(id, |info| {
+        if info.dead_lettered_at.is_none() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("job {id} is not dead-lettered"),
+            ));
+        }
+        info.status = StatusType::Pending;
+        info.result = None;
+        info.started_at = None;
+        info.finished_at = None;
+        info.lease = None;
+        info.attempts.clear();
+        info.dead_lettered_at = None;
+        Ok(())
+    })?;
+    events::publish(JobEvent::Enqueued { id });
+    Ok(info)
+}
+
+/// Whether a job holds its concurrency key: claimed, not ended, and with a
+/// live lease.
+fn holds_key<J: Job>(info: &Info<J>) -> bool {
+    info.concurrency_key.is_some()
+        && !matches!(info.status, StatusType::Pending)
+        && !info.status.is_terminal()
+        && info.lease.as_ref().is_some_and(|lease| !lease.is_expired())
+}
+
+/// The group of jobs sharing a
+/// [window limit](QueueConfig::with_window_limit): their
+/// queue and throttle key.
+fn window_group<J: Job>(info: &Info<J>) -> (&str, Option<&str>) {
+    (queue_of::<J>(info), info.throttle_key.as_deref())
+}
+
+/// How many runs of a job started since `since`, counting its failed
+/// attempts.
+fn runs_since<J: Job>(info: &Info<J>, since: DateTime<Utc>) -> usize {
+    let recent = |at: Option<DateTime<Utc>>| at.is_some_and(|at| at >= since);
+    let failed = info
+        .attempts
+        .iter()
+        .filter(|attempt| recent(attempt.started_at))
+        .count();
+    // The last run is also an attempt if it failed.
+    let last_failed = info.attempts.last().and_then(|a| a.started_at);
+    let current = !matches!(info.status, StatusType::Pending)
+        && recent(info.started_at)
+        && info.started_at != last_failed;
+    failed + current as usize
+}
+
+/// When the window of a limit ending now started.
+fn window_start(window: Duration) -> DateTime<Utc> {
+    chrono::Duration::from_std(window)
+        .ok()
+        .and_then(|window| Utc::now().checked_sub_signed(window))
+        .unwrap_or(DateTime::<Utc>::MIN_UTC)
+}
+
+/// Whether the job just claimed in `info` must be returned to the queue:
+/// another job holds its concurrency key, or its group exceeded its
+/// window limit.
+///
+/// Every worker checks after saving its claim, so of two workers claiming
+/// conflicting jobs at once, at least one sees the other and backs off.
+fn must_back_off<J: Job>(
+    job: &J,
+    info: &Info<J>,
+) -> Result<bool, std::io::Error> {
+    let limit = job.queue_config().window_limit(queue_of::<J>(info));
+    if info.concurrency_key.is_none() && limit.is_none() {
+        return Ok(false);
+    }
+    let others: Vec<Info<J>> =
+        job.scan()?.filter(|other| other.id != info.id).collect();
+    let key_taken = info.concurrency_key.as_ref().is_some_and(|key| {
+        others.iter().any(|other| {
+            holds_key::<J>(other) && other.concurrency_key.as_ref() == Some(key)
+        })
+    });
+    let over_limit = limit.is_some_and(|(runs, window)| {
+        let since = window_start(window);
+        let group = window_group::<J>(info);
+        let started: usize = others
+            .iter()
+            .filter(|other| window_group::<J>(other) == group)
+            .map(|other| runs_since::<J>(other, since))
+            .sum();
+        started + runs_since::<J>(info, since) > runs as usize
+    });
+    Ok(key_taken || over_limit)
+}
+
+/// Whether a job can be claimed: pending, or running with an expired
+/// lease.
+fn is_claimable<J: Job>(info: &Info<J>) -> bool {
+    let expired = !info.status.is_terminal()
+        && info.lease.as_ref().is_some_and(Lease::is_expired);
+    info.metadata.is_some()
+        && (matches!(info.status, StatusType::Pending) || expired)
+}
+
+/// How to enqueue a job (see [`Job::enqueue_with`]).
+///
+/// ```
+/// # use simple_jobs::{queue::EnqueueOptions, FSJob, Job};
+/// # fn example(job: FSJob<u16, String, u16, String>) -> std::io::Result<()> {
+/// let options = EnqueueOptions::new()
+///     .queue("sync")
+///     .priority(3)
+///     .concurrency_key("customer-42-sync");
+/// job.enqueue_with(42, &options)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnqueueOptions {
+    queue: String,
+    priority: u8,
+    concurrency_key: Option<String>,
+    unique_key: Option<String>,
+    throttle_key: Option<String>,
+    resources: Vec<String>,
+    retention: Option<Duration>,
+    webhooks: Vec<String>,
+}
+
+impl Default for EnqueueOptions {
+    fn default() -> Self {
+        Self {
+            queue: DEFAULT_QUEUE.to_string(),
+            priority: 0,
+            concurrency_key: None,
+            unique_key: None,
+            throttle_key: None,
+            resources: vec![],
+            retention: None,
+            webhooks: vec![],
+        }
+    }
+}
+
+impl EnqueueOptions {
+    /// The [default queue](DEFAULT_QUEUE), with priority 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enqueue to the named `queue` (see [`Job::enqueue_to`]).
+    pub fn queue(mut self, queue: impl Into<String>) -> Self {
+        self.queue = queue.into();
+        self
+    }
+
+    /// Claim the job before those with a lower priority (see
+    /// [`Job::enqueue_with_priority`]).
+    pub fn priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Never run the job at the same time as another job with the same
+    /// `key`, e.g. two synchronizations of the same account: while one runs,
+    /// the others wait in their queue.
+    ///
+    /// Holds across all the workers sharing the backend.
+    pub fn concurrency_key(mut self, key: impl Into<String>) -> Self {
+        self.concurrency_key = Some(key.into());
+        self
+    }
+
+    /// Count the job against the
+    /// [window limit](QueueConfig::with_window_limit) of its
+    /// queue with the other jobs with the same `key`, e.g. its tenant.
+    ///
+    /// Jobs without a throttle key share the limit of their queue.
+    pub fn throttle_key(mut self, key: impl Into<String>) -> Self {
+        self.throttle_key = Some(key.into());
+        self
+    }
+
+    /// Run the job only with a permit for `resource`, e.g. `"gpu"` or
+    /// `"db-heavy"`, held while it runs (see
+    /// [`QueueConfig::with_resource_capacity`]).
+    ///
+    /// Call it again for jobs requiring several resources.
+    pub fn resource(mut self, resource: impl Into<String>) -> Self {
+        self.resources.push(resource.into());
+        self
+    }
+
+    /// Only keep the record of the job for `retention` once it ends (see
+    /// [`retention`](crate::retention)).
+    pub fn retain_for(mut self, retention: Duration) -> Self {
+        self.retention = Some(retention);
+        self
+    }
+
+    /// Also notify `url` when the job ends, besides the global webhooks
+    /// (see the module `webhooks`, with the feature `webhooks`).
+    ///
+    /// Call it again for several URLs.
+    pub fn webhook(mut self, url: impl Into<String>) -> Self {
+        self.webhooks.push(url.into());
+        self
+    }
+
+    /// Don't enqueue the job while a job enqueued (or submitted with
+    /// [`Job::submit_unique`]) with the same `key` isn't terminal, e.g. so
+    /// only one reindex is ever pending or running: enqueuing returns the
+    /// id of that job instead.
+    ///
+    /// The key is recorded as the
+    /// [`idempotency_key`](crate::JobInfo::idempotency_key) of the job,
+    /// whose id is derived from it (see [`unique_id`]), as with
+    /// [`Job::submit_unique`]: concurrent enqueues, even from several
+    /// processes, collapse onto one record, and a job enqueued once the
+    /// previous one ended replaces its record.
+    pub fn unique(mut self, key: impl Into<String>) -> Self {
+        self.unique_key = Some(key.into());
+        self
+    }
+}
+
+/// The id of the jobs with the unique `key` (see
+/// [`EnqueueOptions::unique`] and [`Job::submit_unique`]): the same in every
+/// process, so that finding the active job of a key is a single
+/// [`Job::load`].
+pub fn unique_id(key: &str) -> Uuid {
+    ids::from_key(&Uuid::nil(), key)
+}
+
+/// Whether the job `id` exists and isn't terminal.
+pub(crate) fn is_active<J: Job>(
+    job: &J,
+    id: Uuid,
+) -> Result<bool, std::io::Error> {
+    match job.load(id) {
+        Ok(info) => Ok(!info.status.is_terminal()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Save a pending job (see [`Job::enqueue`]).
+pub(crate) fn enqueue<J: Job>(
+    job: &J,
+    metadata: J::Metadata,
+    options: &EnqueueOptions,
+) -> Result<uuid::Uuid, std::io::Error> {
+    job.admit()?;
+    let id = match &options.unique_key {
+        Some(key) => unique_id(key),
+        None => job.id_generator().generate(),
+    };
+    let lock = options.unique_key.as_ref().map(|_| local::record_lock(id));
+    let _guard = lock.as_ref().map(|l| l.lock().expect("cannot get lock"));
+    if options.unique_key.is_some() && is_active(job, id)? {
+        return Ok(id);
+    }
+    check_capacity(job, &options.queue)?;
+    let info: Info<J> = JobInfo {
+        id,
+        status: StatusType::Pending,
+        metadata: Some(metadata),
+        priority: options.priority,
+        queue: Some(options.queue.clone()),
+        concurrency_key: options.concurrency_key.clone(),
+        idempotency_key: options.unique_key.clone(),
+        throttle_key: options.throttle_key.clone(),
+        resources: options.resources.clone(),
+        retention: options.retention,
+        webhooks: options.webhooks.clone(),
+        ..JobInfo::new()
+    };
+    let hooks = job.hooks();
+    if let Err(e) = job.save(&info) {
+        hooks.iter().for_each(|h| h.on_save_error(id, &e));
+        return Err(e);
+    }
+    hooks.iter().for_each(|h| h.on_submit(id));
+    events::publish(JobEvent::Enqueued { id });
+    Ok(id)
+}
+
+/// Claim and start the pending job of `queues` with the highest priority,
+/// or a job with an expired lease (see [`Job::claim_next_from`]).
+pub(crate) fn claim_next<J, F, Fut>(
+    job: &J,
+    queues: &[&str],
+    f: F,
+) -> Result<Option<JobHandle<J>>, std::io::Error>
+where
+    J: Job,
+    F: FnOnce(uuid::Uuid, J, J::Metadata) -> Fut,
+    Fut: Future<Output = Result<J::Output, J::Error>> + Send + 'static,
+{
+    job.admit()?;
+    let paused = job.paused_queues()?;
+    let queues: Vec<&str> = queues
+        .iter()
+        .copied()
+        .filter(|queue| !paused.iter().any(|p| p == queue))
+        .collect();
+    if queues.is_empty() {
+        return Ok(None);
+    }
+    let config = job.queue_config();
+    let infos: Vec<Info<J>> = job.scan()?.collect();
+    let running: Vec<String> = infos
+        .iter()
+        .filter(|info| holds_key::<J>(info))
+        .filter_map(|info| info.concurrency_key.clone())
+        .collect();
+    // The runs started in the current window of each limited group.
+    let mut started: BTreeMap<(String, Option<String>), usize> =
+        BTreeMap::new();
+    for info in &infos {
+        let (queue, key) = window_group::<J>(info);
+        if let Some((_, window)) = config.window_limit(queue) {
+            *started
+                .entry((queue.to_string(), key.map(str::to_string)))
+                .or_default() += runs_since::<J>(info, window_start(window));
+        }
+    }
+    let throttled = |info: &Info<J>| {
+        let (queue, key) = window_group::<J>(info);
+        config.window_limit(queue).is_some_and(|(runs, _)| {
+            let group = (queue.to_string(), key.map(str::to_string));
+            started.get(&group).copied().unwrap_or(0) >= runs as usize
+        })
+    };
+    let mut pending: Vec<Info<J>> = infos
+        .into_iter()
+        .filter(|info| queues.contains(&queue_of::<J>(info)))
+        .filter(is_claimable::<J>)
+        .filter(|info| {
+            info.concurrency_key
+                .as_ref()
+                .is_none_or(|key| !running.contains(key))
+        })
+        .filter(|info| !throttled(info))
+        .collect();
+    let now = Utc::now();
+    pending.sort_by_key(|info| {
+        (
+            std::cmp::Reverse(effective_priority::<J>(&config, info, now)),
+            info.created_at,
+        )
+    });
+    for mut info in pending {
+        let version = info.version;
+        // Not pending: the lease of the previous worker expired.
+        if !matches!(info.status, StatusType::Pending)
+            && !fail::<J>(&config, &mut info, Failure::LeaseExpired)
+        {
+            info.status =
+                StatusType::Failed("the lease of the job expired".into());
+            info.finished_at = info.dead_lettered_at;
+            match job.save_if_version(&mut info, version) {
+                Ok(_) => {
+                    events::publish(JobEvent::DeadLettered { id: info.id })
+                }
+                Err(e) if run::is_conflict(&e) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+            continue;
+        }
+        let Some(slot) = Slot::reserve(&config, queue_of::<J>(&info)) else {
+            continue;
+        };
+        let Some(permits) = Permits::acquire(&config, &info.resources) else {
+            continue;
+        };
+        info.status = StatusType::Started;
+        info.started_at = Some(Utc::now());
+        info.worker = Some(worker::label());
+        info.lease = Some(Lease::new(&config));
+        match job.save_if_version(&mut info, version) {
+            Ok(_) if must_back_off(job, &info)? => {
+                // Another worker claimed a conflicting job meanwhile.
+                info.status = StatusType::Pending;
+                info.started_at = None;
+                info.worker = None;
+                info.lease = None;
+                let version = info.version;
+                match job.save_if_version(&mut info, version) {
+                    Ok(_) => continue,
+                    Err(e) if run::is_conflict(&e) => continue,
+                    Err(e) => return Err(e),
+                }
+            }
+            Ok(_) => {
+                // The slot and the permits are freed when the task of the
+                // job ends, and its contexts know the claim.
+                let token = info.lease.as_ref().expect("just leased").token;
+                let f = move |id, job, metadata| {
+                    let fut = CLAIM.sync_scope(token, || f(id, job, metadata));
+                    CLAIM.scope(token, async move {
+                        let _slot = slot;
+                        let _permits = permits;
+                        fut.await
+                    })
+                };
+                return Ok(Some(run::start(job, info, f)));
+            }
+            // Claimed by another worker since it was listed.
+            Err(e) if run::is_conflict(&e) => continue,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,417 @@
+//! Framing of persisted records.
+//!
+//! A record is the serialized form of a [`JobInfo`](crate::JobInfo), as
+//! stored by a backend.  Records may start with a small header naming the
+//! encoding and the compression used for the rest of the record:
+//!
+//! ```text
+//! +------+-----+-----+---------+----------+-------------+---------+
+//! | 0xB5 | 'S' | 'J' | version | encoding | compression | payload |
+//! +------+-----+-----+---------+----------+-------------+---------+
+//! ```
+//!
+//! Records written with [`RecordCodec::with_checksums`] use version 2 of the
+//! header, followed by the CRC-32 of the payload (4 bytes, little endian),
+//! so damaged records are reported as
+//! [`JobError::Corrupted`](crate::error::JobError::Corrupted).
+//!
+//! Since every record describes itself, a store can hold a mix of records
+//! (e.g. old plain JSON ones and new compressed ones) and still be read
+//! transparently while it is gradually migrated to a new configuration.
+//! Records without a header are plain, uncompressed JSON; they are still
+//! written when no compression is configured, so those stores remain
+//! readable by older releases.
+
+use std::sync::Arc;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::error::JobError;
+
+/// First bytes of a record with a header.  `0xB5` can't start a JSON
+/// document, so records without header are never mistaken for one.
+const MAGIC: [u8; 3] = [0xB5, b'S', b'J'];
+
+/// Version of the header layout.
+const HEADER_VERSION: u8 = 1;
+
+/// Version of the header layout with a checksum.
+const CHECKED_HEADER_VERSION: u8 = 2;
+
+const HEADER_LEN: usize = MAGIC.len() + 3;
+
+const CHECKSUM_LEN: usize = 4;
+
+/// How a record's payload is serialized.
+///
+/// JSON is always available and readable by humans; the others are more
+/// compact, and need the feature of the same name (`bincode`, `msgpack` or
+/// `cbor`).  Using an encoding whose feature is disabled fails with
+/// [`std::io::ErrorKind::Unsupported`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Encoding {
+    /// JSON, using `serde_json`.
+    Json,
+    /// Bincode, using `bincode`.  Not self-describing (see
+    /// [`Encoding::is_self_describing`]).
+    Bincode,
+    /// MessagePack, using `rmp-serde`.
+    MessagePack,
+    /// CBOR, using `ciborium`.
+    Cbor,
+}
+
+impl Encoding {
+    /// The id of the encoding in record headers.
+    pub fn id(&self) -> u8 {
+        match self {
+            Encoding::Json => 0,
+            Encoding::Bincode => 1,
+            Encoding::MessagePack => 2,
+            Encoding::Cbor => 3,
+        }
+    }
+
+    fn from_id(id: u8) -> Result<Self, std::io::Error> {
+        match id {
+            0 => Ok(Encoding::Json),
+            1 => Ok(Encoding::Bincode),
+            2 => Ok(Encoding::MessagePack),
+            3 => Ok(Encoding::Cbor),
+            _ => Err(invalid(format!("unknown record encoding {id}"))),
+        }
+    }
+
+    /// Whether values can be decoded without knowing their type, as
+    /// needed to migrate records (see [`Schema`](crate::versioning::Schema)).
+    pub fn is_self_describing(&self) -> bool {
+        !matches!(self, Encoding::Bincode)
+    }
+
+    /// Serialize a value with this encoding.
+    pub fn to_vec<T: Serialize>(
+        &self,
+        value: &T,
+    ) -> Result<Vec<u8>, std::io::Error> {
+        match self {
+            Encoding::Json => Ok(serde_json::to_vec(value)?),
+            #[cfg(feature = "bincode")]
+            Encoding::Bincode => bincode::serialize(value).map_err(invalid),
+            #[cfg(feature = "msgpack")]
+            Encoding::MessagePack => {
+                rmp_serde::to_vec_named(value).map_err(invalid)
+            }
+            #[cfg(feature = "cbor")]
+            Encoding::Cbor => {
+                let mut bytes = vec![];
+                ciborium::into_writer(value, &mut bytes).map_err(invalid)?;
+                Ok(bytes)
+            }
+            #[allow(unreachable_patterns)]
+            _ => Err(self.disabled()),
+        }
+    }
+
+    /// Deserialize a value serialized with [`Encoding::to_vec`].
+    pub fn from_slice<T: DeserializeOwned>(
+        &self,
+        bytes: &[u8],
+    ) -> Result<T, std::io::Error> {
+        match self {
+            Encoding::Json => Ok(serde_json::from_slice(bytes)?),
+            #[cfg(feature = "bincode")]
+            Encoding::Bincode => bincode::deserialize(bytes).map_err(invalid),
+            #[cfg(feature = "msgpack")]
+            Encoding::MessagePack => {
+                rmp_serde::from_slice(bytes).map_err(invalid)
+            }
+            #[cfg(feature = "cbor")]
+            Encoding::Cbor => ciborium::from_reader(bytes).map_err(invalid),
+            #[allow(unreachable_patterns)]
+            _ => Err(self.disabled()),
+        }
+    }
+
+    #[allow(dead_code)]
+    fn disabled(&self) -> std::io::Error {
+        std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!("the record encoding {self:?} is not enabled"),
+        )
+    }
+}
+
+/// A compression algorithm for record payloads.
+///
+/// Each implementation is identified by an id stored in the record header,
+/// so it must never change once records have been written with it.  Ids
+/// below 128 are reserved for this crate.
+pub trait Compression: Send + Sync {
+    /// The id of the algorithm in record headers.
+    fn id(&self) -> u8;
+
+    /// Compress a payload.
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, std::io::Error>;
+
+    /// Decompress a payload compressed by [`Compression::compress`].
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, std::io::Error>;
+}
+
+/// No compression at all.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Uncompressed;
+
+impl Compression for Uncompressed {
+    fn id(&self) -> u8 {
+        0
+    }
+
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        Ok(data.to_vec())
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        Ok(data.to_vec())
+    }
+}
+
+/// Zstandard compression, using `zstd` (feature `zstd`).
+#[cfg(feature = "zstd")]
+#[derive(Clone, Copy, Debug)]
+pub struct Zstd {
+    /// The compression level (1 to 22).
+    pub level: i32,
+}
+
+#[cfg(feature = "zstd")]
+impl Default for Zstd {
+    fn default() -> Self {
+        Self { level: 3 }
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl Compression for Zstd {
+    fn id(&self) -> u8 {
+        1
+    }
+
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        zstd::encode_all(data, self.level)
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        zstd::decode_all(data)
+    }
+}
+
+/// Gzip compression, using `flate2` (feature `gzip`).
+#[cfg(feature = "gzip")]
+#[derive(Clone, Copy, Debug)]
+pub struct Gzip {
+    /// The compression level (0 to 9).
+    pub level: u32,
+}
+
+#[cfg(feature = "gzip")]
+impl Default for Gzip {
+    fn default() -> Self {
+        Self { level: 6 }
+    }
+}
+
+#[cfg(feature = "gzip")]
+impl Compression for Gzip {
+    fn id(&self) -> u8 {
+        2
+    }
+
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        use std::io::Write;
+
+        let level = flate2::Compression::new(self.level);
+        let mut encoder = flate2::write::GzEncoder::new(vec![], level);
+        encoder.write_all(data)?;
+        encoder.finish()
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        use std::io::Read;
+
+        let mut decompressed = vec![];
+        flate2::read::GzDecoder::new(data).read_to_end(&mut decompressed)?;
+        Ok(decompressed)
+    }
+}
+
+/// The compressions of this crate enabled in this build, always readable.
+fn builtin(id: u8) -> Option<&'static dyn Compression> {
+    match id {
+        0 => Some(&Uncompressed),
+        #[cfg(feature = "zstd")]
+        1 => Some(&Zstd { level: 3 }),
+        #[cfg(feature = "gzip")]
+        2 => Some(&Gzip { level: 6 }),
+        _ => None,
+    }
+}
+
+/// Which encoding and compression to write records with, and which
+/// compressions can be read.
+///
+/// The compressions of this crate (`Zstd` and `Gzip`, with the features
+/// of the same name) are always readable, so directories mixing them with
+/// uncompressed records load transparently.
+#[derive(Clone)]
+pub struct RecordCodec {
+    encoding: Encoding,
+    compression: Arc<dyn Compression>,
+    readable: Vec<Arc<dyn Compression>>,
+    checksums: bool,
+}
+
+impl Default for RecordCodec {
+    fn default() -> Self {
+        Self {
+            encoding: Encoding::Json,
+            compression: Arc::new(Uncompressed),
+            readable: vec![],
+            checksums: false,
+        }
+    }
+}
+
+impl RecordCodec {
+    /// Write records with the given compression.
+    ///
+    /// The previously configured compressions remain readable.
+    pub fn with_compression<C>(mut self, compression: C) -> Self
+    where
+        C: Compression + 'static,
+    {
+        let previous =
+            std::mem::replace(&mut self.compression, Arc::new(compression));
+        self.readable.push(previous);
+        self
+    }
+
+    /// Make records written with `compression` readable, without writing
+    /// new records with it.
+    pub fn with_readable<C>(mut self, compression: C) -> Self
+    where
+        C: Compression + 'static,
+    {
+        self.readable.push(Arc::new(compression));
+        self
+    }
+
+    /// Write records with the given encoding.
+    ///
+    /// Records are read with the encoding named in their header, so records
+    /// written with other encodings remain readable.
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Write records with a checksum, verified when they are read.
+    ///
+    /// Such records always have a header, so they can't be read by releases
+    /// older than the store format 2 (see [`format`](crate::format)).
+    pub fn with_checksums(mut self, checksums: bool) -> Self {
+        self.checksums = checksums;
+        self
+    }
+
+    /// The encoding new records are written with.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    fn compression(&self, id: u8) -> Result<&dyn Compression, std::io::Error> {
+        std::iter::once(&self.compression)
+            .chain(&self.readable)
+            .find(|c| c.id() == id)
+            .map(|c| c.as_ref())
+            .or_else(|| builtin(id))
+            .ok_or_else(|| invalid(format!("unknown record compression {id}")))
+    }
+
+    /// Frame an encoded payload into a record.
+    pub fn encode(&self, payload: Vec<u8>) -> Result<Vec<u8>, std::io::Error> {
+        let compression = self.compression.id();
+        if self.encoding == Encoding::Json
+            && compression == Uncompressed.id()
+            && !self.checksums
+        {
+            return Ok(payload);
+        }
+        let version = if self.checksums {
+            CHECKED_HEADER_VERSION
+        } else {
+            HEADER_VERSION
+        };
+        let payload = self.compression.compress(&payload)?;
+        let mut record =
+            Vec::with_capacity(HEADER_LEN + CHECKSUM_LEN + payload.len());
+        record.extend_from_slice(&MAGIC);
+        record.extend_from_slice(&[version, self.encoding.id(), compression]);
+        if self.checksums {
+            record.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+        }
+        record.extend(payload);
+        Ok(record)
+    }
+
+    /// Extract the encoding and the (decompressed) payload of a record.
+    pub fn decode(
+        &self,
+        record: &[u8],
+    ) -> Result<(Encoding, Vec<u8>), std::io::Error> {
+        if !record.starts_with(&MAGIC) {
+            return Ok((Encoding::Json, record.to_vec()));
+        }
+        if record.len() < HEADER_LEN {
+            return Err(corrupted("truncated record header"));
+        }
+        let header = &record[MAGIC.len()..HEADER_LEN];
+        let mut payload = &record[HEADER_LEN..];
+        match header[0] {
+            HEADER_VERSION => {}
+            CHECKED_HEADER_VERSION => {
+                if payload.len() < CHECKSUM_LEN {
+                    return Err(corrupted("truncated record header"));
+                }
+                let (checksum, rest) = payload.split_at(CHECKSUM_LEN);
+                let checksum = u32::from_le_bytes(
+                    checksum.try_into().expect("checksums have 4 bytes"),
+                );
+                if crc32fast::hash(rest) != checksum {
+                    return Err(corrupted("checksum mismatch"));
+                }
+                payload = rest;
+            }
+            version => {
+                return Err(invalid(format!(
+                    "unknown record header version {version}"
+                )));
+            }
+        }
+        let encoding = Encoding::from_id(header[1])?;
+        let payload = self.compression(header[2])?.decompress(payload)?;
+        Ok((encoding, payload))
+    }
+}
+
+fn corrupted(reason: &str) -> std::io::Error {
+    JobError::Corrupted {
+        id: None,
+        reason: reason.to_string(),
+    }
+    .into()
+}
+
+fn invalid(message: impl ToString) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string())
+}
@@ -0,0 +1,338 @@
+//! Jobs of different kinds sharing one backend.
+//!
+//! A [`Job`] backend is generic over the output of its jobs, so each kind
+//! of job usually gets its own backend.  A [`JobRegistry`] instead maps
+//! names to handlers, each with its own payload and output types: the
+//! metadata of a job is a [`Payload`], with the name of its handler and its
+//! serialized payload, and its output is the serialized output of the
+//! handler.  Workers dispatch the jobs they claim by name.
+//!
+//! The name of the handler is the type tag of the job: a [`JobKind`] names
+//! a handler and the types of its payload and output, and
+//! [`JobRegistry::load_as`] loads the jobs of the kind with these types.
+//! Errors are shared by all kinds, as [`SerializableError`].
+//!
+//! ```
+//! # use serde_json::Value;
+//! # use simple_jobs::{error::SerializableError, registry::{JobRegistry, Payload}, FSJob};
+//! # async fn example(job: FSJob<Value, SerializableError, Payload, ()>) -> std::io::Result<()> {
+//! let mut registry = JobRegistry::new(job);
+//! registry
+//!     .register("double", |_, _, n: u32| async move { Ok(2 * n) })
+//!     .register("greet", |_, _, name: String| async move {
+//!         Ok(format!("Hello, {name}!"))
+//!     });
+//! registry.submit("double", &21)?;
+//! registry.submit("greet", &"world")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::HashMap, future::Future, pin::Pin, sync::Arc};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    error::SerializableError, queue::EnqueueOptions, Info, Job, JobHandle,
+    JobInfo,
+};
+
+/// The metadata of the jobs of a [`JobRegistry`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Payload {
+    /// The name of the handler running the job.
+    pub handler: String,
+    /// The payload passed to the handler, as JSON.
+    pub payload: Value,
+}
+
+impl Payload {
+    /// The payload of a job run by the handler `handler`.
+    pub fn new<T: Serialize>(
+        handler: &str,
+        payload: &T,
+    ) -> Result<Self, std::io::Error> {
+        Ok(Self {
+            handler: handler.to_string(),
+            payload: serde_json::to_value(payload)?,
+        })
+    }
+}
+
+/// A kind of job of a [`JobRegistry`]: the name of its handler, and the
+/// types of its payload and output.
+///
+/// ```
+/// # use simple_jobs::registry::JobKind;
+/// struct Double;
+///
+/// impl JobKind for Double {
+///     const NAME: &'static str = "double";
+///     type Payload = u32;
+///     type Output = u32;
+/// }
+/// ```
+pub trait JobKind {
+    /// The name of the handler of the jobs of this kind.
+    const NAME: &'static str;
+    /// The type of the payloads of the jobs.
+    type Payload: Serialize + DeserializeOwned;
+    /// The type of the outputs of the jobs.
+    type Output: Serialize + DeserializeOwned;
+}
+
+/// The record of a job of kind `K`, with status values of type `S`.
+pub type KindInfo<K, S> = JobInfo<
+    <K as JobKind>::Output,
+    SerializableError,
+    <K as JobKind>::Payload,
+    S,
+>;
+
+type HandlerFuture =
+    Pin<Box<dyn Future<Output = Result<Value, SerializableError>> + Send>>;
+
+type Handler<J> = Arc<dyn Fn(Uuid, J, Value) -> HandlerFuture + Send + Sync>;
+
+/// Named handlers, running the jobs of a backend.
+pub struct JobRegistry<J: Job> {
+    job: J,
+    handlers: HashMap<String, Handler<J>>,
+}
+
+impl<J: Job> Clone for JobRegistry<J> {
+    fn clone(&self) -> Self {
+        Self {
+            job: self.job.clone(),
+            handlers: self.handlers.clone(),
+        }
+    }
+}
+
+impl<J> JobRegistry<J>
+where
+    J: Job<Output = Value, Error = SerializableError, Metadata = Payload>,
+{
+    /// Create a registry without handlers.
+    pub fn new(job: J) -> Self {
+        Self {
+            job,
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// The backend of the registry.
+    pub fn job(&self) -> &J {
+        &self.job
+    }
+
+    /// Register the handler `name`, replacing any handler with that name.
+    ///
+    /// The handler is called like the closures of [`Job::submit`], with the
+    /// deserialized payload instead of the metadata.  Jobs whose payload
+    /// can't be deserialized, or whose output can't be serialized, end with
+    /// the error of serde as their result.  With the feature `tracing`, the
+    /// name is recorded as the `job.name` of the span of the job.
+    pub fn register<T, O, F, Fut>(
+        &mut self,
+        name: &str,
+        handler: F,
+    ) -> &mut Self
+    where
+        T: DeserializeOwned,
+        O: Serialize,
+        F: Fn(Uuid, J, T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<O, SerializableError>> + Send + 'static,
+    {
+        #[cfg(feature = "tracing")]
+        let handler_name = name.to_string();
+        let handler: Handler<J> = Arc::new(move |id, job, payload| {
+            let payload = serde_json::from_value(payload);
+            let fut = payload.map(|payload| handler(id, job, payload));
+            #[cfg(feature = "tracing")]
+            let handler_name = handler_name.clone();
+            Box::pin(async move {
+                #[cfg(feature = "tracing")]
+                crate::spans::set_name(&handler_name);
+                let output =
+                    fut.map_err(|e| SerializableError::new(&e))?.await?;
+                serde_json::to_value(output)
+                    .map_err(|e| SerializableError::new(&e))
+            })
+        });
+        self.handlers.insert(name.to_string(), handler);
+        self
+    }
+
+    /// The names of the registered handlers, in no particular order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.handlers.keys().map(String::as_str)
+    }
+
+    /// Start a job with the handler `name`, like [`Job::submit`].
+    ///
+    /// Fails with [`std::io::ErrorKind::NotFound`] for unknown handlers.
+    pub fn submit<T: Serialize>(
+        &self,
+        name: &str,
+        payload: &T,
+    ) -> Result<JobHandle<J>, std::io::Error> {
+        let handler = self.handler(name)?;
+        self.job.submit(
+            move |id, job, payload| handler(id, job, payload.payload),
+            Payload::new(name, payload)?,
+        )
+    }
+
+    /// Start a new job with the handler and payload of the job `id`, like
+    /// [`Job::resubmit`].
+    ///
+    /// Fails with [`std::io::ErrorKind::NotFound`] if its handler isn't
+    /// registered.
+    pub fn resubmit(&self, id: Uuid) -> Result<JobHandle<J>, std::io::Error> {
+        let info = self.job.load(id)?;
+        let name = info.metadata.map(|m| m.handler).unwrap_or_default();
+        let handler = self.handler(&name)?;
+        self.job.resubmit(id, move |id, job, payload| {
+            handler(id, job, payload.payload)
+        })
+    }
+
+    /// Enqueue a job for the handler `name`, like [`Job::enqueue_with`].
+    ///
+    /// The handler is only needed by the workers claiming the job, so it
+    /// doesn't have to be registered here.
+    pub fn enqueue<T: Serialize>(
+        &self,
+        name: &str,
+        payload: &T,
+        options: &EnqueueOptions,
+    ) -> Result<Uuid, std::io::Error> {
+        self.job.enqueue_with(Payload::new(name, payload)?, options)
+    }
+
+    /// Claim the next job of `queues`, like [`Job::claim_next_from`], and
+    /// run it with the handler it names.
+    ///
+    /// Jobs naming a handler that isn't registered fail (or are tried again,
+    /// up to the [maximum attempts](crate::QueueConfig::with_max_attempts)).
+    pub fn claim_next(
+        &self,
+        queues: &[&str],
+    ) -> Result<Option<JobHandle<J>>, std::io::Error> {
+        self.job.claim_next_from(queues, self.dispatch())
+    }
+
+    /// Resubmit the failed jobs for which `filter` returns `true`, like
+    /// [`Job::retry_failed`], each with the handler it names, returning the
+    /// ids of the new jobs.
+    ///
+    /// Jobs whose handler isn't registered are skipped.
+    pub fn retry_failed<P>(
+        &self,
+        mut filter: P,
+    ) -> Result<Vec<Uuid>, std::io::Error>
+    where
+        P: FnMut(&Info<J>) -> bool,
+    {
+        let registered = |info: &Info<J>| {
+            info.metadata
+                .as_ref()
+                .is_some_and(|m| self.handlers.contains_key(&m.handler))
+        };
+        self.job.retry_failed(
+            |info| registered(info) && filter(info),
+            self.dispatch(),
+        )
+    }
+
+    /// A closure running jobs with the handler they name.
+    fn dispatch(
+        &self,
+    ) -> impl FnOnce(Uuid, J, Payload) -> HandlerFuture + Clone + 'static {
+        let handlers = self.handlers.clone();
+        move |id, job, payload| {
+            let handler = handlers.get(&payload.handler).cloned();
+            match handler {
+                Some(handler) => handler(id, job, payload.payload),
+                None => Box::pin(async move {
+                    Err(SerializableError::msg(format!(
+                        "no handler named {:?}",
+                        payload.handler
+                    )))
+                }),
+            }
+        }
+    }
+
+    /// Load the job `id`, with the payload and output types of its kind.
+    ///
+    /// Fails with [`std::io::ErrorKind::InvalidData`] if the job is of
+    /// another kind, or its payload or output don't have these types.
+    pub fn load_as<K: JobKind>(
+        &self,
+        id: Uuid,
+    ) -> Result<KindInfo<K, J::Status>, std::io::Error>
+    where
+        J::Status: Serialize + DeserializeOwned,
+    {
+        let info = self.job.load(id)?;
+        if !is_kind::<K, _>(&info) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("job {id} is not a {:?} job", K::NAME),
+            ));
+        }
+        typed::<K, _>(info)
+    }
+
+    /// All the readable jobs of kind `K`, with its payload and output
+    /// types.
+    ///
+    /// Requires a backend able to list its jobs (see [`Job::ids`]).  Jobs
+    /// whose payload or output don't have these types are skipped, like
+    /// unreadable jobs.
+    pub fn list_as<K: JobKind>(
+        &self,
+    ) -> Result<Vec<KindInfo<K, J::Status>>, std::io::Error>
+    where
+        J::Status: Serialize + DeserializeOwned,
+    {
+        Ok(self
+            .job
+            .scan()?
+            .filter(is_kind::<K, _>)
+            .filter_map(|info| typed::<K, _>(info).ok())
+            .collect())
+    }
+
+    fn handler(&self, name: &str) -> Result<Handler<J>, std::io::Error> {
+        self.handlers.get(name).cloned().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("no handler named {name:?}"),
+            )
+        })
+    }
+}
+
+/// Whether a job is of kind `K`.
+fn is_kind<K: JobKind, S>(
+    info: &JobInfo<Value, SerializableError, Payload, S>,
+) -> bool {
+    info.metadata.as_ref().map(|m| m.handler.as_str()) == Some(K::NAME)
+}
+
+/// The record of a job, with the payload and output types of kind `K`.
+fn typed<K: JobKind, S: Serialize + DeserializeOwned>(
+    info: JobInfo<Value, SerializableError, Payload, S>,
+) -> Result<KindInfo<K, S>, std::io::Error> {
+    let mut value = serde_json::to_value(info)?;
+    if let Some(metadata) = value.get_mut("metadata") {
+        let payload = metadata.get_mut("payload").map(Value::take);
+        *metadata = payload.unwrap_or(Value::Null);
+    }
+    Ok(serde_json::from_value(value)?)
+}
@@ -0,0 +1,157 @@
+//! Store-and-forward of job records between backends.
+//!
+//! A [`Relay`] copies the records of a local backend (e.g. the [`FSJob`]
+//! store of an edge device) to a central one, so a fleet of agents can
+//! report the state of their jobs to a hub reachable only part of the time.
+//! Every [`Relay::sync`] forwards the records that changed since the last
+//! one; the ids of the records forwarded in a terminal state, which never
+//! change again, are kept in a cursor file so a restarted relay resumes
+//! where it stopped.
+//!
+//! When the central backend already has a terminal record for a job that
+//! is not terminal locally (e.g. it was canceled from the hub), the central
+//! record wins and the job is reported as a conflict.
+//!
+//! [`FSJob`]: crate::FSJob
+
+use std::{collections::HashSet, path::PathBuf, time::Duration};
+
+use uuid::Uuid;
+
+use crate::{events, Info, Job};
+
+/// What a [`Relay::sync`] did.
+#[derive(Debug, Default)]
+pub struct RelayReport {
+    /// The jobs whose record was copied to the central backend.
+    pub forwarded: Vec<Uuid>,
+    /// The jobs not copied because the central record is terminal and the
+    /// local one isn't.
+    pub conflicts: Vec<Uuid>,
+    /// The jobs that couldn't be copied, to be retried on the next sync.
+    pub failed: Vec<(Uuid, std::io::Error)>,
+}
+
+/// Forwards the records of a local backend to a central one.
+pub struct Relay<Local, Central> {
+    local: Local,
+    central: Central,
+    cursor: PathBuf,
+}
+
+impl<Local, Central> Relay<Local, Central>
+where
+    Local: Job,
+    Central: Job<
+        Output = Local::Output,
+        Error = Local::Error,
+        Metadata = Local::Metadata,
+        Status = Local::Status,
+    >,
+{
+    /// Create a relay, keeping its cursor in the file `cursor`.
+    ///
+    /// The local backend must be able to list its jobs (see [`Job::ids`]).
+    pub fn new(local: Local, central: Central, cursor: PathBuf) -> Self {
+        Self {
+            local,
+            central,
+            cursor,
+        }
+    }
+
+    /// The ids of the jobs already forwarded in their terminal state.
+    pub fn forwarded(&self) -> Result<HashSet<Uuid>, std::io::Error> {
+        match std::fs::read(&self.cursor) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Ok(HashSet::new())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn save_cursor(&self, done: &HashSet<Uuid>) -> Result<(), std::io::Error> {
+        let tmp = self.cursor.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec(done)?)?;
+        std::fs::rename(tmp, &self.cursor)
+    }
+
+    /// Forward the records changed since the last sync.
+    ///
+    /// Fails only if the local backend can't be listed or the cursor can't
+    /// be read or written; errors with single records are reported.
+    pub fn sync(&self) -> Result<RelayReport, std::io::Error> {
+        let ids = self.local.ids()?;
+        let mut done = self.forwarded()?;
+        // Forget the records removed from the local backend.
+        let present: HashSet<Uuid> = ids.iter().copied().collect();
+        done.retain(|id| present.contains(id));
+        let pending: Vec<Uuid> =
+            ids.into_iter().filter(|id| !done.contains(id)).collect();
+        let mut report = RelayReport::default();
+        for (id, info) in pending.iter().zip(self.local.load_many(&pending)) {
+            match info.and_then(|info| self.forward(info)) {
+                Ok(Forward::Copied { terminal }) => {
+                    report.forwarded.push(*id);
+                    if terminal {
+                        done.insert(*id);
+                    }
+                }
+                Ok(Forward::Unchanged { terminal }) => {
+                    if terminal {
+                        done.insert(*id);
+                    }
+                }
+                Ok(Forward::Conflict) => {
+                    report.conflicts.push(*id);
+                    done.insert(*id);
+                }
+                Err(e) => report.failed.push((*id, e)),
+            }
+        }
+        self.save_cursor(&done)?;
+        Ok(report)
+    }
+
+    fn forward(&self, info: Info<Local>) -> Result<Forward, std::io::Error> {
+        let terminal = info.status.is_terminal();
+        if let Ok(central) = self.central.load(info.id) {
+            if central.status.is_terminal() && !terminal {
+                return Ok(Forward::Conflict);
+            }
+            if central.status == info.status
+                && central.started_at == info.started_at
+                && central.finished_at == info.finished_at
+                && central.worker == info.worker
+                && central.result.is_some() == info.result.is_some()
+            {
+                return Ok(Forward::Unchanged { terminal });
+            }
+        }
+        self.central.save(&info)?;
+        Ok(Forward::Copied { terminal })
+    }
+
+    /// Sync forever: after local job events, and at least every `interval`.
+    ///
+    /// Only the events of jobs running in this process are seen, so the
+    /// interval bounds the delay for records written by other processes.
+    /// Returns only if a sync fails.
+    pub async fn run(&self, interval: Duration) -> Result<(), std::io::Error> {
+        let mut events = events::subscribe();
+        loop {
+            self.sync()?;
+            tokio::select! {
+                _ = events.recv() => {}
+                _ = tokio::time::sleep(interval) => {}
+            }
+        }
+    }
+}
+
+enum Forward {
+    Copied { terminal: bool },
+    Unchanged { terminal: bool },
+    Conflict,
+}
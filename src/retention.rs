@@ -0,0 +1,120 @@
+//! Expiry of the records of ended jobs.
+//!
+//! Jobs submitted with
+//! [`SubmitOptions::retain_for`](crate::SubmitOptions::retain_for) (or
+//! enqueued with
+//! [`EnqueueOptions::retain_for`](crate::queue::EnqueueOptions::retain_for))
+//! record how long they are kept once they end.  Expired jobs stay in the
+//! backend until swept, either removed with [`purge_expired`], or moved to
+//! an archival backend with [`archive_expired`], keeping the primary store
+//! small.  [`sweep_every`] runs a sweep periodically:
+//!
+//! ```
+//! # use std::time::Duration;
+//! # use simple_jobs::{retention, FSJob};
+//! # fn example(job: FSJob<u16, String, (), ()>, archive: FSJob<u16, String, (), ()>) {
+//! tokio::spawn(retention::sweep_every(Duration::from_secs(3600), move || {
+//!     retention::archive_expired(&job, &archive)
+//! }));
+//! # }
+//! ```
+//!
+//! Jobs without a retention are kept forever.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    archive::{stored, unless_unsupported},
+    Info, Job,
+};
+
+/// When the record of a job expires, if it ended and has a retention.
+pub fn expires_at<J: Job>(info: &Info<J>) -> Option<DateTime<Utc>> {
+    let retention = chrono::Duration::from_std(info.retention?).ok();
+    let finished_at = info.finished_at.filter(|_| info.status.is_terminal())?;
+    Some(
+        retention
+            .and_then(|retention| finished_at.checked_add_signed(retention))
+            .unwrap_or(DateTime::<Utc>::MAX_UTC),
+    )
+}
+
+/// The ids of the expired jobs of `job`.
+///
+/// Requires a backend able to list its jobs (see [`Job::ids`]).
+pub fn expired<J: Job>(job: &J) -> Result<Vec<Uuid>, std::io::Error> {
+    let now = Utc::now();
+    Ok(job
+        .scan()?
+        .filter(|info| expires_at::<J>(info).is_some_and(|at| at <= now))
+        .map(|info| info.id)
+        .collect())
+}
+
+/// Remove the expired jobs of `job` (see [`Job::remove`]), returning their
+/// ids.
+pub fn purge_expired<J: Job>(job: &J) -> Result<Vec<Uuid>, std::io::Error> {
+    let ids = expired(job)?;
+    for &id in &ids {
+        job.remove(id)?;
+    }
+    Ok(ids)
+}
+
+/// Move the expired jobs of `job` to `archive`, returning their ids.
+///
+/// Records, logs and outputs are copied, without the retention, so the
+/// archive keeps them; the jobs are then removed from `job`.  Jobs the
+/// archive already has are only removed.
+pub fn archive_expired<J, A>(
+    job: &J,
+    archive: &A,
+) -> Result<Vec<Uuid>, std::io::Error>
+where
+    J: Job,
+    A: Job<
+        Output = J::Output,
+        Error = J::Error,
+        Metadata = J::Metadata,
+        Status = J::Status,
+    >,
+{
+    let ids = expired(job)?;
+    for &id in &ids {
+        let mut info = job.load(id)?;
+        match archive.load(id) {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                info.retention = None;
+                archive.save(&info)?;
+                for line in &stored(job.logs(id))? {
+                    unless_unsupported(archive.append_log(id, line))?;
+                }
+                for item in &stored(job.outputs(id, 0))? {
+                    unless_unsupported(archive.append_output(id, item))?;
+                }
+            }
+            Err(e) => return Err(e),
+        }
+        job.remove(id)?;
+    }
+    Ok(ids)
+}
+
+/// Call `sweep` (e.g. [`purge_expired`]) every `interval`, until it fails.
+pub async fn sweep_every<F>(
+    interval: Duration,
+    mut sweep: F,
+) -> Result<(), std::io::Error>
+where
+    F: FnMut() -> Result<Vec<Uuid>, std::io::Error>,
+{
+    let mut ticks = tokio::time::interval(interval);
+    loop {
+        ticks.tick().await;
+        sweep()?;
+    }
+}
@@ -0,0 +1,163 @@
+//! Retrying saves that fail.
+//!
+//! The final save of a job happens inside its spawned task, where there is
+//! nobody to return an error to.  Instead of losing the result on the first
+//! backend hiccup, [`Job::submit`] retries that save following the
+//! [`Backoff`] given by [`Job::save_backoff`], reporting every failure to the
+//! [`JobHooks::on_save_error`](crate::JobHooks::on_save_error) hooks, and
+//! publishing a [`JobEvent::SaveFailed`] once it gives up.
+
+use std::{sync::Arc, time::Duration};
+
+use uuid::Uuid;
+
+use crate::{
+    events, hooks::DynHooks, ids::IdGenerator, layers::DynLayer,
+    queue::QueueConfig, run, secrets::SecretProvider, spawn::Spawner, Info,
+    Job, JobEvent,
+};
+
+/// Exponential backoff with jitter.
+#[derive(Clone, Debug)]
+pub struct Backoff {
+    /// Interval before the first retry (and, when polling, after every
+    /// change).
+    pub initial: Duration,
+    /// Upper bound for the interval.
+    pub max: Duration,
+    /// Factor applied to the interval after each attempt.
+    pub multiplier: f64,
+    /// Fraction of the interval (between 0 and 1) randomly added or removed.
+    pub jitter: f64,
+    /// Consecutive failures after which to give up (`None` retries
+    /// forever).
+    pub max_failures: Option<u32>,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(10),
+            max: Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_failures: Some(10),
+        }
+    }
+}
+
+impl Backoff {
+    /// The interval following `interval`.
+    pub(crate) fn next(&self, interval: Duration) -> Duration {
+        interval.mul_f64(self.multiplier).min(self.max)
+    }
+
+    /// `interval`, randomly shortened or lengthened by the jitter.
+    pub(crate) fn jittered(&self, interval: Duration) -> Duration {
+        // A v4 uuid is a cheap source of randomness we already depend on.
+        let random = Uuid::new_v4().as_u128() as u32 as f64 / u32::MAX as f64;
+        let factor = 1.0 + self.jitter.clamp(0.0, 1.0) * (2.0 * random - 1.0);
+        interval.mul_f64(factor)
+    }
+
+    /// Whether to give up after `failures` consecutive failures.
+    pub(crate) fn exhausted(&self, failures: u32) -> bool {
+        self.max_failures.is_some_and(|max| failures >= max)
+    }
+}
+
+/// Save the progress of a job in `info`, retrying on errors.
+///
+/// Returns whether the save eventually succeeded.
+pub(crate) async fn save_with_retry<J: Job>(
+    job: &J,
+    mut info: Info<J>,
+    backoff: &Backoff,
+    hooks: &[DynHooks<J::Output, J::Error>],
+) -> bool {
+    let mut interval = backoff.initial;
+    let mut failures = 0;
+    loop {
+        match run::save_progress(job, &mut info) {
+            Ok(()) => return true,
+            // The job was made terminal by someone else: nothing to retry.
+            Err(e) if run::is_invalid_transition(&e) => return false,
+            // The job was claimed by another worker.
+            Err(e) if run::is_lease_lost(&e) => return false,
+            Err(e) => {
+                failures += 1;
+                hooks.iter().for_each(|h| h.on_save_error(info.id, &e));
+                if backoff.exhausted(failures) {
+                    events::publish(JobEvent::SaveFailed {
+                        id: info.id,
+                        error: e.to_string(),
+                    });
+                    return false;
+                }
+            }
+        }
+        tokio::time::sleep(backoff.jittered(interval)).await;
+        interval = backoff.next(interval);
+    }
+}
+
+/// A [`Job`] wrapping another backend, with a custom [`Backoff`] for
+/// retrying the final save of its jobs.
+#[derive(Clone)]
+pub struct RetrySaves<J> {
+    inner: J,
+    backoff: Backoff,
+}
+
+impl<J: Job> RetrySaves<J> {
+    /// Wrap a backend, retrying failed final saves following `backoff`.
+    pub fn new(inner: J, backoff: Backoff) -> Self {
+        Self { inner, backoff }
+    }
+
+    /// The wrapped backend.
+    pub fn inner(&self) -> &J {
+        &self.inner
+    }
+}
+
+impl<J: Job> Job for RetrySaves<J> {
+    type Output = J::Output;
+    type Error = J::Error;
+    type Metadata = J::Metadata;
+    type Status = J::Status;
+
+    delegate_storage!(inner);
+
+    fn hooks(&self) -> &[DynHooks<Self::Output, Self::Error>] {
+        self.inner.hooks()
+    }
+
+    fn layers(&self) -> &[DynLayer<Self::Output, Self::Error>] {
+        self.inner.layers()
+    }
+
+    fn save_backoff(&self) -> Backoff {
+        self.backoff.clone()
+    }
+
+    fn id_generator(&self) -> Arc<dyn IdGenerator> {
+        self.inner.id_generator()
+    }
+
+    fn spawner(&self) -> Arc<dyn Spawner> {
+        self.inner.spawner()
+    }
+
+    fn admit(&self) -> Result<(), std::io::Error> {
+        self.inner.admit()
+    }
+
+    fn secret_provider(&self) -> Arc<dyn SecretProvider> {
+        self.inner.secret_provider()
+    }
+
+    fn queue_config(&self) -> Arc<QueueConfig> {
+        self.inner.queue_config()
+    }
+}
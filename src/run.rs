@@ -0,0 +1,312 @@
+//! Running a job: the part of [`Job::submit`] happening in the background.
+
+use std::{panic::AssertUnwindSafe, time::Duration};
+
+use chrono::Utc;
+use futures::{future::abortable, Future, FutureExt};
+use uuid::Uuid;
+
+use crate::{
+    error::JobError,
+    events,
+    layers::{self, JobFuture},
+    local, panic_message,
+    queue::{self, Failure},
+    retry, worker, Info, Job, JobEvent, JobHandle, JobInfo, StatusType,
+};
+
+#[cfg(feature = "tracing")]
+use crate::spans;
+
+#[cfg(doc)]
+use crate::retention;
+
+/// Number of version conflicts after which a read-modify-write cycle gives
+/// up.
+pub(crate) const MAX_CONFLICTS: u32 = 8;
+
+/// How to submit a job (see [`Job::submit_with`]).
+///
+/// ```
+/// # use std::time::Duration;
+/// # use simple_jobs::{FSJob, Job, SubmitOptions};
+/// # fn example(job: FSJob<u16, String, u16, String>) -> std::io::Result<()> {
+/// let options = SubmitOptions::new()
+///     .tenant("customer-42")
+///     .retain_for(Duration::from_secs(86_400))
+///     .webhook("https://example.com/jobs");
+/// job.submit_with(|_, _, value| async move { Ok(value) }, 42, &options)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SubmitOptions {
+    retention: Option<Duration>,
+    webhooks: Vec<String>,
+    tenant: Option<String>,
+}
+
+impl SubmitOptions {
+    /// Submit the job as [`Job::submit`] does.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only keep the record of the job for `retention` once it ends, to be
+    /// removed or archived by [`retention::purge_expired`] or
+    /// [`retention::archive_expired`].
+    pub fn retain_for(mut self, retention: Duration) -> Self {
+        self.retention = Some(retention);
+        self
+    }
+
+    /// Also notify `url` when the job ends, besides the global webhooks
+    /// (see the module `webhooks`, with the feature `webhooks`).
+    ///
+    /// Call it again for several URLs.
+    pub fn webhook(mut self, url: impl Into<String>) -> Self {
+        self.webhooks.push(url.into());
+        self
+    }
+
+    /// Make the job owned by `tenant`, e.g. a customer of a SaaS
+    /// application, whose jobs can then be listed and purged apart from the
+    /// others (see [`Job::tenant_ids`]).
+    pub fn tenant(mut self, tenant: impl Into<String>) -> Self {
+        self.tenant = Some(tenant.into());
+        self
+    }
+
+    /// A new job described by the options.
+    pub(crate) fn info<J: Job>(&self, job: &J) -> Info<J> {
+        JobInfo {
+            id: job.id_generator().generate(),
+            retention: self.retention,
+            webhooks: self.webhooks.clone(),
+            tenant_id: self.tenant.clone(),
+            ..JobInfo::new()
+        }
+    }
+}
+
+/// Submit a job, saving first `info` with the metadata (see
+/// [`Job::submit`]).
+pub(crate) fn submit<J, F, Fut>(
+    job: &J,
+    mut info: Info<J>,
+    f: F,
+    metadata: J::Metadata,
+) -> Result<JobHandle<J>, std::io::Error>
+where
+    J: Job,
+    F: FnOnce(Uuid, J, J::Metadata) -> Fut,
+    Fut: Future<Output = Result<J::Output, J::Error>> + Send + 'static,
+{
+    #[cfg(feature = "tracing")]
+    let _span = spans::submit_span::<J>(&info).entered();
+    job.admit()?;
+    let id = info.id;
+    info.metadata = Some(metadata.clone());
+    let hooks = job.hooks();
+    if let Err(e) = job.save(&info) {
+        hooks.iter().for_each(|h| h.on_save_error(id, &e));
+        return Err(e);
+    }
+    let fut = Box::pin(f(id, job.clone(), metadata));
+    let fut = layers::apply(job.layers(), id, &info.metadata, fut);
+    hooks.iter().for_each(|h| h.on_submit(id));
+    events::publish(JobEvent::Submitted { id });
+    Ok(spawn(job, info, fut))
+}
+
+/// Start the job described by `info`, already saved as started by this
+/// process after being claimed (see [`Job::claim_next`]).
+pub(crate) fn start<J, F, Fut>(job: &J, info: Info<J>, f: F) -> JobHandle<J>
+where
+    J: Job,
+    F: FnOnce(Uuid, J, J::Metadata) -> Fut,
+    Fut: Future<Output = Result<J::Output, J::Error>> + Send + 'static,
+{
+    let id = info.id;
+    let metadata = info.metadata.clone().expect("claimed jobs have metadata");
+    let fut = Box::pin(f(id, job.clone(), metadata));
+    let fut = layers::apply(job.layers(), id, &info.metadata, fut);
+    events::publish(JobEvent::Claimed { id });
+    spawn(job, info, fut)
+}
+
+/// Spawn the task running `fut`, the future of the job described by `info`
+/// (already saved), with the spawner of `job`.
+fn spawn<J: Job>(
+    job: &J,
+    info: Info<J>,
+    fut: JobFuture<J::Output, J::Error>,
+) -> JobHandle<J> {
+    let id = info.id;
+    let completion = local::Completion::register(id);
+    #[cfg(feature = "tracing")]
+    let span = spans::job_span::<J>(&info);
+    let task = run(job.clone(), info, fut, completion);
+    #[cfg(feature = "tracing")]
+    let task = tracing::Instrument::instrument(task, span);
+    let (task, abort) = abortable(task);
+    local::set_abort_handle(id, abort.clone());
+    let task = job.spawner().spawn(Box::pin(async move {
+        let _ = task.await;
+    }));
+    JobHandle::new(id, job.clone(), task)
+}
+
+/// Run a job, persisting its transitions and calling its hooks.
+async fn run<J: Job>(
+    job: J,
+    mut info: Info<J>,
+    fut: JobFuture<J::Output, J::Error>,
+    completion: local::Completion,
+) {
+    let id = info.id;
+    let hooks = job.hooks().to_vec();
+    info.started_at = Some(Utc::now());
+    info.worker = Some(worker::label());
+    if let Err(e) = save_progress(&job, &mut info) {
+        hooks.iter().for_each(|h| h.on_save_error(id, &e));
+    }
+    hooks.iter().for_each(|h| h.on_start(id));
+    let (event, failure) = match AssertUnwindSafe(fut).catch_unwind().await {
+        Ok(res) => {
+            let (event, failure) = match &res {
+                Ok(output) => {
+                    hooks.iter().for_each(|h| h.on_success(id, output));
+                    (JobEvent::Finished { id }, None)
+                }
+                Err(error) => {
+                    hooks.iter().for_each(|h| h.on_failure(id, error));
+                    (
+                        JobEvent::Failed { id },
+                        Some(Failure::Error(error.clone())),
+                    )
+                }
+            };
+            info.status = StatusType::Finished;
+            info.result = Some(res);
+            (event, failure)
+        }
+        Err(panic) => {
+            let message = panic_message(panic.as_ref());
+            hooks.iter().for_each(|h| h.on_panic(id, &message));
+            info.status = StatusType::Failed(message.clone());
+            (JobEvent::Failed { id }, Some(Failure::Panic(message)))
+        }
+    };
+    info.finished_at = Some(Utc::now());
+    // Failed jobs claimed from the queue may get another attempt.
+    let outcome = match failure.filter(|_| info.lease.is_some()) {
+        Some(failure) => {
+            match queue::fail::<J>(&job.queue_config(), &mut info, failure) {
+                true => Outcome::Retrying,
+                false => Outcome::DeadLettered,
+            }
+        }
+        None => Outcome::Done,
+    };
+    let backoff = job.save_backoff();
+    if retry::save_with_retry(&job, info, &backoff, &hooks).await {
+        match outcome {
+            Outcome::Retrying => events::publish(JobEvent::Retrying { id }),
+            Outcome::DeadLettered => {
+                completion.notify();
+                events::publish(event);
+                events::publish(JobEvent::DeadLettered { id });
+            }
+            Outcome::Done => {
+                completion.notify();
+                events::publish(event);
+            }
+        }
+    }
+}
+
+/// What became of a job after its run.
+enum Outcome {
+    /// It reached its final state.
+    Done,
+    /// It failed, and was returned to the queue.
+    Retrying,
+    /// It failed, and ran out of attempts.
+    DeadLettered,
+}
+
+/// Save the progress of a job from its task.
+///
+/// The task owns everything but the metadata, the checkpoint and the lease,
+/// which it takes from the backend (see [`Job::update_metadata`]), unless
+/// the job is missing from it.  A job made terminal by another writer is not
+/// overwritten, nor one claimed by another worker after its lease expired.
+/// The save goes through [`Job::save_if_version`], retried on conflicts so the
+/// changes of concurrent writers are merged rather than overwritten.
+pub(crate) fn save_progress<J: Job>(
+    job: &J,
+    info: &mut Info<J>,
+) -> Result<(), std::io::Error> {
+    let mut attempts = 0;
+    loop {
+        let stored = match job.load(info.id) {
+            Ok(stored) => stored,
+            // E.g. removed by another writer: nothing left to merge with.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return job.save(info);
+            }
+            Err(e) => return Err(e),
+        };
+        if stored.status.is_terminal() && stored.status == info.status {
+            // A previous attempt was saved, despite reporting an error.
+            return Ok(());
+        }
+        let token = |info: &Info<J>| info.lease.as_ref().map(|l| l.token);
+        if info.lease.is_some() && token(&stored) != token(info) {
+            // The lease expired, and another worker claimed the job.
+            return Err(JobError::LeaseLost { id: info.id }.into());
+        }
+        if !stored.status.can_transition_to(&info.status) {
+            // E.g. the job was canceled by another process.
+            return Err(JobError::InvalidTransition {
+                id: info.id,
+                from: stored.status.label(),
+                to: info.status.label(),
+            }
+            .into());
+        }
+        info.metadata = stored.metadata;
+        info.checkpoint = stored.checkpoint;
+        // The lease may have been renewed since the claim.
+        info.lease = stored.lease;
+        match job.save_if_version(info, stored.version) {
+            Ok(_) => return Ok(()),
+            Err(e) if is_conflict(&e) && attempts < MAX_CONFLICTS => {
+                attempts += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Whether an error is a [`JobError::InvalidTransition`].
+pub(crate) fn is_invalid_transition(error: &std::io::Error) -> bool {
+    matches!(
+        JobError::from_io(error),
+        Some(JobError::InvalidTransition { .. })
+    )
+}
+
+/// Whether an error is a [`JobError::LeaseLost`].
+pub(crate) fn is_lease_lost(error: &std::io::Error) -> bool {
+    matches!(JobError::from_io(error), Some(JobError::LeaseLost { .. }))
+}
+
+/// Whether an error is a [`JobError::VersionConflict`].
+pub(crate) fn is_conflict(error: &std::io::Error) -> bool {
+    matches!(
+        JobError::from_io(error),
+        Some(JobError::VersionConflict { .. })
+    )
+}
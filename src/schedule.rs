@@ -0,0 +1,271 @@
+//! Jobs enqueued on a schedule, by one of the processes sharing a backend.
+//!
+//! A [`Scheduler`] enqueues the jobs of its entries when they are due,
+//! either periodically (see [`Scheduler::with_periodic`]) or once (see
+//! [`Scheduler::with_delayed`]), and workers claim them like any other
+//! pending job (see [`crate::queue`]):
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use simple_jobs::{schedule::Scheduler, FSJob};
+//! # async fn example() -> std::io::Result<()> {
+//! let job: FSJob<u16, String, String, String> = FSJob::new("/var/jobs".into());
+//! let scheduler = Scheduler::new(job, "reports")
+//!     .with_periodic("hourly", Duration::from_secs(3600), "summary".into())
+//!     .with_delayed("launch", chrono::Utc::now(), "announce".into());
+//! scheduler.run().await
+//! # }
+//! ```
+//!
+//! Every process of an application can run the same scheduler: they elect
+//! a leader through the [lease](Job::acquire_lease) named after the
+//! scheduler, and only the leader enqueues jobs.  The leader renews its
+//! lease on every tick; if it stops (e.g. it crashed), another process
+//! takes over once the lease expires, or right away if the leader
+//! [stepped down](Scheduler::step_down).
+//!
+//! Periodic entries are due at the multiples of their period since the
+//! UNIX epoch, so all the processes agree on their due times.  Each due
+//! time is enqueued once, with the
+//! [unique key](EnqueueOptions::unique) `<scheduler>/<entry>/<due time>`,
+//! from which the id of its job is derived: a new leader catches up with
+//! the last due time of each entry, unless the job of its key is found in
+//! the backend, and two schedulers briefly leading at once enqueue the
+//! same record.  A due time missed while no process was leading is thus
+//! enqueued late, and the earlier ones are skipped.
+//!
+//! Requires a backend able to hold leases, like
+//! [`FSJob`](crate::FSJob), and clocks roughly in sync across the
+//! processes.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    queue::{self, EnqueueOptions, Lease},
+    worker, Job,
+};
+
+/// When the job of an entry is due.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Due {
+    /// At the multiples of the period since the UNIX epoch.
+    Every(Duration),
+    /// Once.
+    At(DateTime<Utc>),
+}
+
+impl Due {
+    /// The last due time at or before `now`, if any.
+    fn last(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match *self {
+            Due::Every(period) => {
+                let period = period.as_millis().max(1) as i64;
+                let due = now.timestamp_millis().div_euclid(period) * period;
+                DateTime::from_timestamp_millis(due)
+            }
+            Due::At(at) => (at <= now).then_some(at),
+        }
+    }
+}
+
+type ErrorCallback = Box<dyn Fn(&std::io::Error) + Send + Sync>;
+
+struct Entry<Metadata> {
+    name: String,
+    due: Due,
+    metadata: Metadata,
+}
+
+/// Enqueues the jobs of its entries when they are due, while it leads the
+/// schedulers of the same name (see the [module](self)).
+pub struct Scheduler<J: Job> {
+    job: J,
+    name: String,
+    entries: Vec<Entry<J::Metadata>>,
+    options: EnqueueOptions,
+    interval: Duration,
+    lease_ttl: Duration,
+    /// Identifies the lease of this scheduler.
+    token: Uuid,
+    leader: AtomicBool,
+    /// The last due time handled for each entry.
+    handled: Mutex<HashMap<String, DateTime<Utc>>>,
+    on_error: Option<ErrorCallback>,
+}
+
+impl<J: Job> Scheduler<J> {
+    /// Default delay between two ticks.
+    pub const INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Default duration of the lease of the leader.
+    pub const LEASE_TTL: Duration = Duration::from_secs(30);
+
+    /// A scheduler enqueuing jobs to `job`, electing its leader with the
+    /// other schedulers named `name`.
+    pub fn new(job: J, name: impl Into<String>) -> Self {
+        Self {
+            job,
+            name: name.into(),
+            entries: vec![],
+            options: EnqueueOptions::new(),
+            interval: Self::INTERVAL,
+            lease_ttl: Self::LEASE_TTL,
+            token: Uuid::new_v4(),
+            leader: AtomicBool::new(false),
+            handled: Mutex::new(HashMap::new()),
+            on_error: None,
+        }
+    }
+
+    /// Enqueue a job with `metadata` every `period`.
+    pub fn with_periodic(
+        mut self,
+        entry: impl Into<String>,
+        period: Duration,
+        metadata: J::Metadata,
+    ) -> Self {
+        self.entries.push(Entry {
+            name: entry.into(),
+            due: Due::Every(period),
+            metadata,
+        });
+        self
+    }
+
+    /// Enqueue a job with `metadata` once, at `at`.
+    pub fn with_delayed(
+        mut self,
+        entry: impl Into<String>,
+        at: DateTime<Utc>,
+        metadata: J::Metadata,
+    ) -> Self {
+        self.entries.push(Entry {
+            name: entry.into(),
+            due: Due::At(at),
+            metadata,
+        });
+        self
+    }
+
+    /// Enqueue the jobs with `options`, e.g. to a named queue.  Their
+    /// [unique key](EnqueueOptions::unique) is replaced with the key of
+    /// their due time.
+    pub fn with_options(mut self, options: EnqueueOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Change the delay between two ticks (see [`Scheduler::tick`]).
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Change how long the lease of the leader lasts without being renewed,
+    /// i.e. how long the schedule can stall when the leader stops.  It
+    /// should span several ticks.
+    pub fn with_lease_ttl(mut self, ttl: Duration) -> Self {
+        self.lease_ttl = ttl;
+        self
+    }
+
+    /// Call `f` with the errors of the ticks of [`Scheduler::run`], which
+    /// keeps running.
+    pub fn on_error<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&std::io::Error) + Send + Sync + 'static,
+    {
+        self.on_error = Some(Box::new(f));
+        self
+    }
+
+    /// Whether this scheduler led at its last tick.
+    pub fn is_leader(&self) -> bool {
+        self.leader.load(Ordering::SeqCst)
+    }
+
+    /// Take or renew the lease of the leader and, if this scheduler leads,
+    /// enqueue the jobs that are due, returning their ids.
+    pub fn tick(&self) -> Result<Vec<Uuid>, std::io::Error> {
+        let lease = Lease {
+            owner: worker::label(),
+            token: self.token,
+            expires_at: chrono::Duration::from_std(self.lease_ttl)
+                .ok()
+                .and_then(|ttl| Utc::now().checked_add_signed(ttl))
+                .unwrap_or(DateTime::<Utc>::MAX_UTC),
+        };
+        let acquired = self.job.acquire_lease(&self.name, &lease);
+        // A slow renewal may let another scheduler take the lease already.
+        let leader = matches!(acquired, Ok(true)) && !lease.is_expired();
+        self.leader.store(leader, Ordering::SeqCst);
+        if !acquired? || !leader {
+            return Ok(vec![]);
+        }
+        let now = Utc::now();
+        let mut handled = self.handled.lock().expect("cannot get lock");
+        let mut ids = vec![];
+        for entry in &self.entries {
+            let Some(due) = entry.due.last(now) else {
+                continue;
+            };
+            if handled.get(&entry.name) == Some(&due) {
+                continue;
+            }
+            let key =
+                format!("{}/{}/{}", self.name, entry.name, due.to_rfc3339());
+            // Found even once finished, unlike by the unique option.
+            match self.job.load(queue::unique_id(&key)) {
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    let options = self.options.clone().unique(key);
+                    let metadata = entry.metadata.clone();
+                    ids.push(self.job.enqueue_with(metadata, &options)?);
+                }
+                Err(e) => return Err(e),
+            }
+            handled.insert(entry.name.clone(), due);
+        }
+        Ok(ids)
+    }
+
+    /// Give up the lease if this scheduler leads, so another one takes over
+    /// at its next tick, e.g. before shutting down.  This scheduler leads
+    /// again if it ticks first.
+    pub fn step_down(&self) -> Result<(), std::io::Error> {
+        self.leader.store(false, Ordering::SeqCst);
+        self.job.release_lease(&self.name, self.token)
+    }
+
+    /// Tick forever, every interval.
+    ///
+    /// A failed tick doesn't lead: the scheduler steps down, so another one
+    /// can take over, reports the error to [`Scheduler::on_error`] and
+    /// ticks again at the next interval.  Returns only if the backend
+    /// can't hold leases ([`std::io::ErrorKind::Unsupported`]).
+    pub async fn run(&self) -> Result<(), std::io::Error> {
+        loop {
+            if let Err(e) = self.tick() {
+                if e.kind() == std::io::ErrorKind::Unsupported {
+                    return Err(e);
+                }
+                // Released on a best-effort basis: it expires anyway.
+                let _ = self.step_down();
+                if let Some(on_error) = &self.on_error {
+                    on_error(&e);
+                }
+            }
+            tokio::time::sleep(self.interval).await;
+        }
+    }
+}
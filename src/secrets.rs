@@ -0,0 +1,180 @@
+//! Secrets given to jobs at execution time, never persisted.
+//!
+//! Jobs submitted with [`Job::submit_with_secrets`] declare the names of
+//! the secrets they need; the values are resolved by the [`SecretProvider`]
+//! of the backend when the job is submitted, and passed to the job as
+//! [`Secrets`], so they never end up in its [`JobInfo`](crate::JobInfo).
+//! The default provider reads environment variables ([`EnvSecrets`]); wrap
+//! a backend in a [`WithSecrets`] to use another one, e.g. [`FileSecrets`]
+//! or a client of a secret manager implementing [`SecretProvider`].
+
+use std::{collections::HashMap, fmt, path::PathBuf, sync::Arc};
+
+use crate::{
+    hooks::DynHooks, ids::IdGenerator, layers::DynLayer, queue::QueueConfig,
+    retry::Backoff, spawn::Spawner, Job,
+};
+
+/// Something able to look up secrets by name.
+pub trait SecretProvider: Send + Sync {
+    /// The value of the secret `name`.
+    fn resolve(&self, name: &str) -> Result<String, std::io::Error>;
+}
+
+/// Secrets read from the environment variables of the process.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EnvSecrets;
+
+impl SecretProvider for EnvSecrets {
+    fn resolve(&self, name: &str) -> Result<String, std::io::Error> {
+        std::env::var(name).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("secret {name}: {e}"),
+            )
+        })
+    }
+}
+
+/// Secrets read from the files of a directory, one per file named after
+/// the secret (as mounted by Docker or Kubernetes).
+///
+/// Trailing newlines are removed.
+#[derive(Clone, Debug)]
+pub struct FileSecrets {
+    directory: PathBuf,
+}
+
+impl FileSecrets {
+    /// Read the secrets from `directory`.
+    pub fn new(directory: PathBuf) -> Self {
+        Self { directory }
+    }
+}
+
+impl SecretProvider for FileSecrets {
+    fn resolve(&self, name: &str) -> Result<String, std::io::Error> {
+        if name.contains(['/', '\\']) || name.starts_with('.') {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("invalid secret name {name:?}"),
+            ));
+        }
+        let value = std::fs::read_to_string(self.directory.join(name))?;
+        Ok(value.trim_end_matches(['\r', '\n']).to_string())
+    }
+}
+
+/// The secrets resolved for a job.
+///
+/// The values are not shown by `Debug`.
+#[derive(Clone, Default)]
+pub struct Secrets {
+    values: HashMap<String, String>,
+}
+
+impl Secrets {
+    /// Resolve the secrets `names` with `provider`.
+    pub fn resolve(
+        provider: &dyn SecretProvider,
+        names: &[String],
+    ) -> Result<Self, std::io::Error> {
+        let values = names
+            .iter()
+            .map(|name| Ok((name.clone(), provider.resolve(name)?)))
+            .collect::<Result<_, std::io::Error>>()?;
+        Ok(Self { values })
+    }
+
+    /// The value of a secret, if it was requested.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    /// The secrets as environment variables, e.g. for
+    /// [`std::process::Command::envs`].
+    pub fn envs(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+impl fmt::Debug for Secrets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.values.keys()).finish()
+    }
+}
+
+/// A [`Job`] wrapping another backend, resolving secrets with a custom
+/// [`SecretProvider`].
+pub struct WithSecrets<J> {
+    inner: J,
+    provider: Arc<dyn SecretProvider>,
+}
+
+impl<J: Clone> Clone for WithSecrets<J> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            provider: self.provider.clone(),
+        }
+    }
+}
+
+impl<J: Job> WithSecrets<J> {
+    /// Wrap a backend, resolving secrets with `provider`.
+    pub fn new<P>(inner: J, provider: P) -> Self
+    where
+        P: SecretProvider + 'static,
+    {
+        Self {
+            inner,
+            provider: Arc::new(provider),
+        }
+    }
+
+    /// The wrapped backend.
+    pub fn inner(&self) -> &J {
+        &self.inner
+    }
+}
+
+impl<J: Job> Job for WithSecrets<J> {
+    type Output = J::Output;
+    type Error = J::Error;
+    type Metadata = J::Metadata;
+    type Status = J::Status;
+
+    delegate_storage!(inner);
+
+    fn hooks(&self) -> &[DynHooks<Self::Output, Self::Error>] {
+        self.inner.hooks()
+    }
+
+    fn layers(&self) -> &[DynLayer<Self::Output, Self::Error>] {
+        self.inner.layers()
+    }
+
+    fn save_backoff(&self) -> Backoff {
+        self.inner.save_backoff()
+    }
+
+    fn id_generator(&self) -> Arc<dyn IdGenerator> {
+        self.inner.id_generator()
+    }
+
+    fn spawner(&self) -> Arc<dyn Spawner> {
+        self.inner.spawner()
+    }
+
+    fn admit(&self) -> Result<(), std::io::Error> {
+        self.inner.admit()
+    }
+
+    fn secret_provider(&self) -> Arc<dyn SecretProvider> {
+        self.provider.clone()
+    }
+
+    fn queue_config(&self) -> Arc<QueueConfig> {
+        self.inner.queue_config()
+    }
+}
@@ -0,0 +1,294 @@
+use std::sync::Arc;
+
+use futures::Stream;
+use uuid::Uuid;
+
+use crate::{AuditEntry, Info, Job, JobStatus, LogLine, StatusChange};
+
+/// Strategy for mapping a job id to one of the underlying shards.
+///
+/// Implementations must be deterministic across processes and releases:
+/// the same id must always map to the same shard, otherwise jobs saved by
+/// one process cannot be found by another.
+pub trait Partitioner: Send + Sync {
+    /// Return the index of the shard responsible for `id`.
+    fn partition(&self, id: &Uuid) -> usize;
+}
+
+/// Consistent hashing over a ring of virtual nodes.
+///
+/// Each shard is placed on the ring several times (the number of virtual
+/// nodes), and an id is assigned to the first node found walking clockwise
+/// from the hash of the id.  Adding a shard only moves roughly `1/n` of the
+/// ids.
+#[derive(Clone, Debug)]
+pub struct ConsistentHash {
+    ring: Vec<(u64, usize)>,
+}
+
+impl ConsistentHash {
+    /// Default number of virtual nodes per shard.
+    pub const VIRTUAL_NODES: usize = 64;
+
+    /// Create a ring for `shards` shards, each placed `virtual_nodes` times.
+    pub fn new(shards: usize, virtual_nodes: usize) -> Self {
+        let mut ring: Vec<(u64, usize)> = (0..shards)
+            .flat_map(|shard| {
+                (0..virtual_nodes.max(1)).map(move |node| {
+                    let key = format!("shard-{shard}-{node}");
+                    (fnv1a(key.as_bytes()), shard)
+                })
+            })
+            .collect();
+        ring.sort_unstable();
+        Self { ring }
+    }
+}
+
+impl Partitioner for ConsistentHash {
+    fn partition(&self, id: &Uuid) -> usize {
+        let hash = fnv1a(id.as_bytes());
+        let pos = self.ring.partition_point(|(h, _)| *h < hash);
+        self.ring
+            .get(pos)
+            .or_else(|| self.ring.first())
+            .map_or(0, |(_, shard)| *shard)
+    }
+}
+
+/// FNV-1a, used because its output is stable across Rust releases (unlike
+/// the std `DefaultHasher`).
+///
+/// FNV mixes the last bytes poorly into the high bits, so similar keys (like
+/// those of the virtual nodes) would cluster on the ring; the result goes
+/// through the SplitMix64 finalizer to spread them out.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let hash = bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+    });
+    let hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    let hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+    hash ^ (hash >> 31)
+}
+
+/// A [`Job`] partitioning job ids across several backends.
+///
+/// Every job is saved to, and loaded from, exactly one of the shards, chosen
+/// by a [`Partitioner`] (by default [`ConsistentHash`]).  The shards must be
+/// given in the same order by every process using the same stores.
+#[derive(Clone)]
+pub struct ShardedJob<J> {
+    shards: Vec<J>,
+    partitioner: Arc<dyn Partitioner>,
+}
+
+impl<J: Job> ShardedJob<J> {
+    /// Create a new [`ShardedJob`] using consistent hashing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is empty.
+    pub fn new(shards: Vec<J>) -> Self {
+        let ring =
+            ConsistentHash::new(shards.len(), ConsistentHash::VIRTUAL_NODES);
+        Self::with_partitioner(shards, ring)
+    }
+
+    /// Create a new [`ShardedJob`] with a custom [`Partitioner`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is empty.
+    pub fn with_partitioner<P>(shards: Vec<J>, partitioner: P) -> Self
+    where
+        P: Partitioner + 'static,
+    {
+        assert!(!shards.is_empty(), "ShardedJob needs at least one shard");
+        Self {
+            shards,
+            partitioner: Arc::new(partitioner),
+        }
+    }
+
+    /// The shard responsible for the job `id`.
+    pub fn shard(&self, id: &Uuid) -> &J {
+        let n = self.shards.len();
+        &self.shards[self.partitioner.partition(id) % n]
+    }
+
+    /// All the underlying shards.
+    pub fn shards(&self) -> &[J] {
+        &self.shards
+    }
+}
+
+impl<J: Job> Job for ShardedJob<J> {
+    type Output = J::Output;
+    type Error = J::Error;
+    type Metadata = J::Metadata;
+    type Status = J::Status;
+
+    fn save(&self, info: &Info<Self>) -> Result<(), std::io::Error> {
+        self.shard(&info.id).save(info)
+    }
+
+    fn load(&self, id: Uuid) -> Result<Info<Self>, std::io::Error> {
+        self.shard(&id).load(id)
+    }
+
+    fn load_status(
+        &self,
+        id: Uuid,
+    ) -> Result<JobStatus<Self::Status>, std::io::Error> {
+        self.shard(&id).load_status(id)
+    }
+
+    fn save_if_version(
+        &self,
+        info: &mut Info<Self>,
+        expected: u64,
+    ) -> Result<u64, std::io::Error> {
+        self.shard(&info.id).save_if_version(info, expected)
+    }
+
+    fn update<F>(&self, id: Uuid, f: F) -> Result<Info<Self>, std::io::Error>
+    where
+        F: FnMut(&mut Info<Self>) -> Result<(), std::io::Error>,
+    {
+        self.shard(&id).update(id, f)
+    }
+
+    /// Group the ids by shard, so each shard gets a single `load_many`.
+    fn load_many(
+        &self,
+        ids: &[Uuid],
+    ) -> Vec<Result<Info<Self>, std::io::Error>> {
+        let n = self.shards.len();
+        let mut groups: Vec<Vec<(usize, Uuid)>> = vec![vec![]; n];
+        for (pos, id) in ids.iter().enumerate() {
+            groups[self.partitioner.partition(id) % n].push((pos, *id));
+        }
+        let mut results: Vec<Option<_>> = ids.iter().map(|_| None).collect();
+        for (shard, group) in self.shards.iter().zip(groups) {
+            let shard_ids: Vec<Uuid> =
+                group.iter().map(|(_, id)| *id).collect();
+            for ((pos, _), res) in group.iter().zip(shard.load_many(&shard_ids))
+            {
+                results[*pos] = Some(res);
+            }
+        }
+        results
+            .into_iter()
+            .map(|res| res.expect("every id belongs to a shard"))
+            .collect()
+    }
+
+    fn append_log(
+        &self,
+        id: Uuid,
+        line: &LogLine,
+    ) -> Result<(), std::io::Error> {
+        self.shard(&id).append_log(id, line)
+    }
+
+    fn logs(&self, id: Uuid) -> Result<Vec<LogLine>, std::io::Error> {
+        self.shard(&id).logs(id)
+    }
+
+    fn append_output(
+        &self,
+        id: Uuid,
+        item: &serde_json::Value,
+    ) -> Result<(), std::io::Error> {
+        self.shard(&id).append_output(id, item)
+    }
+
+    fn outputs(
+        &self,
+        id: Uuid,
+        from: usize,
+    ) -> Result<Vec<serde_json::Value>, std::io::Error> {
+        self.shard(&id).outputs(id, from)
+    }
+
+    fn ids(&self) -> Result<Vec<Uuid>, std::io::Error> {
+        let mut ids = vec![];
+        for shard in &self.shards {
+            ids.extend(shard.ids()?);
+        }
+        Ok(ids)
+    }
+
+    fn tenant_ids(&self, tenant: &str) -> Result<Vec<Uuid>, std::io::Error> {
+        let mut ids = vec![];
+        for shard in &self.shards {
+            ids.extend(shard.tenant_ids(tenant)?);
+        }
+        Ok(ids)
+    }
+
+    /// Pauses or resumes the queue in every shard.
+    fn set_queue_paused(
+        &self,
+        queue: &str,
+        paused: bool,
+    ) -> Result<(), std::io::Error> {
+        for shard in &self.shards {
+            shard.set_queue_paused(queue, paused)?;
+        }
+        Ok(())
+    }
+
+    /// The queues paused in any shard.
+    fn paused_queues(&self) -> Result<Vec<String>, std::io::Error> {
+        let mut queues = vec![];
+        for shard in &self.shards {
+            queues.extend(shard.paused_queues()?);
+        }
+        queues.sort();
+        queues.dedup();
+        Ok(queues)
+    }
+
+    /// Held in the shard of the id derived from `name`, so all the
+    /// processes agree on where it is.
+    fn acquire_lease(
+        &self,
+        name: &str,
+        lease: &crate::queue::Lease,
+    ) -> Result<bool, std::io::Error> {
+        self.shard(&crate::ids::from_key(&Uuid::nil(), name))
+            .acquire_lease(name, lease)
+    }
+
+    fn release_lease(
+        &self,
+        name: &str,
+        token: Uuid,
+    ) -> Result<(), std::io::Error> {
+        self.shard(&crate::ids::from_key(&Uuid::nil(), name))
+            .release_lease(name, token)
+    }
+
+    fn history(
+        &self,
+        id: Uuid,
+    ) -> Result<Vec<StatusChange<Self::Status>>, std::io::Error> {
+        self.shard(&id).history(id)
+    }
+
+    fn audit(
+        &self,
+        id: Uuid,
+    ) -> Result<Vec<AuditEntry<Self::Status>>, std::io::Error> {
+        self.shard(&id).audit(id)
+    }
+
+    fn remove(&self, id: Uuid) -> Result<(), std::io::Error> {
+        self.shard(&id).remove(id)
+    }
+
+    fn changes(&self, id: Uuid) -> impl Stream<Item = ()> + Send + 'static {
+        self.shard(&id).changes(id)
+    }
+}
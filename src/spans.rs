@@ -0,0 +1,127 @@
+//! [`tracing`] spans of the jobs.
+//!
+//! Submitting a job ([`Job::submit`] and the like) runs in a `submit` span,
+//! and each job runs in a `job` span, so the events the job emits (and
+//! those of the crates it calls) carry its fields:
+//!
+//! - `job.id`: the id of the job,
+//! - `job.queue`: the queue it was claimed from, if any,
+//! - `job.name`: the name set with [`set_name`], e.g. the handler of a job
+//!   of a [`JobRegistry`](crate::JobRegistry).
+//!
+//! The calls to an [`FSJob`](crate::FSJob) (saving and loading records) get
+//! `debug` spans of their own.
+//!
+//! The `job` spans are roots by default.  With [`set_propagation`], they
+//! are children of the span current where the job was submitted or
+//! claimed, so the logs of a job correlate with the request that triggered
+//! it:
+//!
+//! ```
+//! # use simple_jobs::{spans, FSJob, Job};
+//! # fn example(job: FSJob<u16, String, (), ()>) -> std::io::Result<()> {
+//! spans::set_propagation(true);
+//! let request = tracing::info_span!("request", path = "/reports");
+//! let _entered = request.enter();
+//! job.submit(|_, _, _| async move {
+//!     tracing::info!("building report");
+//!     Ok(0)
+//! }, ())?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! With the feature `opentelemetry`, jobs also carry the OpenTelemetry
+//! context of the span current where their [`JobInfo`](crate::JobInfo) was
+//! created (on submission, or on [enqueuing](crate::Job::enqueue)), saved
+//! in [`JobInfo::trace_context`](crate::JobInfo::trace_context) as W3C
+//! Trace Context headers.  It becomes the parent of the `job` span, even
+//! when the job is claimed by another process, so distributed traces cross
+//! the job boundary.  The headers are written and read by the global text
+//! map propagator of OpenTelemetry, which must be set:
+//!
+//! ```
+//! # #[cfg(feature = "opentelemetry")]
+//! opentelemetry::global::set_text_map_propagator(
+//!     opentelemetry_sdk::propagation::TraceContextPropagator::new(),
+//! );
+//! ```
+//!
+//! Requires the feature `tracing`.
+
+#[cfg(feature = "opentelemetry")]
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tracing::Span;
+#[cfg(feature = "opentelemetry")]
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::{Info, Job};
+
+/// Whether the `job` spans are children of the submitter's span.
+static PROPAGATION: AtomicBool = AtomicBool::new(false);
+
+/// Make the `job` spans children of the span current where the jobs are
+/// submitted or claimed, instead of roots.  Process-wide.
+pub fn set_propagation(enabled: bool) {
+    PROPAGATION.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether the `job` spans are children of the submitter's span (see
+/// [`set_propagation`]).
+pub fn propagation() -> bool {
+    PROPAGATION.load(Ordering::Relaxed)
+}
+
+/// Record `name` as the `job.name` of the job running in the current task.
+pub fn set_name(name: &str) {
+    Span::current().record("job.name", name);
+}
+
+/// The span of the submission of the job `info`.
+pub(crate) fn submit_span<J: Job>(info: &Info<J>) -> Span {
+    tracing::info_span!("submit", job.id = %info.id)
+}
+
+/// The span running the job `info`, to be created where the job is
+/// submitted or claimed.
+pub(crate) fn job_span<J: Job>(info: &Info<J>) -> Span {
+    let parent = if propagation() {
+        Span::current()
+    } else {
+        Span::none()
+    };
+    let span = tracing::info_span!(
+        parent: &parent,
+        "job",
+        job.id = %info.id,
+        job.queue = info.queue.as_deref(),
+        job.name = tracing::field::Empty,
+    );
+    #[cfg(feature = "opentelemetry")]
+    if !info.trace_context.is_empty() {
+        let _ = span.set_parent(context_from(&info.trace_context));
+    }
+    span
+}
+
+/// The OpenTelemetry context of the current span, as headers.
+#[cfg(feature = "opentelemetry")]
+pub(crate) fn current_context() -> BTreeMap<String, String> {
+    let context = Span::current().context();
+    let mut headers = HashMap::new();
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut headers)
+    });
+    headers.into_iter().collect()
+}
+
+/// The OpenTelemetry context saved as `headers`.
+#[cfg(feature = "opentelemetry")]
+fn context_from(headers: &BTreeMap<String, String>) -> opentelemetry::Context {
+    let headers: HashMap<_, _> = headers.clone().into_iter().collect();
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.extract(&headers)
+    })
+}
@@ -0,0 +1,118 @@
+//! Where job tasks run.
+//!
+//! By default, jobs are spawned on the Tokio runtime current at submit time.
+//! Wrap a backend in a [`WithSpawner`] to run them elsewhere, e.g. on a
+//! dedicated runtime (any [`tokio::runtime::Handle`] is a [`Spawner`]), or
+//! to keep track of them.  A spawner returns the Tokio [`JoinHandle`] of the
+//! task, kept by the [`JobHandle`](crate::JobHandle) of the job, so the
+//! tasks always run on a Tokio runtime.
+
+use std::{pin::Pin, sync::Arc};
+
+use futures::Future;
+use tokio::task::JoinHandle;
+
+use crate::{
+    hooks::DynHooks, ids::IdGenerator, layers::DynLayer, queue::QueueConfig,
+    retry::Backoff, secrets::SecretProvider, Job,
+};
+
+/// The future of a job task, as given to a [`Spawner`].
+pub type TaskFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Something able to run job tasks in the background.
+pub trait Spawner: Send + Sync {
+    /// Run `task` to completion in the background.
+    fn spawn(&self, task: TaskFuture) -> JoinHandle<()>;
+}
+
+/// Spawn on the Tokio runtime current at submit time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CurrentRuntime;
+
+impl Spawner for CurrentRuntime {
+    fn spawn(&self, task: TaskFuture) -> JoinHandle<()> {
+        tokio::spawn(task)
+    }
+}
+
+impl Spawner for tokio::runtime::Handle {
+    fn spawn(&self, task: TaskFuture) -> JoinHandle<()> {
+        tokio::runtime::Handle::spawn(self, task)
+    }
+}
+
+/// A [`Job`] wrapping another backend, spawning its jobs with a custom
+/// [`Spawner`].
+pub struct WithSpawner<J> {
+    inner: J,
+    spawner: Arc<dyn Spawner>,
+}
+
+impl<J: Clone> Clone for WithSpawner<J> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            spawner: self.spawner.clone(),
+        }
+    }
+}
+
+impl<J: Job> WithSpawner<J> {
+    /// Wrap a backend, spawning its jobs with `spawner`.
+    pub fn new<S>(inner: J, spawner: S) -> Self
+    where
+        S: Spawner + 'static,
+    {
+        Self {
+            inner,
+            spawner: Arc::new(spawner),
+        }
+    }
+
+    /// The wrapped backend.
+    pub fn inner(&self) -> &J {
+        &self.inner
+    }
+}
+
+impl<J: Job> Job for WithSpawner<J> {
+    type Output = J::Output;
+    type Error = J::Error;
+    type Metadata = J::Metadata;
+    type Status = J::Status;
+
+    delegate_storage!(inner);
+
+    fn hooks(&self) -> &[DynHooks<Self::Output, Self::Error>] {
+        self.inner.hooks()
+    }
+
+    fn layers(&self) -> &[DynLayer<Self::Output, Self::Error>] {
+        self.inner.layers()
+    }
+
+    fn save_backoff(&self) -> Backoff {
+        self.inner.save_backoff()
+    }
+
+    fn id_generator(&self) -> Arc<dyn IdGenerator> {
+        self.inner.id_generator()
+    }
+
+    fn spawner(&self) -> Arc<dyn Spawner> {
+        self.spawner.clone()
+    }
+
+    fn admit(&self) -> Result<(), std::io::Error> {
+        self.inner.admit()
+    }
+
+    fn secret_provider(&self) -> Arc<dyn SecretProvider> {
+        self.inner.secret_provider()
+    }
+
+    fn queue_config(&self) -> Arc<QueueConfig> {
+        self.inner.queue_config()
+    }
+}
@@ -40,13 +40,18 @@ fn iso8601(st: &SystemTime) -> String {
 }
 
 /// this struct contains the necessary data for storing jobs in an sqlite db
-#[derive(Clone)]
 pub struct DieselSqliteJob<Output, Error> {
     pub db_pool: Pool<ConnectionManager<SqliteConnection>>,
     pub output_type: PhantomData<Output>,
     pub error_type: PhantomData<Error>,
 }
 
+impl<Output, Error> Clone for DieselSqliteJob<Output, Error> {
+    fn clone(&self) -> Self {
+        Self::new(&self.db_pool)
+    }
+}
+
 impl<Output, Error> DieselSqliteJob<Output, Error> {
     /// Create a new [`DieselSqliteJob`].
     ///
@@ -61,7 +66,7 @@ impl<Output, Error> DieselSqliteJob<Output, Error> {
 }
 
 impl<
-        Output: Clone + Send + Sync + Serialize + DeserializeOwned + 'static,
+        Output: Send + Sync + Serialize + DeserializeOwned + 'static,
         Error: Clone + Send + Sync + Serialize + DeserializeOwned + 'static,
     > Job for DieselSqliteJob<Output, Error>
 {
@@ -0,0 +1,142 @@
+//! Statistics over the stored jobs.
+//!
+//! [`Job::stats`](crate::Job::stats) computes the [`JobStats`] of the jobs
+//! of a backend, e.g. those submitted in the last hour, so a health
+//! dashboard can show throughput, failure rate and duration percentiles
+//! without exporting to an external metrics system:
+//!
+//! ```
+//! # use simple_jobs::{FSJob, Job};
+//! # fn example(job: FSJob<u16, String, u16, ()>) -> std::io::Result<()> {
+//! let since = chrono::Utc::now() - chrono::Duration::hours(1);
+//! let stats = job.stats(|info| info.created_at.is_some_and(|at| at >= since))?;
+//! println!(
+//!     "{:?} jobs/s, {:?} failed, p99 {:?}",
+//!     stats.throughput(),
+//!     stats.failure_rate(),
+//!     stats.durations.p99
+//! );
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::{JobInfo, StatusType};
+
+/// The 50th, 95th and 99th percentiles of some durations (nearest rank),
+/// `None` without durations.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Percentiles {
+    /// The median.
+    pub p50: Option<Duration>,
+    /// The 95th percentile.
+    pub p95: Option<Duration>,
+    /// The 99th percentile.
+    pub p99: Option<Duration>,
+}
+
+impl Percentiles {
+    /// The percentiles of `durations`.
+    pub fn of(mut durations: Vec<Duration>) -> Self {
+        durations.sort();
+        let rank = |p: f64| {
+            let n = durations.len();
+            let rank = (p * n as f64).ceil() as usize;
+            durations.get(rank.clamp(1, n.max(1)) - 1).copied()
+        };
+        Self {
+            p50: rank(0.5),
+            p95: rank(0.95),
+            p99: rank(0.99),
+        }
+    }
+}
+
+/// Statistics over some jobs (see [`Job::stats`](crate::Job::stats)).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JobStats {
+    /// The jobs not done yet.
+    pub active: usize,
+    /// The jobs that finished with an output.
+    pub succeeded: usize,
+    /// The jobs that finished with an error, or with
+    /// [`StatusType::Failed`].
+    pub failed: usize,
+    /// The jobs that were canceled or interrupted.
+    pub canceled: usize,
+    /// When the first of the jobs ended.
+    pub first_finished_at: Option<DateTime<Utc>>,
+    /// When the last of the jobs ended.
+    pub last_finished_at: Option<DateTime<Utc>>,
+    /// The time the jobs waited before starting (see
+    /// [`JobInfo::queue_latency`]).
+    pub queue_latencies: Percentiles,
+    /// The time the jobs took executing (see [`JobInfo::run_duration`]).
+    pub durations: Percentiles,
+}
+
+impl JobStats {
+    /// The statistics of `infos`.
+    pub fn of<O, E, M, S>(
+        infos: impl IntoIterator<Item = JobInfo<O, E, M, S>>,
+    ) -> Self {
+        let mut stats = Self::default();
+        let mut latencies = vec![];
+        let mut durations = vec![];
+        for info in infos {
+            match (&info.status, &info.result) {
+                (status, _) if status.is_active() => stats.active += 1,
+                (StatusType::Failed(_), _) | (_, Some(Err(_))) => {
+                    stats.failed += 1
+                }
+                (_, Some(Ok(_))) => stats.succeeded += 1,
+                _ => stats.canceled += 1,
+            }
+            if let Some(at) =
+                info.finished_at.filter(|_| info.status.is_terminal())
+            {
+                stats.first_finished_at = Some(
+                    stats.first_finished_at.map_or(at, |first| first.min(at)),
+                );
+                stats.last_finished_at = Some(
+                    stats.last_finished_at.map_or(at, |last| last.max(at)),
+                );
+            }
+            latencies.extend(info.queue_latency());
+            durations.extend(info.run_duration());
+        }
+        stats.queue_latencies = Percentiles::of(latencies);
+        stats.durations = Percentiles::of(durations);
+        stats
+    }
+
+    /// The number of jobs.
+    pub fn total(&self) -> usize {
+        self.active + self.ended()
+    }
+
+    /// The number of jobs that ended.
+    pub fn ended(&self) -> usize {
+        self.succeeded + self.failed + self.canceled
+    }
+
+    /// The share of the completed jobs that failed, from 0 to 1, `None`
+    /// if no job completed.
+    pub fn failure_rate(&self) -> Option<f64> {
+        let completed = self.succeeded + self.failed;
+        (completed > 0).then(|| self.failed as f64 / completed as f64)
+    }
+
+    /// The jobs ended per second, between the first and the last to end,
+    /// `None` if they all ended at once.
+    pub fn throughput(&self) -> Option<f64> {
+        let span = (self.last_finished_at? - self.first_finished_at?)
+            .to_std()
+            .ok()
+            .filter(|span| !span.is_zero())?;
+        Some(self.ended() as f64 / span.as_secs_f64())
+    }
+}
@@ -0,0 +1,210 @@
+//! Graceful shutdown of the jobs running in a process.
+
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    events,
+    hooks::{DynHooks, JobHooks},
+    ids::IdGenerator,
+    layers::DynLayer,
+    local,
+    queue::QueueConfig,
+    retry::Backoff,
+    secrets::SecretProvider,
+    spawn::Spawner,
+    Info, Job, JobEvent, StatusType,
+};
+
+#[derive(Default)]
+struct State {
+    shutting_down: AtomicBool,
+    in_flight: Mutex<HashSet<Uuid>>,
+}
+
+impl State {
+    /// The tracked jobs still running, forgetting the others.
+    fn running(&self) -> Vec<Uuid> {
+        let mut in_flight = self.in_flight.lock().expect("cannot get lock");
+        in_flight
+            .retain(|id| local::subscribe(*id).is_some_and(|d| !*d.borrow()));
+        in_flight.iter().copied().collect()
+    }
+}
+
+/// Records the jobs submitted through a [`JobSupervisor`].
+struct Tracker(Arc<State>);
+
+impl<Output, Error> JobHooks<Output, Error> for Tracker {
+    fn on_submit(&self, id: Uuid) {
+        self.0.running();
+        self.0.in_flight.lock().expect("cannot get lock").insert(id);
+    }
+}
+
+/// A [`Job`] wrapping another backend, tracking the jobs submitted through
+/// it so that they can be drained on shutdown.
+///
+/// Only the jobs submitted in this process, through this supervisor (or one
+/// of its clones), are tracked: it should wrap any other combinator.
+pub struct JobSupervisor<J: Job> {
+    inner: J,
+    hooks: Vec<DynHooks<J::Output, J::Error>>,
+    state: Arc<State>,
+}
+
+impl<J: Job> Clone for JobSupervisor<J> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            hooks: self.hooks.clone(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<J: Job> JobSupervisor<J> {
+    /// Wrap a backend, keeping the hooks it already has (if any).
+    pub fn new(inner: J) -> Self {
+        let state = Arc::new(State::default());
+        let mut hooks = inner.hooks().to_vec();
+        hooks.push(Arc::new(Tracker(state.clone())));
+        Self {
+            inner,
+            hooks,
+            state,
+        }
+    }
+
+    /// The wrapped backend.
+    pub fn inner(&self) -> &J {
+        &self.inner
+    }
+
+    /// The ids of the jobs submitted through the supervisor that are still
+    /// running.
+    pub fn in_flight(&self) -> Vec<Uuid> {
+        self.state.running()
+    }
+
+    /// Whether [`JobSupervisor::shutdown`] was called.
+    pub fn is_shutting_down(&self) -> bool {
+        self.state.shutting_down.load(Ordering::Acquire)
+    }
+
+    /// Stop accepting submissions and drain the running jobs.
+    ///
+    /// Waits up to `grace` for the running jobs to complete, then aborts
+    /// the remaining ones and saves them as
+    /// [`StatusType::Interrupted`].  Returns the ids of the interrupted
+    /// jobs, or the first error saving them (after trying all of them).
+    pub async fn shutdown(
+        &self,
+        grace: Duration,
+    ) -> Result<Vec<Uuid>, std::io::Error> {
+        self.state.shutting_down.store(true, Ordering::Release);
+        let deadline = tokio::time::Instant::now() + grace;
+        let mut remaining = vec![];
+        for id in self.state.running() {
+            let Some(mut done) = local::subscribe(id) else {
+                continue;
+            };
+            let wait = done.wait_for(|done| *done);
+            if tokio::time::timeout_at(deadline, wait).await.is_err() {
+                remaining.push(id);
+            }
+        }
+        let mut error = None;
+        let mut interrupted = vec![];
+        for id in remaining {
+            local::abort(id);
+            match interrupt(&self.inner, id) {
+                Ok(Some(_)) => interrupted.push(id),
+                Ok(None) => {}
+                Err(e) => {
+                    self.hooks.iter().for_each(|h| h.on_save_error(id, &e));
+                    error.get_or_insert(e);
+                }
+            }
+        }
+        match error {
+            Some(e) => Err(e),
+            None => Ok(interrupted),
+        }
+    }
+}
+
+/// Save a job as interrupted, unless it completed in the meantime.
+///
+/// Returns the interrupted job.
+pub(crate) fn interrupt<J: Job>(
+    job: &J,
+    id: Uuid,
+) -> Result<Option<Info<J>>, std::io::Error> {
+    let mut interrupted = false;
+    let info = job.update(id, |info| {
+        if info.transition(StatusType::Interrupted).is_ok() {
+            info.finished_at = Some(Utc::now());
+            interrupted = true;
+        }
+        Ok(())
+    })?;
+    if !interrupted {
+        return Ok(None);
+    }
+    events::publish(JobEvent::Interrupted { id });
+    Ok(Some(info))
+}
+
+impl<J: Job> Job for JobSupervisor<J> {
+    type Output = J::Output;
+    type Error = J::Error;
+    type Metadata = J::Metadata;
+    type Status = J::Status;
+
+    delegate_storage!(inner);
+
+    fn hooks(&self) -> &[DynHooks<Self::Output, Self::Error>] {
+        &self.hooks
+    }
+
+    fn layers(&self) -> &[DynLayer<Self::Output, Self::Error>] {
+        self.inner.layers()
+    }
+
+    fn save_backoff(&self) -> Backoff {
+        self.inner.save_backoff()
+    }
+
+    fn id_generator(&self) -> Arc<dyn IdGenerator> {
+        self.inner.id_generator()
+    }
+
+    fn spawner(&self) -> Arc<dyn Spawner> {
+        self.inner.spawner()
+    }
+
+    fn admit(&self) -> Result<(), std::io::Error> {
+        if self.is_shutting_down() {
+            return Err(std::io::Error::other("shutting down"));
+        }
+        self.inner.admit()
+    }
+
+    fn secret_provider(&self) -> Arc<dyn SecretProvider> {
+        self.inner.secret_provider()
+    }
+
+    fn queue_config(&self) -> Arc<QueueConfig> {
+        self.inner.queue_config()
+    }
+}
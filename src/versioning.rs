@@ -0,0 +1,106 @@
+//! Versions of an application's records, and the migrations between them.
+//!
+//! The [store format](crate::format) covers the records as laid out by this
+//! crate; the types an application stores in them (its metadata, statuses
+//! and outputs) change too.  A [`Schema`] numbers those changes: records are
+//! saved in an envelope naming the version they were written with, and
+//! loading a record first runs the migrations from its version up to the
+//! current one:
+//!
+//! ```
+//! # use simple_jobs::versioning::Schema;
+//! // Version 1 renamed the metadata field `count` to `records`.
+//! let schema = Schema::new().migration(|info| {
+//!     if let Some(metadata) = info["metadata"].as_object_mut() {
+//!         let count = metadata.remove("count").unwrap_or_default();
+//!         metadata.insert("records".into(), count);
+//!     }
+//!     Ok(())
+//! });
+//! assert_eq!(schema.version(), 1);
+//! ```
+//!
+//! Records without an envelope (written before a schema was configured) are
+//! at version 0.  With no migrations, records are saved without envelope, as
+//! before.
+
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+
+type Migration =
+    Arc<dyn Fn(&mut Value) -> Result<(), std::io::Error> + Send + Sync>;
+
+/// The current version of an application's records, and how to upgrade
+/// older ones (see the [module documentation](self)).
+#[derive(Clone, Default)]
+pub struct Schema {
+    migrations: Vec<Migration>,
+}
+
+impl Schema {
+    /// A schema at version 0, without migrations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the next version, with the migration upgrading a record (a
+    /// serialized [`JobInfo`](crate::JobInfo)) from the previous one.
+    pub fn migration<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&mut Value) -> Result<(), std::io::Error> + Send + Sync + 'static,
+    {
+        self.migrations.push(Arc::new(f));
+        self
+    }
+
+    /// The version records are written with.
+    pub fn version(&self) -> u32 {
+        self.migrations.len() as u32
+    }
+
+    /// Wrap a serialized record in an envelope with the current version.
+    pub fn wrap(&self, info: Value) -> Value {
+        if self.migrations.is_empty() {
+            return info;
+        }
+        json!({ "schema": self.version(), "info": info })
+    }
+
+    /// Extract a record from its envelope, upgraded to the current version.
+    ///
+    /// Fails with [`std::io::ErrorKind::InvalidData`] for records written
+    /// with a newer version.
+    pub fn upgrade(&self, record: Value) -> Result<Value, std::io::Error> {
+        let (version, mut info) = match record {
+            Value::Object(mut envelope)
+                if !envelope.contains_key("id")
+                    && envelope.contains_key("info") =>
+            {
+                let version = envelope
+                    .get("schema")
+                    .and_then(Value::as_u64)
+                    .ok_or_else(|| {
+                        invalid("record envelope without schema".to_string())
+                    })?;
+                (version, envelope.remove("info").unwrap_or_default())
+            }
+            info => (0, info),
+        };
+        if version > u64::from(self.version()) {
+            return Err(invalid(format!(
+                "record written with schema {version}, but only schemas up \
+                 to {} are known",
+                self.version()
+            )));
+        }
+        for migration in &self.migrations[version as usize..] {
+            migration(&mut info)?;
+        }
+        Ok(info)
+    }
+}
+
+fn invalid(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,80 @@
+//! Polling a job with backoff and jitter.
+//!
+//! [`watch`] works with any backend, which makes it suitable for remote
+//! backends where every load is a network round-trip: the polling interval
+//! grows while nothing changes, is randomized so many watchers don't poll in
+//! lockstep, and transient load errors are retried instead of ending the
+//! stream.
+
+use std::time::Duration;
+
+use futures::Stream;
+use uuid::Uuid;
+
+pub use crate::retry::Backoff;
+use crate::{Info, Job, StatusType};
+
+struct State<J: Job> {
+    job: J,
+    last: Option<StatusType<J::Status>>,
+    has_result: bool,
+    interval: Duration,
+    failures: u32,
+    done: bool,
+}
+
+/// Watch a job, yielding its [`JobInfo`](crate::JobInfo) each time its
+/// status or result changes.
+///
+/// The stream ends after yielding the finished job, or after
+/// [`Backoff::max_failures`] consecutive errors loading it.
+pub fn watch<J: Job>(
+    id: Uuid,
+    job: &J,
+    backoff: Backoff,
+) -> impl Stream<Item = Info<J>> + Send + 'static {
+    let state = State {
+        job: job.clone(),
+        last: None,
+        has_result: false,
+        interval: backoff.initial,
+        failures: 0,
+        done: false,
+    };
+    futures::stream::unfold(state, move |mut state| {
+        let backoff = backoff.clone();
+        async move {
+            if state.done {
+                return None;
+            }
+            let mut first = true;
+            loop {
+                if !first {
+                    tokio::time::sleep(backoff.jittered(state.interval)).await;
+                }
+                first = false;
+                match state.job.load(id) {
+                    Ok(info) => {
+                        state.failures = 0;
+                        let changed = state.last.as_ref() != Some(&info.status)
+                            || state.has_result != info.result.is_some();
+                        if changed {
+                            state.last = Some(info.status.clone());
+                            state.has_result = info.result.is_some();
+                            state.interval = backoff.initial;
+                            state.done = info.status.is_terminal();
+                            return Some((info, state));
+                        }
+                    }
+                    Err(_) => {
+                        state.failures += 1;
+                        if backoff.exhausted(state.failures) {
+                            return None;
+                        }
+                    }
+                }
+                state.interval = backoff.next(state.interval);
+            }
+        }
+    })
+}
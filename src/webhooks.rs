@@ -0,0 +1,290 @@
+//! Webhook notifications of the ends of jobs.
+//!
+//! [`deliver`] posts a signed JSON [`Payload`] to webhook URLs when a job of
+//! this process finishes, fails or is dead-lettered, so external systems
+//! don't have to poll.  The URLs are the global ones of the [`Webhooks`],
+//! and those of the job itself (see
+//! [`EnqueueOptions::webhook`](crate::queue::EnqueueOptions::webhook) and
+//! [`SubmitOptions::webhook`](crate::SubmitOptions::webhook)):
+//!
+//! ```
+//! # use simple_jobs::{webhooks::{self, Webhooks}, FSJob};
+//! # fn example(job: FSJob<u16, String, u32, ()>) {
+//! let webhooks = Webhooks::new("shared secret").url("https://example.com/jobs");
+//! tokio::spawn(webhooks::deliver(job, webhooks));
+//! # }
+//! ```
+//!
+//! Every request carries the header [`SIGNATURE_HEADER`], of the form
+//! `t=<timestamp>,v1=<signature>`: the hex HMAC-SHA256 of
+//! `<timestamp>.<body>` with the secret, which receivers check with
+//! [`verify`].  Deliveries failing with an error or a non-2xx response are
+//! retried following the [`Backoff`] of the webhooks, then dropped.
+//!
+//! Requires the feature `webhooks`.
+
+use std::{sync::Arc, time::Duration};
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use crate::{events, Backoff, Info, Job, JobEvent, JobInfo};
+
+/// The header carrying the signature of a delivery.
+pub const SIGNATURE_HEADER: &str = "X-Simple-Jobs-Signature";
+
+/// How a job ended.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Event {
+    /// The job completed with `Ok`.
+    Finished,
+    /// The job completed with `Err`, or panicked.
+    Failed,
+    /// The job claimed from the queue ran out of attempts, after failing
+    /// (see [`Job::dead_letters`]).
+    DeadLettered,
+}
+
+impl Event {
+    /// The event notified for `event`, if any.
+    fn of(event: &JobEvent) -> Option<Self> {
+        match event {
+            JobEvent::Finished { .. } => Some(Event::Finished),
+            JobEvent::Failed { .. } => Some(Event::Failed),
+            JobEvent::DeadLettered { .. } => Some(Event::DeadLettered),
+            _ => None,
+        }
+    }
+}
+
+/// The body of a delivery.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Payload<Output, Error> {
+    /// How the job ended.
+    pub event: Event,
+    /// The id of the job.
+    pub id: Uuid,
+    /// The kind of the final status of the job (see
+    /// [`StatusType::label`](crate::StatusType::label)).
+    pub status: String,
+    /// The result of the job.
+    pub result: Option<Result<Output, Error>>,
+    /// The queue of the job, if it was enqueued.
+    pub queue: Option<String>,
+    /// The tenant owning the job.
+    pub tenant_id: Option<String>,
+    /// When the job was submitted.
+    pub created_at: Option<DateTime<Utc>>,
+    /// When the job started executing.
+    pub started_at: Option<DateTime<Utc>>,
+    /// When the job ended.
+    pub finished_at: Option<DateTime<Utc>>,
+    /// The number of failed attempts of a job claimed from the queue.
+    pub attempts: usize,
+}
+
+impl<Output, Error> Payload<Output, Error> {
+    /// The payload notifying `event` for the job of `info`.
+    pub fn new<Metadata, Status>(
+        event: Event,
+        info: JobInfo<Output, Error, Metadata, Status>,
+    ) -> Self {
+        Self {
+            event,
+            id: info.id,
+            status: info.status.label().to_string(),
+            result: info.result,
+            queue: info.queue,
+            tenant_id: info.tenant_id,
+            created_at: info.created_at,
+            started_at: info.started_at,
+            finished_at: info.finished_at,
+            attempts: info.attempts.len(),
+        }
+    }
+}
+
+/// The global webhooks, and how to deliver to them.
+#[derive(Clone)]
+pub struct Webhooks {
+    urls: Vec<String>,
+    secret: Arc<[u8]>,
+    backoff: Backoff,
+    timeout: Duration,
+    client: reqwest::Client,
+}
+
+impl Webhooks {
+    /// No global webhooks, signing with `secret`, retrying for about 20
+    /// minutes with a timeout of 10 seconds.
+    pub fn new(secret: impl AsRef<[u8]>) -> Self {
+        Self {
+            urls: vec![],
+            secret: secret.as_ref().into(),
+            backoff: Backoff {
+                initial: Duration::from_secs(1),
+                max: Duration::from_secs(5 * 60),
+                max_failures: Some(12),
+                ..Backoff::default()
+            },
+            timeout: Duration::from_secs(10),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Notify `url` of the ends of all the jobs.
+    ///
+    /// Call it again for several URLs.
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.urls.push(url.into());
+        self
+    }
+
+    /// Retry failed deliveries following `backoff`.
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Give up on a delivery attempt after `timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Deliver `event` for the job `id` of `job`, if it is a job of `job`,
+    /// in the background.
+    fn notify<J>(&self, job: &J, event: Event, id: Uuid)
+    where
+        J: Job,
+        J::Output: Serialize,
+        J::Error: Serialize,
+    {
+        let Ok(info): Result<Info<J>, _> = job.load(id) else {
+            return;
+        };
+        let mut urls = self.urls.clone();
+        urls.extend(info.webhooks.iter().cloned());
+        urls.sort();
+        urls.dedup();
+        let Ok(body) = serde_json::to_vec(&Payload::new(event, info)) else {
+            return;
+        };
+        for url in urls {
+            let webhooks = self.clone();
+            let body = body.clone();
+            tokio::spawn(async move { webhooks.post(&url, body).await });
+        }
+    }
+
+    /// Post `body` to `url`, retrying on failures.
+    ///
+    /// Returns whether the delivery eventually succeeded.
+    async fn post(&self, url: &str, body: Vec<u8>) -> bool {
+        let mut interval = self.backoff.initial;
+        let mut failures = 0;
+        loop {
+            let signature = sign(&self.secret, Utc::now().timestamp(), &body);
+            let response = self
+                .client
+                .post(url)
+                .timeout(self.timeout)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(SIGNATURE_HEADER, signature)
+                .body(body.clone())
+                .send()
+                .await;
+            if response.is_ok_and(|r| r.status().is_success()) {
+                return true;
+            }
+            failures += 1;
+            if self.backoff.exhausted(failures) {
+                return false;
+            }
+            tokio::time::sleep(self.backoff.jittered(interval)).await;
+            interval = self.backoff.next(interval);
+        }
+    }
+}
+
+/// Deliver the ends of the jobs of this process to the webhooks, until the
+/// event bus closes.
+///
+/// Only the jobs of `job` are notified.
+pub async fn deliver<J>(job: J, webhooks: Webhooks)
+where
+    J: Job,
+    J::Output: Serialize,
+    J::Error: Serialize,
+{
+    let mut events = events::subscribe();
+    loop {
+        match events.recv().await {
+            Ok(event) => {
+                if let Some(kind) = Event::of(&event) {
+                    webhooks.notify(&job, kind, event.id());
+                }
+            }
+            Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => return,
+        }
+    }
+}
+
+/// The value of the [`SIGNATURE_HEADER`] for `body`, sent at `timestamp`
+/// (in seconds since the epoch).
+pub fn sign(secret: &[u8], timestamp: i64, body: &[u8]) -> String {
+    let signature = mac(secret, timestamp, body).finalize().into_bytes();
+    let hex: String = signature.iter().map(|b| format!("{b:02x}")).collect();
+    format!("t={timestamp},v1={hex}")
+}
+
+/// Whether `signature`, the value of the [`SIGNATURE_HEADER`] of a
+/// delivery, signs `body` with `secret`, and was made less than
+/// `tolerance` ago, to reject replayed deliveries.
+pub fn verify(
+    secret: &[u8],
+    signature: &str,
+    body: &[u8],
+    tolerance: Duration,
+) -> bool {
+    let mut timestamp = None;
+    let mut hex = None;
+    for part in signature.split(',') {
+        match part.split_once('=') {
+            Some(("t", t)) => timestamp = t.parse::<i64>().ok(),
+            Some(("v1", v1)) => hex = Some(v1),
+            _ => {}
+        }
+    }
+    let (Some(timestamp), Some(hex)) = (timestamp, hex) else {
+        return false;
+    };
+    let age = Utc::now().timestamp().abs_diff(timestamp);
+    if age > tolerance.as_secs() || hex.len() % 2 != 0 {
+        return false;
+    }
+    let bytes: Option<Vec<u8>> = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect();
+    bytes.is_some_and(|bytes| {
+        mac(secret, timestamp, body).verify_slice(&bytes).is_ok()
+    })
+}
+
+fn mac(secret: &[u8], timestamp: i64, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret)
+        .expect("HMAC takes keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
@@ -0,0 +1,47 @@
+//! The identity of the worker process, recorded in the jobs it runs.
+//!
+//! Defaults to the host name.  Deployments running several workers per
+//! host should give each a stable label with [`set_label`] before
+//! submitting jobs: [`Job::recover`](crate::Job::recover) only recovers the
+//! jobs recorded with the label of the current worker.
+//!
+//! Deployments writing to replicated stores from several regions can also
+//! set the region of the worker with [`set_region`]; it is recorded as the
+//! [`origin`](crate::JobInfo::origin) of the jobs submitted by the worker.
+
+use std::sync::RwLock;
+
+static LABEL: RwLock<Option<String>> = RwLock::new(None);
+static REGION: RwLock<Option<String>> = RwLock::new(None);
+
+/// Set the label of this worker.
+pub fn set_label(label: impl Into<String>) {
+    *LABEL.write().expect("cannot get lock") = Some(label.into());
+}
+
+/// The label of this worker.
+pub fn label() -> String {
+    if let Some(label) = LABEL.read().expect("cannot get lock").as_ref() {
+        return label.clone();
+    }
+    hostname()
+}
+
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "localhost".to_string())
+}
+
+/// Set the region of this worker.
+pub fn set_region(region: impl Into<String>) {
+    *REGION.write().expect("cannot get lock") = Some(region.into());
+}
+
+/// The region of this worker, if set.
+pub fn region() -> Option<String> {
+    REGION.read().expect("cannot get lock").clone()
+}
@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    alerts::{self, Alert, Alerting},
+    queue::EnqueueOptions,
+    wait, FSJob, Job,
+};
+use tokio::sync::mpsc;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[tokio::test]
+async fn test_alerts() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, u16, ()> = FSJob::new(dir.path().into());
+    let (sender, mut alerts) = mpsc::unbounded_channel();
+    let window = Duration::from_secs(60);
+    let alerting = Alerting::new(window)
+        .failure_rate(0.5)
+        .min_completions(2)
+        .queue_depth(2)
+        .check_every(Duration::from_millis(10))
+        .on_alert(move |alert| sender.send(alert.clone()).unwrap());
+    tokio::spawn(alerts::monitor(job.clone(), alerting));
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let run = |_, _, n: u16| async move {
+        match n {
+            0 => Err(MyError {}),
+            n => Ok(n),
+        }
+    };
+    // A single failure is below the minimum of completions.
+    for n in [0, 1, 0, 0] {
+        let id = job.submit(run, n)?.id();
+        wait(id, &job).await?;
+    }
+    let options = EnqueueOptions::new().queue("emails");
+    job.enqueue_with(1, &options)?;
+    job.enqueue_with(2, &options)?;
+
+    let mut received = vec![];
+    while received.len() < 2 {
+        let alert = tokio::time::timeout(Duration::from_secs(5), alerts.recv())
+            .await
+            .expect("missing alert")
+            .unwrap();
+        received.push(alert);
+    }
+    assert_eq!(
+        received,
+        [
+            Alert::FailureRate {
+                rate: 0.5,
+                failed: 1,
+                completed: 2,
+                window
+            },
+            Alert::QueueDepth {
+                queue: "emails".to_string(),
+                depth: 2
+            },
+        ]
+    );
+    assert_eq!(received[1].text(), "Queue emails has 2 pending jobs");
+    // The alerts were raised once, while above their thresholds.
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(alerts.try_recv().is_err());
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    archive, fs_job::FSJob, Job, JobInfo, LogLevel, LogLine, StatusType,
+};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MyMetadata {
+    value: usize,
+}
+
+type MyFSJob = FSJob<u16, MyError, MyMetadata, u32>;
+
+#[test]
+fn test_export_import() -> std::io::Result<()> {
+    let source_dir = tempfile::tempdir()?;
+    let source: MyFSJob = FSJob::new(source_dir.path().into());
+    let mut finished = JobInfo::new();
+    finished.status = StatusType::Finished;
+    finished.result = Some(Ok(7));
+    finished.metadata = Some(MyMetadata { value: 2 });
+    source.save(&finished)?;
+    let line = LogLine {
+        at: chrono::Utc::now(),
+        level: LogLevel::Info,
+        message: "done".into(),
+    };
+    source.append_log(finished.id, &line)?;
+    source.append_output(finished.id, &serde_json::json!([1, 2]))?;
+    let running = JobInfo::new();
+    source.save(&running)?;
+
+    let mut archive = vec![];
+    let exported = archive::export(&source, &mut archive, |info| {
+        info.status.is_terminal()
+    })?;
+    assert_eq!(exported, 1);
+
+    let target_dir = tempfile::tempdir()?;
+    let target: MyFSJob = FSJob::new(target_dir.path().into());
+    let report = archive::import(&target, archive.as_slice())?;
+    assert_eq!(report.imported, vec![finished.id]);
+    let imported = target.load(finished.id)?;
+    assert_eq!(imported.result.unwrap().unwrap(), 7);
+    assert_eq!(imported.metadata.unwrap().value, 2);
+    assert_eq!(target.logs(finished.id)?, vec![line]);
+    assert_eq!(
+        target.outputs(finished.id, 0)?,
+        vec![serde_json::json!([1, 2])]
+    );
+    assert_eq!(target.ids()?, vec![finished.id]);
+
+    // Importing again leaves the jobs untouched.
+    let report = archive::import(&target, archive.as_slice())?;
+    assert!(report.imported.is_empty());
+    assert_eq!(report.skipped, vec![finished.id]);
+    assert_eq!(target.logs(finished.id)?.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_import_rejects_other_files() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let err = archive::import(&job, &b"{\"id\": 1}\n"[..]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let newer = b"{\"simple_jobs_archive\": 99}\n";
+    let err = archive::import(&job, &newer[..]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    audit::Actor, queue::EnqueueOptions, CancelReason, FSJob, Job, StatusType,
+};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+type MyFSJob = FSJob<u16, MyError, u16, u32>;
+
+#[tokio::test]
+async fn test_audit() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into()).with_audit(true);
+    let options = EnqueueOptions::new().queue("audit");
+    let id = job.enqueue_with(1, &options)?;
+    let handle = job
+        .claim_next_from(&["audit"], |id, job: MyFSJob, n| async move {
+            job.set_status(id, StatusType::StatusValue(7)).unwrap();
+            Ok(n + 1)
+        })?
+        .unwrap();
+    assert_eq!(handle.result().await?.unwrap().unwrap(), 2);
+
+    let audit = job.audit(id)?;
+    let actors: Vec<_> = audit.iter().map(|entry| entry.actor).collect();
+    assert_eq!(
+        actors,
+        [
+            Actor::Submitter,
+            Actor::Worker,
+            Actor::Other,
+            Actor::Job,
+            Actor::Job
+        ]
+    );
+    assert_eq!(audit[0].from, None);
+    assert_eq!(audit[0].to, StatusType::Pending);
+    assert_eq!(audit[1].from, Some(StatusType::Pending));
+    assert_eq!(audit[3].to, StatusType::StatusValue(7));
+    assert_eq!(audit[4].to, StatusType::Finished);
+    assert!(audit.iter().all(|entry| !entry.process.is_empty()));
+
+    let canceled = job.enqueue_with(2, &options)?;
+    job.cancel(canceled, CancelReason::UserAction)?;
+    let audit = job.audit(canceled)?;
+    assert_eq!(audit.last().unwrap().actor, Actor::Canceler);
+
+    job.remove(id)?;
+    assert!(job.audit(id)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_audit_is_off_by_default() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let id = job.enqueue(1)?;
+    assert!(job.audit(id)?.is_empty());
+    Ok(())
+}
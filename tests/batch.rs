@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use simple_jobs::{batch::BatchStatus, fs_job::FSJob, Job};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+type MyFSJob = FSJob<u16, MyError, u16, u32>;
+
+#[tokio::test]
+async fn test_batch() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job = MyFSJob::new(dir.path().into());
+    let run = |_, _, n: u16| async move {
+        if n > 8 {
+            Err(MyError {})
+        } else {
+            Ok(n * 2)
+        }
+    };
+    let batch = job.submit_batch(run, 1..=10)?;
+    assert_eq!(batch.ids().len(), 10);
+    let info = job.load(batch.ids()[0])?;
+    assert_eq!(info.batch_id, Some(batch.id()));
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    batch.on_batch_complete(move |status| {
+        tx.send(status.unwrap()).unwrap();
+    });
+    let expected = BatchStatus {
+        pending: 0,
+        succeeded: 8,
+        failed: 2,
+    };
+    assert_eq!(rx.await.unwrap(), expected);
+    assert!(expected.is_complete());
+    assert_eq!(expected.total(), 10);
+    assert_eq!(batch.status()?, expected);
+    assert_eq!(job.batch_status(batch.id())?, expected);
+
+    job.submit(run, 1)?.result().await?;
+    assert_eq!(job.batch_status(batch.id())?, expected);
+    Ok(())
+}
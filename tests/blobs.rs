@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+use simple_jobs::{blobs::DirBlobStore, fs_job::FSJob, Job};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+type MyFSJob = FSJob<String, MyError, String, u32>;
+
+#[tokio::test]
+async fn test_large_values_go_to_blobs() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let blobs = tempfile::tempdir()?;
+    let job = MyFSJob::new(dir.path().into())
+        .with_blobs(DirBlobStore::new(blobs.path().into()), 100);
+    let large = "x".repeat(1000);
+    let run = |_, _, m: String| async move { Ok(m.repeat(2)) };
+    let big = job.submit(run, large.clone())?;
+    let small = job.submit(run, "small".to_string())?;
+    big.result().await?;
+    small.result().await?;
+
+    let info = job.load(big.id())?;
+    assert_eq!(info.metadata.as_ref(), Some(&large));
+    assert_eq!(info.result.unwrap().unwrap(), large.repeat(2));
+    let record =
+        std::fs::read_to_string(dir.path().join(big.id().to_string()))?;
+    assert!(record.len() < 1000);
+    assert!(record.contains("$blob"));
+    assert_eq!(std::fs::read_dir(blobs.path())?.count(), 2);
+    let info = job.load(small.id())?;
+    assert_eq!(info.result.unwrap().unwrap(), "smallsmall");
+
+    job.remove(big.id())?;
+    assert_eq!(std::fs::read_dir(blobs.path())?.count(), 0);
+    Ok(())
+}
+
+#[test]
+fn test_blobs_are_needed_to_load() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let blobs = tempfile::tempdir()?;
+    let job = MyFSJob::new(dir.path().into())
+        .with_blobs(DirBlobStore::new(blobs.path().into()), 10);
+    let mut info = simple_jobs::JobInfo::new();
+    info.metadata = Some("a long enough metadata".to_string());
+    job.save(&info)?;
+    assert!(MyFSJob::new(dir.path().into()).load(info.id).is_err());
+    Ok(())
+}
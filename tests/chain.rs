@@ -0,0 +1,86 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    chain::{step_id, JobChain},
+    fs_job::FSJob,
+    Job, JobInfo, StatusType,
+};
+use uuid::Uuid;
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+struct MyError {}
+
+type MyFSJob = FSJob<u16, MyError, u16, u32>;
+
+fn chain(calls: Arc<AtomicUsize>) -> JobChain<MyFSJob> {
+    let first = calls.clone();
+    JobChain::new()
+        .then(move |_, _, n| {
+            first.fetch_add(1, Ordering::SeqCst);
+            async move { Ok(n + 1) }
+        })
+        .then(move |_, _, n| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if n > 100 {
+                    Err(MyError {})
+                } else {
+                    Ok(n * 2)
+                }
+            }
+        })
+}
+
+#[tokio::test]
+async fn test_chain() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job = MyFSJob::new(dir.path().into());
+    let calls = Arc::new(AtomicUsize::new(0));
+    let chain = chain(calls.clone());
+    assert_eq!(chain.len(), 2);
+    let id = Uuid::new_v4();
+    assert_eq!(chain.run(&job, id, 20).await?, Ok(42));
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    let first = job.load(step_id(id, 0))?;
+    assert_eq!(first.metadata, Some(20));
+    assert_eq!(first.result.unwrap().unwrap(), 21);
+
+    // Running a finished chain again runs no step.
+    assert_eq!(chain.run(&job, id, 20).await?, Ok(42));
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    assert_eq!(chain.run(&job, Uuid::new_v4(), 200).await?, Err(MyError {}));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_resume_chain() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job = MyFSJob::new(dir.path().into());
+    let id = Uuid::new_v4();
+    // The first step finished and the second one was interrupted by a
+    // crash.
+    job.save(&JobInfo {
+        id: step_id(id, 0),
+        status: StatusType::Finished,
+        result: Some(Ok(5)),
+        metadata: Some(4),
+        ..JobInfo::new()
+    })?;
+    job.save(&JobInfo {
+        id: step_id(id, 1),
+        status: StatusType::Interrupted,
+        metadata: Some(5),
+        ..JobInfo::new()
+    })?;
+    let calls = Arc::new(AtomicUsize::new(0));
+    assert_eq!(chain(calls.clone()).run(&job, id, 4).await?, Ok(10));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    let second = job.load(step_id(id, 1))?;
+    assert_eq!(second.status, StatusType::Finished);
+    Ok(())
+}
@@ -0,0 +1,134 @@
+#![cfg(all(feature = "client", feature = "http"))]
+
+use std::time::Duration;
+
+use axum::{
+    extract::Request,
+    http::StatusCode,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use futures::StreamExt;
+use serde_json::{json, Value};
+use simple_jobs::{
+    client::Client,
+    error::SerializableError,
+    registry::{JobRegistry, Payload},
+    watch::Backoff,
+    FSJob, JobInfo, StatusType,
+};
+use tokio::net::TcpListener;
+
+type MyFSJob = FSJob<Value, SerializableError, Payload, ()>;
+type MyInfo = JobInfo<Value, SerializableError, Payload, ()>;
+
+fn registry(job: &MyFSJob) -> JobRegistry<MyFSJob> {
+    let mut registry = JobRegistry::new(job.clone());
+    registry.register("slow", |_, _, n: u32| async move {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        Ok(2 * n)
+    });
+    registry
+}
+
+fn backoff() -> Backoff {
+    Backoff {
+        initial: Duration::from_millis(10),
+        max: Duration::from_millis(50),
+        max_failures: Some(100),
+        ..Backoff::default()
+    }
+}
+
+/// Serve `app` on `listener` in the background.
+fn serve(listener: TcpListener, app: Router) {
+    tokio::spawn(async move { axum::serve(listener, app).await });
+}
+
+/// Watch the job `id` of the server at `address` until the stream ends.
+async fn watched(address: std::net::SocketAddr, id: uuid::Uuid) -> Vec<MyInfo> {
+    let client = Client::new(format!("http://{address}"));
+    let updates = client.watch(id, backoff()).collect();
+    tokio::time::timeout(Duration::from_secs(10), updates)
+        .await
+        .expect("the watch should end with the job")
+}
+
+fn assert_finished(updates: &[MyInfo]) {
+    let last = updates.last().unwrap();
+    assert_eq!(last.status, StatusType::Finished);
+    assert_eq!(last.result.as_ref().unwrap().as_ref().unwrap(), &json!(42));
+    assert!(updates[..updates.len() - 1]
+        .iter()
+        .all(|info| !info.status.is_terminal()));
+}
+
+async fn no_events(request: Request, next: Next) -> Response {
+    match request.uri().path().ends_with("/events") {
+        true => StatusCode::NOT_FOUND.into_response(),
+        false => next.run(request).await,
+    }
+}
+
+#[tokio::test]
+async fn test_watch_events() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?;
+    serve(listener, simple_jobs::http::router(registry(&job)));
+
+    let id = registry(&job).submit("slow", &21)?.id();
+    assert_finished(&watched(address, id).await);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_watch_polls_without_events() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?;
+    let app = simple_jobs::http::router(registry(&job))
+        .layer(middleware::from_fn(no_events));
+    serve(listener, app);
+
+    let id = registry(&job).submit("slow", &21)?.id();
+    let updates = watched(address, id).await;
+    assert_finished(&updates);
+    // Unchanged records aren't yielded again.
+    assert!(updates.windows(2).all(|w| w[0].status != w[1].status));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_watch_retries_unavailable_servers() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let address = TcpListener::bind("127.0.0.1:0").await?.local_addr()?;
+    let id = registry(&job).submit("slow", &21)?.id();
+
+    // The server only starts after the client failed to connect a few times.
+    let app = simple_jobs::http::router(registry(&job));
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        serve(TcpListener::bind(address).await.unwrap(), app);
+    });
+    assert_finished(&watched(address, id).await);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_watch_gives_up() -> std::io::Result<()> {
+    let address = TcpListener::bind("127.0.0.1:0").await?.local_addr()?;
+    let client = Client::new(format!("http://{address}/"));
+    let backoff = Backoff {
+        max_failures: Some(3),
+        ..backoff()
+    };
+    let updates: Vec<MyInfo> =
+        client.watch(uuid::Uuid::new_v4(), backoff).collect().await;
+    assert!(updates.is_empty());
+    Ok(())
+}
@@ -0,0 +1,236 @@
+//! A conformance suite every backend should pass, run against the
+//! backends of the crate.
+
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    error::JobError, fs_job::FSJob, wait, CancelReason, FailoverJob, Job,
+    JobInfo, ShardedJob, StatusType, SubmitOptions,
+};
+use uuid::Uuid;
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+struct MyMetadata {
+    value: usize,
+}
+
+type MyFSJob = FSJob<u16, MyError, MyMetadata, u32>;
+
+/// Run the whole suite against an empty backend.
+async fn conformance<J>(job: J) -> std::io::Result<()>
+where
+    J: Job<Output = u16, Error = MyError, Metadata = MyMetadata, Status = u32>,
+{
+    storage(&job)?;
+    versions(&job)?;
+    lifecycle(&job).await?;
+    cancellation(&job).await?;
+    scanning(&job)?;
+    tenants(&job).await?;
+    Ok(())
+}
+
+/// Records are saved and loaded back, and listed.
+fn storage<J>(job: &J) -> std::io::Result<()>
+where
+    J: Job<Output = u16, Error = MyError, Metadata = MyMetadata, Status = u32>,
+{
+    let mut info = JobInfo::new();
+    info.metadata = Some(MyMetadata { value: 3 });
+    info.status = StatusType::StatusValue(7);
+    job.save(&info)?;
+    let loaded = job.load(info.id)?;
+    assert_eq!(loaded.id, info.id);
+    assert_eq!(loaded.status, info.status);
+    assert_eq!(loaded.metadata, info.metadata);
+    assert_eq!(loaded.created_at, info.created_at);
+
+    let missing = job.load(Uuid::new_v4()).unwrap_err();
+    assert_eq!(missing.kind(), std::io::ErrorKind::NotFound);
+
+    let ids = [info.id, Uuid::new_v4(), info.id];
+    let many = job.load_many(&ids);
+    assert_eq!(many.len(), 3);
+    assert_eq!(many[0].as_ref().unwrap().id, info.id);
+    assert!(many[1].is_err());
+    assert_eq!(many[2].as_ref().unwrap().id, info.id);
+
+    let batch = [JobInfo::new(), JobInfo::new()];
+    let saved = job.save_many(&batch);
+    assert_eq!(saved.len(), 2);
+    assert!(saved.iter().all(Result::is_ok));
+    for info in &batch {
+        assert_eq!(job.load(info.id)?.id, info.id);
+    }
+
+    assert!(job.ids()?.contains(&info.id));
+    Ok(())
+}
+
+/// Compare-and-swap saves detect concurrent changes.
+fn versions<J>(job: &J) -> std::io::Result<()>
+where
+    J: Job<Output = u16, Error = MyError, Metadata = MyMetadata, Status = u32>,
+{
+    let mut info = JobInfo::new();
+    job.save(&info)?;
+    let stale = info.version;
+    let version = job.save_if_version(&mut info, stale)?;
+    assert_eq!(info.version, version);
+    assert_eq!(job.load(info.id)?.version, version);
+    let err = job.save_if_version(&mut info, stale).unwrap_err();
+    assert_eq!(info.version, version);
+    assert!(matches!(
+        JobError::from_io(&err),
+        Some(JobError::VersionConflict { .. })
+    ));
+    let updated = job.update_metadata(info.id, |m| {
+        *m = Some(MyMetadata { value: 9 });
+    })?;
+    assert!(updated.version > version);
+    assert_eq!(job.load(info.id)?.metadata, Some(MyMetadata { value: 9 }));
+    Ok(())
+}
+
+/// Submitted jobs run to completion, with their transitions persisted.
+async fn lifecycle<J>(job: &J) -> std::io::Result<()>
+where
+    J: Job<Output = u16, Error = MyError, Metadata = MyMetadata, Status = u32>,
+{
+    let ok = job.submit(
+        |id, job: J, m: MyMetadata| async move {
+            job.set_status(id, StatusType::StatusValue(50)).unwrap();
+            Ok(m.value as u16)
+        },
+        MyMetadata { value: 4 },
+    )?;
+    let err =
+        job.submit(|_, _, _| async { Err(MyError {}) }, Default::default())?;
+    let info = wait(ok.id(), job).await?;
+    assert_eq!(info.status, StatusType::Finished);
+    assert_eq!(info.result, Some(Ok(4)));
+    assert!(info.started_at.is_some() && info.finished_at.is_some());
+    let info = wait(err.id(), job).await?;
+    assert_eq!(info.result, Some(Err(MyError {})));
+    Ok(())
+}
+
+/// Running jobs can be canceled, finished ones can't.
+async fn cancellation<J>(job: &J) -> std::io::Result<()>
+where
+    J: Job<Output = u16, Error = MyError, Metadata = MyMetadata, Status = u32>,
+{
+    let handle = job.submit(
+        |_, _, _| async {
+            tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+            Ok(1)
+        },
+        Default::default(),
+    )?;
+    handle.cancel(CancelReason::UserAction)?;
+    let info = wait(handle.id(), job).await?;
+    assert_eq!(info.status, StatusType::Canceled(CancelReason::UserAction));
+    assert!(handle.cancel(CancelReason::UserAction).is_err());
+    Ok(())
+}
+
+/// Jobs are listed, counted, purged and removed.
+fn scanning<J>(job: &J) -> std::io::Result<()>
+where
+    J: Job<Output = u16, Error = MyError, Metadata = MyMetadata, Status = u32>,
+{
+    let listed = job.list()?;
+    assert_eq!(listed.len(), job.ids()?.len());
+    let counts = job.counts()?;
+    assert_eq!(counts.values().sum::<usize>(), listed.len());
+    assert!(counts["finished"] > 0);
+
+    let mut running = JobInfo::new();
+    running.status = StatusType::StatusValue(1);
+    job.save(&running)?;
+    let purged = job.purge(|_| true)?;
+    assert!(!purged.is_empty());
+    assert!(!purged.contains(&running.id));
+    for id in purged {
+        assert_eq!(
+            job.load(id).unwrap_err().kind(),
+            std::io::ErrorKind::NotFound
+        );
+    }
+    assert!(job.ids()?.contains(&running.id));
+    assert!(job.list()?.iter().all(|info| !info.status.is_terminal()));
+
+    job.remove(running.id)?;
+    let err = job.load(running.id).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    let err = job.remove(running.id).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    Ok(())
+}
+
+/// Jobs are listed and purged by tenant.
+async fn tenants<J>(job: &J) -> std::io::Result<()>
+where
+    J: Job<Output = u16, Error = MyError, Metadata = MyMetadata, Status = u32>,
+{
+    let mut ids = vec![];
+    for tenant in ["acme", "acme", "globex"] {
+        let id = job
+            .submit_with(
+                |_, _, _| async { Ok(1) },
+                Default::default(),
+                &SubmitOptions::new().tenant(tenant),
+            )?
+            .id();
+        wait(id, job).await?;
+        ids.push(id);
+    }
+    let mut acme = job.tenant_ids("acme")?;
+    acme.sort();
+    let mut expected = ids[..2].to_vec();
+    expected.sort();
+    assert_eq!(acme, expected);
+    let listed = job.list_tenant("globex")?;
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].tenant_id.as_deref(), Some("globex"));
+
+    let mut purged = job.purge_tenant("acme", |_| true)?;
+    purged.sort();
+    assert_eq!(purged, expected);
+    assert!(job.tenant_ids("acme")?.is_empty());
+    assert_eq!(job.tenant_ids("globex")?, vec![ids[2]]);
+    job.remove(ids[2])
+}
+
+#[tokio::test]
+async fn fs_job_conforms() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    conformance(MyFSJob::new(dir.path().into())).await
+}
+
+#[tokio::test]
+async fn indexed_fs_job_conforms() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    conformance(MyFSJob::new(dir.path().into()).with_index(true)).await
+}
+
+#[tokio::test]
+async fn sharded_job_conforms() -> std::io::Result<()> {
+    let dirs = [tempfile::tempdir()?, tempfile::tempdir()?];
+    let shards = dirs.iter().map(|d| MyFSJob::new(d.path().into())).collect();
+    conformance(ShardedJob::new(shards)).await
+}
+
+#[tokio::test]
+async fn failover_job_conforms() -> std::io::Result<()> {
+    let primary = tempfile::tempdir()?;
+    let secondary = tempfile::tempdir()?;
+    let job = FailoverJob::new(
+        MyFSJob::new(primary.path().into()),
+        MyFSJob::new(secondary.path().into()),
+    )
+    .with_mirror_writes(true);
+    conformance(job).await
+}
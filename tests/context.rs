@@ -0,0 +1,97 @@
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use simple_jobs::{context::LogLevel, fs_job::FSJob, wait, Job};
+use uuid::Uuid;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MyMetadata {
+    value: usize,
+}
+
+type MyFSJob = FSJob<u16, MyError, MyMetadata, u32>;
+
+#[tokio::test]
+async fn test_job_logs() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job = MyFSJob::new(dir.path().into());
+    let id = job
+        .submit(
+            |id, job: MyFSJob, _| async move {
+                let ctx = job.context(id);
+                ctx.info("starting").unwrap();
+                ctx.log(LogLevel::Debug, "step 1").unwrap();
+                ctx.warn("almost done").unwrap();
+                Ok(1)
+            },
+            Default::default(),
+        )?
+        .id();
+    wait(id, &job).await?;
+    let logs = job.logs(id)?;
+    let messages: Vec<_> = logs.iter().map(|l| l.message.as_str()).collect();
+    assert_eq!(messages, ["starting", "step 1", "almost done"]);
+    assert_eq!(logs[2].level, LogLevel::Warn);
+    assert!(logs.windows(2).all(|w| w[0].at <= w[1].at));
+
+    // Logs don't show up as jobs, and jobs without logs have empty ones.
+    assert_eq!(job.ids()?, vec![id]);
+    assert!(job.logs(Uuid::new_v4())?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_results_stream() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job = MyFSJob::new(dir.path().into());
+    let id = job
+        .submit(
+            |id, job: MyFSJob, _| async move {
+                let ctx = job.context(id);
+                for i in 0..5u32 {
+                    ctx.emit(&i).unwrap();
+                    tokio::time::sleep(std::time::Duration::from_millis(5))
+                        .await;
+                }
+                Ok(1)
+            },
+            Default::default(),
+        )?
+        .id();
+    let items: Vec<u32> = job
+        .results_stream::<u32>(id)
+        .map(|item| item.unwrap())
+        .collect()
+        .await;
+    assert_eq!(items, [0, 1, 2, 3, 4]);
+    assert_eq!(job.outputs(id, 3)?, [3, 4]);
+    assert!(job.outputs(id, 9)?.is_empty());
+    assert_eq!(job.ids()?, vec![id]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_update_metadata() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job = MyFSJob::new(dir.path().into());
+    let id = job
+        .submit(
+            |id, job: MyFSJob, _| async move {
+                let ctx = job.context(id);
+                for _ in 0..3 {
+                    ctx.update_metadata(|m| {
+                        m.get_or_insert_with(Default::default).value += 1
+                    })
+                    .unwrap();
+                }
+                Ok(1)
+            },
+            MyMetadata { value: 10 },
+        )?
+        .id();
+    let info = wait(id, &job).await?;
+    assert_eq!(info.metadata.unwrap().value, 13);
+    Ok(())
+}
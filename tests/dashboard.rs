@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    dashboard::RECENT_FAILURES, queue::EnqueueOptions, FSJob, Job, JobInfo,
+    StatusType,
+};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+type MyFSJob = FSJob<u16, MyError, u16, ()>;
+
+fn failed(seconds_ago: i64) -> JobInfo<u16, MyError, u16, ()> {
+    JobInfo {
+        status: StatusType::Finished,
+        result: Some(Err(MyError {})),
+        finished_at: Some(Utc::now() - chrono::Duration::seconds(seconds_ago)),
+        ..JobInfo::new()
+    }
+}
+
+#[test]
+fn test_dashboard_snapshot() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let options = EnqueueOptions::new().queue("emails");
+    let oldest = job.enqueue_with(1, &options)?;
+    job.enqueue_with(2, &options)?;
+    job.enqueue(3)?;
+    let mut info = job.load(oldest)?;
+    info.created_at = Some(Utc::now() - chrono::Duration::minutes(5));
+    job.save(&info)?;
+    for seconds_ago in 0..12 {
+        job.save(&failed(seconds_ago))?;
+    }
+    job.save(&JobInfo {
+        status: StatusType::Finished,
+        result: Some(Ok(1)),
+        ..JobInfo::new()
+    })?;
+
+    let snapshot = job.dashboard_snapshot()?;
+    let depths: Vec<_> = snapshot
+        .queue_depths
+        .iter()
+        .map(|(queue, depth)| (queue.as_str(), *depth))
+        .collect();
+    assert_eq!(depths, [("default", 1), ("emails", 2)]);
+    let counts: Vec<_> = snapshot.status_counts.into_iter().collect();
+    assert_eq!(counts, [("finished", 13), ("pending", 3)]);
+    assert_eq!(snapshot.recent_failures.len(), RECENT_FAILURES);
+    let finished: Vec<_> = snapshot
+        .recent_failures
+        .iter()
+        .map(|info| info.finished_at)
+        .collect();
+    assert!(finished.windows(2).all(|w| w[0] >= w[1]));
+    let age = snapshot.oldest_pending_age.unwrap();
+    assert!(age >= Duration::from_secs(5 * 60));
+    Ok(())
+}
+
+#[test]
+fn test_dashboard_snapshot_without_jobs() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let snapshot = job.dashboard_snapshot()?;
+    assert!(snapshot.queue_depths.is_empty());
+    assert!(snapshot.recent_failures.is_empty());
+    assert_eq!(snapshot.oldest_pending_age, None);
+    Ok(())
+}
@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    fs_job::FSJob, queue::Failure, Job, JobHandle, QueueConfig, StatusType,
+    WithQueues,
+};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MyMetadata {
+    value: u16,
+}
+
+type MyFSJob = WithQueues<FSJob<u16, MyError, MyMetadata, u32>>;
+
+const MAX_ATTEMPTS: u32 = 2;
+
+/// A backend in `dir`, dead-lettering the jobs after [`MAX_ATTEMPTS`].
+fn backend(dir: &std::path::Path) -> MyFSJob {
+    let config = QueueConfig::new().with_max_attempts(MAX_ATTEMPTS);
+    WithQueues::new(FSJob::new(dir.into()), config)
+}
+
+async fn finished(handle: JobHandle<MyFSJob>) {
+    while !handle.is_finished() {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+}
+
+#[tokio::test]
+async fn test_failed_jobs_are_dead_lettered() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job = backend(dir.path());
+    let id = job.enqueue(MyMetadata { value: 1 })?;
+    let fail = |_, _, _| async { Err(MyError {}) };
+
+    finished(job.claim_next(fail)?.unwrap()).await;
+    let info = job.load(id)?;
+    assert_eq!(info.status, StatusType::Pending);
+    assert_eq!(info.attempts.len(), 1);
+    assert!(job.dead_letters()?.is_empty());
+
+    finished(job.claim_next(fail)?.unwrap()).await;
+    assert!(job.claim_next(fail)?.is_none());
+    let dead = job.dead_letters()?;
+    assert_eq!(dead.len(), 1);
+    assert_eq!(dead[0].id, id);
+    assert_eq!(dead[0].status, StatusType::Finished);
+    assert!(matches!(dead[0].result, Some(Err(MyError {}))));
+    assert_eq!(dead[0].attempts.len(), MAX_ATTEMPTS as usize);
+    assert!(dead[0]
+        .attempts
+        .iter()
+        .all(|a| matches!(a.failure, Failure::Error(MyError {}))));
+
+    let info = job.redrive(id)?;
+    assert_eq!(info.status, StatusType::Pending);
+    assert!(info.attempts.is_empty());
+    let handle = job
+        .claim_next(|_, _, m: MyMetadata| async move { Ok(m.value) })?
+        .unwrap();
+    assert_eq!(handle.result().await?.unwrap().unwrap(), 1);
+    assert!(job.dead_letters()?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_expired_leases_count_as_attempts() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job = backend(dir.path());
+    let id = job.enqueue(MyMetadata { value: 1 })?;
+    let hang = |_, _, _| futures::future::pending();
+    for _ in 0..MAX_ATTEMPTS {
+        // The worker hangs, as if it crashed, until its lease expires.
+        job.claim_next(hang)?.unwrap();
+        let mut info = job.load(id)?;
+        info.lease.as_mut().unwrap().expires_at = chrono::Utc::now();
+        job.save(&info)?;
+    }
+    assert!(job.claim_next(hang)?.is_none());
+    let info = job.load(id)?;
+    assert!(matches!(info.status, StatusType::Failed(_)));
+    assert!(info.dead_lettered_at.is_some());
+    assert!(info
+        .attempts
+        .iter()
+        .all(|a| matches!(a.failure, Failure::LeaseExpired)));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_redrive_requires_dead_letter() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job = backend(dir.path());
+    let id = job.enqueue(MyMetadata { value: 1 })?;
+    let err = job.redrive(id).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_retries_resume_from_checkpoints() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job = backend(dir.path());
+    let id = job.enqueue(MyMetadata { value: 10 })?;
+    // Processes the items from the last checkpoint, and fails halfway the
+    // first time.
+    let run = |id, job: MyFSJob, metadata: MyMetadata| async move {
+        let ctx = job.context(id);
+        let start = ctx.last_checkpoint::<u16>().unwrap().unwrap_or(0);
+        for item in start..metadata.value {
+            if start == 0 && item == 5 {
+                return Err(MyError {});
+            }
+            ctx.checkpoint(&(item + 1)).unwrap();
+        }
+        Ok(start)
+    };
+
+    finished(job.claim_next(run)?.unwrap()).await;
+    let info = job.load(id)?;
+    assert_eq!(info.status, StatusType::Pending);
+    assert_eq!(info.checkpoint, Some(serde_json::json!(5)));
+
+    let handle = job.claim_next(run)?.unwrap();
+    assert_eq!(handle.result().await?.unwrap().unwrap(), 5);
+    assert_eq!(job.load(id)?.checkpoint, Some(serde_json::json!(10)));
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use simple_jobs::{
+    describe::{Catalog, Describe},
+    StatusType,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+enum MyStatus {
+    Downloading,
+}
+
+impl Describe for MyStatus {
+    fn message_key(&self) -> String {
+        match self {
+            MyStatus::Downloading => "status.downloading".to_string(),
+        }
+    }
+}
+
+#[test]
+fn test_catalog_from_json() -> std::io::Result<()> {
+    let mut catalog = Catalog::new("en");
+    catalog.load_json("en", r#"{"status.downloading": "Downloading"}"#)?;
+    catalog.load_json("de", r#"{"status.finished": "Fertig"}"#)?;
+    let downloading = StatusType::StatusValue(MyStatus::Downloading);
+    let finished: StatusType<MyStatus> = StatusType::Finished;
+    assert_eq!(catalog.describe("de", &downloading), "Downloading");
+    assert_eq!(catalog.describe("de", &finished), "Fertig");
+    assert_eq!(catalog.describe("en", &finished), "status.finished");
+    Ok(())
+}
+
+#[test]
+fn test_negotiate() {
+    let catalog = Catalog::new("en")
+        .with_message("en", "status.finished", "Finished")
+        .with_message("es", "status.finished", "Terminado")
+        .with_message("pt-BR", "status.finished", "Concluído");
+    assert_eq!(catalog.negotiate("es"), "es");
+    assert_eq!(catalog.negotiate("fr-CA, es-MX;q=0.8"), "es");
+    assert_eq!(catalog.negotiate("en;q=0.5, pt-br"), "pt-BR");
+    assert_eq!(catalog.negotiate("es;q=0, fr"), "en");
+    assert_eq!(catalog.negotiate(""), "en");
+}
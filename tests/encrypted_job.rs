@@ -0,0 +1,104 @@
+#![cfg(feature = "encryption")]
+
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    encrypted_job::Sealed, wait, EncryptedJob, FSJob, Job, JobInfo, StatusType,
+};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MyMetadata {
+    email: String,
+}
+
+type Store = FSJob<Sealed, Sealed, Sealed, Sealed>;
+
+const KEY: [u8; 32] = [7; 32];
+
+#[tokio::test]
+async fn test_encrypted_job() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: EncryptedJob<Store, u16, MyError, MyMetadata, u32> =
+        EncryptedJob::new(FSJob::new(dir.path().into()), &KEY);
+    let metadata = MyMetadata {
+        email: "someone@example.com".into(),
+    };
+    let id = job
+        .submit(
+            |id, job: EncryptedJob<Store, u16, MyError, MyMetadata, u32>, _| async move {
+                job.context(id).checkpoint(&"row of someone@example.com").unwrap();
+                Ok(41u16 + 1)
+            },
+            metadata,
+        )?
+        .id();
+    let info = wait(id, &job).await?;
+    assert_eq!(info.status, StatusType::Finished);
+    assert_eq!(info.result.unwrap().unwrap(), 42);
+    assert_eq!(info.metadata.unwrap().email, "someone@example.com");
+    let checkpoint = job.context(id).last_checkpoint::<String>()?;
+    assert_eq!(checkpoint.unwrap(), "row of someone@example.com");
+
+    let record = std::fs::read_to_string(dir.path().join(id.to_string()))?;
+    assert!(!record.contains("someone@example.com"));
+    assert!(record.contains("Finished"));
+    assert_eq!(job.counts()?.get("finished"), Some(&1));
+    Ok(())
+}
+
+#[test]
+fn test_wrong_key() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: EncryptedJob<Store, u16, MyError, MyMetadata, u32> =
+        EncryptedJob::new(FSJob::new(dir.path().into()), &KEY);
+    let mut info = JobInfo::new();
+    info.status = StatusType::StatusValue(3);
+    info.metadata = Some(MyMetadata::default());
+    job.save(&info)?;
+    assert_eq!(job.load(info.id)?.status, StatusType::StatusValue(3));
+
+    let other: EncryptedJob<Store, u16, MyError, MyMetadata, u32> =
+        EncryptedJob::new(FSJob::new(dir.path().into()), &[8; 32]);
+    let err = other.load(info.id).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    Ok(())
+}
+
+#[test]
+fn test_sealed_values_are_bound_to_their_job() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: EncryptedJob<Store, u16, MyError, MyMetadata, u32> =
+        EncryptedJob::new(FSJob::new(dir.path().into()), &KEY);
+    let mut info = JobInfo::new();
+    info.metadata = Some(MyMetadata::default());
+    job.save(&info)?;
+
+    let mut moved = job.inner().load(info.id)?;
+    moved.id = uuid::Uuid::new_v4();
+    job.inner().save(&moved)?;
+    let err = job.load(moved.id).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    Ok(())
+}
+
+#[test]
+fn test_save_many_and_load_status() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: EncryptedJob<Store, u16, MyError, MyMetadata, u32> =
+        EncryptedJob::new(FSJob::new(dir.path().into()), &KEY);
+    let mut a = JobInfo::new();
+    a.status = StatusType::StatusValue(1);
+    let mut b = JobInfo::new();
+    b.status = StatusType::StatusValue(2);
+    let saved = job.save_many(&[a.clone(), b.clone()]);
+    assert!(saved.iter().all(Result::is_ok));
+    assert_eq!(job.load_status(a.id)?.status, StatusType::StatusValue(1));
+    assert_eq!(job.load_status(b.id)?.status, StatusType::StatusValue(2));
+    assert!(matches!(
+        job.inner().load_status(b.id)?.status,
+        StatusType::StatusValue(_)
+    ));
+    Ok(())
+}
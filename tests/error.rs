@@ -0,0 +1,51 @@
+use simple_jobs::{error::SerializableError, fs_job::FSJob, wait, Job};
+
+#[derive(Debug)]
+struct Outer(std::num::ParseIntError);
+
+impl std::fmt::Display for Outer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("cannot read the count")
+    }
+}
+
+impl std::error::Error for Outer {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+fn parse(s: &str) -> Result<u16, Box<dyn std::error::Error + Send + Sync>> {
+    Ok(s.parse().map_err(Outer)?)
+}
+
+#[tokio::test]
+async fn test_boxed_errors() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, SerializableError, (), u32> =
+        FSJob::new(dir.path().into());
+    let id = job.submit(|_, _, _| async { Ok(parse("x")?) }, ())?.id();
+    let info = wait(id, &job).await?;
+    let error = info.result.unwrap().unwrap_err();
+    assert_eq!(error.message, "cannot read the count");
+    assert_eq!(error.chain, ["invalid digit found in string"]);
+    assert_eq!(
+        error.to_string(),
+        "cannot read the count: invalid digit found in string"
+    );
+    Ok(())
+}
+
+#[cfg(feature = "anyhow")]
+#[test]
+fn test_anyhow_errors() {
+    use anyhow::Context;
+
+    let error = "x"
+        .parse::<u16>()
+        .context("cannot read the count")
+        .unwrap_err();
+    let error = SerializableError::from(error);
+    assert_eq!(error.message, "cannot read the count");
+    assert_eq!(error.chain, ["invalid digit found in string"]);
+}
@@ -0,0 +1,20 @@
+use simple_jobs::{CancelReason, JobEvent};
+use uuid::Uuid;
+
+#[test]
+fn test_event_json() {
+    let id = Uuid::new_v4();
+    let event = JobEvent::DeadLettered { id };
+    let json = serde_json::to_value(&event).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({ "event": "dead_lettered", "id": id })
+    );
+    assert_eq!(json["event"], event.name());
+    let event = JobEvent::Canceled {
+        id,
+        reason: CancelReason::UserAction,
+    };
+    let json = serde_json::to_string(&event).unwrap();
+    assert_eq!(serde_json::from_str::<JobEvent>(&json).unwrap(), event);
+}
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    failover_job::{latest_terminal_wins, FailoverJob, Resolution},
+    fs_job::FSJob,
+    wait, Job, JobInfo, StatusType,
+};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MyMetadata {
+    value: usize,
+}
+
+type MyFSJob = FSJob<u16, MyError, MyMetadata, u32>;
+
+#[tokio::test]
+async fn test_failover_mirrors_writes() -> std::io::Result<()> {
+    let primary_dir = tempfile::tempdir()?;
+    let secondary_dir = tempfile::tempdir()?;
+    let primary: MyFSJob = FSJob::new(primary_dir.path().into());
+    let secondary: MyFSJob = FSJob::new(secondary_dir.path().into());
+    let job = FailoverJob::new(primary, secondary).with_mirror_writes(true);
+    let metadata = Default::default();
+    let id = job
+        .submit(|_id, _job, _| async move { Ok(1u16) }, metadata)?
+        .id();
+    wait(id, &job).await?;
+    let info = job.secondary().load(id)?;
+    assert_eq!(info.status, StatusType::Finished);
+    Ok(())
+}
+
+#[test]
+fn test_failover_marks_stale_reads() -> std::io::Result<()> {
+    let primary_dir = tempfile::tempdir()?;
+    let secondary_dir = tempfile::tempdir()?;
+    let primary: MyFSJob = FSJob::new(primary_dir.path().join("missing"));
+    let secondary: MyFSJob = FSJob::new(secondary_dir.path().into());
+    let job = FailoverJob::new(primary, secondary);
+    let info = JobInfo::new();
+    job.secondary().save(&info)?;
+    let read = job.load_marked(info.id)?;
+    assert!(read.stale);
+    assert_eq!(read.info.status, StatusType::Started);
+    assert!(job.save(&info).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_latest_terminal_wins() -> std::io::Result<()> {
+    let primary_dir = tempfile::tempdir()?;
+    let secondary_dir = tempfile::tempdir()?;
+    let primary: MyFSJob = FSJob::new(primary_dir.path().into());
+    let secondary: MyFSJob = FSJob::new(secondary_dir.path().into());
+    let job = FailoverJob::new(primary.clone(), secondary.clone())
+        .with_mirror_writes(true)
+        .with_resolution(Resolution::LatestTerminalWins);
+
+    // The job finished in the region of the secondary, while the primary
+    // still saw it running.
+    let mut running = JobInfo::new();
+    running.origin = Some("eu".into());
+    running.started_at = Some(chrono::Utc::now());
+    let mut finished = running.clone();
+    finished.status = StatusType::Finished;
+    finished.finished_at = Some(chrono::Utc::now());
+    primary.save(&running)?;
+    secondary.save(&finished)?;
+    assert_eq!(job.load(running.id)?.status, StatusType::Finished);
+
+    // A late write of the running state doesn't replace the finished one.
+    job.save(&running)?;
+    assert_eq!(secondary.load(running.id)?.status, StatusType::Finished);
+    assert_eq!(job.load(running.id)?.status, StatusType::Finished);
+
+    // Between terminal records, the latest one wins, whatever the order.
+    let mut canceled = finished.clone();
+    canceled.status = StatusType::Canceled(simple_jobs::CancelReason::Timeout);
+    canceled.finished_at = Some(chrono::Utc::now());
+    assert!(matches!(
+        latest_terminal_wins(&finished, &canceled).status,
+        StatusType::Canceled(_)
+    ));
+    assert!(matches!(
+        latest_terminal_wins(&canceled, &finished).status,
+        StatusType::Canceled(_)
+    ));
+    Ok(())
+}
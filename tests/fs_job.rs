@@ -1,5 +1,12 @@
 use serde::{Deserialize, Serialize};
-use simple_jobs::{fs_job::FSJob, wait, Job, StatusType};
+use simple_jobs::{
+    error::JobError,
+    format,
+    fs_job::{Durability, FSJob},
+    naming::FileNaming,
+    record::{Compression, Encoding},
+    wait, Job, JobEvent, JobInfo, StatusType,
+};
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 struct MyError {}
@@ -15,9 +22,852 @@ async fn test_submit() -> std::io::Result<()> {
     let metadata = Default::default();
     let job: FSJob<u16, MyError, MyMetadata, u32> =
         FSJob::new(dir.path().into());
-    let j = job.submit(|_id, _job, _| async move { Ok(1u16) }, metadata)?;
+    let j = job
+        .submit(|_id, _job, _| async move { Ok(1u16) }, metadata)?
+        .id();
     let j2 = wait(j, &job).await?;
     assert_eq!(j2.status, StatusType::Finished);
     assert_eq!(j2.result.unwrap().unwrap(), 1u16);
     Ok(())
 }
+
+#[test]
+fn test_newer_store_is_rejected() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    std::fs::write(dir.path().join(".format"), "999")?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let err = job.save(&JobInfo::new()).unwrap_err();
+    assert_eq!(
+        JobError::from_io(&err),
+        Some(&JobError::IncompatibleStore {
+            found: 999,
+            supported: format::CURRENT
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn test_store_format_is_recorded() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    assert_eq!(job.store_format()?, format::LEGACY);
+    job.save(&JobInfo::new())?;
+    assert_eq!(job.store_format()?, format::CURRENT);
+    Ok(())
+}
+
+/// A toy compression, flipping every bit.
+struct Flip;
+
+impl Compression for Flip {
+    fn id(&self) -> u8 {
+        200
+    }
+
+    fn compress(&self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        Ok(data.iter().map(|b| !b).collect())
+    }
+
+    fn decompress(&self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        self.compress(data)
+    }
+}
+
+#[test]
+fn test_mixed_compression() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let plain: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let old = JobInfo::new();
+    plain.save(&old)?;
+    let compressed = plain.clone().with_compression(Flip);
+    let new = JobInfo::new();
+    compressed.save(&new)?;
+    assert_ne!(std::fs::read(dir.path().join(new.id.to_string()))?[0], b'{');
+    assert_eq!(compressed.load(old.id)?.id, old.id);
+    assert_eq!(compressed.load(new.id)?.id, new.id);
+    assert!(plain.load(new.id).is_err());
+    let reader = plain.with_readable_compression(Flip);
+    assert_eq!(reader.load(new.id)?.id, new.id);
+    Ok(())
+}
+
+/// Save a job as a crashed process would have left it.
+fn orphan(
+    job: &FSJob<u16, MyError, MyMetadata, u32>,
+    value: usize,
+) -> std::io::Result<uuid::Uuid> {
+    let mut info = JobInfo::new();
+    info.metadata = Some(MyMetadata { value });
+    job.save(&info)?;
+    Ok(info.id)
+}
+
+#[tokio::test]
+async fn test_recover() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let done = job.submit(|_, _, _| async { Ok(1) }, Default::default())?;
+    wait(done.id(), &job).await?;
+    let orphaned = orphan(&job, 1)?;
+
+    assert_eq!(job.ids()?.len(), 2);
+    assert_eq!(job.recover()?, vec![orphaned]);
+    let info = job.load(orphaned)?;
+    assert_eq!(info.status, StatusType::Interrupted);
+    assert!(info.finished_at.is_some());
+    assert_eq!(job.load(done.id())?.status, StatusType::Finished);
+    assert!(job.recover()?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_recover_skips_other_workers() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let done = job.submit(|_, _, _| async { Ok(1) }, Default::default())?;
+    let info = wait(done.id(), &job).await?;
+    assert_eq!(info.worker, Some(simple_jobs::worker::label()));
+
+    let mut elsewhere = JobInfo::new();
+    elsewhere.worker = Some(format!("not-{}", simple_jobs::worker::label()));
+    job.save(&elsewhere)?;
+    assert!(job.recover()?.is_empty());
+    assert_eq!(job.load(elsewhere.id)?.status, StatusType::Started);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_recover_with_resubmit() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let orphaned = orphan(&job, 7)?;
+    let recovered = job.recover_with(|job, info| {
+        let metadata = info.metadata.clone().unwrap_or_default();
+        job.submit(|_, _, m| async move { Ok(m.value as u16) }, metadata)
+            .map(Some)
+    })?;
+    assert_eq!(recovered.len(), 1);
+    let (old, new) = recovered[0];
+    assert_eq!(old, orphaned);
+    let info = wait(new.unwrap(), &job).await?;
+    assert!(matches!(info.result, Some(Ok(7))));
+    assert_eq!(info.metadata.unwrap().value, 7);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_submit_unique() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let slow = |_, _, _| async {
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        Ok(1)
+    };
+    let first = job.submit_unique("delivery-1", slow, Default::default())?;
+    assert!(first.is_new());
+    let again = job.submit_unique("delivery-1", slow, Default::default())?;
+    assert!(!again.is_new());
+    assert_eq!(again.id(), first.id());
+    let other = job.submit_unique("delivery-2", slow, Default::default())?;
+    assert_ne!(other.id(), first.id());
+    // Another process sharing the directory finds the same job.
+    let shared: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let again = shared.submit_unique("delivery-1", slow, Default::default())?;
+    assert_eq!((again.is_new(), again.id()), (false, first.id()));
+
+    // Once the job is done, the key can be used again.
+    wait(first.id(), &job).await?;
+    let later = job.submit_unique("delivery-1", slow, Default::default())?;
+    assert!(later.is_new());
+    assert_eq!(later.id(), first.id());
+    assert_eq!(
+        job.load(later.id())?.idempotency_key.as_deref(),
+        Some("delivery-1")
+    );
+    Ok(())
+}
+
+/// An output that can't be cloned.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Buffer(Vec<u8>);
+
+#[tokio::test]
+async fn test_output_without_clone() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<Buffer, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let id = job
+        .submit(
+            |_, _, _| async { Ok(Buffer(vec![1, 2, 3])) },
+            Default::default(),
+        )?
+        .id();
+    let info = wait(id, &job).await?;
+    assert!(matches!(info.result, Some(Ok(Buffer(ref b))) if b == &[1, 2, 3]));
+    Ok(())
+}
+
+/// Save and load a finished job with `encoding`, next to a JSON one.
+#[cfg(any(feature = "bincode", feature = "msgpack", feature = "cbor"))]
+async fn roundtrip(encoding: Encoding) -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let json: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let old = JobInfo::new();
+    json.save(&old)?;
+    let job = json.clone().with_encoding(encoding);
+    let id = job
+        .submit(|_, _, _| async { Ok(5) }, MyMetadata { value: 3 })?
+        .id();
+    let info = wait(id, &job).await?;
+    assert_eq!(info.result.unwrap().unwrap(), 5);
+    assert_eq!(info.metadata.unwrap().value, 3);
+    assert_ne!(std::fs::read(dir.path().join(id.to_string()))?[0], b'{');
+    assert_eq!(job.load(old.id)?.id, old.id);
+    assert_eq!(json.load(id)?.id, id);
+    Ok(())
+}
+
+#[cfg(feature = "bincode")]
+#[tokio::test]
+async fn test_bincode() -> std::io::Result<()> {
+    roundtrip(Encoding::Bincode).await
+}
+
+#[cfg(feature = "msgpack")]
+#[tokio::test]
+async fn test_msgpack() -> std::io::Result<()> {
+    roundtrip(Encoding::MessagePack).await
+}
+
+#[cfg(feature = "cbor")]
+#[tokio::test]
+async fn test_cbor() -> std::io::Result<()> {
+    roundtrip(Encoding::Cbor).await
+}
+
+#[cfg(not(feature = "cbor"))]
+#[test]
+fn test_disabled_encoding() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into()).with_encoding(Encoding::Cbor);
+    let err = job.save(&JobInfo::new()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+    Ok(())
+}
+
+#[test]
+fn test_atomic_saves() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into()).with_sync(true);
+    let mut info = JobInfo::new();
+    job.save(&info)?;
+    info.status = StatusType::Finished;
+    job.save(&info)?;
+    assert_eq!(job.load(info.id)?.status, StatusType::Finished);
+
+    // A write interrupted by a crash leaves only a temporary file behind.
+    std::fs::write(dir.path().join(format!(".{}.0.tmp", info.id)), b"{\"id")?;
+    assert_eq!(job.load(info.id)?.status, StatusType::Finished);
+    assert_eq!(job.ids()?, vec![info.id]);
+    let files = std::fs::read_dir(dir.path())?
+        .filter(|e| {
+            let name = e.as_ref().unwrap().file_name();
+            name.to_string_lossy().ends_with(".tmp")
+        })
+        .count();
+    assert_eq!(files, 1);
+    Ok(())
+}
+
+#[test]
+fn test_locking() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into()).with_locking(true);
+    let info = JobInfo::new();
+    job.save(&info)?;
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            // Separate instances, as in separate processes.
+            let job: FSJob<u16, MyError, MyMetadata, u32> =
+                FSJob::new(dir.path().into()).with_locking(true);
+            std::thread::spawn(move || {
+                for _ in 0..10 {
+                    job.update_metadata(info.id, |m| {
+                        m.get_or_insert_with(Default::default).value += 1
+                    })
+                    .unwrap();
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(job.load(info.id)?.metadata.unwrap().value, 40);
+    assert!(dir.path().join(format!(".{}.lock", info.id)).exists());
+    assert_eq!(job.ids()?, vec![info.id]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_subdirectories() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let flat: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let old = JobInfo::new();
+    flat.save(&old)?;
+
+    let job = flat.clone().with_subdirectories(true);
+    let id = job
+        .submit(|_, _, _| async { Ok(1) }, Default::default())?
+        .id();
+    wait(id, &job).await?;
+    let hex = id.simple().to_string();
+    let nested = dir.path().join(&hex[..2]).join(&hex[2..4]);
+    assert!(nested.join(id.to_string()).exists());
+    assert!(!dir.path().join(id.to_string()).exists());
+
+    // Both layouts are readable, and saving moves a job to the new one.
+    assert_eq!(job.load(old.id)?.id, old.id);
+    assert_eq!(flat.load(id)?.id, id);
+    let mut ids = job.ids()?;
+    ids.sort();
+    let mut expected = vec![old.id, id];
+    expected.sort();
+    assert_eq!(ids, expected);
+    job.save(&old)?;
+    assert!(!dir.path().join(old.id.to_string()).exists());
+    assert_eq!(job.ids()?.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn test_init() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("a").join("b");
+    let job: FSJob<u16, MyError, MyMetadata, u32> = FSJob::init(path.clone())?;
+    assert!(path.is_dir());
+    assert_eq!(job.store_format()?, format::CURRENT);
+
+    let file = dir.path().join("file");
+    std::fs::write(&file, b"")?;
+    let err = FSJob::<u16, MyError, MyMetadata, u32>::init(file.clone())
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains(&file.display().to_string()));
+
+    std::fs::write(path.join(".format"), "999")?;
+    let err = FSJob::<u16, MyError, MyMetadata, u32>::init(path)
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_async_io() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into())
+            .with_locking(true)
+            .with_subdirectories(true)
+            .with_sync(true);
+    let mut info = JobInfo::new();
+    info.metadata = Some(MyMetadata { value: 4 });
+    job.save_async(&info).await?;
+    assert_eq!(job.load(info.id)?.metadata.unwrap().value, 4);
+    info.status = StatusType::Finished;
+    job.save(&info)?;
+    assert_eq!(job.load_async(info.id).await?.status, StatusType::Finished);
+    let err = job.load_async(uuid::Uuid::new_v4()).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_async_io_in_namespace() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into()).with_namespace("tenant-a");
+    let info = JobInfo::new();
+    job.save_async(&info).await?;
+    assert!(dir
+        .path()
+        .join("tenant-a")
+        .join(info.id.to_string())
+        .exists());
+    assert_eq!(job.load_async(info.id).await?.id, info.id);
+    Ok(())
+}
+
+/// Save a large job with `compression`, and load it back along with an
+/// uncompressed one, without configuring the compression for reading.
+#[cfg(any(feature = "zstd", feature = "gzip"))]
+fn compressed_roundtrip<C>(compression: C) -> std::io::Result<()>
+where
+    C: Compression + 'static,
+{
+    let dir = tempfile::tempdir()?;
+    let plain: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let old = JobInfo::new();
+    plain.save(&old)?;
+    let compressed = plain.clone().with_compression(compression);
+    let mut new = JobInfo::new();
+    new.idempotency_key = Some("x".repeat(10_000));
+    compressed.save(&new)?;
+    let size = std::fs::metadata(dir.path().join(new.id.to_string()))?.len();
+    assert!(size < 1_000);
+    assert_eq!(plain.load(new.id)?.idempotency_key, new.idempotency_key);
+    assert_eq!(compressed.load(old.id)?.id, old.id);
+    Ok(())
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_zstd() -> std::io::Result<()> {
+    compressed_roundtrip(simple_jobs::record::Zstd::default())
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_gzip() -> std::io::Result<()> {
+    compressed_roundtrip(simple_jobs::record::Gzip::default())
+}
+
+#[test]
+fn test_scan_skips_corrupt_jobs() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let mut events = simple_jobs::events::subscribe();
+    let mut good = JobInfo::new();
+    good.status = StatusType::Finished;
+    job.save(&good)?;
+    let corrupt = uuid::Uuid::new_v4();
+    std::fs::write(dir.path().join(corrupt.to_string()), b"{\"id\": ")?;
+
+    let listed = job.list()?;
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].id, good.id);
+    assert_eq!(job.counts()?.get("finished"), Some(&1));
+    let unreadable = std::iter::from_fn(|| events.try_recv().ok())
+        .any(|e| matches!(e, JobEvent::Unreadable { id, .. } if id == corrupt));
+    assert!(unreadable);
+
+    // Corrupt jobs can still be removed by id.
+    job.remove(corrupt)?;
+    assert_eq!(job.ids()?, vec![good.id]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_index() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into()).with_index(true);
+    let id = job
+        .submit(|_, _, _| async { Ok(1) }, Default::default())?
+        .id();
+    wait(id, &job).await?;
+    let mut running = JobInfo::new();
+    running.status = StatusType::StatusValue(3);
+    job.save(&running)?;
+
+    let index = job.index()?;
+    assert_eq!(index.len(), 2);
+    assert_eq!(index[0].id, id);
+    assert_eq!(index[0].status, StatusType::Finished);
+    assert!(index[0].finished_at.is_some());
+    assert_eq!(index[1].status, StatusType::StatusValue(3.into()));
+
+    // Counts come from the index, not from the job files.
+    std::fs::remove_file(dir.path().join(id.to_string()))?;
+    assert_eq!(job.counts()?.get("finished"), Some(&1));
+    job.rebuild_index()?;
+    assert_eq!(job.counts()?.get("finished"), None);
+
+    job.remove(running.id)?;
+    assert!(job.index()?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_history() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into()).with_history(true);
+    let id = job
+        .submit(
+            |id, job: FSJob<u16, MyError, MyMetadata, u32>, _| async move {
+                for step in [1, 1, 2] {
+                    job.set_status(id, StatusType::StatusValue(step)).unwrap();
+                }
+                Ok(1)
+            },
+            Default::default(),
+        )?
+        .id();
+    wait(id, &job).await?;
+    let history = job.history(id)?;
+    let statuses: Vec<_> = history.iter().map(|c| c.status.clone()).collect();
+    assert_eq!(
+        statuses,
+        [
+            StatusType::Started,
+            StatusType::StatusValue(1),
+            StatusType::StatusValue(2),
+            StatusType::Finished
+        ]
+    );
+    assert!(history.windows(2).all(|w| w[0].at <= w[1].at));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_wait_for_another_process() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let mut info = JobInfo::new();
+    job.save(&info)?;
+    let other = job.clone();
+    let id = info.id;
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        info.status = StatusType::Finished;
+        other.save(&info).unwrap();
+    });
+    let started = std::time::Instant::now();
+    assert_eq!(wait(id, &job).await?.status, StatusType::Finished);
+    assert!(started.elapsed() < std::time::Duration::from_millis(900));
+    Ok(())
+}
+
+#[cfg(feature = "notify")]
+#[tokio::test]
+async fn test_changes_are_notified() -> std::io::Result<()> {
+    use futures::StreamExt;
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let info = JobInfo::new();
+    let mut changes = Box::pin(job.changes(info.id));
+    let quiet =
+        tokio::time::timeout(Duration::from_millis(300), changes.next());
+    assert!(quiet.await.is_err());
+    job.save(&info)?;
+    let changed =
+        tokio::time::timeout(Duration::from_millis(300), changes.next());
+    assert_eq!(changed.await?, Some(()));
+    Ok(())
+}
+
+#[test]
+fn test_checksums() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let quarantine = dir.path().join("quarantine");
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into())
+            .with_checksums(true)
+            .with_quarantine(quarantine.clone());
+    let info = JobInfo::new();
+    job.save(&info)?;
+    assert_eq!(job.load(info.id)?.id, info.id);
+
+    let path = dir.path().join(info.id.to_string());
+    let mut record = std::fs::read(&path)?;
+    let last = record.len() - 1;
+    record[last] ^= 1;
+    std::fs::write(&path, record)?;
+    let err = job.load(info.id).err().unwrap();
+    assert!(matches!(
+        JobError::from_io(&err),
+        Some(JobError::Corrupted { id: Some(id), .. }) if *id == info.id
+    ));
+    assert!(quarantine.join(info.id.to_string()).exists());
+    let err = job.load(info.id).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    Ok(())
+}
+
+#[test]
+fn test_truncated_record_is_corrupted() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let info = JobInfo::new();
+    job.save(&info)?;
+    let path = dir.path().join(info.id.to_string());
+    let record = std::fs::read(&path)?;
+    std::fs::write(&path, &record[..record.len() / 2])?;
+    let err = job.load(info.id).err().unwrap();
+    assert!(matches!(
+        JobError::from_io(&err),
+        Some(JobError::Corrupted { .. })
+    ));
+    // Without a quarantine directory, the file stays in place.
+    assert!(path.exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_durability() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    for durability in [
+        Durability::None,
+        Durability::Flush,
+        Durability::Fsync,
+        Durability::FsyncDir,
+    ] {
+        let job: FSJob<u16, MyError, MyMetadata, u32> =
+            FSJob::new(dir.path().into())
+                .with_subdirectories(true)
+                .with_durability(durability);
+        let mut info = JobInfo::new();
+        job.save(&info)?;
+        info.status = StatusType::Finished;
+        job.save_async(&info).await?;
+        assert_eq!(job.load(info.id)?.status, StatusType::Finished);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_file_naming() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let plain: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let old = JobInfo::new();
+    plain.save(&old)?;
+
+    let naming = FileNaming::new()
+        .with_prefix("report-")
+        .with_extension(".json")
+        .with_date(true)
+        .with_status(true);
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into()).with_naming(naming);
+    let mut info = JobInfo::new();
+    info.created_at = Some("2024-05-01T12:00:00Z".parse().unwrap());
+    job.save(&info)?;
+    let name = format!("report-2024-05-01_{}.started.json", info.id);
+    assert!(dir.path().join(&name).exists());
+
+    info.status = StatusType::Finished;
+    job.save_async(&info).await?;
+    assert!(!dir.path().join(&name).exists());
+    let name = format!("report-2024-05-01_{}.finished.json", info.id);
+    assert!(dir.path().join(&name).exists());
+    assert_eq!(job.load(info.id)?.status, StatusType::Finished);
+    assert_eq!(job.load_async(info.id).await?.status, StatusType::Finished);
+
+    // Files written with the default naming are still found, and renamed
+    // when saved again.
+    let mut ids = job.ids()?;
+    ids.sort();
+    let mut expected = vec![old.id, info.id];
+    expected.sort();
+    assert_eq!(ids, expected);
+    job.save(&job.load(old.id)?)?;
+    assert!(!dir.path().join(old.id.to_string()).exists());
+
+    job.remove(info.id)?;
+    assert!(!dir.path().join(&name).exists());
+    assert_eq!(job.ids()?, vec![old.id]);
+    Ok(())
+}
+
+#[test]
+fn test_builder() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::builder(dir.path().join("jobs"))
+            .subdirectories(true)
+            .locking(true)
+            .durability(Durability::Fsync)
+            .naming(FileNaming::new().with_extension(".json"))
+            .build()?;
+    assert_eq!(job.store_format()?, format::CURRENT);
+    let info = JobInfo::new();
+    job.save(&info)?;
+    let hex = info.id.simple().to_string();
+    let path = dir
+        .path()
+        .join("jobs")
+        .join(&hex[..2])
+        .join(&hex[2..4])
+        .join(format!("{}.json", info.id));
+    assert!(path.exists());
+
+    let file = dir.path().join("file");
+    std::fs::write(&file, b"")?;
+    let built: std::io::Result<FSJob<u16, MyError, MyMetadata, u32>> =
+        FSJob::builder(file).build();
+    assert!(built.is_err());
+    Ok(())
+}
+
+#[cfg(feature = "bincode")]
+#[test]
+fn test_builder_rejects_unversioned_migrations() {
+    let dir = tempfile::tempdir().unwrap();
+    let schema = simple_jobs::Schema::new().migration(|_| Ok(()));
+    let built: std::io::Result<FSJob<u16, MyError, MyMetadata, u32>> =
+        FSJob::builder(dir.path().into())
+            .encoding(Encoding::Bincode)
+            .schema(schema)
+            .build();
+    let err = built.err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_read_only() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let mut info = JobInfo::new();
+    job.save(&info)?;
+
+    let reader: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::read_only(dir.path().into());
+    assert_eq!(reader.load(info.id)?.id, info.id);
+    assert_eq!(reader.list()?.len(), 1);
+    let read_only = |err: std::io::Error| {
+        assert_eq!(JobError::from_io(&err), Some(&JobError::ReadOnly));
+    };
+    info.status = StatusType::Finished;
+    read_only(reader.save(&info).err().unwrap());
+    read_only(reader.remove(info.id).err().unwrap());
+    read_only(
+        reader
+            .submit(|_, _, _| async { Ok(1u16) }, MyMetadata::default())
+            .err()
+            .unwrap(),
+    );
+    assert_eq!(job.load(info.id)?.status, StatusType::Started);
+    assert_eq!(job.ids()?, vec![info.id]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_failed_cancel_keeps_the_job_running() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let id = job
+        .submit(
+            |_, _, _| async {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                Ok(1u16)
+            },
+            MyMetadata::default(),
+        )?
+        .id();
+    let reader: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::read_only(dir.path().into());
+    let err = reader
+        .cancel(id, simple_jobs::CancelReason::UserAction)
+        .err()
+        .unwrap();
+    assert_eq!(JobError::from_io(&err), Some(&JobError::ReadOnly));
+    let timeout = std::time::Duration::from_secs(5);
+    let info = tokio::time::timeout(timeout, wait(id, &job))
+        .await
+        .expect("the job was aborted")?;
+    assert_eq!(info.status, StatusType::Finished);
+    assert_eq!(info.result.unwrap().unwrap(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_read_only_never_writes() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let missing: std::io::Result<FSJob<u16, MyError, MyMetadata, u32>> =
+        FSJob::builder(dir.path().join("missing"))
+            .read_only(true)
+            .build();
+    assert!(missing.is_err());
+    assert!(!dir.path().join("missing").exists());
+
+    let quarantine = dir.path().join("quarantine");
+    let reader: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::builder(dir.path().into())
+            .read_only(true)
+            .quarantine(quarantine.clone())
+            .build()?;
+    let corrupt = uuid::Uuid::new_v4();
+    std::fs::write(dir.path().join(corrupt.to_string()), b"{\"id\": ")?;
+    assert!(reader.load(corrupt).is_err());
+    assert!(!quarantine.exists());
+    assert!(!dir.path().join(".format").exists());
+    Ok(())
+}
+
+#[test]
+fn test_namespaces() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let root: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let staging: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into()).with_namespace("staging");
+    let production: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::builder(dir.path().into())
+            .namespace("production")
+            .subdirectories(true)
+            .build()?;
+    let a = JobInfo::new();
+    staging.save(&a)?;
+    let b = JobInfo::new();
+    production.save(&b)?;
+    assert!(dir.path().join("staging").join(a.id.to_string()).exists());
+    assert_eq!(staging.ids()?, vec![a.id]);
+    assert_eq!(production.ids()?, vec![b.id]);
+    assert!(root.ids()?.is_empty());
+    assert!(staging.load(b.id).is_err());
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "invalid namespace")]
+fn test_invalid_namespace() {
+    let _: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new("/tmp".into()).with_namespace("../other");
+}
+
+#[test]
+fn test_load_status_skips_result_and_metadata() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<String, MyError, String, u32> =
+        FSJob::new(dir.path().into());
+    let mut info = JobInfo::new();
+    info.metadata = Some("not a MyMetadata".to_string());
+    info.result = Some(Ok("not a u16".to_string()));
+    info.status = StatusType::StatusValue(7);
+    job.save(&info)?;
+
+    let other: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let status = other.load_status(info.id)?;
+    assert_eq!(status.id, info.id);
+    assert_eq!(status.status, StatusType::StatusValue(7));
+    assert_eq!(status.created_at, info.created_at);
+    assert!(other.load(info.id).is_err());
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use serde::{Deserialize, Serialize};
+use simple_jobs::{fs_job::FSJob, wait, Hooked, Job, JobHooks};
+use uuid::Uuid;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MyMetadata {
+    value: usize,
+}
+
+#[derive(Default)]
+struct Counters {
+    submitted: AtomicUsize,
+    started: AtomicUsize,
+    succeeded: AtomicUsize,
+    failed: AtomicUsize,
+    save_errors: AtomicUsize,
+}
+
+#[derive(Clone, Default)]
+struct CountingHooks(Arc<Counters>);
+
+impl JobHooks<u16, MyError> for CountingHooks {
+    fn on_submit(&self, _id: Uuid) {
+        self.0.submitted.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn on_start(&self, _id: Uuid) {
+        self.0.started.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn on_success(&self, _id: Uuid, _output: &u16) {
+        self.0.succeeded.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn on_failure(&self, _id: Uuid, _error: &MyError) {
+        self.0.failed.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn on_save_error(&self, _id: Uuid, _error: &std::io::Error) {
+        self.0.save_errors.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+type MyFSJob = FSJob<u16, MyError, MyMetadata, u32>;
+
+#[tokio::test]
+async fn test_hooks() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let hooks = CountingHooks::default();
+    let job =
+        Hooked::new(MyFSJob::new(dir.path().into())).with_hooks(hooks.clone());
+    let ok = job
+        .submit(|_, _, _| async { Ok(1u16) }, Default::default())?
+        .id();
+    let err = job
+        .submit(|_, _, _| async { Err(MyError {}) }, Default::default())?
+        .id();
+    wait(ok, &job).await?;
+    wait(err, &job).await?;
+    let counters = &hooks.0;
+    assert_eq!(counters.submitted.load(Ordering::SeqCst), 2);
+    assert_eq!(counters.started.load(Ordering::SeqCst), 2);
+    assert_eq!(counters.succeeded.load(Ordering::SeqCst), 1);
+    assert_eq!(counters.failed.load(Ordering::SeqCst), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_hooks_on_save_error() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let hooks = CountingHooks::default();
+    let job = Hooked::new(MyFSJob::new(dir.path().join("missing")))
+        .with_hooks(hooks.clone());
+    assert!(job
+        .submit(|_, _, _| async { Ok(1u16) }, Default::default())
+        .is_err());
+    assert_eq!(hooks.0.save_errors.load(Ordering::SeqCst), 1);
+    assert_eq!(hooks.0.submitted.load(Ordering::SeqCst), 0);
+    Ok(())
+}
@@ -0,0 +1,315 @@
+#![cfg(feature = "http")]
+
+use std::io::Read;
+
+use axum::{
+    body::Body,
+    http::{header, Request, Response, StatusCode},
+    Router,
+};
+use serde_json::{json, Value};
+use simple_jobs::{
+    describe::Catalog,
+    error::SerializableError,
+    registry::{JobRegistry, Payload},
+    wait, FSJob, Job,
+};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+type MyFSJob = FSJob<Value, SerializableError, Payload, ()>;
+
+async fn call(
+    app: &Router,
+    method: &str,
+    uri: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json");
+    let body = body.map_or(Body::empty(), |body| Body::from(body.to_string()));
+    let response = app
+        .clone()
+        .oneshot(request.body(body).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+    (status, body)
+}
+
+async fn get(
+    app: &Router,
+    uri: &str,
+    headers: &[(&str, &str)],
+) -> Response<Body> {
+    let mut request = Request::builder().uri(uri);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    app.clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+async fn bytes_of(response: Response<Body>) -> Vec<u8> {
+    axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap()
+        .to_vec()
+}
+
+fn id_of(body: &Value) -> Uuid {
+    body["id"].as_str().unwrap().parse().unwrap()
+}
+
+#[tokio::test]
+async fn test_http() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let mut registry = JobRegistry::new(job.clone());
+    registry.register("double", |_, _, n: u32| async move { Ok(2 * n) });
+    let app = simple_jobs::http::router(registry);
+
+    let submission = json!({ "handler": "double", "payload": 21 });
+    let (status, body) = call(&app, "POST", "/jobs", Some(submission)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = id_of(&body);
+    wait(id, &job).await?;
+
+    let (status, body) = call(&app, "GET", &format!("/jobs/{id}"), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["result"], json!({ "Ok": 42 }));
+
+    let (status, body) =
+        call(&app, "POST", &format!("/jobs/{id}/retry"), None).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let retried = id_of(&body);
+    wait(retried, &job).await?;
+
+    let (_, body) = call(&app, "GET", "/jobs?status=finished", None).await;
+    let ids: Vec<_> = body.as_array().unwrap().iter().map(id_of).collect();
+    assert_eq!(ids.len(), 2);
+    assert!(ids.contains(&id) && ids.contains(&retried));
+    let (_, body) = call(&app, "GET", "/jobs?handler=other", None).await;
+    assert_eq!(body, json!([]));
+    let (_, body) = call(&app, "GET", "/jobs?limit=1", None).await;
+    assert_eq!(body.as_array().unwrap().len(), 1);
+
+    let uri = format!("/jobs/{id}/cancel");
+    let (status, body) = call(&app, "POST", &uri, None).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(body["error"].is_string());
+
+    let uri = format!("/jobs/{id}");
+    let (status, _) = call(&app, "DELETE", &uri, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = call(&app, "GET", &uri, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let submission = json!({ "handler": "missing", "payload": 1 });
+    let (status, _) = call(&app, "POST", "/jobs", Some(submission)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_cancel() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let registry = JobRegistry::new(job.clone());
+    let id = registry.enqueue("slow", &1, &Default::default())?;
+    let app = simple_jobs::http::router(registry);
+    let uri = format!("/jobs/{id}/cancel");
+    let (status, _) = call(&app, "POST", &uri, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, body) = call(&app, "GET", "/jobs?status=canceled", None).await;
+    assert_eq!(body.as_array().unwrap().len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_etag() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let registry = JobRegistry::new(job.clone());
+    let id = registry.enqueue("slow", &1, &Default::default())?;
+    let app = simple_jobs::http::router(registry);
+    let uri = format!("/jobs/{id}");
+
+    let response = get(&app, &uri, &[]).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = response.headers()[header::ETAG]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let response = get(&app, &uri, &[("if-none-match", &etag)]).await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert!(bytes_of(response).await.is_empty());
+    let weak = format!("\"other\", W/{etag}");
+    let response = get(&app, &uri, &[("if-none-match", &weak)]).await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+    // Even a save that doesn't bump the version changes the tag.
+    let mut info = job.load(id)?;
+    info.priority = 5;
+    job.save(&info)?;
+    let response = get(&app, &uri, &[("if-none-match", &etag)]).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_ne!(response.headers()[header::ETAG], etag.as_str());
+
+    let response = get(&app, "/jobs", &[]).await;
+    let etag = response.headers()[header::ETAG]
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert!(etag.starts_with("W/"));
+    let response = get(&app, "/jobs", &[("if-none-match", &etag)]).await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+    // A change of status or a new job changes the tag of the listing.
+    let (status, _) = call(&app, "POST", &format!("{uri}/cancel"), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let response = get(&app, "/jobs", &[("if-none-match", &etag)]).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = response.headers()[header::ETAG]
+        .to_str()
+        .unwrap()
+        .to_string();
+    JobRegistry::new(job).enqueue("slow", &2, &Default::default())?;
+    let response = get(&app, "/jobs", &[("if-none-match", &etag)]).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_gzip() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let registry = JobRegistry::new(job);
+    let first = registry.enqueue("slow", &1, &Default::default())?;
+    let app = simple_jobs::http::router(registry.clone());
+    let gzip = [("accept-encoding", "deflate, gzip;q=0.8")];
+
+    // Small responses aren't worth compressing.
+    let response = get(&app, &format!("/jobs/{first}"), &gzip).await;
+    assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+
+    for n in 0..20 {
+        registry.enqueue("slow", &n, &Default::default())?;
+    }
+    let plain = bytes_of(get(&app, "/jobs", &[]).await).await;
+    assert!(plain.len() >= simple_jobs::http::COMPRESSION_THRESHOLD);
+    let response = get(&app, "/jobs", &gzip).await;
+    assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+    assert_eq!(response.headers()[header::VARY], "accept-encoding");
+    assert!(response.headers()[header::ETAG]
+        .to_str()
+        .unwrap()
+        .starts_with("W/"));
+    let compressed = bytes_of(response).await;
+    assert!(compressed.len() < plain.len());
+    let mut decompressed = vec![];
+    flate2::read::GzDecoder::new(compressed.as_slice())
+        .read_to_end(&mut decompressed)?;
+    assert_eq!(decompressed, plain);
+
+    let refused = [("accept-encoding", "gzip;q=0")];
+    let response = get(&app, "/jobs", &refused).await;
+    assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_statuses() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let registry = JobRegistry::new(job);
+    let first = registry.enqueue("slow", &1, &Default::default())?;
+    let second = registry.enqueue("slow", &2, &Default::default())?;
+    let app = simple_jobs::http::router(registry);
+    let missing = Uuid::new_v4();
+
+    let ids = json!({ "ids": [second, missing, first] });
+    let (status, body) = call(&app, "POST", "/jobs/status", Some(ids)).await;
+    assert_eq!(status, StatusCode::OK);
+    let statuses = body.as_array().unwrap();
+    assert_eq!(statuses.len(), 3);
+    assert_eq!(id_of(&statuses[0]), second);
+    assert_eq!(statuses[0]["status"], json!("Pending"));
+    assert!(statuses[0].get("metadata").is_none());
+    assert_eq!(id_of(&statuses[1]), missing);
+    assert!(statuses[1]["error"].is_string());
+    assert_eq!(id_of(&statuses[2]), first);
+
+    let max = simple_jobs::http::MAX_STATUS_IDS;
+    let ids = json!({ "ids": vec![first; max + 1] });
+    let (status, _) = call(&app, "POST", "/jobs/status", Some(ids)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_catalog() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let registry = JobRegistry::new(job.clone());
+    let catalog = Catalog::new("en")
+        .with_message("en", "status.pending", "Waiting to start")
+        .with_message("es", "status.pending", "En espera");
+    let app = simple_jobs::http::router_with_catalog(registry, catalog);
+    let id = JobRegistry::new(job).enqueue("slow", &2, &Default::default())?;
+
+    let uri = format!("/jobs/{id}");
+    let response =
+        get(&app, &uri, &[("accept-language", "es-ES, en;q=0.5")]).await;
+    assert_eq!(response.headers()[header::VARY], "accept-language");
+    let body: Value = serde_json::from_slice(&bytes_of(response).await)?;
+    assert_eq!(body["description"], "En espera");
+    let response = get(&app, &uri, &[]).await;
+    let english = response.headers()[header::ETAG].clone();
+    let body: Value = serde_json::from_slice(&bytes_of(response).await)?;
+    assert_eq!(body["description"], "Waiting to start");
+
+    let headers = [
+        ("accept-language", "es"),
+        ("if-none-match", english.to_str().unwrap()),
+    ];
+    assert_eq!(get(&app, &uri, &headers).await.status(), StatusCode::OK);
+
+    let response = get(&app, "/jobs", &[("accept-language", "es")]).await;
+    let body: Value = serde_json::from_slice(&bytes_of(response).await)?;
+    assert_eq!(body[0]["description"], "En espera");
+    let ids = json!({ "ids": [id] });
+    let (_, body) = call(&app, "POST", "/jobs/status", Some(ids)).await;
+    assert_eq!(body[0]["description"], "Waiting to start");
+
+    let (status, body) = call(&app, "GET", "/dashboard", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status_counts"]["pending"], 1);
+    assert!(body["oldest_pending_age"].as_f64().is_some());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_without_catalog() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let id = JobRegistry::new(job.clone()).enqueue(
+        "slow",
+        &2,
+        &Default::default(),
+    )?;
+    let app = simple_jobs::http::router(JobRegistry::new(job));
+    let response = get(&app, &format!("/jobs/{id}"), &[]).await;
+    assert!(response.headers().get(header::VARY).is_none());
+    let body: Value = serde_json::from_slice(&bytes_of(response).await)?;
+    assert!(body.get("description").is_none());
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    fs_job::FSJob,
+    ids::{from_key, TimeOrderedIds, WithIds},
+    wait, Job,
+};
+use uuid::Uuid;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MyMetadata {
+    value: usize,
+}
+
+type MyFSJob = FSJob<u16, MyError, MyMetadata, u32>;
+
+#[tokio::test]
+async fn test_time_ordered_ids() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job = WithIds::new(MyFSJob::new(dir.path().into()), TimeOrderedIds);
+    let mut ids = vec![];
+    for _ in 0..3 {
+        let id = job
+            .submit(|_, _, _| async { Ok(1) }, Default::default())?
+            .id();
+        assert_eq!(id.get_version_num(), 7);
+        ids.push(id);
+        tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+    }
+    let mut sorted = ids.clone();
+    sorted.sort();
+    assert_eq!(ids, sorted);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_submit_with_id() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job = MyFSJob::new(dir.path().into());
+    let namespace = Uuid::new_v4();
+    let id = from_key(&namespace, "order-1234");
+    assert_eq!(id, from_key(&namespace, "order-1234"));
+    assert_ne!(id, from_key(&namespace, "order-1235"));
+
+    let handle =
+        job.submit_with_id(id, |_, _, _| async { Ok(1) }, Default::default())?;
+    assert_eq!(handle.id(), id);
+    wait(id, &job).await?;
+    let err = job
+        .submit_with_id(id, |_, _, _| async { Ok(2) }, Default::default())
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+    assert!(matches!(job.load(id)?.result, Some(Ok(1))));
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    fs_job::FSJob,
+    ingest::{Ingest, Submission},
+    wait, Job, JobHandle,
+};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MyMetadata {
+    value: usize,
+}
+
+type MyFSJob = FSJob<u16, MyError, MyMetadata, u32>;
+
+fn double(
+    job: &MyFSJob,
+    submission: Submission,
+) -> std::io::Result<JobHandle<MyFSJob>> {
+    let value = submission
+        .field("value")
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| std::io::Error::other("missing value"))?;
+    job.submit(
+        |_, _, m| async move { Ok(2 * m.value as u16) },
+        MyMetadata { value },
+    )
+}
+
+#[tokio::test]
+async fn test_ingest() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job = MyFSJob::new(dir.path().into());
+    let ingest = Ingest::new(job.clone()).with_handler("double", double);
+    let id =
+        ingest.submit(Submission::new("double").with_field("value", "21"))?;
+    let info = wait(id, &job).await?;
+    assert!(matches!(info.result, Some(Ok(42))));
+    assert!(ingest.submit(Submission::new("double")).is_err());
+    let err = ingest.submit(Submission::new("triple")).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    Ok(())
+}
+
+#[cfg(feature = "form")]
+#[test]
+fn test_form() {
+    let submission = Submission::from_form("double", b"value=21&note=a+b%21");
+    assert_eq!(submission.handler, "double");
+    assert_eq!(submission.field("value"), Some("21"));
+    assert_eq!(submission.field("note"), Some("a b!"));
+}
+
+#[cfg(feature = "email")]
+#[test]
+fn test_email() -> std::io::Result<()> {
+    let raw = b"From: someone@example.com\r\n\
+        Subject: double please\r\n\
+        Value: 21\r\n\
+        \r\n\
+        Thanks!\r\n";
+    let submission = Submission::from_email(raw)?;
+    assert_eq!(submission.handler, "double");
+    assert_eq!(submission.field("from"), Some("someone@example.com"));
+    assert_eq!(submission.field("value"), Some("21"));
+    assert_eq!(submission.body.trim(), "Thanks!");
+    Ok(())
+}
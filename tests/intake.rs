@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+use simple_jobs::{fs_job::FSJob, intake::DropDirectory, wait, Job};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MyMetadata {
+    value: usize,
+}
+
+type MyFSJob = FSJob<u16, MyError, MyMetadata, u32>;
+
+fn length(
+    job: &MyFSJob,
+    args: Vec<u8>,
+) -> std::io::Result<simple_jobs::JobHandle<MyFSJob>> {
+    let metadata = MyMetadata { value: args.len() };
+    job.submit(|_, _, m| async move { Ok(m.value as u16) }, metadata)
+}
+
+#[tokio::test]
+async fn test_drop_directory() -> std::io::Result<()> {
+    let jobs = tempfile::tempdir()?;
+    let inbox = tempfile::tempdir()?;
+    let job = MyFSJob::new(jobs.path().into());
+    let intake = DropDirectory::new(job.clone(), inbox.path().into())?
+        .with_handler("length", length)
+        .with_handler("broken", |_: &MyFSJob, _| {
+            Err(std::io::Error::other("bad arguments"))
+        });
+    std::fs::write(inbox.path().join("length.1.txt"), "hello")?;
+    std::fs::write(inbox.path().join("broken.txt"), "hello")?;
+    std::fs::write(inbox.path().join("unknown.txt"), "hello")?;
+    std::fs::write(inbox.path().join(".length.partial"), "hello")?;
+
+    let intakes = intake.scan()?;
+    assert_eq!(intakes.len(), 3);
+    let submitted = intakes
+        .iter()
+        .find(|i| i.file_name == "length.1.txt")
+        .unwrap();
+    let info = wait(*submitted.result.as_ref().unwrap(), &job).await?;
+    assert!(matches!(info.result, Some(Ok(5))));
+    assert!(intakes
+        .iter()
+        .filter(|i| i.file_name != "length.1.txt")
+        .all(|i| i.result.is_err()));
+
+    assert!(intake.processed_dir().join("length.1.txt").exists());
+    assert!(intake.failed_dir().join("broken.txt").exists());
+    assert!(intake.failed_dir().join("unknown.txt").exists());
+    assert!(inbox.path().join(".length.partial").exists());
+    assert!(intake.scan()?.is_empty());
+    Ok(())
+}
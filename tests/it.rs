@@ -0,0 +1,213 @@
+//! Integration tests against real worker processes and services.
+//!
+//! Run with `cargo test --features it --test it`.  The workers are this test
+//! binary, started again to run the ignored test [`worker`].  The services
+//! run in containers, so their tests are ignored unless Docker is available
+//! and `-- --include-ignored` is given.
+#![cfg(feature = "it")]
+
+use std::{path::Path, process::Stdio, time::Duration};
+
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    publish, queue::Failure, worker, FSJob, Job, QueueConfig, StatusType,
+    WithQueues,
+};
+use testcontainers::{
+    core::{IntoContainerPort, WaitFor},
+    runners::AsyncRunner,
+    GenericImage,
+};
+use tokio::process::{Child, Command};
+use uuid::Uuid;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+type MyFSJob = WithQueues<FSJob<u16, MyError, u16, ()>>;
+
+/// The variable giving its job directory to a worker process.
+const JOBS_DIR: &str = "SIMPLE_JOBS_IT_JOBS";
+/// The variable giving a worker process the directory where it records its
+/// runs.
+const RUNS_DIR: &str = "SIMPLE_JOBS_IT_RUNS";
+/// The variable making a worker process hang in the first job it runs, as
+/// if it crashed.
+const HANG: &str = "SIMPLE_JOBS_IT_HANG";
+
+/// How long a test waits for the workers.
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The backend shared by the test and its workers, offering the jobs of
+/// crashed workers again after a second.
+fn backend(dir: &Path) -> MyFSJob {
+    let config =
+        QueueConfig::new().with_visibility_timeout(Duration::from_secs(1));
+    WithQueues::new(FSJob::new(dir.into()).with_locking(true), config)
+}
+
+/// Start a worker process on the jobs of `jobs`, recording its runs in
+/// `runs`.
+fn spawn_worker(
+    jobs: &Path,
+    runs: &Path,
+    hang: bool,
+) -> std::io::Result<Child> {
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .args(["worker", "--exact", "--ignored", "--quiet"])
+        .env(JOBS_DIR, jobs)
+        .env(RUNS_DIR, runs)
+        .stdout(Stdio::null())
+        .kill_on_drop(true);
+    if hang {
+        command.env(HANG, "1");
+    }
+    command.spawn()
+}
+
+/// Wait for a worker process to claim every job and exit.
+async fn finished(mut worker: Child) -> std::io::Result<()> {
+    let status = tokio::time::timeout(TIMEOUT, worker.wait())
+        .await
+        .expect("the worker hangs")?;
+    assert!(status.success());
+    Ok(())
+}
+
+/// The number of times the job `id` ran, in any worker.
+fn runs_of(runs: &Path, id: Uuid) -> std::io::Result<usize> {
+    let prefix = id.to_string();
+    Ok(std::fs::read_dir(runs)?
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry.file_name().to_string_lossy().starts_with(&prefix)
+        })
+        .count())
+}
+
+/// Run as a worker process by the other tests, claiming the jobs of the
+/// backend until they have all ended.
+#[tokio::test]
+#[ignore = "run by the other tests in worker processes"]
+async fn worker() -> std::io::Result<()> {
+    let (Ok(jobs), Ok(runs)) =
+        (std::env::var(JOBS_DIR), std::env::var(RUNS_DIR))
+    else {
+        return Ok(());
+    };
+    let pid = std::process::id();
+    worker::set_label(format!("worker-{pid}"));
+    let hang = std::env::var(HANG).is_ok();
+    let job = backend(jobs.as_ref());
+    loop {
+        let runs = runs.clone();
+        let run = move |id: Uuid, _, n| async move {
+            std::fs::File::create_new(
+                Path::new(&runs).join(format!("{id}-{pid}")),
+            )
+            .unwrap();
+            if hang {
+                futures::future::pending::<()>().await;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            Ok(n)
+        };
+        match job.claim_next(run)? {
+            Some(handle) => {
+                handle.result().await?;
+            }
+            None if job.scan()?.all(|info| info.status.is_terminal()) => {
+                return Ok(());
+            }
+            None => tokio::time::sleep(Duration::from_millis(20)).await,
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_workers_run_each_job_once() -> std::io::Result<()> {
+    let jobs = tempfile::tempdir()?;
+    let runs = tempfile::tempdir()?;
+    let job = backend(jobs.path());
+    let ids = (0..40)
+        .map(|n| job.enqueue(n))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    let workers = (0..4)
+        .map(|_| spawn_worker(jobs.path(), runs.path(), false))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    for worker in workers {
+        finished(worker).await?;
+    }
+    for (n, id) in ids.into_iter().enumerate() {
+        let info = job.load(id)?;
+        assert_eq!(info.status, StatusType::Finished);
+        assert_eq!(info.result.unwrap().unwrap(), n as u16);
+        assert!(info.attempts.is_empty());
+        assert_eq!(runs_of(runs.path(), id)?, 1);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_crashed_worker_loses_its_lease() -> std::io::Result<()> {
+    let jobs = tempfile::tempdir()?;
+    let runs = tempfile::tempdir()?;
+    let job = backend(jobs.path());
+    let id = job.enqueue(7)?;
+    let mut crashed = spawn_worker(jobs.path(), runs.path(), true)?;
+    let label = format!("worker-{}", crashed.id().unwrap());
+    tokio::time::timeout(TIMEOUT, async {
+        while runs_of(runs.path(), id).unwrap() == 0 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("the job never ran");
+    crashed.kill().await?;
+
+    finished(spawn_worker(jobs.path(), runs.path(), false)?).await?;
+    let info = job.load(id)?;
+    assert_eq!(info.status, StatusType::Finished);
+    assert_eq!(info.result.unwrap().unwrap(), 7);
+    assert_eq!(info.attempts.len(), 1);
+    assert!(matches!(info.attempts[0].failure, Failure::LeaseExpired));
+    assert_eq!(info.attempts[0].worker.as_ref(), Some(&label));
+    assert_eq!(runs_of(runs.path(), id)?, 2);
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn test_events_reach_a_nats_server() -> std::io::Result<()> {
+    let nats = GenericImage::new("nats", "2.10")
+        .with_exposed_port(4222.tcp())
+        .with_wait_for(WaitFor::message_on_either_std("Server is ready"))
+        .start()
+        .await
+        .expect("cannot start NATS; is Docker running?");
+    let host = nats.get_host().await.unwrap();
+    let port = nats.get_host_port_ipv4(4222).await.unwrap();
+    let client = async_nats::connect(format!("{host}:{port}")).await.unwrap();
+    let mut messages = client.subscribe("jobs.>").await.unwrap();
+    tokio::spawn(publish::to_nats(client, "jobs"));
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, u16, ()> = FSJob::new(dir.path().into());
+    let id = job.enqueue(1)?.to_string();
+    // The other tests of the process publish events too.
+    let message = tokio::time::timeout(TIMEOUT, async {
+        loop {
+            let message = messages.next().await.expect("NATS disconnected");
+            if String::from_utf8_lossy(&message.payload).contains(&id) {
+                return message;
+            }
+        }
+    })
+    .await
+    .expect("missing message");
+    assert_eq!(message.subject.as_str(), "jobs.enqueued");
+    Ok(())
+}
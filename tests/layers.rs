@@ -0,0 +1,133 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    fs_job::FSJob,
+    layers::{
+        ConcurrencyLimitLayer, JobFuture, Layered, RateLimitLayer, TimeoutLayer,
+    },
+    wait, Job,
+};
+use uuid::Uuid;
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+enum MyError {
+    Timeout,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MyMetadata {
+    value: usize,
+}
+
+type MyFSJob = FSJob<u16, MyError, MyMetadata, u32>;
+
+#[tokio::test]
+async fn test_timeout_layer() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job =
+        Layered::new(MyFSJob::new(dir.path().into()))
+            .layer(TimeoutLayer::new(Duration::from_millis(50), || {
+                MyError::Timeout
+            }));
+    let slow = job
+        .submit(
+            |_, _, _| async {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                Ok(1u16)
+            },
+            Default::default(),
+        )?
+        .id();
+    let fast = job
+        .submit(|_, _, _| async { Ok(2u16) }, Default::default())?
+        .id();
+    assert_eq!(wait(slow, &job).await?.result, Some(Err(MyError::Timeout)));
+    assert_eq!(wait(fast, &job).await?.result, Some(Ok(2u16)));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_layers_order_and_limit() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let trace = Arc::new(Mutex::new(vec![]));
+    let tracer = |name: &'static str| {
+        let trace = trace.clone();
+        move |_id: Uuid,
+              fut: JobFuture<u16, MyError>|
+              -> JobFuture<u16, MyError> {
+            let trace = trace.clone();
+            Box::pin(async move {
+                trace.lock().unwrap().push(name);
+                fut.await
+            })
+        }
+    };
+    let job = Layered::new(MyFSJob::new(dir.path().into()))
+        .layer(tracer("outer"))
+        .layer(ConcurrencyLimitLayer::new(1))
+        .layer(tracer("inner"));
+    let id = job
+        .submit(|_, _, _| async { Ok(1u16) }, Default::default())?
+        .id();
+    wait(id, &job).await?;
+    assert_eq!(*trace.lock().unwrap(), vec!["outer", "inner"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rate_limit_layer() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job = Layered::new(MyFSJob::new(dir.path().into()))
+        .layer(RateLimitLayer::new(10, Duration::from_secs(1)));
+    let start = tokio::time::Instant::now();
+    let mut ids = vec![];
+    for _ in 0..4 {
+        ids.push(
+            job.submit(|_, _, _| async { Ok(1u16) }, Default::default())?
+                .id(),
+        );
+    }
+    for id in ids {
+        wait(id, &job).await?;
+    }
+    assert!(start.elapsed() >= Duration::from_millis(300));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_keyed_rate_limit_layer() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job = Layered::new(MyFSJob::new(dir.path().into())).layer(
+        RateLimitLayer::keyed(
+            1,
+            Duration::from_millis(500),
+            |m: &MyMetadata| m.value.to_string(),
+        ),
+    );
+    let started = Arc::new(Mutex::new(vec![]));
+    let start = tokio::time::Instant::now();
+    let mut ids = vec![];
+    for value in [1, 2, 1] {
+        let started = started.clone();
+        let f = move |_, _, _| async move {
+            started.lock().unwrap().push((value, start.elapsed()));
+            Ok(1u16)
+        };
+        ids.push(job.submit(f, MyMetadata { value })?.id());
+    }
+    for id in ids {
+        wait(id, &job).await?;
+    }
+    let started = started.lock().unwrap();
+    let late: Vec<_> = started
+        .iter()
+        .filter(|(_, at)| *at >= Duration::from_millis(400))
+        .map(|(value, _)| *value)
+        .collect();
+    assert_eq!(late, vec![1]);
+    Ok(())
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "metrics")]
+
+use std::time::Duration;
+
+use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+use serde::{Deserialize, Serialize};
+use simple_jobs::{queue::EnqueueOptions, wait, FSJob, Job};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[tokio::test]
+async fn test_metrics() -> std::io::Result<()> {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    recorder.install().unwrap();
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, u16, ()> = FSJob::new(dir.path().into());
+    tokio::spawn(simple_jobs::metrics::record(
+        job.clone(),
+        Duration::from_millis(10),
+    ));
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let run = |_, _, n: u16| async move {
+        match n {
+            0 => Err(MyError {}),
+            n => Ok(n),
+        }
+    };
+    let ok = job.submit(run, 1)?.id();
+    let failed = job.submit(run, 0)?.id();
+    wait(ok, &job).await?;
+    wait(failed, &job).await?;
+    let options = EnqueueOptions::new().queue("metrics");
+    job.enqueue_with(1, &options)?;
+    job.enqueue_with(2, &options)?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let metrics: Vec<_> = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .map(|(key, _, _, value)| (key.key().name().to_string(), value))
+        .collect();
+    let value = |name: &str| {
+        metrics
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value)
+    };
+    assert_eq!(
+        value("simple_jobs_submitted_total"),
+        Some(&DebugValue::Counter(4))
+    );
+    assert_eq!(
+        value("simple_jobs_succeeded_total"),
+        Some(&DebugValue::Counter(1))
+    );
+    assert_eq!(
+        value("simple_jobs_failed_total"),
+        Some(&DebugValue::Counter(1))
+    );
+    assert!(matches!(
+        value("simple_jobs_run_duration_seconds"),
+        Some(DebugValue::Histogram(durations)) if durations.len() == 2
+    ));
+    assert!(matches!(
+        value("simple_jobs_queue_depth"),
+        Some(DebugValue::Gauge(depth)) if depth.into_inner() == 2.0
+    ));
+    Ok(())
+}
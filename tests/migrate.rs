@@ -0,0 +1,62 @@
+use std::cell::RefCell;
+
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    fs_job::FSJob,
+    migrate::{migrate, Migration},
+    Job, JobInfo, StatusType,
+};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MyMetadata {
+    value: usize,
+}
+
+type MyFSJob = FSJob<u16, MyError, MyMetadata, u32>;
+
+#[test]
+fn test_migrate() -> std::io::Result<()> {
+    let from_dir = tempfile::tempdir()?;
+    let from: MyFSJob = FSJob::new(from_dir.path().into());
+    let to_dir = tempfile::tempdir()?;
+    let to: MyFSJob =
+        FSJob::new(to_dir.path().into()).with_subdirectories(true);
+    let mut finished = JobInfo::new();
+    finished.status = StatusType::Finished;
+    from.save(&finished)?;
+    from.append_output(finished.id, &serde_json::json!("item"))?;
+    let running = JobInfo::new();
+    from.save(&running)?;
+    let corrupt = uuid::Uuid::new_v4();
+    std::fs::write(from_dir.path().join(corrupt.to_string()), b"{")?;
+
+    let progress = RefCell::new(vec![]);
+    let report = Migration::new(&from, &to)
+        .filter(|info| info.status.is_terminal())
+        .dry_run(true)
+        .on_progress(|p| progress.borrow_mut().push((p.done, p.total)))
+        .run()?;
+    assert_eq!(report.copied, vec![finished.id]);
+    assert_eq!(report.filtered, vec![running.id]);
+    assert_eq!(report.unreadable.len(), 1);
+    assert_eq!(report.unreadable[0].0, corrupt);
+    assert_eq!(*progress.borrow(), vec![(1, 3), (2, 3), (3, 3)]);
+    assert!(to.ids()?.is_empty());
+
+    let report = migrate(&from, &to, |_| true)?;
+    let mut copied = report.copied.clone();
+    copied.sort();
+    let mut expected = vec![finished.id, running.id];
+    expected.sort();
+    assert_eq!(copied, expected);
+    assert_eq!(to.load(finished.id)?.status, StatusType::Finished);
+    assert_eq!(to.outputs(finished.id, 0)?, vec![serde_json::json!("item")]);
+
+    let report = migrate(&from, &to, |_| true)?;
+    assert!(report.copied.is_empty());
+    assert_eq!(report.existing.len(), 2);
+    Ok(())
+}
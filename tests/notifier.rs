@@ -0,0 +1,187 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    notifier::{Notice, Notifier, Notify, NotifyFuture},
+    wait, FSJob, Hooked, Job,
+};
+use tokio::sync::mpsc;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+type MyFSJob = FSJob<u16, MyError, u16, ()>;
+
+/// Forwards the notices to a channel, tagged with the method called.
+struct Recorder(mpsc::UnboundedSender<(&'static str, Notice)>);
+
+impl Notifier for Recorder {
+    fn on_finished(&self, notice: Notice) -> NotifyFuture {
+        let _ = self.0.send(("finished", notice));
+        Box::pin(async { Ok(()) })
+    }
+
+    fn on_failed(&self, notice: Notice) -> NotifyFuture {
+        let _ = self.0.send(("failed", notice));
+        Box::pin(async { Ok(()) })
+    }
+}
+
+async fn run(
+    _: uuid::Uuid,
+    _: Hooked<MyFSJob>,
+    n: u16,
+) -> Result<u16, MyError> {
+    match n {
+        0 => Err(MyError {}),
+        1 => panic!("boom"),
+        n => Ok(n),
+    }
+}
+
+async fn received<T>(receiver: &mut mpsc::UnboundedReceiver<T>) -> T {
+    tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+        .await
+        .expect("missing notification")
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_notifier() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let (sender, mut notices) = mpsc::unbounded_channel();
+    let job = Hooked::new(MyFSJob::new(dir.path().into()))
+        .with_hooks(Notify::new(Recorder(sender)).named("nightly"));
+
+    let ok = job.submit(run, 2)?.id();
+    wait(ok, &job).await?;
+    let (method, notice) = received(&mut notices).await;
+    assert_eq!(method, "finished");
+    assert_eq!(notice.id, ok);
+    assert_eq!(notice.subject(), format!("Job nightly {ok} finished"));
+
+    let failed = job.submit(run, 0)?.id();
+    wait(failed, &job).await?;
+    let (method, notice) = received(&mut notices).await;
+    assert_eq!(method, "failed");
+    assert_eq!(
+        notice.text(),
+        format!("Job nightly {failed} failed: MyError")
+    );
+
+    let panicked = job.submit(run, 1)?.id();
+    wait(panicked, &job).await?;
+    let (method, notice) = received(&mut notices).await;
+    assert_eq!(method, "failed");
+    assert_eq!(notice.error.as_deref(), Some("panicked: boom"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_failures_only() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let (sender, mut notices) = mpsc::unbounded_channel();
+    let job = Hooked::new(MyFSJob::new(dir.path().into()))
+        .with_hooks(Notify::new(Recorder(sender)).failures_only());
+    let ok = job.submit(run, 2)?.id();
+    wait(ok, &job).await?;
+    let failed = job.submit(run, 0)?.id();
+    wait(failed, &job).await?;
+    let (method, notice) = received(&mut notices).await;
+    assert_eq!((method, notice.id), ("failed", failed));
+    assert_eq!(notice.subject(), format!("Job {failed} failed"));
+    Ok(())
+}
+
+/// Serve one connection on a local port, answering each line read with the
+/// reply of `answer`, and forward the lines to the returned channel.
+#[cfg(any(feature = "slack", feature = "smtp"))]
+async fn serve(
+    greeting: &'static str,
+    answer: fn(&str) -> Option<&'static str>,
+) -> (u16, mpsc::UnboundedReceiver<String>) {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (sender, receiver) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut stream = BufReader::new(stream);
+        stream.write_all(greeting.as_bytes()).await.unwrap();
+        let mut line = String::new();
+        while stream.read_line(&mut line).await.unwrap() > 0 {
+            if let Some(reply) = answer(&line) {
+                stream.write_all(reply.as_bytes()).await.unwrap();
+            }
+            let _ = sender.send(std::mem::take(&mut line));
+        }
+    });
+    (port, receiver)
+}
+
+#[cfg(feature = "slack")]
+#[tokio::test]
+async fn test_slack() -> std::io::Result<()> {
+    use simple_jobs::notifier::SlackNotifier;
+
+    // The body of the request has no newline: reply after the headers.
+    let (port, mut lines) = serve("", |line| match line {
+        "\r\n" => Some("HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n"),
+        _ => None,
+    })
+    .await;
+    let dir = tempfile::tempdir()?;
+    let slack = SlackNotifier::new(format!("http://127.0.0.1:{port}/hook"));
+    let job = Hooked::new(MyFSJob::new(dir.path().into()))
+        .with_hooks(Notify::new(slack));
+    let id = job.submit(run, 0)?.id();
+    wait(id, &job).await?;
+    let line = received(&mut lines).await;
+    assert_eq!(line, "POST /hook HTTP/1.1\r\n");
+    while received(&mut lines).await != "\r\n" {}
+    Ok(())
+}
+
+#[cfg(feature = "smtp")]
+#[tokio::test]
+async fn test_email() -> std::io::Result<()> {
+    use lettre::{AsyncSmtpTransport, Tokio1Executor};
+    use simple_jobs::notifier::EmailNotifier;
+
+    let (port, mut lines) = serve("220 localhost\r\n", |line| {
+        match line.split([' ', '\r']).next().unwrap() {
+            "EHLO" | "MAIL" | "RCPT" | "." => Some("250 OK\r\n"),
+            "DATA" => Some("354 Go on\r\n"),
+            "QUIT" => Some("221 Bye\r\n"),
+            _ => None,
+        }
+    })
+    .await;
+    let transport =
+        AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+            .port(port)
+            .build();
+    let email = EmailNotifier::new(
+        transport,
+        "jobs@example.com".parse().unwrap(),
+        "oncall@example.com".parse().unwrap(),
+    );
+    let dir = tempfile::tempdir()?;
+    let job = Hooked::new(MyFSJob::new(dir.path().into()))
+        .with_hooks(Notify::new(email).named("nightly"));
+    let id = job.submit(run, 0)?.id();
+    wait(id, &job).await?;
+    let mut message = vec![];
+    loop {
+        let line = received(&mut lines).await;
+        if line == ".\r\n" {
+            break;
+        }
+        message.push(line);
+    }
+    let subject = format!("Subject: Job nightly {id} failed\r\n");
+    assert!(message.contains(&"RCPT TO:<oncall@example.com>\r\n".to_string()));
+    assert!(message.contains(&subject));
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+use simple_jobs::prelude::*;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MyMetadata {
+    value: usize,
+}
+
+#[tokio::test]
+async fn test_prelude_is_enough() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let handle: JobHandle<_> =
+        job.submit(|_, _, _| async { Ok(1) }, Default::default())?;
+    let info: JobInfo<_, _, _, _> = wait(handle.id(), &job).await?;
+    assert_eq!(info.status, StatusType::Finished);
+    Ok(())
+}
@@ -0,0 +1,64 @@
+#![cfg(feature = "nats")]
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use simple_jobs::{FSJob, Job, JobEvent};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+    sync::mpsc,
+};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+/// Serve the NATS protocol on a local port, forwarding the messages
+/// published to the returned channel.
+async fn serve() -> (String, mpsc::UnboundedReceiver<(String, String)>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let (sender, receiver) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut stream = BufReader::new(stream);
+        let info = r#"{"server_id":"test","version":"2.10.0","go":"go1.22","host":"127.0.0.1","port":4222,"max_payload":1048576,"proto":1,"headers":true}"#;
+        let info = format!("INFO {info}\r\n");
+        stream.write_all(info.as_bytes()).await.unwrap();
+        let mut line = String::new();
+        while stream.read_line(&mut line).await.unwrap() > 0 {
+            if line.starts_with("PING") {
+                stream.write_all(b"PONG\r\n").await.unwrap();
+            } else if let Some(publish) = line.strip_prefix("PUB ") {
+                let subject = publish.split(' ').next().unwrap().to_string();
+                let mut payload = String::new();
+                stream.read_line(&mut payload).await.unwrap();
+                let payload = payload.trim_end().to_string();
+                let _ = sender.send((subject, payload));
+            }
+            line.clear();
+        }
+    });
+    (address, receiver)
+}
+
+#[tokio::test]
+async fn test_to_nats() -> std::io::Result<()> {
+    let (address, mut messages) = serve().await;
+    let client = async_nats::connect(address).await.unwrap();
+    tokio::spawn(simple_jobs::publish::to_nats(client, "jobs"));
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, u16, ()> = FSJob::new(dir.path().into());
+    let id = job.enqueue(1)?;
+    let (subject, payload) =
+        tokio::time::timeout(Duration::from_secs(5), messages.recv())
+            .await
+            .expect("missing message")
+            .unwrap();
+    assert_eq!(subject, "jobs.enqueued");
+    let event: JobEvent = serde_json::from_str(&payload)?;
+    assert_eq!(event, JobEvent::Enqueued { id });
+    Ok(())
+}
@@ -0,0 +1,541 @@
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    fs_job::FSJob, queue, wait, CancelReason, Job, JobError, QueueConfig,
+    StatusType, UniqueSubmission, WithQueues,
+};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MyMetadata {
+    value: u16,
+}
+
+type MyFSJob = FSJob<u16, MyError, MyMetadata, u32>;
+
+#[tokio::test]
+async fn test_enqueue_and_claim() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let submitter: MyFSJob = FSJob::new(dir.path().into());
+    let worker: MyFSJob = FSJob::new(dir.path().into());
+    let first = submitter.enqueue(MyMetadata { value: 1 })?;
+    let second = submitter.enqueue(MyMetadata { value: 2 })?;
+    assert_eq!(submitter.load(first)?.status, StatusType::Pending);
+    assert!(submitter.recover()?.is_empty());
+
+    let run = |_id, _job, metadata: MyMetadata| async move {
+        Ok(metadata.value * 10)
+    };
+    let handle = worker.claim_next(run)?.unwrap();
+    assert_eq!(handle.id(), first);
+    let info = wait(first, &submitter).await?;
+    assert_eq!(info.status, StatusType::Finished);
+    assert_eq!(info.result.unwrap().unwrap(), 10);
+    assert!(info.worker.is_some());
+
+    let handle = worker.claim_next(run)?.unwrap();
+    assert_eq!(handle.id(), second);
+    assert_eq!(handle.result().await?.unwrap().unwrap(), 20);
+    assert!(worker.claim_next(run)?.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_jobs_are_claimed_once() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into()).with_locking(true);
+    for value in 0..8 {
+        job.enqueue(MyMetadata { value })?;
+    }
+    let workers: Vec<_> = (0..4)
+        .map(|_| {
+            let job: MyFSJob = FSJob::new(dir.path().into()).with_locking(true);
+            tokio::task::spawn_blocking(move || {
+                let mut claimed = vec![];
+                while let Some(handle) = job
+                    .claim_next(
+                        |_, _, m: MyMetadata| async move { Ok(m.value) },
+                    )
+                    .unwrap()
+                {
+                    claimed.push(handle.id());
+                }
+                claimed
+            })
+        })
+        .collect();
+    let mut claimed = vec![];
+    for worker in workers {
+        claimed.extend(worker.await.unwrap());
+    }
+    claimed.sort();
+    claimed.dedup();
+    assert_eq!(claimed.len(), 8);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_expired_leases_are_claimed_again() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let id = job.enqueue(MyMetadata { value: 1 })?;
+    let (release, released) = tokio::sync::oneshot::channel::<()>();
+    let crashed = job
+        .claim_next(|_, _, _| async move {
+            released.await.ok();
+            Ok(1)
+        })?
+        .unwrap();
+    let mut info = job.load(id)?;
+    let lease = info.lease.clone().unwrap();
+    assert!(!lease.is_expired());
+    assert!(job.claim_next(|_, _, _| async { Ok(2) })?.is_none());
+
+    // The worker stops renewing its claim, e.g. because it hung.
+    info.lease.as_mut().unwrap().expires_at = chrono::Utc::now();
+    job.save(&info)?;
+    let handle = job.claim_next(|_, _, _| async { Ok(2) })?.unwrap();
+    assert_eq!(handle.id(), id);
+    assert_eq!(handle.result().await?.unwrap().unwrap(), 2);
+    assert_ne!(job.load(id)?.lease.unwrap().token, lease.token);
+
+    release.send(()).unwrap();
+    while !crashed.is_finished() {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert_eq!(job.load(id)?.result.unwrap().unwrap(), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_extend_lease() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let id = job.enqueue(MyMetadata { value: 1 })?;
+    let (release, released) = tokio::sync::oneshot::channel::<()>();
+    let handle = job
+        .claim_next(|_, _, _| async move {
+            released.await.ok();
+            Ok(1)
+        })?
+        .unwrap();
+    let mut info = job.load(id)?;
+    info.lease.as_mut().unwrap().expires_at = chrono::Utc::now();
+    job.save(&info)?;
+
+    let lease = job.context(id).extend_lease()?;
+    assert!(!lease.is_expired());
+    assert_eq!(job.load(id)?.lease.unwrap(), lease);
+    assert!(job.claim_next(|_, _, _| async { Ok(2) })?.is_none());
+
+    release.send(()).unwrap();
+    assert_eq!(handle.result().await?.unwrap().unwrap(), 1);
+    // The run kept the renewed lease.
+    assert_eq!(job.load(id)?.lease.unwrap(), lease);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_extend_lost_lease() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let id = job.enqueue(MyMetadata { value: 1 })?;
+    let err = job.context(id).extend_lease().err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+    let (release, released) = tokio::sync::oneshot::channel::<()>();
+    job.claim_next(|_, _, _| async move {
+        released.await.ok();
+        Ok(1)
+    })?
+    .unwrap();
+    let mut info = job.load(id)?;
+    info.lease.as_mut().unwrap().owner = "another worker".into();
+    job.save(&info)?;
+    let err = job.context(id).extend_lease().err().unwrap();
+    assert!(matches!(
+        JobError::from_io(&err),
+        Some(JobError::LeaseLost { .. })
+    ));
+    release.send(()).unwrap();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_extend_stale_claim() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let id = job.enqueue(MyMetadata { value: 1 })?;
+    let (release, released) = tokio::sync::oneshot::channel::<()>();
+    let (report, reported) = tokio::sync::oneshot::channel();
+    job.claim_next(|id, job: MyFSJob, _| async move {
+        let ctx = job.context(id);
+        released.await.ok();
+        report.send(ctx.extend_lease()).ok();
+        Ok(1)
+    })?
+    .unwrap();
+    // The lease expires, and the same worker claims the job again.
+    let mut info = job.load(id)?;
+    info.lease.as_mut().unwrap().expires_at = chrono::Utc::now();
+    job.save(&info)?;
+    let handle = job
+        .claim_next(|id, job: MyFSJob, _| async move {
+            job.context(id).extend_lease().map_err(|_| MyError {})?;
+            Ok(2)
+        })?
+        .unwrap();
+    assert_eq!(handle.result().await?.unwrap().unwrap(), 2);
+
+    release.send(()).unwrap();
+    let stale =
+        tokio::time::timeout(std::time::Duration::from_secs(5), reported)
+            .await
+            .expect("the stale run hangs")
+            .unwrap();
+    assert!(matches!(
+        JobError::from_io(&stale.err().unwrap()),
+        Some(JobError::LeaseLost { .. })
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_priority() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let low = job.enqueue(MyMetadata { value: 1 })?;
+    let high = job.enqueue_with_priority(MyMetadata { value: 2 }, 5)?;
+    let mid = job.enqueue_with_priority(MyMetadata { value: 3 }, 2)?;
+    let mut claimed = vec![];
+    while let Some(handle) = job.claim_next(|_, _, _| async { Ok(1) })? {
+        claimed.push(handle.id());
+    }
+    assert_eq!(claimed, vec![high, mid, low]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aging() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let config =
+        QueueConfig::new().with_aging(std::time::Duration::from_secs(3600));
+    let job = WithQueues::new(MyFSJob::new(dir.path().into()), config);
+    let old = job.enqueue(MyMetadata { value: 1 })?;
+    let mut info = job.load(old)?;
+    info.created_at = Some(chrono::Utc::now() - chrono::Duration::hours(3));
+    job.save(&info)?;
+    let urgent = job.enqueue_with_priority(MyMetadata { value: 2 }, 2)?;
+    let handle = job.claim_next(|_, _, _| async { Ok(1) })?.unwrap();
+    assert_eq!(handle.id(), old);
+    let handle = job.claim_next(|_, _, _| async { Ok(1) })?.unwrap();
+    assert_eq!(handle.id(), urgent);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_named_queues() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let email = job.enqueue_to("emails", MyMetadata { value: 1 }, 0)?;
+    let default = job.enqueue(MyMetadata { value: 2 })?;
+    assert_eq!(job.load(email)?.queue.as_deref(), Some("emails"));
+    assert_eq!(job.list_queue("emails")?.len(), 1);
+
+    let run = |_, _, _| async { Ok(1) };
+    assert_eq!(job.claim_next(run)?.unwrap().id(), default);
+    assert!(job.claim_next(run)?.is_none());
+    assert!(job.claim_next_from(&["reports"], run)?.is_none());
+    let handle = job.claim_next_from(&["reports", "emails"], run)?.unwrap();
+    assert_eq!(handle.id(), email);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_queue_concurrency() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let config = QueueConfig::new().with_concurrency("thumbnails", 1);
+    let job = WithQueues::new(MyFSJob::new(dir.path().into()), config);
+    job.enqueue_to("thumbnails", MyMetadata { value: 1 }, 0)?;
+    job.enqueue_to("thumbnails", MyMetadata { value: 2 }, 0)?;
+    let (release, released) = tokio::sync::oneshot::channel::<()>();
+    let first = job
+        .claim_next_from(&["thumbnails"], |_, _, _| async move {
+            released.await.ok();
+            Ok(1)
+        })?
+        .unwrap();
+    let run = |_, _, _| async { Ok(2) };
+    assert!(job.claim_next_from(&["thumbnails"], run)?.is_none());
+
+    release.send(()).unwrap();
+    first.result().await?;
+    while !first.is_finished() {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert!(job.claim_next_from(&["thumbnails"], run)?.is_some());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_queue_configs_are_per_backend() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let config = QueueConfig::new().with_concurrency("thumbnails", 1);
+    let limited = WithQueues::new(MyFSJob::new(dir.path().into()), config);
+    let unlimited: MyFSJob = FSJob::new(dir.path().into());
+    for value in 0..3 {
+        limited.enqueue_to("thumbnails", MyMetadata { value }, 0)?;
+    }
+    let hang = |_, _, _| futures::future::pending();
+    assert!(limited.claim_next_from(&["thumbnails"], hang)?.is_some());
+    assert!(limited.claim_next_from(&["thumbnails"], hang)?.is_none());
+    // Clones share the running jobs of the same configuration.
+    let clone = limited.clone();
+    assert!(clone.claim_next_from(&["thumbnails"], hang)?.is_none());
+    let hang = |_, _, _| futures::future::pending();
+    assert!(unlimited.claim_next_from(&["thumbnails"], hang)?.is_some());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_resources() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let config = QueueConfig::new().with_resource_capacity("gpu", 1);
+    let job = WithQueues::new(MyFSJob::new(dir.path().into()), config);
+    let gpu = queue::EnqueueOptions::new().resource("gpu");
+    job.enqueue_with(MyMetadata { value: 1 }, &gpu.clone().queue("renders"))?;
+    let second = job.enqueue_with(MyMetadata { value: 2 }, &gpu)?;
+    let other =
+        job.enqueue_with(MyMetadata { value: 3 }, &Default::default())?;
+    assert_eq!(job.load(second)?.resources, vec!["gpu".to_string()]);
+    let (release, released) = tokio::sync::oneshot::channel::<()>();
+    let first = job
+        .claim_next_from(&["renders"], |_, _, _| async move {
+            released.await.ok();
+            Ok(1)
+        })?
+        .unwrap();
+    // The other queue has a job requiring the GPU, and one that doesn't.
+    let run = |_, _, _| async { Ok(2) };
+    let handle = job.claim_next(run)?.unwrap();
+    assert_eq!(handle.id(), other);
+    assert!(job.claim_next(run)?.is_none());
+
+    release.send(()).unwrap();
+    first.result().await?;
+    while !first.is_finished() {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert_eq!(job.claim_next(run)?.unwrap().id(), second);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cancel_many() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let first = job.enqueue_to("imports", MyMetadata { value: 1 }, 0)?;
+    let second = job.enqueue_to("imports", MyMetadata { value: 2 }, 0)?;
+    let other = job.enqueue(MyMetadata { value: 3 })?;
+    let running = job.submit(
+        |_, _, _| async {
+            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+            Ok(1)
+        },
+        MyMetadata { value: 4 },
+    )?;
+    let done =
+        job.submit(|_, _, _| async { Ok(5) }, MyMetadata { value: 5 })?;
+    done.result().await?;
+
+    let imports = |info: &simple_jobs::JobInfo<_, _, _, _>| {
+        info.queue.as_deref() == Some("imports")
+    };
+    let mut canceled = job.cancel_many(imports, CancelReason::UserAction)?;
+    canceled.sort();
+    let mut expected = vec![first, second];
+    expected.sort();
+    assert_eq!(canceled, expected);
+    assert!(matches!(job.load(first)?.status, StatusType::Canceled(_)));
+    assert_eq!(job.load(other)?.status, StatusType::Pending);
+
+    let mut canceled = job.cancel_many(|_| true, CancelReason::Drain)?;
+    canceled.sort();
+    let mut expected = vec![other, running.id()];
+    expected.sort();
+    assert_eq!(canceled, expected);
+    let info = wait(running.id(), &job).await?;
+    assert!(matches!(info.status, StatusType::Canceled(_)));
+    assert_eq!(job.load(done.id())?.status, StatusType::Finished);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pause_queue() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let operator: MyFSJob = FSJob::new(dir.path().into());
+    let worker: MyFSJob = FSJob::new(dir.path().into());
+    operator.pause_queue("default")?;
+    assert_eq!(worker.paused_queues()?, vec!["default".to_string()]);
+    let id = operator.enqueue(MyMetadata { value: 1 })?;
+    let report = operator.enqueue_to("reports", MyMetadata { value: 2 }, 0)?;
+
+    let run = |_, _, _| async { Ok(1) };
+    assert!(worker.claim_next(run)?.is_none());
+    let handle = worker.claim_next_from(&["default", "reports"], run)?;
+    assert_eq!(handle.unwrap().id(), report);
+
+    operator.resume_queue("default")?;
+    assert!(worker.paused_queues()?.is_empty());
+    assert_eq!(worker.claim_next(run)?.unwrap().id(), id);
+    let err = operator.pause_queue("../jobs").err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_queue_capacity() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let config = QueueConfig::new().with_capacity("uploads", 2);
+    let job = WithQueues::new(MyFSJob::new(dir.path().into()), config);
+    job.enqueue_to("uploads", MyMetadata { value: 1 }, 0)?;
+    job.enqueue_to("uploads", MyMetadata { value: 2 }, 0)?;
+    let err = job
+        .enqueue_to("uploads", MyMetadata { value: 3 }, 0)
+        .err()
+        .unwrap();
+    assert_eq!(
+        JobError::from_io(&err),
+        Some(&JobError::QueueFull {
+            queue: "uploads".into(),
+            capacity: 2
+        })
+    );
+    // Other queues are unaffected.
+    job.enqueue(MyMetadata { value: 4 })?;
+
+    let waiting = tokio::spawn({
+        let job = job.clone();
+        async move {
+            queue::enqueue_when_ready(
+                &job,
+                "uploads",
+                MyMetadata { value: 3 },
+                0,
+            )
+            .await
+        }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert!(!waiting.is_finished());
+    job.claim_next_from(&["uploads"], |_, _, _| async { Ok(1) })?;
+    let id = waiting.await.unwrap()?;
+    assert_eq!(job.load(id)?.status, StatusType::Pending);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_concurrency_key() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let sync = queue::EnqueueOptions::new().concurrency_key("customer-42");
+    let first = job.enqueue_with(MyMetadata { value: 1 }, &sync)?;
+    let second = job.enqueue_with(MyMetadata { value: 2 }, &sync)?;
+    let other = job.enqueue(MyMetadata { value: 3 })?;
+    assert_eq!(
+        job.load(first)?.concurrency_key.as_deref(),
+        Some("customer-42")
+    );
+
+    let (release, released) = tokio::sync::oneshot::channel::<()>();
+    let handle = job
+        .claim_next(|_, _, _| async move {
+            released.await.ok();
+            Ok(1)
+        })?
+        .unwrap();
+    assert_eq!(handle.id(), first);
+    let run = |_, _, _| async { Ok(2) };
+    assert_eq!(job.claim_next(run)?.unwrap().id(), other);
+    assert!(job.claim_next(run)?.is_none());
+
+    release.send(()).unwrap();
+    handle.result().await?;
+    assert_eq!(job.claim_next(run)?.unwrap().id(), second);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unique_while_active() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let reindex = queue::EnqueueOptions::new().unique("reindex");
+    let id = job.enqueue_with(MyMetadata { value: 1 }, &reindex)?;
+    assert_eq!(job.enqueue_with(MyMetadata { value: 2 }, &reindex)?, id);
+    let submission = job.submit_unique(
+        "reindex",
+        |_, _, _| async { Ok(3) },
+        Default::default(),
+    )?;
+    assert!(
+        matches!(submission, UniqueSubmission::Existing(existing) if existing == id)
+    );
+
+    let handle = job.claim_next(|_, _, _| async { Ok(1) })?.unwrap();
+    assert_eq!(handle.id(), id);
+    handle.result().await?;
+    // The next job of the key replaces the record of the ended one.
+    let next = job.enqueue_with(MyMetadata { value: 2 }, &reindex)?;
+    assert_eq!(next, id);
+    let info = job.load(next)?;
+    assert_eq!(info.status, StatusType::Pending);
+    assert_eq!(info.metadata.unwrap().value, 2);
+
+    // Backends shared by several processes find the same job.
+    let other: MyFSJob = FSJob::new(dir.path().into());
+    assert_eq!(other.enqueue_with(MyMetadata { value: 3 }, &reindex)?, id);
+    assert_eq!(job.ids()?.len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_window_limit() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let config = || {
+        QueueConfig::new().with_window_limit(
+            "emails",
+            2,
+            std::time::Duration::from_secs(3600),
+        )
+    };
+    let job = WithQueues::new(MyFSJob::new(dir.path().into()), config());
+    let options = |tenant: &str| {
+        queue::EnqueueOptions::new()
+            .queue("emails")
+            .throttle_key(tenant)
+    };
+    let throttled = [
+        job.enqueue_with(MyMetadata { value: 1 }, &options("a"))?,
+        job.enqueue_with(MyMetadata { value: 2 }, &options("a"))?,
+        job.enqueue_with(MyMetadata { value: 3 }, &options("a"))?,
+    ];
+    let other = job.enqueue_with(MyMetadata { value: 4 }, &options("b"))?;
+    assert_eq!(job.load(other)?.throttle_key.as_deref(), Some("b"));
+
+    let mut claimed = vec![];
+    while let Some(handle) =
+        job.claim_next_from(&["emails"], |_, _, _| async { Ok(1) })?
+    {
+        claimed.push(handle.id());
+        handle.result().await?;
+    }
+    assert_eq!(claimed, vec![throttled[0], throttled[1], other]);
+
+    // The runs are counted from the records, e.g. after a restart.
+    let restarted = WithQueues::new(MyFSJob::new(dir.path().into()), config());
+    let run = |_, _, _| async { Ok(1) };
+    assert!(restarted.claim_next_from(&["emails"], run)?.is_none());
+    assert_eq!(restarted.load(throttled[2])?.status, StatusType::Pending);
+    Ok(())
+}
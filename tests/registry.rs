@@ -0,0 +1,156 @@
+use serde_json::{json, Value};
+use simple_jobs::{
+    error::SerializableError,
+    fs_job::FSJob,
+    queue::EnqueueOptions,
+    registry::{JobKind, JobRegistry, Payload},
+    wait, Job, StatusType,
+};
+
+type MyFSJob = FSJob<Value, SerializableError, Payload, u32>;
+
+fn registry(job: MyFSJob) -> JobRegistry<MyFSJob> {
+    let mut registry = JobRegistry::new(job);
+    registry
+        .register("double", |_, _, n: u32| async move { Ok(2 * n) })
+        .register("greet", |_, _, name: String| async move {
+            Ok(format!("Hello, {name}!"))
+        });
+    registry
+}
+
+#[tokio::test]
+async fn test_registry() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job = MyFSJob::new(dir.path().into());
+    let registry = registry(job.clone());
+    let double = registry.submit("double", &21)?;
+    let greet = registry.submit("greet", &"world")?;
+    assert_eq!(double.result().await?, Some(Ok(json!(42))));
+    assert_eq!(greet.result().await?, Some(Ok(json!("Hello, world!"))));
+    let info = job.load(double.id())?;
+    assert_eq!(info.metadata, Some(Payload::new("double", &21)?));
+
+    let err = registry.submit("triple", &1).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    let mut names: Vec<_> = registry.names().collect();
+    names.sort();
+    assert_eq!(names, ["double", "greet"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_registry_resubmit() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let registry = registry(MyFSJob::new(dir.path().into()));
+    let first = registry.submit("double", &21)?;
+    first.result().await?;
+    let again = registry.resubmit(first.id())?;
+    assert_eq!(again.result().await?, Some(Ok(json!(42))));
+    let info = registry.job().load(again.id())?;
+    assert_eq!(info.resubmitted_from, Some(first.id()));
+    assert_eq!(info.metadata, Some(Payload::new("double", &21)?));
+
+    let other = JobRegistry::new(registry.job().clone());
+    let err = other.resubmit(first.id()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_registry_retry_failed() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job = MyFSJob::new(dir.path().into());
+    let registry = registry(job.clone());
+    let failed = registry.submit("double", &"not a number")?;
+    let ok = registry.submit("double", &1)?;
+    failed.result().await?;
+    ok.result().await?;
+    let mut other = JobRegistry::new(job.clone());
+    other.register("fail", |_, _, _: ()| async {
+        Err::<(), _>(SerializableError::msg("failed"))
+    });
+    other.submit("fail", &())?.result().await?;
+
+    let ids = registry.retry_failed(|_| true)?;
+    assert_eq!(ids.len(), 1);
+    assert_eq!(job.load(ids[0])?.resubmitted_from, Some(failed.id()));
+    wait(ids[0], &job).await?;
+    // The failed job was already resubmitted, and failed again.
+    assert!(registry
+        .retry_failed(|info| info.resubmitted_from.is_none())?
+        .is_empty());
+    let retried = registry.retry_failed(|_| true)?;
+    assert_eq!(retried.len(), 1);
+    assert_eq!(job.load(retried[0])?.resubmitted_from, Some(ids[0]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_invalid_payload() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let registry = registry(MyFSJob::new(dir.path().into()));
+    let handle = registry.submit("double", &"twenty-one")?;
+    let info = wait(handle.id(), registry.job()).await?;
+    assert_eq!(info.status, StatusType::Finished);
+    assert!(matches!(info.result, Some(Err(_))));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_registry_queue() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    // The submitter doesn't need the handlers.
+    let submitter = JobRegistry::new(MyFSJob::new(dir.path().into()));
+    let worker = registry(MyFSJob::new(dir.path().into()));
+    let options = EnqueueOptions::new();
+    let double = submitter.enqueue("double", &21, &options)?;
+    let greet = submitter.enqueue("greet", &"queue", &options)?;
+    let handle = worker.claim_next(&["default"])?.unwrap();
+    assert_eq!(handle.id(), double);
+    assert_eq!(handle.result().await?, Some(Ok(json!(42))));
+    let handle = worker.claim_next(&["default"])?.unwrap();
+    assert_eq!(handle.id(), greet);
+    assert_eq!(handle.result().await?, Some(Ok(json!("Hello, queue!"))));
+    assert!(worker.claim_next(&["default"])?.is_none());
+    Ok(())
+}
+
+struct Double;
+
+impl JobKind for Double {
+    const NAME: &'static str = "double";
+    type Payload = u32;
+    type Output = u32;
+}
+
+struct Greet;
+
+impl JobKind for Greet {
+    const NAME: &'static str = "greet";
+    type Payload = String;
+    type Output = String;
+}
+
+#[tokio::test]
+async fn test_load_as() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let registry = registry(MyFSJob::new(dir.path().into()));
+    let double = registry.submit("double", &21)?;
+    let greet = registry.submit("greet", &"world")?;
+    double.result().await?;
+    greet.result().await?;
+
+    let info = registry.load_as::<Double>(double.id())?;
+    assert_eq!(info.metadata, Some(21));
+    assert_eq!(info.result.unwrap().unwrap(), 42);
+    let info = registry.load_as::<Greet>(greet.id())?;
+    assert_eq!(info.result.unwrap().unwrap(), "Hello, world!");
+    let err = registry.load_as::<Greet>(double.id()).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    let doubles = registry.list_as::<Double>()?;
+    assert_eq!(doubles.len(), 1);
+    assert_eq!(doubles[0].id, double.id());
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    fs_job::FSJob, relay::Relay, wait, CancelReason, Job, JobInfo, StatusType,
+};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MyMetadata {
+    value: usize,
+}
+
+type MyFSJob = FSJob<u16, MyError, MyMetadata, u32>;
+
+#[tokio::test]
+async fn test_relay() -> std::io::Result<()> {
+    let edge = tempfile::tempdir()?;
+    let hub = tempfile::tempdir()?;
+    let cursor = tempfile::tempdir()?;
+    let local = MyFSJob::new(edge.path().into());
+    let central = MyFSJob::new(hub.path().into());
+    let relay = Relay::new(
+        local.clone(),
+        central.clone(),
+        cursor.path().join("cursor"),
+    );
+
+    let finished =
+        local.submit(|_, _, _| async { Ok(3) }, Default::default())?;
+    wait(finished.id(), &local).await?;
+    let running = JobInfo::new();
+    local.save(&running)?;
+
+    let report = relay.sync()?;
+    assert_eq!(report.forwarded.len(), 2);
+    assert!(report.failed.is_empty());
+    assert!(matches!(central.load(finished.id())?.result, Some(Ok(3))));
+    assert_eq!(relay.forwarded()?.len(), 1);
+
+    // Nothing changed: nothing to forward.
+    assert!(relay.sync()?.forwarded.is_empty());
+
+    // Progress is forwarded.
+    local.set_status(running.id, StatusType::StatusValue(50))?;
+    assert_eq!(relay.sync()?.forwarded, vec![running.id]);
+    assert_eq!(
+        central.load(running.id)?.status,
+        StatusType::StatusValue(50)
+    );
+
+    // A job canceled on the hub is not overwritten.
+    central.cancel(running.id, CancelReason::UserAction)?;
+    local.set_status(running.id, StatusType::StatusValue(60))?;
+    let report = relay.sync()?;
+    assert_eq!(report.conflicts, vec![running.id]);
+    assert!(matches!(
+        central.load(running.id)?.status,
+        StatusType::Canceled(_)
+    ));
+
+    // A restarted relay resumes from its cursor.
+    let relay = Relay::new(local, central, cursor.path().join("cursor"));
+    let report = relay.sync()?;
+    assert!(report.forwarded.is_empty() && report.conflicts.is_empty());
+    Ok(())
+}
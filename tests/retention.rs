@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    fs_job::FSJob, queue::EnqueueOptions, retention, Job, SubmitOptions,
+};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+type MyFSJob = FSJob<u16, MyError, u16, u32>;
+
+#[tokio::test]
+async fn test_purge_expired() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job = MyFSJob::new(dir.path().into());
+    let run = |_, _, n| async move { Ok(n) };
+    let expired = SubmitOptions::new().retain_for(Duration::ZERO);
+    let expired = job.submit_with(run, 1, &expired)?;
+    let kept = SubmitOptions::new().retain_for(Duration::from_secs(3600));
+    let kept = job.submit_with(run, 2, &kept)?;
+    let forever = job.submit(run, 3)?;
+    let options = EnqueueOptions::new().retain_for(Duration::ZERO);
+    let pending = job.enqueue_with(4, &options)?;
+    for handle in [&expired, &kept, &forever] {
+        handle.result().await?;
+    }
+    let info = job.load(kept.id())?;
+    let expires_at = retention::expires_at::<MyFSJob>(&info).unwrap();
+    assert!(expires_at > info.finished_at.unwrap());
+    assert!(retention::expires_at::<MyFSJob>(&job.load(pending)?).is_none());
+
+    assert_eq!(retention::purge_expired(&job)?, vec![expired.id()]);
+    assert!(job.load(expired.id()).is_err());
+    assert!(job.load(kept.id()).is_ok());
+    assert!(job.load(forever.id()).is_ok());
+    assert!(retention::purge_expired(&job)?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_archive_expired() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let archive_dir = tempfile::tempdir()?;
+    let job = MyFSJob::new(dir.path().into());
+    let archive = MyFSJob::new(archive_dir.path().into());
+    let handle = job.submit_with(
+        |id, job: MyFSJob, n| async move {
+            job.context(id).info("archived too").unwrap();
+            Ok(n)
+        },
+        1,
+        &SubmitOptions::new().retain_for(Duration::ZERO),
+    )?;
+    handle.result().await?;
+
+    let sweep = retention::sweep_every(Duration::from_millis(10), || {
+        retention::archive_expired(&job, &archive)
+    });
+    tokio::time::timeout(Duration::from_millis(100), sweep)
+        .await
+        .err()
+        .unwrap();
+    assert!(job.load(handle.id()).is_err());
+    let info = archive.load(handle.id())?;
+    assert!(matches!(info.result, Some(Ok(1))));
+    assert_eq!(info.retention, None);
+    assert_eq!(archive.logs(handle.id())?.len(), 1);
+    assert!(retention::expired(&archive)?.is_empty());
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    events, fs_job::FSJob, wait, Backoff, Job, JobEvent, RetrySaves, StatusType,
+};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MyMetadata {
+    value: usize,
+}
+
+type MyFSJob = FSJob<u16, MyError, MyMetadata, u32>;
+
+fn backoff(max_failures: u32) -> Backoff {
+    Backoff {
+        initial: Duration::from_millis(20),
+        max: Duration::from_millis(20),
+        max_failures: Some(max_failures),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_final_save_is_retried() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let jobs = dir.path().join("jobs");
+    std::fs::create_dir(&jobs)?;
+    let job = RetrySaves::new(MyFSJob::new(jobs.clone()), backoff(20));
+    let id = job
+        .submit(
+            |_, _, _| async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Ok(1u16)
+            },
+            Default::default(),
+        )?
+        .id();
+    std::fs::remove_dir_all(&jobs)?;
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    std::fs::create_dir(&jobs)?;
+    let info = wait(id, &job).await?;
+    assert_eq!(info.status, StatusType::Finished);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_save_failure_is_reported() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let jobs = dir.path().join("jobs");
+    std::fs::create_dir(&jobs)?;
+    let mut events = events::subscribe();
+    let job = RetrySaves::new(MyFSJob::new(jobs.clone()), backoff(3));
+    let id = job
+        .submit(
+            |_, _, _| async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Ok(1u16)
+            },
+            Default::default(),
+        )?
+        .id();
+    std::fs::remove_dir_all(&jobs)?;
+    loop {
+        match events.recv().await.unwrap() {
+            JobEvent::SaveFailed { id: failed, .. } if failed == id => break,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unreadable_job_is_not_overwritten() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let mut events = events::subscribe();
+    let job = RetrySaves::new(MyFSJob::new(dir.path().into()), backoff(3));
+    let id = job
+        .submit(
+            |_, _, _| async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Ok(1u16)
+            },
+            Default::default(),
+        )?
+        .id();
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    let path = dir.path().join(id.to_string());
+    std::fs::write(&path, "not a job")?;
+    let failed = async {
+        loop {
+            match events.recv().await.unwrap() {
+                JobEvent::SaveFailed { id: failed, .. } if failed == id => {
+                    break
+                }
+                _ => {}
+            }
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(5), failed)
+        .await
+        .expect("the job was overwritten");
+    assert_eq!(std::fs::read_to_string(&path)?, "not a job");
+    Ok(())
+}
@@ -0,0 +1,216 @@
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    queue::{self, EnqueueOptions, Lease},
+    schedule::Scheduler,
+    FSJob, Job, JobError, QueueConfig, StatusType, WithQueues,
+};
+use uuid::Uuid;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MyMetadata {
+    value: u16,
+}
+
+type MyFSJob = FSJob<u16, MyError, MyMetadata, u32>;
+
+fn lease(token: Uuid, ttl: chrono::Duration) -> Lease {
+    Lease {
+        owner: "test".into(),
+        token,
+        expires_at: Utc::now() + ttl,
+    }
+}
+
+#[tokio::test]
+async fn test_leases() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let first: MyFSJob = FSJob::new(dir.path().into());
+    let second: MyFSJob = FSJob::new(dir.path().into());
+    let hour = chrono::Duration::hours(1);
+    let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+
+    assert!(first.acquire_lease("leader", &lease(a, hour))?);
+    assert!(!second.acquire_lease("leader", &lease(b, hour))?);
+    // Renewed by its holder only.
+    assert!(first.acquire_lease("leader", &lease(a, hour))?);
+    second.release_lease("leader", b)?;
+    assert!(!second.acquire_lease("leader", &lease(b, hour))?);
+
+    first.release_lease("leader", a)?;
+    assert!(second.acquire_lease("leader", &lease(b, -hour))?);
+    // Expired.
+    assert!(first.acquire_lease("leader", &lease(a, hour))?);
+    assert!(first.acquire_lease("other", &lease(b, hour))?);
+
+    let err = first
+        .acquire_lease("../leader", &lease(a, hour))
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_leader() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let scheduler = |job: &MyFSJob| {
+        Scheduler::new(job.clone(), "reports").with_periodic(
+            "often",
+            Duration::from_millis(50),
+            MyMetadata { value: 1 },
+        )
+    };
+    let (first, second) = (scheduler(&job), scheduler(&job));
+
+    let mut enqueued = vec![];
+    for _ in 0..30 {
+        enqueued.extend(first.tick()?);
+        assert!(second.tick()?.is_empty());
+        assert!(first.is_leader() && !second.is_leader());
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let keys: HashSet<_> = job
+        .scan()?
+        .map(|info| {
+            assert_eq!(info.status, StatusType::Pending);
+            info.idempotency_key.unwrap()
+        })
+        .collect();
+    assert!(enqueued.len() >= 4);
+    assert_eq!(keys.len(), enqueued.len());
+    assert!(keys.iter().all(|key| key.starts_with("reports/often/")));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_failover() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let scheduler = |job: &MyFSJob| {
+        Scheduler::new(job.clone(), "reports")
+            .with_lease_ttl(Duration::from_millis(100))
+    };
+    let (first, second) = (scheduler(&job), scheduler(&job));
+
+    first.tick()?;
+    second.tick()?;
+    assert!(first.is_leader() && !second.is_leader());
+    // The leader stops ticking, as if it crashed.
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    second.tick()?;
+    first.tick()?;
+    assert!(second.is_leader() && !first.is_leader());
+
+    second.step_down()?;
+    assert!(!second.is_leader());
+    first.tick()?;
+    assert!(first.is_leader());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_delayed() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let at = Utc::now() + chrono::Duration::milliseconds(100);
+    let scheduler = |job: &MyFSJob| {
+        Scheduler::new(job.clone(), "reports").with_delayed(
+            "launch",
+            at,
+            MyMetadata { value: 7 },
+        )
+    };
+    let first = scheduler(&job);
+
+    assert!(first.tick()?.is_empty());
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    let ids = first.tick()?;
+    assert_eq!(ids.len(), 1);
+    let info = job.load(ids[0])?;
+    assert_eq!(info.metadata.unwrap().value, 7);
+    let key = format!("reports/launch/{}", at.to_rfc3339());
+    assert_eq!(info.idempotency_key.as_ref(), Some(&key));
+    assert_eq!(ids[0], queue::unique_id(&key));
+    assert!(first.tick()?.is_empty());
+
+    // A new leader finds the job already enqueued, even once finished.
+    let mut info = job.load(ids[0])?;
+    info.status = StatusType::Finished;
+    job.save(&info)?;
+    first.step_down()?;
+    let second = scheduler(&job);
+    assert!(second.tick()?.is_empty());
+    assert!(second.is_leader());
+    assert_eq!(job.ids()?.len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_concurrent_leaders() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let at = Utc::now();
+    let key = format!("reports/launch/{}", at.to_rfc3339());
+    // Enqueued by a leader whose lease expired while it enqueued.
+    let stale = EnqueueOptions::new().unique(key.as_str());
+    let id = job.enqueue_with(MyMetadata { value: 7 }, &stale)?;
+
+    let scheduler = Scheduler::new(job.clone(), "reports").with_delayed(
+        "launch",
+        at,
+        MyMetadata { value: 7 },
+    );
+    assert!(scheduler.tick()?.is_empty());
+    assert_eq!(job.ids()?, vec![id]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_run_survives_errors() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let config = QueueConfig::new().with_capacity("reports", 0);
+    let job = WithQueues::new(MyFSJob::new(dir.path().into()), config);
+    let errors = Arc::new(AtomicUsize::new(0));
+    let scheduler = Scheduler::new(job.clone(), "reports")
+        .with_options(EnqueueOptions::new().queue("reports"))
+        .with_periodic(
+            "often",
+            Duration::from_millis(10),
+            MyMetadata::default(),
+        )
+        .with_interval(Duration::from_millis(10))
+        .on_error({
+            let errors = errors.clone();
+            move |e| {
+                assert!(matches!(
+                    JobError::from_io(e),
+                    Some(JobError::QueueFull { .. })
+                ));
+                errors.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+    let run = scheduler.run();
+    let stopped = tokio::time::timeout(Duration::from_millis(200), run).await;
+    assert!(stopped.is_err(), "run should keep ticking");
+    assert!(errors.load(Ordering::SeqCst) >= 2);
+    // The failing scheduler stepped down.
+    assert!(!scheduler.is_leader());
+    let other = Scheduler::new(job, "reports");
+    other.tick()?;
+    assert!(other.is_leader());
+    Ok(())
+}
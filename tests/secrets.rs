@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    fs_job::FSJob,
+    secrets::{FileSecrets, Secrets},
+    wait, Job, WithSecrets,
+};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MyMetadata {
+    value: usize,
+}
+
+type MyFSJob = FSJob<u16, MyError, MyMetadata, u32>;
+
+#[tokio::test]
+async fn test_secrets_are_not_saved() -> std::io::Result<()> {
+    let jobs = tempfile::tempdir()?;
+    let secrets = tempfile::tempdir()?;
+    std::fs::write(secrets.path().join("token"), "hunter2\n")?;
+    let job = WithSecrets::new(
+        MyFSJob::new(jobs.path().into()),
+        FileSecrets::new(secrets.path().into()),
+    );
+    let id = job
+        .submit_with_secrets(
+            &["token"],
+            |_, _, _, secrets: Secrets| async move {
+                assert_eq!(format!("{secrets:?}"), r#"{"token"}"#);
+                Ok(secrets.get("token").map_or(0, |t| t.len() as u16))
+            },
+            Default::default(),
+        )?
+        .id();
+    let info = wait(id, &job).await?;
+    assert!(matches!(info.result, Some(Ok(7))));
+    let saved = std::fs::read_to_string(jobs.path().join(id.to_string()))?;
+    assert!(!saved.contains("hunter2"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_missing_secret_fails_the_submission() -> std::io::Result<()> {
+    let jobs = tempfile::tempdir()?;
+    let secrets = tempfile::tempdir()?;
+    let job = WithSecrets::new(
+        MyFSJob::new(jobs.path().into()),
+        FileSecrets::new(secrets.path().into()),
+    );
+    let err = job
+        .submit_with_secrets(
+            &["missing"],
+            |_, _, _, _| async { Ok(1) },
+            Default::default(),
+        )
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    assert!(job.ids()?.is_empty());
+    Ok(())
+}
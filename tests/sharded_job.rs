@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    fs_job::FSJob,
+    sharded_job::{ConsistentHash, Partitioner, ShardedJob},
+    wait, Job, StatusType,
+};
+use uuid::Uuid;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MyMetadata {
+    value: usize,
+}
+
+type MyFSJob = FSJob<u16, MyError, MyMetadata, u32>;
+
+#[tokio::test]
+async fn test_sharded_submit() -> std::io::Result<()> {
+    let dirs = [tempfile::tempdir()?, tempfile::tempdir()?];
+    let shards: Vec<MyFSJob> =
+        dirs.iter().map(|d| FSJob::new(d.path().into())).collect();
+    let job = ShardedJob::new(shards);
+    let mut ids = vec![];
+    for _ in 0..20 {
+        let metadata = Default::default();
+        ids.push(
+            job.submit(|_id, _job, _| async move { Ok(1u16) }, metadata)?
+                .id(),
+        );
+    }
+    for (id, info) in ids.iter().zip(job.load_many(&ids)) {
+        assert_eq!(&info?.id, id);
+    }
+    for id in ids {
+        let info = wait(id, &job).await?;
+        assert_eq!(info.status, StatusType::Finished);
+        assert!(job.shard(&id).load(id).is_ok());
+    }
+    for dir in &dirs {
+        assert!(std::fs::read_dir(dir.path())?.count() > 0);
+    }
+    Ok(())
+}
+
+#[test]
+fn consistent_hash_moves_few_ids() {
+    let three = ConsistentHash::new(3, ConsistentHash::VIRTUAL_NODES);
+    let four = ConsistentHash::new(4, ConsistentHash::VIRTUAL_NODES);
+    let ids: Vec<Uuid> = (0..1000).map(|_| Uuid::new_v4()).collect();
+    let moved = ids
+        .iter()
+        .filter(|id| three.partition(id) != four.partition(id))
+        .count();
+    assert!(moved < 500, "moved {moved} of 1000 ids");
+}
+
+#[test]
+fn consistent_hash_is_balanced() {
+    let ring = ConsistentHash::new(2, ConsistentHash::VIRTUAL_NODES);
+    let first = (0..1000)
+        .filter(|_| ring.partition(&Uuid::new_v4()) == 0)
+        .count();
+    assert!(
+        (300..700).contains(&first),
+        "{first} of 1000 ids in shard 0"
+    );
+}
@@ -0,0 +1,216 @@
+#![cfg(feature = "tracing")]
+
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use simple_jobs::{
+    error::SerializableError, registry::Payload, spans, wait, FSJob, Job,
+    JobRegistry,
+};
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Event, Metadata, Subscriber,
+};
+use tracing_core::span::Current;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+/// A span seen by a [`Recorder`].
+#[derive(Clone, Debug)]
+struct SeenSpan {
+    name: &'static str,
+    metadata: &'static Metadata<'static>,
+    parent: Option<u64>,
+    fields: HashMap<&'static str, String>,
+}
+
+impl Visit for SeenSpan {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.fields.insert(field.name(), format!("{value:?}"));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields.insert(field.name(), value.to_string());
+    }
+}
+
+/// A subscriber recording the spans, for tests on a single thread.
+#[derive(Clone, Default)]
+struct Recorder {
+    spans: Arc<Mutex<Vec<SeenSpan>>>,
+    entered: Arc<Mutex<Vec<u64>>>,
+}
+
+impl Recorder {
+    fn spans(&self) -> Vec<SeenSpan> {
+        self.spans.lock().unwrap().clone()
+    }
+
+    /// The names of the span `id` and its ancestors.
+    fn ancestry(&self, id: u64) -> Vec<&'static str> {
+        let spans = self.spans();
+        let mut names = vec![];
+        let mut next = Some(id);
+        while let Some(id) = next {
+            let span = &spans[id as usize - 1];
+            names.push(span.name);
+            next = span.parent;
+        }
+        names
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+        let parent = match attributes.parent() {
+            Some(parent) => Some(parent.into_u64()),
+            None if attributes.is_contextual() => {
+                self.entered.lock().unwrap().last().copied()
+            }
+            None => None,
+        };
+        let mut span = SeenSpan {
+            name: attributes.metadata().name(),
+            metadata: attributes.metadata(),
+            parent,
+            fields: HashMap::new(),
+        };
+        attributes.record(&mut span);
+        let mut spans = self.spans.lock().unwrap();
+        spans.push(span);
+        Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, id: &Id, values: &Record<'_>) {
+        let mut spans = self.spans.lock().unwrap();
+        values.record(&mut spans[id.into_u64() as usize - 1]);
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, id: &Id) {
+        self.entered.lock().unwrap().push(id.into_u64());
+    }
+
+    fn exit(&self, _: &Id) {
+        self.entered.lock().unwrap().pop();
+    }
+
+    fn current_span(&self) -> Current {
+        match self.entered.lock().unwrap().last() {
+            Some(&id) => Current::new(
+                Id::from_u64(id),
+                self.spans()[id as usize - 1].metadata,
+            ),
+            None => Current::none(),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_job_spans() -> std::io::Result<()> {
+    let recorder = Recorder::default();
+    let _default = tracing::subscriber::set_default(recorder.clone());
+    spans::set_propagation(true);
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u64, MyError, (), ()> = FSJob::new(dir.path().into());
+
+    let request = tracing::info_span!("request");
+    let id = {
+        let _entered = request.enter();
+        job.submit(
+            |_, _, _| async move {
+                let current = tracing::Span::current();
+                Ok(current.id().map(|id| id.into_u64()).unwrap_or_default())
+            },
+            (),
+        )?
+        .id()
+    };
+    let span = wait(id, &job).await?.result.unwrap().unwrap();
+
+    assert_eq!(recorder.ancestry(span), ["job", "submit", "request"]);
+    let seen = &recorder.spans()[span as usize - 1];
+    assert_eq!(seen.fields["job.id"], id.to_string());
+    assert!(recorder
+        .spans()
+        .iter()
+        .any(|span| span.name == "save"
+            && span.fields["job.id"] == id.to_string()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_registry_names_job_spans() -> std::io::Result<()> {
+    let recorder = Recorder::default();
+    let _default = tracing::subscriber::set_default(recorder.clone());
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<Value, SerializableError, Payload, ()> =
+        FSJob::new(dir.path().into());
+    let mut registry = JobRegistry::new(job.clone());
+    registry.register("double", |_, _, n: u32| async move { Ok(2 * n) });
+    let id = registry.submit("double", &21)?.id();
+    wait(id, &job).await?;
+
+    let spans = recorder.spans();
+    let span = spans.iter().find(|span| span.name == "job").unwrap();
+    assert_eq!(span.fields["job.id"], id.to_string());
+    assert_eq!(span.fields["job.name"], "double");
+    Ok(())
+}
+
+#[cfg(feature = "opentelemetry")]
+#[tokio::test]
+async fn test_trace_context_crosses_the_queue() -> std::io::Result<()> {
+    use opentelemetry::trace::{TraceContextExt, TracerProvider};
+    use opentelemetry_sdk::{
+        propagation::TraceContextPropagator, trace::SdkTracerProvider,
+    };
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    opentelemetry::global::set_text_map_propagator(
+        TraceContextPropagator::new(),
+    );
+    let provider = SdkTracerProvider::builder().build();
+    let subscriber = tracing_subscriber::registry().with(
+        tracing_opentelemetry::layer().with_tracer(provider.tracer("test")),
+    );
+    let _default = tracing::subscriber::set_default(subscriber);
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<String, MyError, u8, ()> = FSJob::new(dir.path().into());
+
+    let request = tracing::info_span!("request");
+    let trace_id = request.context().span().span_context().trace_id();
+    let id = {
+        let _entered = request.enter();
+        job.enqueue(0)?
+    };
+    assert!(job.load(id)?.trace_context.contains_key("traceparent"));
+
+    let handle = job
+        .claim_next(|_, _, _| async move {
+            let context = tracing::Span::current().context();
+            Ok(context.span().span_context().trace_id().to_string())
+        })?
+        .unwrap();
+    assert_eq!(handle.id(), id);
+    assert_eq!(
+        handle.result().await?.unwrap().unwrap(),
+        trace_id.to_string()
+    );
+    Ok(())
+}
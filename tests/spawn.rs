@@ -0,0 +1,75 @@
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    fs_job::FSJob,
+    spawn::{TaskFuture, WithSpawner},
+    wait, Job, Spawner, StatusType,
+};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MyMetadata {
+    value: usize,
+}
+
+type MyFSJob = FSJob<u16, MyError, MyMetadata, u32>;
+
+#[tokio::test]
+async fn test_dedicated_runtime() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("dedicated")
+        .enable_all()
+        .build()?;
+    let job = WithSpawner::new(
+        MyFSJob::new(dir.path().into()),
+        runtime.handle().clone(),
+    );
+    let handle = job.submit(
+        |_, _, _| async {
+            let name = std::thread::current().name().map(str::to_string);
+            Ok(if name.as_deref() == Some("dedicated") {
+                1
+            } else {
+                0
+            })
+        },
+        Default::default(),
+    )?;
+    let info = wait(handle.id(), &job).await?;
+    assert!(matches!(info.status, StatusType::Finished));
+    assert!(matches!(info.result, Some(Ok(1))));
+    runtime.shutdown_background();
+    Ok(())
+}
+
+/// Records the number of spawned tasks before handing them to Tokio.
+#[derive(Clone, Default)]
+struct Counting(Arc<Mutex<usize>>);
+
+impl Spawner for Counting {
+    fn spawn(&self, task: TaskFuture) -> tokio::task::JoinHandle<()> {
+        *self.0.lock().unwrap() += 1;
+        tokio::spawn(task)
+    }
+}
+
+#[tokio::test]
+async fn test_custom_spawner() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let spawner = Counting::default();
+    let job =
+        WithSpawner::new(MyFSJob::new(dir.path().into()), spawner.clone());
+    for _ in 0..3 {
+        let id = job
+            .submit(|_, _, _| async { Ok(1) }, Default::default())?
+            .id();
+        wait(id, &job).await?;
+    }
+    assert_eq!(*spawner.0.lock().unwrap(), 3);
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use simple_jobs::{FSJob, Job, JobInfo, JobStats, Percentiles, StatusType};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+type MyFSJob = FSJob<u16, MyError, String, ()>;
+
+fn ended(
+    start: DateTime<Utc>,
+    seconds: i64,
+    result: Result<u16, MyError>,
+) -> JobInfo<u16, MyError, String, ()> {
+    JobInfo {
+        status: StatusType::Finished,
+        result: Some(result),
+        created_at: Some(start),
+        started_at: Some(start),
+        finished_at: Some(start + chrono::Duration::seconds(seconds)),
+        metadata: Some("report".to_string()),
+        ..JobInfo::new()
+    }
+}
+
+#[test]
+fn test_stats() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: MyFSJob = FSJob::new(dir.path().into());
+    let start = Utc::now() - chrono::Duration::hours(1);
+    for seconds in 1..=9 {
+        job.save(&ended(start, seconds, Ok(1)))?;
+    }
+    job.save(&ended(start, 10, Err(MyError {})))?;
+    job.save(&JobInfo::new())?;
+    let mut other = ended(start, 100, Ok(1));
+    other.metadata = Some("other".to_string());
+    job.save(&other)?;
+
+    let stats = job.stats(|info| info.metadata.as_deref() != Some("other"))?;
+    assert_eq!(stats.total(), 11);
+    assert_eq!(stats.active, 1);
+    assert_eq!(stats.succeeded, 9);
+    assert_eq!(stats.failed, 1);
+    assert_eq!(stats.failure_rate(), Some(0.1));
+    assert_eq!(stats.throughput(), Some(10.0 / 9.0));
+    assert_eq!(
+        stats.durations,
+        Percentiles {
+            p50: Some(Duration::from_secs(5)),
+            p95: Some(Duration::from_secs(10)),
+            p99: Some(Duration::from_secs(10)),
+        }
+    );
+    assert_eq!(stats.queue_latencies.p99, Some(Duration::ZERO));
+    Ok(())
+}
+
+#[test]
+fn test_stats_without_jobs() {
+    let stats = JobStats::of(Vec::<JobInfo<u16, MyError, String, ()>>::new());
+    assert_eq!(stats.total(), 0);
+    assert_eq!(stats.failure_rate(), None);
+    assert_eq!(stats.throughput(), None);
+    assert_eq!(stats.durations, Percentiles::default());
+}
@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use simple_jobs::{fs_job::FSJob, wait, Job, JobSupervisor, StatusType};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MyMetadata {
+    value: usize,
+}
+
+type MyFSJob = FSJob<u16, MyError, MyMetadata, u32>;
+
+fn sleeping(
+    job: &JobSupervisor<MyFSJob>,
+    millis: u64,
+) -> std::io::Result<uuid::Uuid> {
+    let id = job
+        .submit(
+            move |_, _, _| async move {
+                tokio::time::sleep(Duration::from_millis(millis)).await;
+                Ok(1)
+            },
+            Default::default(),
+        )?
+        .id();
+    Ok(id)
+}
+
+#[tokio::test]
+async fn test_shutdown() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job = JobSupervisor::new(MyFSJob::new(dir.path().into()));
+    let quick = sleeping(&job, 20)?;
+    let slow = sleeping(&job, 10_000)?;
+    assert_eq!(job.in_flight().len(), 2);
+
+    let interrupted = job.shutdown(Duration::from_millis(200)).await?;
+    assert_eq!(interrupted, vec![slow]);
+    assert_eq!(job.load(quick)?.status, StatusType::Finished);
+    let info = wait(slow, &job).await?;
+    assert_eq!(info.status, StatusType::Interrupted);
+    assert!(info.finished_at.is_some());
+    assert!(job.in_flight().is_empty());
+
+    assert!(job.is_shutting_down());
+    assert!(sleeping(&job, 0).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_in_flight_forgets_completed_jobs() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job = JobSupervisor::new(MyFSJob::new(dir.path().into()));
+    let id = sleeping(&job, 0)?;
+    wait(id, &job).await?;
+    assert!(job.in_flight().is_empty());
+    assert!(job.shutdown(Duration::ZERO).await?.is_empty());
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+use simple_jobs::{fs_job::FSJob, versioning::Schema, wait, Job, JobInfo};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct OldMetadata {
+    count: usize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+struct NewMetadata {
+    records: usize,
+    source: String,
+}
+
+fn schema() -> Schema {
+    Schema::new()
+        .migration(|info| {
+            if let Some(metadata) = info["metadata"].as_object_mut() {
+                let count = metadata.remove("count").unwrap_or_default();
+                metadata.insert("records".into(), count);
+            }
+            Ok(())
+        })
+        .migration(|info| {
+            if let Some(metadata) = info["metadata"].as_object_mut() {
+                metadata.insert("source".into(), "unknown".into());
+            }
+            Ok(())
+        })
+}
+
+#[tokio::test]
+async fn test_migrations() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let old: FSJob<u16, MyError, OldMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let id = old
+        .submit(|_, _, _| async { Ok(1) }, OldMetadata { count: 7 })?
+        .id();
+    wait(id, &old).await?;
+
+    let new: FSJob<u16, MyError, NewMetadata, u32> =
+        FSJob::new(dir.path().into()).with_schema(schema());
+    let info = new.load(id)?;
+    let expected = NewMetadata {
+        records: 7,
+        source: "unknown".into(),
+    };
+    assert_eq!(info.metadata, Some(expected.clone()));
+
+    // Saved records carry their version, and aren't migrated again.
+    new.save(&info)?;
+    let record: serde_json::Value = serde_json::from_slice(&std::fs::read(
+        dir.path().join(id.to_string()),
+    )?)?;
+    assert_eq!(record["schema"], 2);
+    assert_eq!(new.load(id)?.metadata, Some(expected));
+    Ok(())
+}
+
+#[test]
+fn test_newer_schema() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let newer: FSJob<u16, MyError, NewMetadata, u32> =
+        FSJob::new(dir.path().into()).with_schema(schema());
+    let info = JobInfo::new();
+    newer.save(&info)?;
+
+    let older: FSJob<u16, MyError, NewMetadata, u32> =
+        FSJob::new(dir.path().into())
+            .with_schema(Schema::new().migration(|_| Ok(())));
+    let err = older.load(info.id).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    fs_job::FSJob,
+    watch::{watch, Backoff},
+    Job, JobInfo, StatusType,
+};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MyMetadata {
+    value: usize,
+}
+
+#[tokio::test]
+async fn test_watch_retries_until_job_appears() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let mut info = JobInfo::new();
+    let id = info.id;
+    let writer = job.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        writer.save(&info).unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        info.status = StatusType::Finished;
+        info.result = Some(Ok(1u16));
+        writer.save(&info).unwrap();
+    });
+    let backoff = Backoff {
+        max: Duration::from_millis(20),
+        ..Default::default()
+    };
+    let updates: Vec<_> = watch(id, &job, backoff).collect().await;
+    assert_eq!(updates.last().unwrap().status, StatusType::Finished);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_watch_gives_up_on_missing_job() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, MyMetadata, u32> =
+        FSJob::new(dir.path().into());
+    let backoff = Backoff {
+        max_failures: Some(3),
+        ..Default::default()
+    };
+    let updates: Vec<_> =
+        watch(uuid::Uuid::new_v4(), &job, backoff).collect().await;
+    assert!(updates.is_empty());
+    Ok(())
+}
@@ -0,0 +1,166 @@
+#![cfg(feature = "webhooks")]
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use simple_jobs::{
+    wait,
+    webhooks::{self, Event, Payload, Webhooks, SIGNATURE_HEADER},
+    Backoff, FSJob, Job, SubmitOptions,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+    sync::mpsc,
+};
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+struct MyError {}
+
+/// A request received by the test server.
+struct Request {
+    path: String,
+    signature: String,
+    body: Vec<u8>,
+    accepted: bool,
+}
+
+/// Serve HTTP on a local port, failing the first request, and forward the
+/// requests to the returned channel.
+async fn serve() -> (String, mpsc::UnboundedReceiver<Request>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut first = true;
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(stream);
+            let sender = sender.clone();
+            let accepted = !std::mem::take(&mut first);
+            let status = match accepted {
+                true => "200 OK",
+                false => "500 Internal Server Error",
+            };
+            tokio::spawn(async move {
+                let mut line = String::new();
+                stream.read_line(&mut line).await.unwrap();
+                let path = line.split(' ').nth(1).unwrap().to_string();
+                let (mut length, mut signature) = (0, String::new());
+                loop {
+                    line.clear();
+                    stream.read_line(&mut line).await.unwrap();
+                    let Some((name, value)) = line.trim().split_once(": ")
+                    else {
+                        break;
+                    };
+                    match name.to_lowercase() {
+                        name if name == "content-length" => {
+                            length = value.parse().unwrap()
+                        }
+                        name if name == SIGNATURE_HEADER.to_lowercase() => {
+                            signature = value.to_string()
+                        }
+                        _ => {}
+                    }
+                }
+                let mut body = vec![0; length];
+                stream.read_exact(&mut body).await.unwrap();
+                let response = format!(
+                    "HTTP/1.1 {status}\r\ncontent-length: 0\r\n\
+                     connection: close\r\n\r\n"
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+                let _ = sender.send(Request {
+                    path,
+                    signature,
+                    body,
+                    accepted,
+                });
+            });
+        }
+    });
+    (address, receiver)
+}
+
+#[tokio::test]
+async fn test_webhooks() -> std::io::Result<()> {
+    let (address, mut requests) = serve().await;
+    let dir = tempfile::tempdir()?;
+    let job: FSJob<u16, MyError, u16, ()> = FSJob::new(dir.path().into());
+    let backoff = Backoff {
+        initial: Duration::from_millis(10),
+        ..Backoff::default()
+    };
+    let hooks = Webhooks::new("secret")
+        .url(format!("{address}/global"))
+        .backoff(backoff);
+    tokio::spawn(webhooks::deliver(job.clone(), hooks));
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let run = |_, _, n: u16| async move {
+        match n {
+            0 => Err(MyError {}),
+            n => Ok(n),
+        }
+    };
+    let ok = job.submit(run, 1)?.id();
+    wait(ok, &job).await?;
+    let url = format!("{address}/job");
+    let failed = job
+        .submit_with(run, 0, &SubmitOptions::new().webhook(url))?
+        .id();
+    wait(failed, &job).await?;
+
+    let mut received = vec![];
+    while received.len() < 3 {
+        let request = tokio::time::timeout(Duration::from_secs(5), async {
+            requests.recv().await.unwrap()
+        })
+        .await
+        .expect("missing deliveries");
+        let tolerance = Duration::from_secs(60);
+        assert!(webhooks::verify(
+            b"secret",
+            &request.signature,
+            &request.body,
+            tolerance
+        ));
+        let payload: Payload<u16, MyError> =
+            serde_json::from_slice(&request.body)?;
+        if request.accepted {
+            received.push((request.path, payload));
+        }
+    }
+    // The delivery refused by the server was retried.
+    received.sort_by_key(|(path, payload)| (path.clone(), payload.event));
+    let deliveries: Vec<_> = received
+        .iter()
+        .map(|(path, payload)| (path.as_str(), payload.id, payload.event))
+        .collect();
+    assert_eq!(
+        deliveries,
+        [
+            ("/global", ok, Event::Finished),
+            ("/global", failed, Event::Failed),
+            ("/job", failed, Event::Failed),
+        ]
+    );
+    let (_, payload) = &received[2];
+    assert_eq!(payload.result, Some(Err(MyError {})));
+    assert_eq!(payload.status, "finished");
+    Ok(())
+}
+
+#[test]
+fn test_verify() {
+    let now = chrono::Utc::now().timestamp();
+    let tolerance = Duration::from_secs(60);
+    let signature = webhooks::sign(b"secret", now, b"{}");
+    assert!(webhooks::verify(b"secret", &signature, b"{}", tolerance));
+    assert!(!webhooks::verify(b"other", &signature, b"{}", tolerance));
+    assert!(!webhooks::verify(b"secret", &signature, b"[]", tolerance));
+    let old = webhooks::sign(b"secret", now - 3600, b"{}");
+    assert!(!webhooks::verify(b"secret", &old, b"{}", tolerance));
+    assert!(!webhooks::verify(b"secret", "t=1,v1=zz", b"{}", tolerance));
+}
@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+use simple_jobs::{fs_job::FSJob, wait, worker, Job};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MyError {}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MyMetadata {
+    value: usize,
+}
+
+type MyFSJob = FSJob<u16, MyError, MyMetadata, u32>;
+
+#[tokio::test]
+async fn test_worker_label() -> std::io::Result<()> {
+    assert!(!worker::label().is_empty());
+    worker::set_label("worker-7");
+    let dir = tempfile::tempdir()?;
+    let job = MyFSJob::new(dir.path().into());
+    let handle = job.submit(|_, _, _| async { Ok(1) }, Default::default())?;
+    let info = wait(handle.id(), &job).await?;
+    assert_eq!(info.worker.as_deref(), Some("worker-7"));
+    Ok(())
+}